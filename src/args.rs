@@ -1,5 +1,5 @@
 use {
-    anyhow::Result,
+    anyhow::{Context, Result},
     clap::Arg,
     path_clean::PathClean,
     std::{path::PathBuf, str::FromStr},
@@ -22,6 +22,7 @@ pub(crate) struct CallArgs {
     #[allow(dead_code)]
     pub privileges: Privilege,
     pub command: Command,
+    pub user: crate::config::UserConfig,
 }
 
 impl CallArgs {
@@ -30,6 +31,8 @@ impl CallArgs {
     }
 }
 
+// One per target, the size of the configs does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
 pub(crate) enum Subsystem {
     #[cfg(feature = "sub+postgres")]
@@ -57,12 +60,30 @@ pub(crate) enum Command {
         path: PathBuf,
         shell: clap_complete::Shell,
     },
-    Subsystem(Subsystem),
+    /// The subsystem command once per selected target, named unless it is the top-level subsystem,
+    /// and how many targets run at a time
+    Subsystem { targets: Vec<(Option<String>, Subsystem)>, parallel: usize, metrics: crate::core::prometheus::MetricsOutput, report: Option<PathBuf>, events: Option<String> },
 }
 
 pub(crate) struct ClapArgumentLoader {}
 
 impl ClapArgumentLoader {
+    fn metrics_output(matches: &clap::ArgMatches) -> Result<crate::core::prometheus::MetricsOutput> {
+        let push = matches.get_one::<String>("metrics_push").cloned();
+        if push.is_some() && !cfg!(feature = "pushgateway") {
+            anyhow::bail!("--metrics-push needs qop built with the pushgateway feature");
+        }
+        Ok(crate::core::prometheus::MetricsOutput { file: matches.get_one::<String>("metrics_file").map(PathBuf::from), push })
+    }
+
+    /// `--report` of `up` and `down`, which covers every target of the run
+    fn report_path(matches: &clap::ArgMatches) -> Option<PathBuf> {
+        match matches.subcommand() {
+            | Some(("up" | "down", subc)) => subc.get_one::<String>("report").map(PathBuf::from),
+            | _ => None,
+        }
+    }
+
     fn get_absolute_path(matches: &clap::ArgMatches, name: &str) -> Result<PathBuf> {
         let path_str: &String = matches.get_one(name).unwrap();
         let path = std::path::Path::new(path_str);
@@ -86,7 +107,15 @@ impl ClapArgumentLoader {
             .author("cchexcode <alexanderh.weber@outlook.com>")
             .propagate_version(true)
             .subcommand_required(false)
-            .args([Arg::new("experimental").short('e').long("experimental").help("Enables experimental features.").num_args(0)])
+            .args([
+                Arg::new("experimental").short('e').long("experimental").help("Enables experimental features.").num_args(0),
+                Arg::new("quiet").short('q').long("quiet").global(true).num_args(0).conflicts_with("verbose").help("Print only errors and the final summary"),
+                Arg::new("verbose").short('v').long("verbose").global(true).num_args(0).help("Also print the executed SQL, timings and connection details"),
+                Arg::new("color").long("color").global(true).value_parser(["auto", "always", "never"]).help("When to use colors; auto follows NO_COLOR and whether stdout is a terminal [default: auto]"),
+                Arg::new("plain").long("plain").global(true).num_args(0).help("Print ASCII only, without emoji and box drawing"),
+                Arg::new("no-pager").long("no-pager").global(true).num_args(0).help("Print long diff and log output directly instead of through $PAGER"),
+                Arg::new("output").short('o').long("output").value_parser(["human", "json"]).help("Print the result of every command as JSON on stdout, status messages go to stderr"),
+            ])
             .subcommand(
                 clap::Command::new("man").about("Renders the manual.")
                     .arg(clap::Arg::new("out").short('o').long("out").required(true))
@@ -110,6 +139,21 @@ impl ClapArgumentLoader {
                 let pg = clap::Command::new("postgres")
                     .aliases(["pg"]).about("Manages PostgreSQL migrations.")
                     .arg(clap::Arg::new("path").short('p').long("path").default_value("qop.toml"))
+                    .arg(clap::Arg::new("profile").long("profile").required(false).help("Profile from the config file to apply (defaults to $QOP_PROFILE)"))
+                    .arg(clap::Arg::new("target").long("target").required(false).help("Target from the [targets] of the config file to run against"))
+                    .arg(clap::Arg::new("all_targets").long("all-targets").action(clap::ArgAction::SetTrue).conflicts_with("target").help("Run against every target of this subsystem in the config file"))
+                    .arg(clap::Arg::new("conn").long("conn").required(false).conflicts_with("all_targets").help("Connection to use instead of the one in the config (defaults to $QOP_CONNECTION)"))
+                    .arg(clap::Arg::new("force_protected").long("force-protected").action(clap::ArgAction::SetTrue).help("Allow destructive commands and all seeds on a protected config"))
+                    .arg(clap::Arg::new("override_window").long("override-window").action(clap::ArgAction::SetTrue).help("Run commands that change the database outside the maintenance window of the config"))
+                    .arg(clap::Arg::new("no_lock").long("no-lock").action(clap::ArgAction::SetTrue).help("Do not take the run lock that keeps concurrent up/down runs apart"))
+                    .arg(clap::Arg::new("run_lock_timeout").long("run-lock-timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Time to wait for the run lock of another qop run, e.g. 2m (overrides run_lock_timeout from the config)"))
+                    .arg(clap::Arg::new("parallel").long("parallel").required(false).value_parser(clap::value_parser!(usize)).help("Run up to N targets or tenants at a time, reporting all failures at the end"))
+                    .arg(clap::Arg::new("metrics_file").long("metrics-file").required(false).help("Write Prometheus metrics of the run to this textfile, e.g. for the node exporter"))
+                    .arg(clap::Arg::new("metrics_push").long("metrics-push").required(false).help("Push Prometheus metrics of the run to this Pushgateway URL, e.g. http://pushgateway:9091/metrics/job/qop"))
+                    .arg(clap::Arg::new("events").long("events").required(false).help("Write run and migration events as JSON lines to fd:<N>, a file descriptor such as a pipe, or to a file"))
+                    .arg(clap::Arg::new("lock_timeout").long("lock-timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Time a statement may wait for a lock, e.g. 5s (overrides lock_timeout from the config)"))
+                    .arg(clap::Arg::new("lock_check").long("lock-check").required(false).value_parser(["warn", "wait"]).help("Before running DDL, warn about or wait for long-running transactions locking the affected tables"))
+                    .arg(clap::Arg::new("schemas").long("schemas").required(false).value_delimiter(',').help("Tenant schemas to run the command in, one after another or up to --parallel at a time (overrides tenants from the config)"))
                     .subcommand_required(true)
                     .subcommand(
                         clap::Command::new("config")
@@ -120,36 +164,227 @@ impl ClapArgumentLoader {
                                     .about("Writes a sample configuration for Postgres.")
                                     .arg(clap::Arg::new("conn").short('c').long("conn").help("Database connection string").required(true))
                             )
+                            .subcommand(
+                                clap::Command::new("upgrade")
+                                    .about("Rewrites a config written for an older qop version to the current format.")
+                                    .arg(clap::Arg::new("dry_run").long("dry-run").action(clap::ArgAction::SetTrue).help("Print the upgraded config instead of writing it"))
+                            )
                     )
                     .subcommand(clap::Command::new("init").about("Initializes the database."))
                     .subcommand(clap::Command::new("new").about("Creates a new migration.")
                         .arg(clap::Arg::new("comment").short('c').long("comment").help("Comment for the migration"))
-                        .arg(clap::Arg::new("locked").long("lock").num_args(0).help("Mark migration as locked (cannot be reverted without --unlock)")))
+                        .arg(clap::Arg::new("locked").long("lock").num_args(0).help("Mark migration as locked (cannot be reverted without --unlock)"))
+                        .arg(clap::Arg::new("up").long("up").required(false).help("File whose content becomes up.sql"))
+                        .arg(clap::Arg::new("down").long("down").required(false).help("File whose content becomes down.sql"))
+                        .arg(clap::Arg::new("auto_down").long("auto-down").num_args(0).conflicts_with("down").help("Generate a best-effort down.sql from up.sql"))
+                        .arg(clap::Arg::new("stdin").long("stdin").num_args(0).conflicts_with("up").help("Read up.sql from stdin"))
+                        .arg(clap::Arg::new("template").long("template").required(false).help("Template from the templates directory to start from (defaults to the `default` template if present)")))
                     .subcommand(clap::Command::new("up").about("Runs the migrations.")
-                        .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false))
+                        .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Statement timeout, e.g. 30s, 5m or 1h (a bare number is seconds)"))
                         .arg(clap::Arg::new("count").short('c').long("count").required(false))
+                        .arg(clap::Arg::new("tag").long("tag").action(clap::ArgAction::Append).help("Only apply migrations tagged with TAG in their meta.toml; repeatable"))
                         .arg(clap::Arg::new("diff").short('d').long("diff").required(false).num_args(0).help("Show migration diff before applying"))
                         .arg(clap::Arg::new("dry").long("dry").required(false).num_args(0).help("Execute migration in a transaction but rollback instead of committing").conflicts_with("yes"))
                         .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
                         .arg(clap::Arg::new("unlock").long("unlock").num_args(0).help("Allow reverting locked migrations"))
+                        .arg(clap::Arg::new("single-transaction").long("single-transaction").num_args(0).help("Apply all migrations and their records in one transaction, so a failure leaves the database as it was"))
+                        .arg(clap::Arg::new("keep-going").long("keep-going").num_args(0).requires("single-transaction").help("Roll a failing migration back to its savepoint and continue with the next one; later migrations are not checked for depending on it, and the run still fails"))
+                        .arg(clap::Arg::new("shadow").long("shadow").num_args(0).conflicts_with("dry").help("Apply the pending migrations to a scratch copy of the database and report the outcome, leaving the database itself untouched"))
+                        .arg(clap::Arg::new("assert-idempotent").long("assert-idempotent").num_args(0).conflicts_with_all(["shadow", "single-transaction"]).help("Run every pending migration twice in a rolled-back transaction and fail if a second run errors"))
+                        .arg(clap::Arg::new("resume").long("resume").num_args(0).conflicts_with_all(["shadow", "assert-idempotent", "single-transaction", "dry"]).help("Continue where the last failed up stopped, skipping the statements of the failed migration that already ran"))
+                        .arg(clap::Arg::new("check").long("check").num_args(0).conflicts_with_all(["dry", "yes", "shadow", "assert-idempotent", "resume", "count"]).help("Apply nothing; exit with 0 when all migrations are applied, 2 when some are pending, 3 when applied migrations were modified or are missing locally"))
+                        .arg(clap::Arg::new("report").long("report").required(false).help("Write a report of the run for change management to this file: Markdown, or HTML for .html files"))
                     )
                     .subcommand(clap::Command::new("down").about("Rolls back the migrations.")
-                        .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false))
+                        .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Statement timeout, e.g. 30s, 5m or 1h (a bare number is seconds)"))
                         .arg(clap::Arg::new("remote").short('r').long("remote").required(false).num_args(0))
                         .arg(clap::Arg::new("count").short('c').long("count").required(false))
+                        .arg(clap::Arg::new("to").long("to").required(false).conflicts_with("count").help("Revert all migrations newer than this migration ID"))
+                        .arg(clap::Arg::new("tag").long("tag").action(clap::ArgAction::Append).help("Only revert migrations tagged with TAG in their meta.toml; repeatable"))
                         .arg(clap::Arg::new("diff").short('d').long("diff").required(false).num_args(0).help("Show migration diff before applying"))
                         .arg(clap::Arg::new("dry").long("dry").required(false).num_args(0).help("Execute migration in a transaction but rollback instead of committing").conflicts_with("yes"))
                         .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
                         .arg(clap::Arg::new("unlock").long("unlock").num_args(0).help("Allow reverting locked migrations"))
+                        .arg(clap::Arg::new("report").long("report").required(false).help("Write a report of the run for change management to this file: Markdown, or HTML for .html files"))
                     )
-                    .subcommand(clap::Command::new("list").about("Lists all applied migrations.")
+                    .subcommand(clap::Command::new("list").visible_aliases(["status"]).about("Lists all applied migrations.")
+                        .arg(clap::Arg::new("tag").long("tag").action(clap::ArgAction::Append).help("Only list migrations tagged with TAG in their meta.toml; repeatable"))
+                        .arg(clap::Arg::new("check").long("check").num_args(0).help("Instead of listing, exit with 0 when all migrations are applied, 2 when some are pending, 3 when applied migrations were modified or are missing locally"))
+                        .arg(clap::Arg::new("wide").short('w').long("wide").num_args(0).help("Also show which OS user applied each migration and from which host"))
                         .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
                     )
                     .subcommand(clap::Command::new("history").about("Manages migration history.").subcommand_required(true)
                         .subcommand(clap::Command::new("sync").about("Upserts all remote migrations locally."))
                         .subcommand(clap::Command::new("fix").about("Shuffles all non-run local migrations to the end of the chain."))
+                        .subcommand(clap::Command::new("verify").about("Verifies the pre chain of applied migrations and reports gaps, forks and mismatches.")
+                            .arg(clap::Arg::new("fix").long("fix").required(false).num_args(0).help("Rebuild the chain from the order in which migrations were applied"))
+                            .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).requires("fix").help("Skip confirmation prompts"))
+                            .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                        )
+                    )
+                    .subcommand(clap::Command::new("diff").about("Shows pending migration operations without applying them.")
+                        .arg(clap::Arg::new("down").long("down").required(false).num_args(0).help("Preview the down SQL of the most recently applied migrations"))
+                        .arg(clap::Arg::new("count").short('c').long("count").required(false).value_parser(clap::value_parser!(usize)).help("Number of migrations to preview (defaults to all pending, or 1 with --down)"))
+                        .arg(clap::Arg::new("id").long("id").required(false).conflicts_with("count").help("Preview a single migration"))
+                        .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format; json includes the parsed operations of each migration"))
+                    )
+                    .subcommand(clap::Command::new("plan").about("Lists pending migrations with the operations they perform and their risks.")
+                        .arg(clap::Arg::new("count").short('c').long("count").required(false).value_parser(clap::value_parser!(usize)).help("Only plan the next COUNT pending migrations"))
+                        .arg(clap::Arg::new("tag").long("tag").action(clap::ArgAction::Append).help("Only plan migrations tagged with TAG in their meta.toml; repeatable"))
+                        .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                    )
+                    .subcommand(clap::Command::new("verify").about("Verifies that applied migrations match their local files.")
+                        .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                    )
+                    .subcommand(clap::Command::new("squash").about("Squashes a range of migrations into a single migration.")
+                        .arg(clap::Arg::new("from").long("from").required(true).help("Oldest migration ID of the range (inclusive)"))
+                        .arg(clap::Arg::new("to").long("to").required(true).help("Newest migration ID of the range (inclusive)"))
+                        .arg(clap::Arg::new("comment").short('c').long("comment").help("Comment for the squashed migration"))
+                        .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                    )
+                    .subcommand(clap::Command::new("baseline").about("Marks all migrations up to the given ID as applied without executing them.")
+                        .arg(clap::Arg::new("id").help("Newest migration ID to mark as applied").required(true))
+                        .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                    )
+                    .subcommand(clap::Command::new("log").about("Queries the migration log.").subcommand_required(true)
+                        .subcommand(clap::Command::new("list").about("Lists log entries, newest first.")
+                            .arg(clap::Arg::new("migration").short('m').long("migration").required(false).help("Only show entries of this migration ID"))
+                            .arg(clap::Arg::new("operation").long("operation").required(false).help("Only show entries of this operation (e.g. up, down)"))
+                            .arg(clap::Arg::new("since").long("since").required(false).help("Only show entries executed at or after this time (RFC 3339 or YYYY-MM-DD[ HH:MM:SS], UTC)"))
+                            .arg(clap::Arg::new("until").long("until").required(false).help("Only show entries executed at or before this time (RFC 3339 or YYYY-MM-DD[ HH:MM:SS], UTC)"))
+                            .arg(clap::Arg::new("limit").short('n').long("limit").required(false).value_parser(clap::value_parser!(usize)).help("Maximum number of entries to show"))
+                            .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                        )
+                        .subcommand(clap::Command::new("show").about("Shows a single log entry including the executed SQL.")
+                            .arg(clap::Arg::new("id").help("Log entry ID").required(true))
+                            .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                        )
+                        .subcommand(clap::Command::new("export").about("Exports the log with the comment and qop version of each migration for audits, oldest first.")
+                            .arg(clap::Arg::new("format").short('f').long("format").required(false).value_parser(["csv", "json"]).default_value("csv").help("Export format"))
+                            .arg(clap::Arg::new("since").long("since").required(false).help("Only export entries executed at or after this time (RFC 3339 or YYYY-MM-DD[ HH:MM:SS], UTC)"))
+                            .arg(clap::Arg::new("until").long("until").required(false).help("Only export entries executed at or before this time (RFC 3339 or YYYY-MM-DD[ HH:MM:SS], UTC)"))
+                            .arg(clap::Arg::new("out").long("out").required(false).help("Write the export to this file instead of stdout"))
+                        )
+                    )
+                    .subcommand(clap::Command::new("rename").aliases(["mv", "move"]).about("Renames a migration to a new ID, updating the tracking records if it is applied.")
+                        .arg(clap::Arg::new("id").help("Current migration ID").required(true))
+                        .arg(clap::Arg::new("new_id").help("New migration ID").required(true))
+                        .arg(clap::Arg::new("comment").short('c').long("comment").help("Replace the comment in meta.toml"))
+                        .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                    )
+                    .subcommand(clap::Command::new("lock").about("Locks a migration in meta.toml and the tracking table.")
+                        .arg(clap::Arg::new("id").help("Migration ID to lock").required(true))
+                    )
+                    .subcommand(clap::Command::new("unlock").about("Unlocks a migration in meta.toml and the tracking table.")
+                        .arg(clap::Arg::new("id").help("Migration ID to unlock").required(true))
+                    )
+                    .subcommand(clap::Command::new("rm").aliases(["remove"]).about("Deletes a local migration directory.")
+                        .arg(clap::Arg::new("id").help("Migration ID to remove").required(true))
+                        .arg(clap::Arg::new("force").long("force").num_args(0).help("Allow removing an applied migration (requires --also-remote)"))
+                        .arg(clap::Arg::new("also_remote").long("also-remote").num_args(0).requires("force").help("Also delete the tracking record and log entries without running the down SQL"))
+                        .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                    )
+                    .subcommand(clap::Command::new("prune").about("Deletes tracking records of applied migrations that no longer exist locally.")
+                        .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                    )
+                    .subcommand(clap::Command::new("repair").about("Overwrites the stored SQL of an applied migration with the local files.")
+                        .arg(clap::Arg::new("id").help("Migration ID to repair").required(true))
+                        .arg(clap::Arg::new("up").long("up").num_args(0).help("Repair the stored up SQL"))
+                        .arg(clap::Arg::new("down").long("down").num_args(0).help("Repair the stored down SQL"))
+                        .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                    )
+                    .subcommand(clap::Command::new("snapshot").about("Dumps the current database schema, recording the last applied migration.")
+                        .arg(clap::Arg::new("out").short('o').long("out").required(false).help("File to write the snapshot to (default: stdout)"))
+                    )
+                    .subcommand(clap::Command::new("test").about("Applies and reverts all migrations against an ephemeral database.")
+                        .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Statement timeout, e.g. 30s, 5m or 1h (a bare number is seconds)"))
+                    )
+                    .subcommand(clap::Command::new("generate").about("Generates a migration from the difference between the declarative schema and the migrations.")
+                        .arg(clap::Arg::new("schema").short('s').long("schema").required(false).help("Declarative schema file (defaults to the configured schema file)"))
+                        .arg(clap::Arg::new("from_db").long("from-db").num_args(0).conflicts_with("schema").help("Capture changes made to the live database outside of the applied migrations instead"))
+                        .arg(clap::Arg::new("comment").short('c').long("comment").required(false).help("Comment of the generated migration"))
+                        .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                    )
+                    .subcommand(clap::Command::new("lint").about("Checks pending migrations for common mistakes.")
+                        .arg(clap::Arg::new("all").short('a').long("all").num_args(0).help("Lint all local migrations instead of only pending ones"))
+                        .arg(clap::Arg::new("strict").long("strict").num_args(0).help("Fail on warnings as well as errors"))
+                        .arg(clap::Arg::new("large_table_rows").long("large-table-rows").required(false).default_value("100000").value_parser(clap::value_parser!(i64).range(0..)).help("Estimated row count from which a table counts as large"))
+                        .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                    )
+                    .subcommand(clap::Command::new("fmt").about("Formats the up.sql and down.sql files of pending migrations.")
+                        .arg(clap::Arg::new("check").long("check").num_args(0).help("Only check formatting and exit non-zero if any file would change"))
+                        .arg(clap::Arg::new("all").short('a').long("all").num_args(0).help("Format all local migrations, including applied ones, instead of only pending ones"))
+                    )
+                    .subcommand(clap::Command::new("tui").about("Opens an interactive view to browse, apply, revert, lock and sync migrations.")
+                        .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Statement timeout, e.g. 30s, 5m or 1h (a bare number is seconds)"))
+                    )
+                    .subcommand(clap::Command::new("graph").about("Renders the pre-chain of applied migrations, highlighting breaks and forks.")
+                        .arg(clap::Arg::new("format").short('f').long("format").required(false).value_parser(["ascii", "dot"]).default_value("ascii").help("Output format (dot renders a Graphviz graph)"))
+                    )
+                    .subcommand(clap::Command::new("export").about("Packages all local migrations and a manifest of the applied state into a bundle.")
+                        .arg(clap::Arg::new("out").short('o').long("out").required(true).help("Path of the bundle to write (.tar.gz)"))
+                    )
+                    .subcommand(clap::Command::new("import").about("Verifies a bundle and extracts its migrations into the migration directory, or converts the migrations of another tool.")
+                        .arg(clap::Arg::new("bundle").required(true).value_name("SOURCE").help("Path of the bundle to import, the changelog of --from liquibase, the migrations directory of --from sqlx or --from dbmate, or the offline SQL of --from alembic"))
+                        .arg(clap::Arg::new("from").long("from").default_value("bundle").value_parser(["bundle", "liquibase", "sqlx", "alembic", "dbmate"]).help("Format of the source; other tools' migrations are converted and those their tracking table lists are recorded as applied"))
+                        .arg(clap::Arg::new("history_table").long("history-table").help("Tracking table of the other tool (default: databasechangelog for Liquibase, _sqlx_migrations for sqlx, alembic_version for Alembic, schema_migrations for dbmate)").conflicts_with("no_history"))
+                        .arg(clap::Arg::new("no_history").long("no-history").num_args(0).help("Only convert the migrations, record none of them as applied"))
+                        .arg(clap::Arg::new("force").long("force").num_args(0).help("Overwrite local migrations whose content differs from the bundle"))
+                        .arg(clap::Arg::new("yes").short('y').long("yes").num_args(0).help("Skip confirmation prompts"))
+                    )
+                    .subcommand(clap::Command::new("seed").about("Runs seed data from the seeds directory, tracked separately from migrations.").subcommand_required(true)
+                        .subcommand(clap::Command::new("run").about("Runs all seeds that have not been run yet.")
+                            .arg(clap::Arg::new("profile").short('P').long("profile").required(false).help("Also run the seeds in seeds/<PROFILE>/"))
+                            .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Statement timeout, e.g. 30s, 5m or 1h (a bare number is seconds)"))
+                            .arg(clap::Arg::new("force").long("force").num_args(0).help("Run seeds again whose file changed since they were run"))
+                            .arg(clap::Arg::new("dry").long("dry").required(false).num_args(0).help("Execute seeds in a transaction but rollback instead of committing").conflicts_with("yes"))
+                            .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                        )
+                        .subcommand(clap::Command::new("list").about("Lists seeds and whether they have been run.")
+                            .arg(clap::Arg::new("profile").short('P').long("profile").required(false).help("Also list the seeds in seeds/<PROFILE>/"))
+                            .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                        )
+                        .subcommand(clap::Command::new("reset").about("Forgets that seeds have been run so they run again; seeded data is not removed.")
+                            .arg(clap::Arg::new("profile").short('P').long("profile").required(false).help("Also reset the seeds in seeds/<PROFILE>/"))
+                            .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                        )
+                    )
+                    .subcommand(clap::Command::new("meta").about("Validates and repairs the meta.toml files of local migrations.").subcommand_required(true)
+                        .subcommand(clap::Command::new("check").about("Reports unknown keys, values of the wrong type and missing meta.toml files.")
+                            .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                        )
+                        .subcommand(clap::Command::new("fix").about("Creates missing meta.toml files with defaults and renames or removes unknown keys.")
+                            .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                        )
+                    )
+                    .subcommand(clap::Command::new("bootstrap").about("Generates the first migration from the schema of an existing database.")
+                        .arg(clap::Arg::new("comment").short('c').long("comment").required(false).help("Comment for the generated migration"))
+                        .arg(clap::Arg::new("baseline").long("baseline").num_args(0).help("Record the generated migration as applied without executing it"))
+                        .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                    )
+                    .subcommand(clap::Command::new("wait").about("Waits until the database accepts queries.")
+                        .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false).default_value("60s").value_parser(crate::core::duration::parse_duration).help("Maximum time to wait (e.g. 500ms, 30s, 2m)"))
+                        .arg(clap::Arg::new("interval").short('i').long("interval").required(false).default_value("2s").value_parser(crate::core::duration::parse_duration).help("Time between connection attempts"))
+                    )
+                    .subcommand(clap::Command::new("ping").about("Checks the connection and the tracking tables without changing anything.")
+                        .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                    )
+                    .subcommand(clap::Command::new("console").about("Opens the native database client on the configured connection.")
+                        .arg(clap::Arg::new("client").long("client").required(false).help("Client executable to use instead of the default"))
+                        .arg(clap::Arg::new("args").num_args(0..).allow_hyphen_values(true).last(true).help("Additional arguments passed to the client (after --)"))
+                    )
+                    .subcommand(clap::Command::new("drift").about("Compares the live schema with the snapshot recorded at the last up/down.")
+                        .arg(clap::Arg::new("snapshot").short('s').long("snapshot").required(false).help("Snapshot file to compare against (defaults to the configured snapshot)"))
+                        .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                    )
+                    .subcommand(clap::Command::new("bench").about("Benchmarks pending migrations on scratch copies of the database.")
+                        .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Statement timeout, e.g. 30s, 5m or 1h (a bare number is seconds)"))
+                        .arg(clap::Arg::new("iterations").short('n').long("iterations").required(false).default_value("5").help("Number of times the pending migrations are executed"))
+                        .arg(clap::Arg::new("baseline").short('b').long("baseline").required(false).help("Baseline report (JSON) to compare the results against"))
+                        .arg(clap::Arg::new("save").short('s').long("save").required(false).help("Write the results as a baseline report (JSON) to this path"))
+                        .arg(clap::Arg::new("threshold").long("threshold").required(false).default_value("20").help("Allowed slowdown in percent compared to the baseline"))
+                        .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
                     )
-                    .subcommand(clap::Command::new("diff").about("Shows pending migration operations without applying them."))
                     .subcommand(
                         clap::Command::new("apply")
                             .about("Applies or reverts a specific migration by ID.")
@@ -157,8 +392,10 @@ impl ClapArgumentLoader {
                             .subcommand(
                                 clap::Command::new("up")
                                     .about("Applies a specific migration.")
-                                    .arg(clap::Arg::new("id").help("Migration ID to apply").required(true))
-                                    .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false))
+                                    .arg(clap::Arg::new("id").num_args(1..).help("Migration IDs to apply; prompts for a selection when neither IDs nor a range are given in a terminal").required(false))
+                                    .arg(clap::Arg::new("from").long("from").required(false).conflicts_with("id").help("Oldest pending migration ID to apply (inclusive)"))
+                                    .arg(clap::Arg::new("to").long("to").required(false).conflicts_with("id").help("Newest pending migration ID to apply (inclusive)"))
+                                    .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Statement timeout, e.g. 30s, 5m or 1h (a bare number is seconds)"))
                                     .arg(clap::Arg::new("dry").long("dry").required(false).num_args(0).help("Execute migration in a transaction but rollback instead of committing").conflicts_with("yes"))
                                     .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
                                     .arg(clap::Arg::new("locked").long("lock").num_args(0).help("Mark applied migration as locked (cannot be reverted without --unlock)"))
@@ -166,12 +403,14 @@ impl ClapArgumentLoader {
                             .subcommand(
                                 clap::Command::new("down")
                                     .about("Reverts a specific migration.")
-                                    .arg(clap::Arg::new("id").help("Migration ID to revert").required(true))
-                                    .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false))
+                                    .arg(clap::Arg::new("id").num_args(1..).help("Migration IDs to revert; prompts for a selection when neither IDs nor a range are given in a terminal").required(false))
+                                    .arg(clap::Arg::new("from").long("from").required(false).conflicts_with("id").help("Oldest applied migration ID to revert (inclusive)"))
+                                    .arg(clap::Arg::new("to").long("to").required(false).conflicts_with("id").help("Newest applied migration ID to revert (inclusive)"))
+                                    .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Statement timeout, e.g. 30s, 5m or 1h (a bare number is seconds)"))
                                     .arg(clap::Arg::new("remote").short('r').long("remote").required(false).num_args(0))
                                     .arg(clap::Arg::new("dry").long("dry").required(false).num_args(0).help("Execute migration in a transaction but rollback instead of committing").conflicts_with("yes"))
                                     .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
-                                    .arg(clap::Arg::new("locked").long("lock").num_args(0).help("Mark applied migration as locked (cannot be reverted without --unlock)"))
+                                    .arg(clap::Arg::new("unlock").long("unlock").num_args(0).help("Allow reverting a locked migration"))
                             )
                     );
                 subsystem = subsystem.subcommand(pg);
@@ -181,6 +420,18 @@ impl ClapArgumentLoader {
             {
                 let sql = clap::Command::new("sqlite").aliases(["sql"]).about("Manages SQLite migrations.")
                     .arg(clap::Arg::new("path").short('p').long("path").default_value("qop.toml"))
+                    .arg(clap::Arg::new("profile").long("profile").required(false).help("Profile from the config file to apply (defaults to $QOP_PROFILE)"))
+                    .arg(clap::Arg::new("target").long("target").required(false).help("Target from the [targets] of the config file to run against"))
+                    .arg(clap::Arg::new("all_targets").long("all-targets").action(clap::ArgAction::SetTrue).conflicts_with("target").help("Run against every target of this subsystem in the config file"))
+                    .arg(clap::Arg::new("conn").long("conn").required(false).conflicts_with("all_targets").help("Connection to use instead of the one in the config (defaults to $QOP_CONNECTION)"))
+                    .arg(clap::Arg::new("force_protected").long("force-protected").action(clap::ArgAction::SetTrue).help("Allow destructive commands and all seeds on a protected config"))
+                    .arg(clap::Arg::new("override_window").long("override-window").action(clap::ArgAction::SetTrue).help("Run commands that change the database outside the maintenance window of the config"))
+                    .arg(clap::Arg::new("no_lock").long("no-lock").action(clap::ArgAction::SetTrue).help("Do not take the run lock that keeps concurrent up/down runs apart"))
+                    .arg(clap::Arg::new("run_lock_timeout").long("run-lock-timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Time to wait for the run lock of another qop run, e.g. 2m (overrides run_lock_timeout from the config)"))
+                    .arg(clap::Arg::new("parallel").long("parallel").required(false).value_parser(clap::value_parser!(usize)).help("Run up to N targets or tenants at a time, reporting all failures at the end"))
+                    .arg(clap::Arg::new("metrics_file").long("metrics-file").required(false).help("Write Prometheus metrics of the run to this textfile, e.g. for the node exporter"))
+                    .arg(clap::Arg::new("metrics_push").long("metrics-push").required(false).help("Push Prometheus metrics of the run to this Pushgateway URL, e.g. http://pushgateway:9091/metrics/job/qop"))
+                    .arg(clap::Arg::new("events").long("events").required(false).help("Write run and migration events as JSON lines to fd:<N>, a file descriptor such as a pipe, or to a file"))
                     .subcommand_required(true)
                     .subcommand(
                         clap::Command::new("config")
@@ -191,36 +442,227 @@ impl ClapArgumentLoader {
                                     .about("Writes a sample configuration for SQLite.")
                                     .arg(clap::Arg::new("db").short('d').long("db").help("Database file path").required(true))
                             )
+                            .subcommand(
+                                clap::Command::new("upgrade")
+                                    .about("Rewrites a config written for an older qop version to the current format.")
+                                    .arg(clap::Arg::new("dry_run").long("dry-run").action(clap::ArgAction::SetTrue).help("Print the upgraded config instead of writing it"))
+                            )
                     )
                     .subcommand(clap::Command::new("init").about("Initializes the database."))
                     .subcommand(clap::Command::new("new").about("Creates a new migration.")
                         .arg(clap::Arg::new("comment").short('c').long("comment").help("Comment for the migration"))
-                        .arg(clap::Arg::new("locked").long("lock").num_args(0).help("Mark migration as locked (cannot be reverted without --unlock)")))
+                        .arg(clap::Arg::new("locked").long("lock").num_args(0).help("Mark migration as locked (cannot be reverted without --unlock)"))
+                        .arg(clap::Arg::new("up").long("up").required(false).help("File whose content becomes up.sql"))
+                        .arg(clap::Arg::new("down").long("down").required(false).help("File whose content becomes down.sql"))
+                        .arg(clap::Arg::new("auto_down").long("auto-down").num_args(0).conflicts_with("down").help("Generate a best-effort down.sql from up.sql"))
+                        .arg(clap::Arg::new("stdin").long("stdin").num_args(0).conflicts_with("up").help("Read up.sql from stdin"))
+                        .arg(clap::Arg::new("template").long("template").required(false).help("Template from the templates directory to start from (defaults to the `default` template if present)")))
                     .subcommand(clap::Command::new("up").about("Runs the migrations.")
-                        .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false))
+                        .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Statement timeout, e.g. 30s, 5m or 1h (a bare number is seconds)"))
                         .arg(clap::Arg::new("count").short('c').long("count").required(false))
+                        .arg(clap::Arg::new("tag").long("tag").action(clap::ArgAction::Append).help("Only apply migrations tagged with TAG in their meta.toml; repeatable"))
                         .arg(clap::Arg::new("diff").short('d').long("diff").required(false).num_args(0).help("Show migration diff before applying"))
                         .arg(clap::Arg::new("dry").long("dry").required(false).num_args(0).help("Execute migration in a transaction but rollback instead of committing").conflicts_with("yes"))
                         .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
                         .arg(clap::Arg::new("unlock").long("unlock").num_args(0).help("Allow reverting locked migrations"))
+                        .arg(clap::Arg::new("single-transaction").long("single-transaction").num_args(0).help("Apply all migrations and their records in one transaction, so a failure leaves the database as it was"))
+                        .arg(clap::Arg::new("keep-going").long("keep-going").num_args(0).requires("single-transaction").help("Roll a failing migration back to its savepoint and continue with the next one; later migrations are not checked for depending on it, and the run still fails"))
+                        .arg(clap::Arg::new("shadow").long("shadow").num_args(0).conflicts_with("dry").help("Apply the pending migrations to a scratch copy of the database and report the outcome, leaving the database itself untouched"))
+                        .arg(clap::Arg::new("assert-idempotent").long("assert-idempotent").num_args(0).conflicts_with_all(["shadow", "single-transaction"]).help("Run every pending migration twice in a rolled-back transaction and fail if a second run errors"))
+                        .arg(clap::Arg::new("resume").long("resume").num_args(0).conflicts_with_all(["shadow", "assert-idempotent", "single-transaction", "dry"]).help("Continue where the last failed up stopped, skipping the statements of the failed migration that already ran"))
+                        .arg(clap::Arg::new("check").long("check").num_args(0).conflicts_with_all(["dry", "yes", "shadow", "assert-idempotent", "resume", "count"]).help("Apply nothing; exit with 0 when all migrations are applied, 2 when some are pending, 3 when applied migrations were modified or are missing locally"))
+                        .arg(clap::Arg::new("report").long("report").required(false).help("Write a report of the run for change management to this file: Markdown, or HTML for .html files"))
                     )
                     .subcommand(clap::Command::new("down").about("Rolls back the migrations.")
-                        .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false))
+                        .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Statement timeout, e.g. 30s, 5m or 1h (a bare number is seconds)"))
                         .arg(clap::Arg::new("remote").short('r').long("remote").required(false).num_args(0))
                         .arg(clap::Arg::new("count").short('c').long("count").required(false))
+                        .arg(clap::Arg::new("to").long("to").required(false).conflicts_with("count").help("Revert all migrations newer than this migration ID"))
+                        .arg(clap::Arg::new("tag").long("tag").action(clap::ArgAction::Append).help("Only revert migrations tagged with TAG in their meta.toml; repeatable"))
                         .arg(clap::Arg::new("diff").short('d').long("diff").required(false).num_args(0).help("Show migration diff before applying"))
                         .arg(clap::Arg::new("dry").long("dry").required(false).num_args(0).help("Execute migration in a transaction but rollback instead of committing").conflicts_with("yes"))
                         .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
                         .arg(clap::Arg::new("unlock").long("unlock").num_args(0).help("Allow reverting locked migrations"))
+                        .arg(clap::Arg::new("report").long("report").required(false).help("Write a report of the run for change management to this file: Markdown, or HTML for .html files"))
                     )
-                    .subcommand(clap::Command::new("list").about("Lists all applied migrations.")
+                    .subcommand(clap::Command::new("list").visible_aliases(["status"]).about("Lists all applied migrations.")
+                        .arg(clap::Arg::new("tag").long("tag").action(clap::ArgAction::Append).help("Only list migrations tagged with TAG in their meta.toml; repeatable"))
+                        .arg(clap::Arg::new("check").long("check").num_args(0).help("Instead of listing, exit with 0 when all migrations are applied, 2 when some are pending, 3 when applied migrations were modified or are missing locally"))
+                        .arg(clap::Arg::new("wide").short('w').long("wide").num_args(0).help("Also show which OS user applied each migration and from which host"))
                         .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
                     )
                     .subcommand(clap::Command::new("history").about("Manages migration history.").subcommand_required(true)
                         .subcommand(clap::Command::new("sync").about("Upserts all remote migrations locally."))
                         .subcommand(clap::Command::new("fix").about("Shuffles all non-run local migrations to the end of the chain."))
+                        .subcommand(clap::Command::new("verify").about("Verifies the pre chain of applied migrations and reports gaps, forks and mismatches.")
+                            .arg(clap::Arg::new("fix").long("fix").required(false).num_args(0).help("Rebuild the chain from the order in which migrations were applied"))
+                            .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).requires("fix").help("Skip confirmation prompts"))
+                            .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                        )
+                    )
+                    .subcommand(clap::Command::new("diff").about("Shows pending migration operations without applying them.")
+                        .arg(clap::Arg::new("down").long("down").required(false).num_args(0).help("Preview the down SQL of the most recently applied migrations"))
+                        .arg(clap::Arg::new("count").short('c').long("count").required(false).value_parser(clap::value_parser!(usize)).help("Number of migrations to preview (defaults to all pending, or 1 with --down)"))
+                        .arg(clap::Arg::new("id").long("id").required(false).conflicts_with("count").help("Preview a single migration"))
+                        .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format; json includes the parsed operations of each migration"))
+                    )
+                    .subcommand(clap::Command::new("plan").about("Lists pending migrations with the operations they perform and their risks.")
+                        .arg(clap::Arg::new("count").short('c').long("count").required(false).value_parser(clap::value_parser!(usize)).help("Only plan the next COUNT pending migrations"))
+                        .arg(clap::Arg::new("tag").long("tag").action(clap::ArgAction::Append).help("Only plan migrations tagged with TAG in their meta.toml; repeatable"))
+                        .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                    )
+                    .subcommand(clap::Command::new("verify").about("Verifies that applied migrations match their local files.")
+                        .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                    )
+                    .subcommand(clap::Command::new("squash").about("Squashes a range of migrations into a single migration.")
+                        .arg(clap::Arg::new("from").long("from").required(true).help("Oldest migration ID of the range (inclusive)"))
+                        .arg(clap::Arg::new("to").long("to").required(true).help("Newest migration ID of the range (inclusive)"))
+                        .arg(clap::Arg::new("comment").short('c').long("comment").help("Comment for the squashed migration"))
+                        .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                    )
+                    .subcommand(clap::Command::new("baseline").about("Marks all migrations up to the given ID as applied without executing them.")
+                        .arg(clap::Arg::new("id").help("Newest migration ID to mark as applied").required(true))
+                        .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                    )
+                    .subcommand(clap::Command::new("log").about("Queries the migration log.").subcommand_required(true)
+                        .subcommand(clap::Command::new("list").about("Lists log entries, newest first.")
+                            .arg(clap::Arg::new("migration").short('m').long("migration").required(false).help("Only show entries of this migration ID"))
+                            .arg(clap::Arg::new("operation").long("operation").required(false).help("Only show entries of this operation (e.g. up, down)"))
+                            .arg(clap::Arg::new("since").long("since").required(false).help("Only show entries executed at or after this time (RFC 3339 or YYYY-MM-DD[ HH:MM:SS], UTC)"))
+                            .arg(clap::Arg::new("until").long("until").required(false).help("Only show entries executed at or before this time (RFC 3339 or YYYY-MM-DD[ HH:MM:SS], UTC)"))
+                            .arg(clap::Arg::new("limit").short('n').long("limit").required(false).value_parser(clap::value_parser!(usize)).help("Maximum number of entries to show"))
+                            .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                        )
+                        .subcommand(clap::Command::new("show").about("Shows a single log entry including the executed SQL.")
+                            .arg(clap::Arg::new("id").help("Log entry ID").required(true))
+                            .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                        )
+                        .subcommand(clap::Command::new("export").about("Exports the log with the comment and qop version of each migration for audits, oldest first.")
+                            .arg(clap::Arg::new("format").short('f').long("format").required(false).value_parser(["csv", "json"]).default_value("csv").help("Export format"))
+                            .arg(clap::Arg::new("since").long("since").required(false).help("Only export entries executed at or after this time (RFC 3339 or YYYY-MM-DD[ HH:MM:SS], UTC)"))
+                            .arg(clap::Arg::new("until").long("until").required(false).help("Only export entries executed at or before this time (RFC 3339 or YYYY-MM-DD[ HH:MM:SS], UTC)"))
+                            .arg(clap::Arg::new("out").long("out").required(false).help("Write the export to this file instead of stdout"))
+                        )
+                    )
+                    .subcommand(clap::Command::new("rename").aliases(["mv", "move"]).about("Renames a migration to a new ID, updating the tracking records if it is applied.")
+                        .arg(clap::Arg::new("id").help("Current migration ID").required(true))
+                        .arg(clap::Arg::new("new_id").help("New migration ID").required(true))
+                        .arg(clap::Arg::new("comment").short('c').long("comment").help("Replace the comment in meta.toml"))
+                        .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                    )
+                    .subcommand(clap::Command::new("lock").about("Locks a migration in meta.toml and the tracking table.")
+                        .arg(clap::Arg::new("id").help("Migration ID to lock").required(true))
+                    )
+                    .subcommand(clap::Command::new("unlock").about("Unlocks a migration in meta.toml and the tracking table.")
+                        .arg(clap::Arg::new("id").help("Migration ID to unlock").required(true))
+                    )
+                    .subcommand(clap::Command::new("rm").aliases(["remove"]).about("Deletes a local migration directory.")
+                        .arg(clap::Arg::new("id").help("Migration ID to remove").required(true))
+                        .arg(clap::Arg::new("force").long("force").num_args(0).help("Allow removing an applied migration (requires --also-remote)"))
+                        .arg(clap::Arg::new("also_remote").long("also-remote").num_args(0).requires("force").help("Also delete the tracking record and log entries without running the down SQL"))
+                        .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                    )
+                    .subcommand(clap::Command::new("prune").about("Deletes tracking records of applied migrations that no longer exist locally.")
+                        .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                    )
+                    .subcommand(clap::Command::new("repair").about("Overwrites the stored SQL of an applied migration with the local files.")
+                        .arg(clap::Arg::new("id").help("Migration ID to repair").required(true))
+                        .arg(clap::Arg::new("up").long("up").num_args(0).help("Repair the stored up SQL"))
+                        .arg(clap::Arg::new("down").long("down").num_args(0).help("Repair the stored down SQL"))
+                        .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                    )
+                    .subcommand(clap::Command::new("snapshot").about("Dumps the current database schema, recording the last applied migration.")
+                        .arg(clap::Arg::new("out").short('o').long("out").required(false).help("File to write the snapshot to (default: stdout)"))
+                    )
+                    .subcommand(clap::Command::new("test").about("Applies and reverts all migrations against an ephemeral database.")
+                        .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Statement timeout, e.g. 30s, 5m or 1h (a bare number is seconds)"))
+                    )
+                    .subcommand(clap::Command::new("generate").about("Generates a migration from the difference between the declarative schema and the migrations.")
+                        .arg(clap::Arg::new("schema").short('s').long("schema").required(false).help("Declarative schema file (defaults to the configured schema file)"))
+                        .arg(clap::Arg::new("from_db").long("from-db").num_args(0).conflicts_with("schema").help("Capture changes made to the live database outside of the applied migrations instead"))
+                        .arg(clap::Arg::new("comment").short('c').long("comment").required(false).help("Comment of the generated migration"))
+                        .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                    )
+                    .subcommand(clap::Command::new("lint").about("Checks pending migrations for common mistakes.")
+                        .arg(clap::Arg::new("all").short('a').long("all").num_args(0).help("Lint all local migrations instead of only pending ones"))
+                        .arg(clap::Arg::new("strict").long("strict").num_args(0).help("Fail on warnings as well as errors"))
+                        .arg(clap::Arg::new("large_table_rows").long("large-table-rows").required(false).default_value("100000").value_parser(clap::value_parser!(i64).range(0..)).help("Estimated row count from which a table counts as large"))
+                        .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                    )
+                    .subcommand(clap::Command::new("fmt").about("Formats the up.sql and down.sql files of pending migrations.")
+                        .arg(clap::Arg::new("check").long("check").num_args(0).help("Only check formatting and exit non-zero if any file would change"))
+                        .arg(clap::Arg::new("all").short('a').long("all").num_args(0).help("Format all local migrations, including applied ones, instead of only pending ones"))
+                    )
+                    .subcommand(clap::Command::new("tui").about("Opens an interactive view to browse, apply, revert, lock and sync migrations.")
+                        .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Statement timeout, e.g. 30s, 5m or 1h (a bare number is seconds)"))
+                    )
+                    .subcommand(clap::Command::new("graph").about("Renders the pre-chain of applied migrations, highlighting breaks and forks.")
+                        .arg(clap::Arg::new("format").short('f').long("format").required(false).value_parser(["ascii", "dot"]).default_value("ascii").help("Output format (dot renders a Graphviz graph)"))
+                    )
+                    .subcommand(clap::Command::new("export").about("Packages all local migrations and a manifest of the applied state into a bundle.")
+                        .arg(clap::Arg::new("out").short('o').long("out").required(true).help("Path of the bundle to write (.tar.gz)"))
+                    )
+                    .subcommand(clap::Command::new("import").about("Verifies a bundle and extracts its migrations into the migration directory, or converts the migrations of another tool.")
+                        .arg(clap::Arg::new("bundle").required(true).value_name("SOURCE").help("Path of the bundle to import, the changelog of --from liquibase, the migrations directory of --from sqlx or --from dbmate, or the offline SQL of --from alembic"))
+                        .arg(clap::Arg::new("from").long("from").default_value("bundle").value_parser(["bundle", "liquibase", "sqlx", "alembic", "dbmate"]).help("Format of the source; other tools' migrations are converted and those their tracking table lists are recorded as applied"))
+                        .arg(clap::Arg::new("history_table").long("history-table").help("Tracking table of the other tool (default: databasechangelog for Liquibase, _sqlx_migrations for sqlx, alembic_version for Alembic, schema_migrations for dbmate)").conflicts_with("no_history"))
+                        .arg(clap::Arg::new("no_history").long("no-history").num_args(0).help("Only convert the migrations, record none of them as applied"))
+                        .arg(clap::Arg::new("force").long("force").num_args(0).help("Overwrite local migrations whose content differs from the bundle"))
+                        .arg(clap::Arg::new("yes").short('y').long("yes").num_args(0).help("Skip confirmation prompts"))
+                    )
+                    .subcommand(clap::Command::new("seed").about("Runs seed data from the seeds directory, tracked separately from migrations.").subcommand_required(true)
+                        .subcommand(clap::Command::new("run").about("Runs all seeds that have not been run yet.")
+                            .arg(clap::Arg::new("profile").short('P').long("profile").required(false).help("Also run the seeds in seeds/<PROFILE>/"))
+                            .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Statement timeout, e.g. 30s, 5m or 1h (a bare number is seconds)"))
+                            .arg(clap::Arg::new("force").long("force").num_args(0).help("Run seeds again whose file changed since they were run"))
+                            .arg(clap::Arg::new("dry").long("dry").required(false).num_args(0).help("Execute seeds in a transaction but rollback instead of committing").conflicts_with("yes"))
+                            .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                        )
+                        .subcommand(clap::Command::new("list").about("Lists seeds and whether they have been run.")
+                            .arg(clap::Arg::new("profile").short('P').long("profile").required(false).help("Also list the seeds in seeds/<PROFILE>/"))
+                            .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                        )
+                        .subcommand(clap::Command::new("reset").about("Forgets that seeds have been run so they run again; seeded data is not removed.")
+                            .arg(clap::Arg::new("profile").short('P').long("profile").required(false).help("Also reset the seeds in seeds/<PROFILE>/"))
+                            .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                        )
+                    )
+                    .subcommand(clap::Command::new("meta").about("Validates and repairs the meta.toml files of local migrations.").subcommand_required(true)
+                        .subcommand(clap::Command::new("check").about("Reports unknown keys, values of the wrong type and missing meta.toml files.")
+                            .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                        )
+                        .subcommand(clap::Command::new("fix").about("Creates missing meta.toml files with defaults and renames or removes unknown keys.")
+                            .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                        )
+                    )
+                    .subcommand(clap::Command::new("bootstrap").about("Generates the first migration from the schema of an existing database.")
+                        .arg(clap::Arg::new("comment").short('c').long("comment").required(false).help("Comment for the generated migration"))
+                        .arg(clap::Arg::new("baseline").long("baseline").num_args(0).help("Record the generated migration as applied without executing it"))
+                        .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
+                    )
+                    .subcommand(clap::Command::new("wait").about("Waits until the database accepts queries.")
+                        .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false).default_value("60s").value_parser(crate::core::duration::parse_duration).help("Maximum time to wait (e.g. 500ms, 30s, 2m)"))
+                        .arg(clap::Arg::new("interval").short('i').long("interval").required(false).default_value("2s").value_parser(crate::core::duration::parse_duration).help("Time between connection attempts"))
+                    )
+                    .subcommand(clap::Command::new("ping").about("Checks the connection and the tracking tables without changing anything.")
+                        .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                    )
+                    .subcommand(clap::Command::new("console").about("Opens the native database client on the configured connection.")
+                        .arg(clap::Arg::new("client").long("client").required(false).help("Client executable to use instead of the default"))
+                        .arg(clap::Arg::new("args").num_args(0..).allow_hyphen_values(true).last(true).help("Additional arguments passed to the client (after --)"))
+                    )
+                    .subcommand(clap::Command::new("drift").about("Compares the live schema with the snapshot recorded at the last up/down.")
+                        .arg(clap::Arg::new("snapshot").short('s').long("snapshot").required(false).help("Snapshot file to compare against (defaults to the configured snapshot)"))
+                        .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
+                    )
+                    .subcommand(clap::Command::new("bench").about("Benchmarks pending migrations on scratch copies of the database.")
+                        .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Statement timeout, e.g. 30s, 5m or 1h (a bare number is seconds)"))
+                        .arg(clap::Arg::new("iterations").short('n').long("iterations").required(false).default_value("5").help("Number of times the pending migrations are executed"))
+                        .arg(clap::Arg::new("baseline").short('b').long("baseline").required(false).help("Baseline report (JSON) to compare the results against"))
+                        .arg(clap::Arg::new("save").short('s').long("save").required(false).help("Write the results as a baseline report (JSON) to this path"))
+                        .arg(clap::Arg::new("threshold").long("threshold").required(false).default_value("20").help("Allowed slowdown in percent compared to the baseline"))
+                        .arg(clap::Arg::new("output").short('o').long("output").required(false).value_parser(["human", "json"]).help("Output format"))
                     )
-                    .subcommand(clap::Command::new("diff").about("Shows pending migration operations without applying them."))
                     .subcommand(
                         clap::Command::new("apply")
                             .about("Applies or reverts a specific migration by ID.")
@@ -228,8 +670,10 @@ impl ClapArgumentLoader {
                             .subcommand(
                                 clap::Command::new("up")
                                     .about("Applies a specific migration.")
-                                    .arg(clap::Arg::new("id").help("Migration ID to apply").required(true))
-                                    .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false))
+                                    .arg(clap::Arg::new("id").num_args(1..).help("Migration IDs to apply; prompts for a selection when neither IDs nor a range are given in a terminal").required(false))
+                                    .arg(clap::Arg::new("from").long("from").required(false).conflicts_with("id").help("Oldest pending migration ID to apply (inclusive)"))
+                                    .arg(clap::Arg::new("to").long("to").required(false).conflicts_with("id").help("Newest pending migration ID to apply (inclusive)"))
+                                    .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Statement timeout, e.g. 30s, 5m or 1h (a bare number is seconds)"))
                                     .arg(clap::Arg::new("dry").long("dry").required(false).num_args(0).help("Execute migration in a transaction but rollback instead of committing").conflicts_with("yes"))
                                     .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
                                     .arg(clap::Arg::new("locked").long("lock").num_args(0).help("Mark applied migration as locked (cannot be reverted without --unlock)"))
@@ -237,12 +681,14 @@ impl ClapArgumentLoader {
                             .subcommand(
                                 clap::Command::new("down")
                                     .about("Reverts a specific migration.")
-                                    .arg(clap::Arg::new("id").help("Migration ID to revert").required(true))
-                                    .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false))
+                                    .arg(clap::Arg::new("id").num_args(1..).help("Migration IDs to revert; prompts for a selection when neither IDs nor a range are given in a terminal").required(false))
+                                    .arg(clap::Arg::new("from").long("from").required(false).conflicts_with("id").help("Oldest applied migration ID to revert (inclusive)"))
+                                    .arg(clap::Arg::new("to").long("to").required(false).conflicts_with("id").help("Newest applied migration ID to revert (inclusive)"))
+                                    .arg(clap::Arg::new("timeout").short('t').long("timeout").required(false).value_parser(crate::core::duration::parse_seconds).help("Statement timeout, e.g. 30s, 5m or 1h (a bare number is seconds)"))
                                     .arg(clap::Arg::new("remote").short('r').long("remote").required(false).num_args(0))
                                     .arg(clap::Arg::new("dry").long("dry").required(false).num_args(0).help("Execute migration in a transaction but rollback instead of committing").conflicts_with("yes"))
                                     .arg(clap::Arg::new("yes").short('y').long("yes").required(false).num_args(0).help("Skip confirmation prompts"))
-                                    .arg(clap::Arg::new("locked").long("lock").num_args(0).help("Mark applied migration as locked (cannot be reverted without --unlock)"))
+                                    .arg(clap::Arg::new("unlock").long("unlock").num_args(0).help("Allow reverting a locked migration"))
                             )
                     );
                 subsystem = subsystem.subcommand(sql);
@@ -262,6 +708,28 @@ impl ClapArgumentLoader {
         } else {
             Privilege::Normal
         };
+        let mut user = crate::config::UserConfig::load()?;
+        if let Some(policy) = std::env::var(crate::config::NON_INTERACTIVE_ENV).ok().filter(|p| !p.is_empty()) {
+            user.ui.non_interactive = policy.parse()?;
+        }
+        user.ui.verbosity = match (command.get_flag("quiet"), command.get_flag("verbose")) {
+            | (true, _) => crate::core::output::Verbosity::Quiet,
+            | (_, true) => crate::core::output::Verbosity::Verbose,
+            | _ => crate::core::output::Verbosity::Normal,
+        };
+        // Commands with an output format of their own default to the global one
+        let output = command.get_one::<String>("output").map(|s| s.as_str()).unwrap_or("human");
+        user.ui.json = output == "json";
+        if command.get_flag("no-pager") {
+            user.ui.pager = false;
+        }
+        user.ui.color = match command.get_one::<String>("color").map(|s| s.as_str()) {
+            | Some("always") => true,
+            | Some("never") => false,
+            // See https://no-color.org, any non-empty value turns colors off
+            | _ => user.ui.color && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && std::io::IsTerminal::is_terminal(&std::io::stdout()),
+        };
+        user.ui.plain |= command.get_flag("plain");
 
         let cmd = if let Some(subc) = command.subcommand_matches("man") {
             Command::Manual {
@@ -283,44 +751,108 @@ impl ClapArgumentLoader {
             {
                 if let Some(postgres_subc) = subsystem_subc.subcommand_matches("postgres") {
                     let path = Self::get_absolute_path(postgres_subc, "path")?;
-                    let (pg_cfg, postgres_cmd) = if let Some(config_subc) = postgres_subc.subcommand_matches("config") {
+                    let (targets, postgres_cmd) = if let Some(config_subc) = postgres_subc.subcommand_matches("config") {
                         if let Some(init_subc) = config_subc.subcommand_matches("init") {
                             let conn = init_subc.get_one::<String>("conn").unwrap().clone();
                             (
-                                crate::subsystem::postgres::config::SubsystemPostgres::default(),
+                                vec![(None, path.clone(), crate::subsystem::postgres::config::SubsystemPostgres::default())],
                                 crate::subsystem::postgres::commands::Command::Config(
                                     crate::subsystem::postgres::commands::ConfigCommand::Init { connection: conn }
                                 )
                             )
+                        } else if let Some(upgrade_subc) = config_subc.subcommand_matches("upgrade") {
+                            (
+                                vec![(None, path.clone(), crate::subsystem::postgres::config::SubsystemPostgres::default())],
+                                crate::subsystem::postgres::commands::Command::Config(
+                                    crate::subsystem::postgres::commands::ConfigCommand::Upgrade { dry_run: upgrade_subc.get_flag("dry_run") }
+                                )
+                            )
                         } else { unreachable!() }
                     } else {
-                        let cfg: crate::config::Config = toml::from_str(&std::fs::read_to_string(&path)?)?;
-                        // Validate CLI version against config requirement
-                        crate::config::WithVersion { version: cfg.version.clone() }
-                            .validate(env!("CARGO_PKG_VERSION"))?;
-                        #[cfg(feature = "sub+sqlite")]
-                        let pg_cfg = match cfg.subsystem { crate::config::Subsystem::Postgres(c) => c, _ => anyhow::bail!("config is not postgres"), };
-                        #[cfg(not(feature = "sub+sqlite"))]
-                        let pg_cfg = match cfg.subsystem { crate::config::Subsystem::Postgres(c) => c };
+                        let profile = postgres_subc.get_one::<String>("profile").cloned().or_else(|| std::env::var(crate::config::PROFILE_ENV).ok().filter(|p| !p.is_empty()));
+                        let all_targets = postgres_subc.get_flag("all_targets");
+                        let parallel = postgres_subc.get_one::<usize>("parallel").copied();
+                        let conn = postgres_subc.get_one::<String>("conn").cloned().or_else(|| std::env::var(crate::config::CONNECTION_ENV).ok().filter(|c| !c.is_empty()));
+                        if conn.is_some() && all_targets {
+                            anyhow::bail!("a connection override cannot be combined with --all-targets");
+                        }
+                        let mut targets = Vec::new();
+                        for target in crate::config::Config::load_targets(&path, profile.as_deref(), postgres_subc.get_one::<String>("target").map(|t| t.as_str()), all_targets, &user)? {
+                            // Validate CLI version against config requirement
+                            crate::config::WithVersion { version: target.config.version.clone() }
+                                .validate(env!("CARGO_PKG_VERSION"))?;
+                            #[cfg(feature = "sub+sqlite")]
+                            let mut pg_cfg = match (target.config.subsystem, &target.name) {
+                                | (crate::config::Subsystem::Postgres(c), _) => c,
+                                // --all-targets runs every target of the invoked subsystem
+                                | (_, Some(_)) if all_targets => continue,
+                                | (_, Some(name)) => anyhow::bail!("target {} is not a postgres target", name),
+                                | (_, None) => anyhow::bail!("config is not postgres"),
+                            };
+                            #[cfg(not(feature = "sub+sqlite"))]
+                            let mut pg_cfg = match target.config.subsystem { crate::config::Subsystem::Postgres(c) => c };
+                            if let Some(lock_timeout) = postgres_subc.get_one::<u64>("lock_timeout") {
+                                pg_cfg.lock_timeout = Some(*lock_timeout);
+                            }
+                            if let Some(mode) = postgres_subc.get_one::<String>("lock_check") {
+                                let check = pg_cfg.lock_check.get_or_insert_with(Default::default);
+                                check.mode = match mode.as_str() {
+                                    | "wait" => crate::subsystem::postgres::config::LockCheckMode::Wait,
+                                    | _ => crate::subsystem::postgres::config::LockCheckMode::Warn,
+                                };
+                            }
+                            pg_cfg.profile = target.profile;
+                            if let Some(schemas) = postgres_subc.get_many::<String>("schemas") {
+                                pg_cfg.tenants = Some(crate::subsystem::postgres::config::TenantsConfig { schemas: schemas.cloned().collect(), query: None, parallel: None });
+                            }
+                            if let (Some(tenants), Some(parallel)) = (pg_cfg.tenants.as_mut(), parallel) {
+                                tenants.parallel = Some(parallel);
+                            }
+                            pg_cfg.no_run_lock = postgres_subc.get_flag("no_lock");
+                            if let Some(run_lock_timeout) = postgres_subc.get_one::<u64>("run_lock_timeout") {
+                                pg_cfg.run_lock_timeout = Some(*run_lock_timeout);
+                            }
+                            if let Some(conn) = &conn {
+                                pg_cfg.connection = crate::config::DataSource::Static(conn.clone());
+                            }
+                            targets.push((target.name, target.path, pg_cfg));
+                        }
+                        if targets.is_empty() {
+                            anyhow::bail!("no postgres targets defined in {}", path.display());
+                        }
                         let postgres_cmd = if let Some(_) = postgres_subc.subcommand_matches("init") {
                             crate::subsystem::postgres::commands::Command::Init
                         } else if let Some(new_subc) = postgres_subc.subcommand_matches("new") {
                             crate::subsystem::postgres::commands::Command::New { 
                                 comment: new_subc.get_one::<String>("comment").cloned(),
-                                locked: new_subc.get_flag("locked")
+                                locked: new_subc.get_flag("locked"),
+                                up: new_subc.get_one::<String>("up").map(std::path::PathBuf::from),
+                                down: new_subc.get_one::<String>("down").map(std::path::PathBuf::from),
+                                stdin: new_subc.get_flag("stdin"),
+                                auto_down: new_subc.get_flag("auto_down"),
+                                template: new_subc.get_one::<String>("template").cloned(),
                             }
                         } else if let Some(up_subc) = postgres_subc.subcommand_matches("up") {
                             crate::subsystem::postgres::commands::Command::Up {
-                                timeout: up_subc.get_one::<String>("timeout").map(|s| s.parse::<u64>().unwrap()),
+                                timeout: up_subc.get_one::<u64>("timeout").copied(),
                                 count: up_subc.get_one::<String>("count").map(|s| s.parse::<usize>().unwrap()),
+                                tags: up_subc.get_many::<String>("tag").map(|v| v.cloned().collect()).unwrap_or_default(),
                                 diff: up_subc.get_flag("diff"),
                                 dry: up_subc.get_flag("dry"),
                                 yes: up_subc.get_flag("yes"),
+                                single_transaction: up_subc.get_flag("single-transaction"),
+                                keep_going: up_subc.get_flag("keep-going"),
+                                shadow: up_subc.get_flag("shadow"),
+                                assert_idempotent: up_subc.get_flag("assert-idempotent"),
+                                resume: up_subc.get_flag("resume"),
+                                check: up_subc.get_flag("check"),
                             }
                         } else if let Some(down_subc) = postgres_subc.subcommand_matches("down") {
                             crate::subsystem::postgres::commands::Command::Down {
-                                timeout: down_subc.get_one::<String>("timeout").map(|s| s.parse::<u64>().unwrap()),
-                                count: down_subc.get_one::<String>("count").unwrap().parse::<usize>().unwrap(),
+                                timeout: down_subc.get_one::<u64>("timeout").copied(),
+                                count: down_subc.get_one::<String>("count").map(|s| s.parse::<usize>().unwrap()),
+                                to: down_subc.get_one::<String>("to").cloned(),
+                                tags: down_subc.get_many::<String>("tag").map(|v| v.cloned().collect()).unwrap_or_default(),
                                 remote: down_subc.get_flag("remote"),
                                 diff: down_subc.get_flag("diff"),
                                 dry: down_subc.get_flag("dry"),
@@ -328,35 +860,305 @@ impl ClapArgumentLoader {
                                 unlock: down_subc.get_flag("unlock"),
                             }
                         } else if let Some(list_subc) = postgres_subc.subcommand_matches("list") {
-                            let out = match list_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or("human") {
+                            let out = match list_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
                                 "human" => crate::subsystem::postgres::commands::Output::Human,
                                 "json" => crate::subsystem::postgres::commands::Output::Json,
                                 _ => crate::subsystem::postgres::commands::Output::Human,
                             };
-                            crate::subsystem::postgres::commands::Command::List { output: out }
+                            crate::subsystem::postgres::commands::Command::List {
+                                tags: list_subc.get_many::<String>("tag").map(|v| v.cloned().collect()).unwrap_or_default(),
+                                output: out,
+                                check: list_subc.get_flag("check"),
+                                wide: list_subc.get_flag("wide"),
+                            }
                         } else if let Some(history_subc) = postgres_subc.subcommand_matches("history") {
                             let history_cmd = if let Some(_) = history_subc.subcommand_matches("sync") {
                                 crate::subsystem::postgres::commands::HistoryCommand::Sync
                             } else if let Some(_) = history_subc.subcommand_matches("fix") {
                                 crate::subsystem::postgres::commands::HistoryCommand::Fix
+                            } else if let Some(verify_subc) = history_subc.subcommand_matches("verify") {
+                                let out = match verify_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                    "json" => crate::subsystem::postgres::commands::Output::Json,
+                                    _ => crate::subsystem::postgres::commands::Output::Human,
+                                };
+                                crate::subsystem::postgres::commands::HistoryCommand::Verify {
+                                    fix: verify_subc.get_flag("fix"),
+                                    yes: verify_subc.get_flag("yes"),
+                                    output: out,
+                                }
                             } else {
                                 unreachable!();
                             };
                             crate::subsystem::postgres::commands::Command::History(history_cmd)
-                        } else if let Some(_) = postgres_subc.subcommand_matches("diff") {
-                            crate::subsystem::postgres::commands::Command::Diff
+                        } else if let Some(diff_subc) = postgres_subc.subcommand_matches("diff") {
+                            crate::subsystem::postgres::commands::Command::Diff {
+                                down: diff_subc.get_flag("down"),
+                                count: diff_subc.get_one::<usize>("count").copied(),
+                                id: diff_subc.get_one::<String>("id").cloned(),
+                                output: match diff_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                    "json" => crate::subsystem::postgres::commands::Output::Json,
+                                    _ => crate::subsystem::postgres::commands::Output::Human,
+                                },
+                            }
+                        } else if let Some(verify_subc) = postgres_subc.subcommand_matches("verify") {
+                            let out = match verify_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                "json" => crate::subsystem::postgres::commands::Output::Json,
+                                _ => crate::subsystem::postgres::commands::Output::Human,
+                            };
+                            crate::subsystem::postgres::commands::Command::Verify { output: out }
+                        } else if let Some(squash_subc) = postgres_subc.subcommand_matches("squash") {
+                            crate::subsystem::postgres::commands::Command::Squash {
+                                from: squash_subc.get_one::<String>("from").unwrap().clone(),
+                                to: squash_subc.get_one::<String>("to").unwrap().clone(),
+                                comment: squash_subc.get_one::<String>("comment").cloned(),
+                                yes: squash_subc.get_flag("yes"),
+                            }
+                        } else if let Some(baseline_subc) = postgres_subc.subcommand_matches("baseline") {
+                            crate::subsystem::postgres::commands::Command::Baseline {
+                                id: baseline_subc.get_one::<String>("id").unwrap().clone(),
+                                yes: baseline_subc.get_flag("yes"),
+                            }
+                        } else if let Some(log_subc) = postgres_subc.subcommand_matches("log") {
+                            let log_cmd = if let Some(list_subc) = log_subc.subcommand_matches("list") {
+                                let out = match list_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                    "json" => crate::subsystem::postgres::commands::Output::Json,
+                                    _ => crate::subsystem::postgres::commands::Output::Human,
+                                };
+                                crate::subsystem::postgres::commands::LogCommand::List {
+                                    migration_id: list_subc.get_one::<String>("migration").cloned(),
+                                    operation: list_subc.get_one::<String>("operation").cloned(),
+                                    since: list_subc.get_one::<String>("since").cloned(),
+                                    until: list_subc.get_one::<String>("until").cloned(),
+                                    limit: list_subc.get_one::<usize>("limit").copied(),
+                                    output: out,
+                                }
+                            } else if let Some(show_subc) = log_subc.subcommand_matches("show") {
+                                let out = match show_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                    "json" => crate::subsystem::postgres::commands::Output::Json,
+                                    _ => crate::subsystem::postgres::commands::Output::Human,
+                                };
+                                crate::subsystem::postgres::commands::LogCommand::Show {
+                                    id: show_subc.get_one::<String>("id").unwrap().clone(),
+                                    output: out,
+                                }
+                            } else if let Some(export_subc) = log_subc.subcommand_matches("export") {
+                                let format = match export_subc.get_one::<String>("format").map(|s| s.as_str()).unwrap_or("csv") {
+                                    "json" => crate::subsystem::postgres::commands::LogExportFormat::Json,
+                                    _ => crate::subsystem::postgres::commands::LogExportFormat::Csv,
+                                };
+                                crate::subsystem::postgres::commands::LogCommand::Export {
+                                    format,
+                                    since: export_subc.get_one::<String>("since").cloned(),
+                                    until: export_subc.get_one::<String>("until").cloned(),
+                                    out: export_subc.get_one::<String>("out").map(PathBuf::from),
+                                }
+                            } else {
+                                unreachable!();
+                            };
+                            crate::subsystem::postgres::commands::Command::Log(log_cmd)
+                        } else if let Some(rename_subc) = postgres_subc.subcommand_matches("rename") {
+                            crate::subsystem::postgres::commands::Command::Rename {
+                                id: rename_subc.get_one::<String>("id").unwrap().clone(),
+                                new_id: rename_subc.get_one::<String>("new_id").unwrap().clone(),
+                                comment: rename_subc.get_one::<String>("comment").cloned(),
+                                yes: rename_subc.get_flag("yes"),
+                            }
+                        } else if let Some(lock_subc) = postgres_subc.subcommand_matches("lock") {
+                            crate::subsystem::postgres::commands::Command::Lock {
+                                id: lock_subc.get_one::<String>("id").unwrap().clone(),
+                            }
+                        } else if let Some(unlock_subc) = postgres_subc.subcommand_matches("unlock") {
+                            crate::subsystem::postgres::commands::Command::Unlock {
+                                id: unlock_subc.get_one::<String>("id").unwrap().clone(),
+                            }
+                        } else if let Some(rm_subc) = postgres_subc.subcommand_matches("rm") {
+                            crate::subsystem::postgres::commands::Command::Remove {
+                                id: rm_subc.get_one::<String>("id").unwrap().clone(),
+                                force: rm_subc.get_flag("force"),
+                                also_remote: rm_subc.get_flag("also_remote"),
+                                yes: rm_subc.get_flag("yes"),
+                            }
+                        } else if let Some(prune_subc) = postgres_subc.subcommand_matches("prune") {
+                            crate::subsystem::postgres::commands::Command::Prune {
+                                yes: prune_subc.get_flag("yes"),
+                            }
+                        } else if let Some(repair_subc) = postgres_subc.subcommand_matches("repair") {
+                            crate::subsystem::postgres::commands::Command::Repair {
+                                id: repair_subc.get_one::<String>("id").unwrap().clone(),
+                                up: repair_subc.get_flag("up"),
+                                down: repair_subc.get_flag("down"),
+                                yes: repair_subc.get_flag("yes"),
+                            }
+                        } else if let Some(snapshot_subc) = postgres_subc.subcommand_matches("snapshot") {
+                            crate::subsystem::postgres::commands::Command::Snapshot {
+                                out: snapshot_subc.get_one::<String>("out").map(std::path::PathBuf::from),
+                            }
+                        } else if let Some(test_subc) = postgres_subc.subcommand_matches("test") {
+                            crate::subsystem::postgres::commands::Command::Test {
+                                timeout: test_subc.get_one::<u64>("timeout").copied(),
+                            }
+                        } else if let Some(generate_subc) = postgres_subc.subcommand_matches("generate") {
+                            crate::subsystem::postgres::commands::Command::Generate {
+                                schema: generate_subc.get_one::<String>("schema").map(std::path::PathBuf::from),
+                                from_db: generate_subc.get_flag("from_db"),
+                                comment: generate_subc.get_one::<String>("comment").cloned(),
+                                yes: generate_subc.get_flag("yes"),
+                            }
+                        } else if let Some(plan_subc) = postgres_subc.subcommand_matches("plan") {
+                            crate::subsystem::postgres::commands::Command::Plan {
+                                count: plan_subc.get_one::<usize>("count").copied(),
+                                tags: plan_subc.get_many::<String>("tag").map(|v| v.cloned().collect()).unwrap_or_default(),
+                                output: match plan_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                    "json" => crate::subsystem::postgres::commands::Output::Json,
+                                    _ => crate::subsystem::postgres::commands::Output::Human,
+                                },
+                            }
+                        } else if let Some(lint_subc) = postgres_subc.subcommand_matches("lint") {
+                            let out = match lint_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                "json" => crate::subsystem::postgres::commands::Output::Json,
+                                _ => crate::subsystem::postgres::commands::Output::Human,
+                            };
+                            crate::subsystem::postgres::commands::Command::Lint {
+                                all: lint_subc.get_flag("all"),
+                                strict: lint_subc.get_flag("strict"),
+                                large_table_rows: *lint_subc.get_one::<i64>("large_table_rows").unwrap(),
+                                output: out,
+                            }
+                        } else if let Some(fmt_subc) = postgres_subc.subcommand_matches("fmt") {
+                            crate::subsystem::postgres::commands::Command::Fmt {
+                                check: fmt_subc.get_flag("check"),
+                                all: fmt_subc.get_flag("all"),
+                            }
+                        } else if let Some(tui_subc) = postgres_subc.subcommand_matches("tui") {
+                            crate::subsystem::postgres::commands::Command::Tui {
+                                timeout: tui_subc.get_one::<u64>("timeout").copied(),
+                            }
+                        } else if let Some(graph_subc) = postgres_subc.subcommand_matches("graph") {
+                            let format = match graph_subc.get_one::<String>("format").map(|s| s.as_str()).unwrap_or("ascii") {
+                                "dot" => crate::subsystem::postgres::commands::GraphFormat::Dot,
+                                _ => crate::subsystem::postgres::commands::GraphFormat::Ascii,
+                            };
+                            crate::subsystem::postgres::commands::Command::Graph { format }
+                        } else if let Some(export_subc) = postgres_subc.subcommand_matches("export") {
+                            crate::subsystem::postgres::commands::Command::Export {
+                                out: std::path::PathBuf::from(export_subc.get_one::<String>("out").unwrap()),
+                            }
+                        } else if let Some(import_subc) = postgres_subc.subcommand_matches("import") {
+                            crate::subsystem::postgres::commands::Command::Import {
+                                bundle: std::path::PathBuf::from(import_subc.get_one::<String>("bundle").unwrap()),
+                                from: match import_subc.get_one::<String>("from").map(|s| s.as_str()) {
+                                    Some("liquibase") => crate::subsystem::postgres::commands::ImportFormat::Liquibase,
+                                    Some("sqlx") => crate::subsystem::postgres::commands::ImportFormat::Sqlx,
+                                    Some("alembic") => crate::subsystem::postgres::commands::ImportFormat::Alembic,
+                                    Some("dbmate") => crate::subsystem::postgres::commands::ImportFormat::Dbmate,
+                                    _ => crate::subsystem::postgres::commands::ImportFormat::Bundle,
+                                },
+                                history_table: import_subc.get_one::<String>("history_table").cloned(),
+                                history: !import_subc.get_flag("no_history"),
+                                force: import_subc.get_flag("force"),
+                                yes: import_subc.get_flag("yes"),
+                            }
+                        } else if let Some(seed_subc) = postgres_subc.subcommand_matches("seed") {
+                            let seed_cmd = if let Some(run_subc) = seed_subc.subcommand_matches("run") {
+                                crate::subsystem::postgres::commands::SeedCommand::Run {
+                                    profile: run_subc.get_one::<String>("profile").cloned(),
+                                    allowed: None,
+                                    timeout: run_subc.get_one::<u64>("timeout").copied(),
+                                    force: run_subc.get_flag("force"),
+                                    dry: run_subc.get_flag("dry"),
+                                    yes: run_subc.get_flag("yes"),
+                                }
+                            } else if let Some(list_subc) = seed_subc.subcommand_matches("list") {
+                                let out = match list_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                    "json" => crate::subsystem::postgres::commands::Output::Json,
+                                    _ => crate::subsystem::postgres::commands::Output::Human,
+                                };
+                                crate::subsystem::postgres::commands::SeedCommand::List {
+                                    profile: list_subc.get_one::<String>("profile").cloned(),
+                                    output: out,
+                                }
+                            } else if let Some(reset_subc) = seed_subc.subcommand_matches("reset") {
+                                crate::subsystem::postgres::commands::SeedCommand::Reset {
+                                    profile: reset_subc.get_one::<String>("profile").cloned(),
+                                    yes: reset_subc.get_flag("yes"),
+                                }
+                            } else {
+                                unreachable!();
+                            };
+                            crate::subsystem::postgres::commands::Command::Seed(seed_cmd)
+                        } else if let Some(meta_subc) = postgres_subc.subcommand_matches("meta") {
+                            let meta_cmd = if let Some(check_subc) = meta_subc.subcommand_matches("check") {
+                                let out = match check_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                    "json" => crate::subsystem::postgres::commands::Output::Json,
+                                    _ => crate::subsystem::postgres::commands::Output::Human,
+                                };
+                                crate::subsystem::postgres::commands::MetaCommand::Check { output: out }
+                            } else if let Some(fix_subc) = meta_subc.subcommand_matches("fix") {
+                                crate::subsystem::postgres::commands::MetaCommand::Fix { yes: fix_subc.get_flag("yes") }
+                            } else {
+                                unreachable!();
+                            };
+                            crate::subsystem::postgres::commands::Command::Meta(meta_cmd)
+                        } else if let Some(bootstrap_subc) = postgres_subc.subcommand_matches("bootstrap") {
+                            crate::subsystem::postgres::commands::Command::Bootstrap {
+                                comment: bootstrap_subc.get_one::<String>("comment").cloned(),
+                                baseline: bootstrap_subc.get_flag("baseline"),
+                                yes: bootstrap_subc.get_flag("yes"),
+                            }
+                        } else if let Some(wait_subc) = postgres_subc.subcommand_matches("wait") {
+                            crate::subsystem::postgres::commands::Command::Wait {
+                                timeout: *wait_subc.get_one::<std::time::Duration>("timeout").unwrap(),
+                                interval: *wait_subc.get_one::<std::time::Duration>("interval").unwrap(),
+                            }
+                        } else if let Some(ping_subc) = postgres_subc.subcommand_matches("ping") {
+                            let out = match ping_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                "json" => crate::subsystem::postgres::commands::Output::Json,
+                                _ => crate::subsystem::postgres::commands::Output::Human,
+                            };
+                            crate::subsystem::postgres::commands::Command::Ping { output: out }
+                        } else if let Some(console_subc) = postgres_subc.subcommand_matches("console") {
+                            crate::subsystem::postgres::commands::Command::Console {
+                                client: console_subc.get_one::<String>("client").cloned(),
+                                args: console_subc.get_many::<String>("args").map(|v| v.cloned().collect()).unwrap_or_default(),
+                            }
+                        } else if let Some(drift_subc) = postgres_subc.subcommand_matches("drift") {
+                            let out = match drift_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                "json" => crate::subsystem::postgres::commands::Output::Json,
+                                _ => crate::subsystem::postgres::commands::Output::Human,
+                            };
+                            crate::subsystem::postgres::commands::Command::Drift {
+                                snapshot: drift_subc.get_one::<String>("snapshot").map(std::path::PathBuf::from),
+                                output: out,
+                            }
+                        } else if let Some(bench_subc) = postgres_subc.subcommand_matches("bench") {
+                            let out = match bench_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                "json" => crate::subsystem::postgres::commands::Output::Json,
+                                _ => crate::subsystem::postgres::commands::Output::Human,
+                            };
+                            crate::subsystem::postgres::commands::Command::Bench {
+                                timeout: bench_subc.get_one::<u64>("timeout").copied(),
+                                iterations: bench_subc.get_one::<String>("iterations").unwrap().parse::<usize>()?,
+                                baseline: bench_subc.get_one::<String>("baseline").map(PathBuf::from),
+                                save: bench_subc.get_one::<String>("save").map(PathBuf::from),
+                                threshold: bench_subc.get_one::<String>("threshold").unwrap().parse::<f64>()?,
+                                output: out,
+                            }
                         } else if let Some(apply_subc) = postgres_subc.subcommand_matches("apply") {
                             if let Some(up_subc) = apply_subc.subcommand_matches("up") {
                                 crate::subsystem::postgres::commands::Command::Apply(crate::subsystem::postgres::commands::MigrationApply::Up {
-                                    id: up_subc.get_one::<String>("id").unwrap().clone(),
-                                    timeout: up_subc.get_one::<String>("timeout").map(|s| s.parse::<u64>().unwrap()),
+                                    ids: up_subc.get_many::<String>("id").map(|v| v.cloned().collect()).unwrap_or_default(),
+                                    from: up_subc.get_one::<String>("from").cloned(),
+                                    to: up_subc.get_one::<String>("to").cloned(),
+                                    timeout: up_subc.get_one::<u64>("timeout").copied(),
                                     dry: up_subc.get_flag("dry"),
                                     yes: up_subc.get_flag("yes"),
                                 })
                             } else if let Some(down_subc) = apply_subc.subcommand_matches("down") {
                                 crate::subsystem::postgres::commands::Command::Apply(crate::subsystem::postgres::commands::MigrationApply::Down {
-                                    id: down_subc.get_one::<String>("id").unwrap().clone(),
-                                    timeout: down_subc.get_one::<String>("timeout").map(|s| s.parse::<u64>().unwrap()),
+                                    ids: down_subc.get_many::<String>("id").map(|v| v.cloned().collect()).unwrap_or_default(),
+                                    from: down_subc.get_one::<String>("from").cloned(),
+                                    to: down_subc.get_one::<String>("to").cloned(),
+                                    timeout: down_subc.get_one::<u64>("timeout").copied(),
                                     remote: down_subc.get_flag("remote"),
                                     dry: down_subc.get_flag("dry"),
                                     yes: down_subc.get_flag("yes"),
@@ -368,9 +1170,41 @@ impl ClapArgumentLoader {
                         } else {
                             unreachable!();
                         };
-                        (pg_cfg, postgres_cmd)
+                        (targets, postgres_cmd)
                     };
-                    return Ok(CallArgs { privileges, command: Command::Subsystem(Subsystem::Postgres { path, config: pg_cfg, command: postgres_cmd }) });
+                    let force_protected = postgres_subc.get_flag("force_protected");
+                    let override_window = postgres_subc.get_flag("override_window");
+                    let mut subsystems = Vec::new();
+                    for (name, path, config) in targets {
+                        let mut command = postgres_cmd.clone();
+                        let config_name = name.as_ref().map(|name| format!("target {}", name)).unwrap_or_else(|| path.display().to_string());
+                        if let (Some(window), Some(refused), false) = (&config.window, command.window_name(), override_window) {
+                            window.check().with_context(|| format!("{} refuses {}", config_name, refused))?;
+                        }
+                        if config.protected {
+                            let confirms = match command.protected_name() {
+                                | Some(refused) if !force_protected => {
+                                    anyhow::bail!("{} is protected and refuses {}; pass --force-protected to run it anyway", config_name, refused);
+                                },
+                                | Some(_) => false,
+                                | None if force_protected => command.require_confirmation(),
+                                | None => {
+                                    command.restrict_seeds(&config.allowed_seeds);
+                                    command.require_confirmation()
+                                },
+                            };
+                            if confirms && user.ui.non_interactive == crate::config::NonInteractive::Yes && !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+                                anyhow::bail!("{} is protected and needs an interactive confirmation, non_interactive = \"yes\" does not apply", config_name);
+                            }
+                        }
+                        subsystems.push((name, Subsystem::Postgres { path, config, command }));
+                    }
+                    let targets = subsystems;
+                    let parallel = postgres_subc.get_one::<usize>("parallel").copied().unwrap_or(1);
+                    let metrics = Self::metrics_output(postgres_subc)?;
+                    let report = Self::report_path(postgres_subc);
+                    let events = postgres_subc.get_one::<String>("events").cloned();
+                    return Ok(CallArgs { privileges, command: Command::Subsystem { targets, parallel, metrics, report, events }, user });
                 }
             }
             // Try sqlite branch if feature enabled
@@ -378,44 +1212,91 @@ impl ClapArgumentLoader {
             {
                 if let Some(sqlite_subc) = subsystem_subc.subcommand_matches("sqlite") {
                     let path = Self::get_absolute_path(sqlite_subc, "path")?;
-                    let (sql_cfg, sqlite_cmd) = if let Some(config_subc) = sqlite_subc.subcommand_matches("config") {
+                    let (targets, sqlite_cmd) = if let Some(config_subc) = sqlite_subc.subcommand_matches("config") {
                         if let Some(init_subc) = config_subc.subcommand_matches("init") {
                             let db = init_subc.get_one::<String>("db").unwrap().clone();
                             (
-                                crate::subsystem::sqlite::config::SubsystemSqlite::default(),
+                                vec![(None, path.clone(), crate::subsystem::sqlite::config::SubsystemSqlite::default())],
                                 crate::subsystem::sqlite::commands::Command::Config(
                                     crate::subsystem::sqlite::commands::ConfigCommand::Init { path: db }
                                 )
                             )
+                        } else if let Some(upgrade_subc) = config_subc.subcommand_matches("upgrade") {
+                            (
+                                vec![(None, path.clone(), crate::subsystem::sqlite::config::SubsystemSqlite::default())],
+                                crate::subsystem::sqlite::commands::Command::Config(
+                                    crate::subsystem::sqlite::commands::ConfigCommand::Upgrade { dry_run: upgrade_subc.get_flag("dry_run") }
+                                )
+                            )
                         } else { unreachable!() }
                     } else {
-                        let cfg: crate::config::Config = toml::from_str(&std::fs::read_to_string(&path)?)?;
-                        // Validate CLI version against config requirement
-                        crate::config::WithVersion { version: cfg.version.clone() }
-                            .validate(env!("CARGO_PKG_VERSION"))?;
-                        #[cfg(feature = "sub+postgres")]
-                        let sql_cfg = match cfg.subsystem { crate::config::Subsystem::Sqlite(c) => c, _ => anyhow::bail!("config is not sqlite"), };
-                        #[cfg(not(feature = "sub+postgres"))]
-                        let sql_cfg = match cfg.subsystem { crate::config::Subsystem::Sqlite(c) => c };
+                        let profile = sqlite_subc.get_one::<String>("profile").cloned().or_else(|| std::env::var(crate::config::PROFILE_ENV).ok().filter(|p| !p.is_empty()));
+                        let all_targets = sqlite_subc.get_flag("all_targets");
+                        let conn = sqlite_subc.get_one::<String>("conn").cloned().or_else(|| std::env::var(crate::config::CONNECTION_ENV).ok().filter(|c| !c.is_empty()));
+                        if conn.is_some() && all_targets {
+                            anyhow::bail!("a connection override cannot be combined with --all-targets");
+                        }
+                        let mut targets = Vec::new();
+                        for target in crate::config::Config::load_targets(&path, profile.as_deref(), sqlite_subc.get_one::<String>("target").map(|t| t.as_str()), all_targets, &user)? {
+                            // Validate CLI version against config requirement
+                            crate::config::WithVersion { version: target.config.version.clone() }
+                                .validate(env!("CARGO_PKG_VERSION"))?;
+                            #[cfg(feature = "sub+postgres")]
+                            let mut sql_cfg = match (target.config.subsystem, &target.name) {
+                                | (crate::config::Subsystem::Sqlite(c), _) => c,
+                                // --all-targets runs every target of the invoked subsystem
+                                | (_, Some(_)) if all_targets => continue,
+                                | (_, Some(name)) => anyhow::bail!("target {} is not a sqlite target", name),
+                                | (_, None) => anyhow::bail!("config is not sqlite"),
+                            };
+                            #[cfg(not(feature = "sub+postgres"))]
+                            let mut sql_cfg = match target.config.subsystem { crate::config::Subsystem::Sqlite(c) => c };
+                            sql_cfg.profile = target.profile;
+                            sql_cfg.no_run_lock = sqlite_subc.get_flag("no_lock");
+                            if let Some(run_lock_timeout) = sqlite_subc.get_one::<u64>("run_lock_timeout") {
+                                sql_cfg.run_lock_timeout = Some(*run_lock_timeout);
+                            }
+                            if let Some(conn) = &conn {
+                                sql_cfg.connection = crate::config::DataSource::Static(conn.clone());
+                            }
+                            targets.push((target.name, target.path, sql_cfg));
+                        }
+                        if targets.is_empty() {
+                            anyhow::bail!("no sqlite targets defined in {}", path.display());
+                        }
                         let sqlite_cmd = if let Some(_) = sqlite_subc.subcommand_matches("init") {
                             crate::subsystem::sqlite::commands::Command::Init
                         } else if let Some(new_subc) = sqlite_subc.subcommand_matches("new") {
                             crate::subsystem::sqlite::commands::Command::New { 
                                 comment: new_subc.get_one::<String>("comment").cloned(),
-                                locked: new_subc.get_flag("locked")
+                                locked: new_subc.get_flag("locked"),
+                                up: new_subc.get_one::<String>("up").map(std::path::PathBuf::from),
+                                down: new_subc.get_one::<String>("down").map(std::path::PathBuf::from),
+                                stdin: new_subc.get_flag("stdin"),
+                                auto_down: new_subc.get_flag("auto_down"),
+                                template: new_subc.get_one::<String>("template").cloned(),
                             }
                         } else if let Some(up_subc) = sqlite_subc.subcommand_matches("up") {
                             crate::subsystem::sqlite::commands::Command::Up {
-                                timeout: up_subc.get_one::<String>("timeout").map(|s| s.parse::<u64>().unwrap()),
+                                timeout: up_subc.get_one::<u64>("timeout").copied(),
                                 count: up_subc.get_one::<String>("count").map(|s| s.parse::<usize>().unwrap()),
+                                tags: up_subc.get_many::<String>("tag").map(|v| v.cloned().collect()).unwrap_or_default(),
                                 diff: up_subc.get_flag("diff"),
                                 dry: up_subc.get_flag("dry"),
                                 yes: up_subc.get_flag("yes"),
+                                single_transaction: up_subc.get_flag("single-transaction"),
+                                keep_going: up_subc.get_flag("keep-going"),
+                                shadow: up_subc.get_flag("shadow"),
+                                assert_idempotent: up_subc.get_flag("assert-idempotent"),
+                                resume: up_subc.get_flag("resume"),
+                                check: up_subc.get_flag("check"),
                             }
                         } else if let Some(down_subc) = sqlite_subc.subcommand_matches("down") {
                             crate::subsystem::sqlite::commands::Command::Down {
-                                timeout: down_subc.get_one::<String>("timeout").map(|s| s.parse::<u64>().unwrap()),
-                                count: down_subc.get_one::<String>("count").unwrap().parse::<usize>().unwrap(),
+                                timeout: down_subc.get_one::<u64>("timeout").copied(),
+                                count: down_subc.get_one::<String>("count").map(|s| s.parse::<usize>().unwrap()),
+                                to: down_subc.get_one::<String>("to").cloned(),
+                                tags: down_subc.get_many::<String>("tag").map(|v| v.cloned().collect()).unwrap_or_default(),
                                 remote: down_subc.get_flag("remote"),
                                 diff: down_subc.get_flag("diff"),
                                 dry: down_subc.get_flag("dry"),
@@ -423,35 +1304,305 @@ impl ClapArgumentLoader {
                                 unlock: down_subc.get_flag("unlock"),
                             }
                         } else if let Some(list_subc) = sqlite_subc.subcommand_matches("list") {
-                            let out = match list_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or("human") {
+                            let out = match list_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
                                 "human" => crate::subsystem::sqlite::commands::Output::Human,
                                 "json" => crate::subsystem::sqlite::commands::Output::Json,
                                 _ => crate::subsystem::sqlite::commands::Output::Human,
                             };
-                            crate::subsystem::sqlite::commands::Command::List { output: out }
+                            crate::subsystem::sqlite::commands::Command::List {
+                                tags: list_subc.get_many::<String>("tag").map(|v| v.cloned().collect()).unwrap_or_default(),
+                                output: out,
+                                check: list_subc.get_flag("check"),
+                                wide: list_subc.get_flag("wide"),
+                            }
                         } else if let Some(history_subc) = sqlite_subc.subcommand_matches("history") {
                             let history_cmd = if let Some(_) = history_subc.subcommand_matches("sync") {
                                 crate::subsystem::sqlite::commands::HistoryCommand::Sync
                             } else if let Some(_) = history_subc.subcommand_matches("fix") {
                                 crate::subsystem::sqlite::commands::HistoryCommand::Fix
+                            } else if let Some(verify_subc) = history_subc.subcommand_matches("verify") {
+                                let out = match verify_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                    "json" => crate::subsystem::sqlite::commands::Output::Json,
+                                    _ => crate::subsystem::sqlite::commands::Output::Human,
+                                };
+                                crate::subsystem::sqlite::commands::HistoryCommand::Verify {
+                                    fix: verify_subc.get_flag("fix"),
+                                    yes: verify_subc.get_flag("yes"),
+                                    output: out,
+                                }
                             } else {
                                 unreachable!();
                             };
                             crate::subsystem::sqlite::commands::Command::History(history_cmd)
-                        } else if let Some(_) = sqlite_subc.subcommand_matches("diff") {
-                            crate::subsystem::sqlite::commands::Command::Diff
+                        } else if let Some(diff_subc) = sqlite_subc.subcommand_matches("diff") {
+                            crate::subsystem::sqlite::commands::Command::Diff {
+                                down: diff_subc.get_flag("down"),
+                                count: diff_subc.get_one::<usize>("count").copied(),
+                                id: diff_subc.get_one::<String>("id").cloned(),
+                                output: match diff_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                    "json" => crate::subsystem::sqlite::commands::Output::Json,
+                                    _ => crate::subsystem::sqlite::commands::Output::Human,
+                                },
+                            }
+                        } else if let Some(verify_subc) = sqlite_subc.subcommand_matches("verify") {
+                            let out = match verify_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                "json" => crate::subsystem::sqlite::commands::Output::Json,
+                                _ => crate::subsystem::sqlite::commands::Output::Human,
+                            };
+                            crate::subsystem::sqlite::commands::Command::Verify { output: out }
+                        } else if let Some(squash_subc) = sqlite_subc.subcommand_matches("squash") {
+                            crate::subsystem::sqlite::commands::Command::Squash {
+                                from: squash_subc.get_one::<String>("from").unwrap().clone(),
+                                to: squash_subc.get_one::<String>("to").unwrap().clone(),
+                                comment: squash_subc.get_one::<String>("comment").cloned(),
+                                yes: squash_subc.get_flag("yes"),
+                            }
+                        } else if let Some(baseline_subc) = sqlite_subc.subcommand_matches("baseline") {
+                            crate::subsystem::sqlite::commands::Command::Baseline {
+                                id: baseline_subc.get_one::<String>("id").unwrap().clone(),
+                                yes: baseline_subc.get_flag("yes"),
+                            }
+                        } else if let Some(log_subc) = sqlite_subc.subcommand_matches("log") {
+                            let log_cmd = if let Some(list_subc) = log_subc.subcommand_matches("list") {
+                                let out = match list_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                    "json" => crate::subsystem::sqlite::commands::Output::Json,
+                                    _ => crate::subsystem::sqlite::commands::Output::Human,
+                                };
+                                crate::subsystem::sqlite::commands::LogCommand::List {
+                                    migration_id: list_subc.get_one::<String>("migration").cloned(),
+                                    operation: list_subc.get_one::<String>("operation").cloned(),
+                                    since: list_subc.get_one::<String>("since").cloned(),
+                                    until: list_subc.get_one::<String>("until").cloned(),
+                                    limit: list_subc.get_one::<usize>("limit").copied(),
+                                    output: out,
+                                }
+                            } else if let Some(show_subc) = log_subc.subcommand_matches("show") {
+                                let out = match show_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                    "json" => crate::subsystem::sqlite::commands::Output::Json,
+                                    _ => crate::subsystem::sqlite::commands::Output::Human,
+                                };
+                                crate::subsystem::sqlite::commands::LogCommand::Show {
+                                    id: show_subc.get_one::<String>("id").unwrap().clone(),
+                                    output: out,
+                                }
+                            } else if let Some(export_subc) = log_subc.subcommand_matches("export") {
+                                let format = match export_subc.get_one::<String>("format").map(|s| s.as_str()).unwrap_or("csv") {
+                                    "json" => crate::subsystem::sqlite::commands::LogExportFormat::Json,
+                                    _ => crate::subsystem::sqlite::commands::LogExportFormat::Csv,
+                                };
+                                crate::subsystem::sqlite::commands::LogCommand::Export {
+                                    format,
+                                    since: export_subc.get_one::<String>("since").cloned(),
+                                    until: export_subc.get_one::<String>("until").cloned(),
+                                    out: export_subc.get_one::<String>("out").map(PathBuf::from),
+                                }
+                            } else {
+                                unreachable!();
+                            };
+                            crate::subsystem::sqlite::commands::Command::Log(log_cmd)
+                        } else if let Some(rename_subc) = sqlite_subc.subcommand_matches("rename") {
+                            crate::subsystem::sqlite::commands::Command::Rename {
+                                id: rename_subc.get_one::<String>("id").unwrap().clone(),
+                                new_id: rename_subc.get_one::<String>("new_id").unwrap().clone(),
+                                comment: rename_subc.get_one::<String>("comment").cloned(),
+                                yes: rename_subc.get_flag("yes"),
+                            }
+                        } else if let Some(lock_subc) = sqlite_subc.subcommand_matches("lock") {
+                            crate::subsystem::sqlite::commands::Command::Lock {
+                                id: lock_subc.get_one::<String>("id").unwrap().clone(),
+                            }
+                        } else if let Some(unlock_subc) = sqlite_subc.subcommand_matches("unlock") {
+                            crate::subsystem::sqlite::commands::Command::Unlock {
+                                id: unlock_subc.get_one::<String>("id").unwrap().clone(),
+                            }
+                        } else if let Some(rm_subc) = sqlite_subc.subcommand_matches("rm") {
+                            crate::subsystem::sqlite::commands::Command::Remove {
+                                id: rm_subc.get_one::<String>("id").unwrap().clone(),
+                                force: rm_subc.get_flag("force"),
+                                also_remote: rm_subc.get_flag("also_remote"),
+                                yes: rm_subc.get_flag("yes"),
+                            }
+                        } else if let Some(prune_subc) = sqlite_subc.subcommand_matches("prune") {
+                            crate::subsystem::sqlite::commands::Command::Prune {
+                                yes: prune_subc.get_flag("yes"),
+                            }
+                        } else if let Some(repair_subc) = sqlite_subc.subcommand_matches("repair") {
+                            crate::subsystem::sqlite::commands::Command::Repair {
+                                id: repair_subc.get_one::<String>("id").unwrap().clone(),
+                                up: repair_subc.get_flag("up"),
+                                down: repair_subc.get_flag("down"),
+                                yes: repair_subc.get_flag("yes"),
+                            }
+                        } else if let Some(snapshot_subc) = sqlite_subc.subcommand_matches("snapshot") {
+                            crate::subsystem::sqlite::commands::Command::Snapshot {
+                                out: snapshot_subc.get_one::<String>("out").map(std::path::PathBuf::from),
+                            }
+                        } else if let Some(test_subc) = sqlite_subc.subcommand_matches("test") {
+                            crate::subsystem::sqlite::commands::Command::Test {
+                                timeout: test_subc.get_one::<u64>("timeout").copied(),
+                            }
+                        } else if let Some(generate_subc) = sqlite_subc.subcommand_matches("generate") {
+                            crate::subsystem::sqlite::commands::Command::Generate {
+                                schema: generate_subc.get_one::<String>("schema").map(std::path::PathBuf::from),
+                                from_db: generate_subc.get_flag("from_db"),
+                                comment: generate_subc.get_one::<String>("comment").cloned(),
+                                yes: generate_subc.get_flag("yes"),
+                            }
+                        } else if let Some(plan_subc) = sqlite_subc.subcommand_matches("plan") {
+                            crate::subsystem::sqlite::commands::Command::Plan {
+                                count: plan_subc.get_one::<usize>("count").copied(),
+                                tags: plan_subc.get_many::<String>("tag").map(|v| v.cloned().collect()).unwrap_or_default(),
+                                output: match plan_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                    "json" => crate::subsystem::sqlite::commands::Output::Json,
+                                    _ => crate::subsystem::sqlite::commands::Output::Human,
+                                },
+                            }
+                        } else if let Some(lint_subc) = sqlite_subc.subcommand_matches("lint") {
+                            let out = match lint_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                "json" => crate::subsystem::sqlite::commands::Output::Json,
+                                _ => crate::subsystem::sqlite::commands::Output::Human,
+                            };
+                            crate::subsystem::sqlite::commands::Command::Lint {
+                                all: lint_subc.get_flag("all"),
+                                strict: lint_subc.get_flag("strict"),
+                                large_table_rows: *lint_subc.get_one::<i64>("large_table_rows").unwrap(),
+                                output: out,
+                            }
+                        } else if let Some(fmt_subc) = sqlite_subc.subcommand_matches("fmt") {
+                            crate::subsystem::sqlite::commands::Command::Fmt {
+                                check: fmt_subc.get_flag("check"),
+                                all: fmt_subc.get_flag("all"),
+                            }
+                        } else if let Some(tui_subc) = sqlite_subc.subcommand_matches("tui") {
+                            crate::subsystem::sqlite::commands::Command::Tui {
+                                timeout: tui_subc.get_one::<u64>("timeout").copied(),
+                            }
+                        } else if let Some(graph_subc) = sqlite_subc.subcommand_matches("graph") {
+                            let format = match graph_subc.get_one::<String>("format").map(|s| s.as_str()).unwrap_or("ascii") {
+                                "dot" => crate::subsystem::sqlite::commands::GraphFormat::Dot,
+                                _ => crate::subsystem::sqlite::commands::GraphFormat::Ascii,
+                            };
+                            crate::subsystem::sqlite::commands::Command::Graph { format }
+                        } else if let Some(export_subc) = sqlite_subc.subcommand_matches("export") {
+                            crate::subsystem::sqlite::commands::Command::Export {
+                                out: std::path::PathBuf::from(export_subc.get_one::<String>("out").unwrap()),
+                            }
+                        } else if let Some(import_subc) = sqlite_subc.subcommand_matches("import") {
+                            crate::subsystem::sqlite::commands::Command::Import {
+                                bundle: std::path::PathBuf::from(import_subc.get_one::<String>("bundle").unwrap()),
+                                from: match import_subc.get_one::<String>("from").map(|s| s.as_str()) {
+                                    Some("liquibase") => crate::subsystem::sqlite::commands::ImportFormat::Liquibase,
+                                    Some("sqlx") => crate::subsystem::sqlite::commands::ImportFormat::Sqlx,
+                                    Some("alembic") => crate::subsystem::sqlite::commands::ImportFormat::Alembic,
+                                    Some("dbmate") => crate::subsystem::sqlite::commands::ImportFormat::Dbmate,
+                                    _ => crate::subsystem::sqlite::commands::ImportFormat::Bundle,
+                                },
+                                history_table: import_subc.get_one::<String>("history_table").cloned(),
+                                history: !import_subc.get_flag("no_history"),
+                                force: import_subc.get_flag("force"),
+                                yes: import_subc.get_flag("yes"),
+                            }
+                        } else if let Some(seed_subc) = sqlite_subc.subcommand_matches("seed") {
+                            let seed_cmd = if let Some(run_subc) = seed_subc.subcommand_matches("run") {
+                                crate::subsystem::sqlite::commands::SeedCommand::Run {
+                                    profile: run_subc.get_one::<String>("profile").cloned(),
+                                    allowed: None,
+                                    timeout: run_subc.get_one::<u64>("timeout").copied(),
+                                    force: run_subc.get_flag("force"),
+                                    dry: run_subc.get_flag("dry"),
+                                    yes: run_subc.get_flag("yes"),
+                                }
+                            } else if let Some(list_subc) = seed_subc.subcommand_matches("list") {
+                                let out = match list_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                    "json" => crate::subsystem::sqlite::commands::Output::Json,
+                                    _ => crate::subsystem::sqlite::commands::Output::Human,
+                                };
+                                crate::subsystem::sqlite::commands::SeedCommand::List {
+                                    profile: list_subc.get_one::<String>("profile").cloned(),
+                                    output: out,
+                                }
+                            } else if let Some(reset_subc) = seed_subc.subcommand_matches("reset") {
+                                crate::subsystem::sqlite::commands::SeedCommand::Reset {
+                                    profile: reset_subc.get_one::<String>("profile").cloned(),
+                                    yes: reset_subc.get_flag("yes"),
+                                }
+                            } else {
+                                unreachable!();
+                            };
+                            crate::subsystem::sqlite::commands::Command::Seed(seed_cmd)
+                        } else if let Some(meta_subc) = sqlite_subc.subcommand_matches("meta") {
+                            let meta_cmd = if let Some(check_subc) = meta_subc.subcommand_matches("check") {
+                                let out = match check_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                    "json" => crate::subsystem::sqlite::commands::Output::Json,
+                                    _ => crate::subsystem::sqlite::commands::Output::Human,
+                                };
+                                crate::subsystem::sqlite::commands::MetaCommand::Check { output: out }
+                            } else if let Some(fix_subc) = meta_subc.subcommand_matches("fix") {
+                                crate::subsystem::sqlite::commands::MetaCommand::Fix { yes: fix_subc.get_flag("yes") }
+                            } else {
+                                unreachable!();
+                            };
+                            crate::subsystem::sqlite::commands::Command::Meta(meta_cmd)
+                        } else if let Some(bootstrap_subc) = sqlite_subc.subcommand_matches("bootstrap") {
+                            crate::subsystem::sqlite::commands::Command::Bootstrap {
+                                comment: bootstrap_subc.get_one::<String>("comment").cloned(),
+                                baseline: bootstrap_subc.get_flag("baseline"),
+                                yes: bootstrap_subc.get_flag("yes"),
+                            }
+                        } else if let Some(wait_subc) = sqlite_subc.subcommand_matches("wait") {
+                            crate::subsystem::sqlite::commands::Command::Wait {
+                                timeout: *wait_subc.get_one::<std::time::Duration>("timeout").unwrap(),
+                                interval: *wait_subc.get_one::<std::time::Duration>("interval").unwrap(),
+                            }
+                        } else if let Some(ping_subc) = sqlite_subc.subcommand_matches("ping") {
+                            let out = match ping_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                "json" => crate::subsystem::sqlite::commands::Output::Json,
+                                _ => crate::subsystem::sqlite::commands::Output::Human,
+                            };
+                            crate::subsystem::sqlite::commands::Command::Ping { output: out }
+                        } else if let Some(console_subc) = sqlite_subc.subcommand_matches("console") {
+                            crate::subsystem::sqlite::commands::Command::Console {
+                                client: console_subc.get_one::<String>("client").cloned(),
+                                args: console_subc.get_many::<String>("args").map(|v| v.cloned().collect()).unwrap_or_default(),
+                            }
+                        } else if let Some(drift_subc) = sqlite_subc.subcommand_matches("drift") {
+                            let out = match drift_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                "json" => crate::subsystem::sqlite::commands::Output::Json,
+                                _ => crate::subsystem::sqlite::commands::Output::Human,
+                            };
+                            crate::subsystem::sqlite::commands::Command::Drift {
+                                snapshot: drift_subc.get_one::<String>("snapshot").map(std::path::PathBuf::from),
+                                output: out,
+                            }
+                        } else if let Some(bench_subc) = sqlite_subc.subcommand_matches("bench") {
+                            let out = match bench_subc.get_one::<String>("output").map(|s| s.as_str()).unwrap_or(output) {
+                                "json" => crate::subsystem::sqlite::commands::Output::Json,
+                                _ => crate::subsystem::sqlite::commands::Output::Human,
+                            };
+                            crate::subsystem::sqlite::commands::Command::Bench {
+                                timeout: bench_subc.get_one::<u64>("timeout").copied(),
+                                iterations: bench_subc.get_one::<String>("iterations").unwrap().parse::<usize>()?,
+                                baseline: bench_subc.get_one::<String>("baseline").map(PathBuf::from),
+                                save: bench_subc.get_one::<String>("save").map(PathBuf::from),
+                                threshold: bench_subc.get_one::<String>("threshold").unwrap().parse::<f64>()?,
+                                output: out,
+                            }
                         } else if let Some(apply_subc) = sqlite_subc.subcommand_matches("apply") {
                             if let Some(up_subc) = apply_subc.subcommand_matches("up") {
                                 crate::subsystem::sqlite::commands::Command::Apply(crate::subsystem::sqlite::commands::MigrationApply::Up {
-                                    id: up_subc.get_one::<String>("id").unwrap().clone(),
-                                    timeout: up_subc.get_one::<String>("timeout").map(|s| s.parse::<u64>().unwrap()),
+                                    ids: up_subc.get_many::<String>("id").map(|v| v.cloned().collect()).unwrap_or_default(),
+                                    from: up_subc.get_one::<String>("from").cloned(),
+                                    to: up_subc.get_one::<String>("to").cloned(),
+                                    timeout: up_subc.get_one::<u64>("timeout").copied(),
                                     dry: up_subc.get_flag("dry"),
                                     yes: up_subc.get_flag("yes"),
                                 })
                             } else if let Some(down_subc) = apply_subc.subcommand_matches("down") {
                                 crate::subsystem::sqlite::commands::Command::Apply(crate::subsystem::sqlite::commands::MigrationApply::Down {
-                                    id: down_subc.get_one::<String>("id").unwrap().clone(),
-                                    timeout: down_subc.get_one::<String>("timeout").map(|s| s.parse::<u64>().unwrap()),
+                                    ids: down_subc.get_many::<String>("id").map(|v| v.cloned().collect()).unwrap_or_default(),
+                                    from: down_subc.get_one::<String>("from").cloned(),
+                                    to: down_subc.get_one::<String>("to").cloned(),
+                                    timeout: down_subc.get_one::<u64>("timeout").copied(),
                                     remote: down_subc.get_flag("remote"),
                                     dry: down_subc.get_flag("dry"),
                                     yes: down_subc.get_flag("yes"),
@@ -463,9 +1614,41 @@ impl ClapArgumentLoader {
                         } else {
                             unreachable!();
                         };
-                        (sql_cfg, sqlite_cmd)
+                        (targets, sqlite_cmd)
                     };
-                    return Ok(CallArgs { privileges, command: Command::Subsystem(Subsystem::Sqlite { path, config: sql_cfg, command: sqlite_cmd }) });
+                    let force_protected = sqlite_subc.get_flag("force_protected");
+                    let override_window = sqlite_subc.get_flag("override_window");
+                    let mut subsystems = Vec::new();
+                    for (name, path, config) in targets {
+                        let mut command = sqlite_cmd.clone();
+                        let config_name = name.as_ref().map(|name| format!("target {}", name)).unwrap_or_else(|| path.display().to_string());
+                        if let (Some(window), Some(refused), false) = (&config.window, command.window_name(), override_window) {
+                            window.check().with_context(|| format!("{} refuses {}", config_name, refused))?;
+                        }
+                        if config.protected {
+                            let confirms = match command.protected_name() {
+                                | Some(refused) if !force_protected => {
+                                    anyhow::bail!("{} is protected and refuses {}; pass --force-protected to run it anyway", config_name, refused);
+                                },
+                                | Some(_) => false,
+                                | None if force_protected => command.require_confirmation(),
+                                | None => {
+                                    command.restrict_seeds(&config.allowed_seeds);
+                                    command.require_confirmation()
+                                },
+                            };
+                            if confirms && user.ui.non_interactive == crate::config::NonInteractive::Yes && !std::io::IsTerminal::is_terminal(&std::io::stdin()) {
+                                anyhow::bail!("{} is protected and needs an interactive confirmation, non_interactive = \"yes\" does not apply", config_name);
+                            }
+                        }
+                        subsystems.push((name, Subsystem::Sqlite { path, config, command }));
+                    }
+                    let targets = subsystems;
+                    let parallel = sqlite_subc.get_one::<usize>("parallel").copied().unwrap_or(1);
+                    let metrics = Self::metrics_output(sqlite_subc)?;
+                    let report = Self::report_path(sqlite_subc);
+                    let events = sqlite_subc.get_one::<String>("events").cloned();
+                    return Ok(CallArgs { privileges, command: Command::Subsystem { targets, parallel, metrics, report, events }, user });
                 }
             }
             return Err(anyhow::anyhow!("subsystem required"));
@@ -473,7 +1656,7 @@ impl ClapArgumentLoader {
             anyhow::bail!("unknown command")
         };
 
-        let callargs = CallArgs { privileges, command: cmd };
+        let callargs = CallArgs { privileges, command: cmd, user };
 
         callargs.validate()?;
         Ok(callargs)
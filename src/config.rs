@@ -43,6 +43,500 @@ pub struct Config {
     pub subsystem: Subsystem,
 }
 
+/// Read a value from a file referenced by the config at `config_path`, trimming surrounding whitespace
+pub fn read_from_file(config_path: &std::path::Path, file: &str) -> anyhow::Result<String> {
+    let file = config_path.parent().map(|dir| dir.join(file)).unwrap_or_else(|| file.into());
+    let value = std::fs::read_to_string(&file)?.trim().to_string();
+    if value.is_empty() {
+        anyhow::bail!("{} is empty", file.display());
+    }
+    Ok(value)
+}
+
+/// Read a value from the OS credential store (Keychain, Credential Manager or Secret Service)
+#[cfg(feature = "keyring")]
+pub fn read_from_keyring(source: &KeyringSource) -> anyhow::Result<String> {
+    let entry = keyring::Entry::new(&source.service, &source.user)?;
+    let value = entry.get_password()?.trim().to_string();
+    if value.is_empty() {
+        anyhow::bail!("keyring entry {}/{} is empty", source.service, source.user);
+    }
+    Ok(value)
+}
+
+#[cfg(feature = "vault")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct VaultSource {
+    /// Defaults to $VAULT_ADDR
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    /// API path below `/v1/`, e.g. `secret/data/qop` (KV v2) or `database/creds/app`
+    pub path: String,
+    /// Field of the secret holding the connection string
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Connection string with `{field}` placeholders, for secrets holding credentials only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    #[serde(default)]
+    pub auth: VaultAuth,
+    /// Seconds to wait for each request (default 30)
+    #[serde(default, deserialize_with = "crate::core::duration::deserialize_seconds", skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+#[cfg(feature = "vault")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VaultAuth {
+    /// Token from an environment variable, $VAULT_TOKEN by default
+    Token {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token_env: Option<String>,
+    },
+    /// AppRole login with the secret id taken from an environment variable
+    Approle {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mount: Option<String>,
+        role_id: String,
+        secret_id_env: String,
+    },
+}
+
+#[cfg(feature = "vault")]
+impl Default for VaultAuth {
+    fn default() -> Self {
+        VaultAuth::Token { token_env: None }
+    }
+}
+
+/// Run a command referenced by the config at `config_path` from the config directory and return its
+/// trimmed stdout. The command is killed when it does not finish within its timeout.
+pub fn read_from_command(config_path: &std::path::Path, command: &CommandSource) -> anyhow::Result<String> {
+    use std::{io::Read, process::Stdio, time::{Duration, Instant}};
+
+    let timeout = Duration::from_secs(command.timeout.unwrap_or(30));
+    let mut process = std::process::Command::new(&command.program);
+    process.args(&command.args).stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped());
+    if let Some(dir) = config_path.parent() {
+        process.current_dir(dir);
+    }
+    let mut child = process.spawn().map_err(|e| anyhow::anyhow!("failed to start {}: {}", command.program, e))?;
+
+    // Drain both pipes on threads so a chatty command cannot block on a full pipe
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let stdout_reader = std::thread::spawn(move || {
+        let mut out = String::new();
+        stdout.read_to_string(&mut out).map(|_| out)
+    });
+    let stderr_reader = std::thread::spawn(move || {
+        let mut out = String::new();
+        let _ = stderr.read_to_string(&mut out);
+        out
+    });
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if start.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!("{} did not finish within {}s", command.program, timeout.as_secs());
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    let stdout = stdout_reader.join().map_err(|_| anyhow::anyhow!("failed to read the output of {}", command.program))??;
+    let stderr = stderr_reader.join().unwrap_or_default();
+    if !status.success() {
+        anyhow::bail!("{} exited with {}: {}", command.program, status, stderr.trim());
+    }
+    let value = stdout.trim().to_string();
+    if value.is_empty() {
+        anyhow::bail!("{} printed nothing to stdout", command.program);
+    }
+    Ok(value)
+}
+
+/// Environment variable selecting a profile when `--profile` is not given
+pub const PROFILE_ENV: &str = "QOP_PROFILE";
+
+/// Environment variable overriding the connection when `--conn` is not given
+pub const CONNECTION_ENV: &str = "QOP_CONNECTION";
+
+/// Environment variable overriding the location of the user config
+pub const USER_CONFIG_ENV: &str = "QOP_USER_CONFIG";
+
+/// Environment variable overriding `non_interactive` of the user config, e.g. in CI
+pub const NON_INTERACTIVE_ENV: &str = "QOP_NON_INTERACTIVE";
+
+/// Per-developer settings from `~/.config/qop/config.toml`, applied to every project
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct UserConfig {
+    /// Profile used when neither `--profile` nor `QOP_PROFILE` is given
+    pub profile: Option<String>,
+    /// `[subsystem.<kind>]` defaults that the project config overrides key by key
+    #[serde(default)]
+    pub subsystem: toml::Table,
+    #[serde(default)]
+    pub ui: UiPreferences,
+    pub notify: Option<NotifyConfig>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct UiPreferences {
+    /// Colors in the interactive UI and SQL previews; `--color`, `NO_COLOR` and stdout not being a terminal override it
+    #[serde(default = "default_true")]
+    pub color: bool,
+    /// Emoji in status messages
+    #[serde(default = "default_true")]
+    pub emoji: bool,
+    /// ASCII only, without emoji and box drawing, for CI logs and terminals that mangle unicode
+    #[serde(default)]
+    pub plain: bool,
+    /// Answer to confirmation prompts when stdin is not a terminal
+    #[serde(default)]
+    pub non_interactive: NonInteractive,
+    /// Page `diff` and `log show` output longer than the terminal, `--no-pager` turns it off for one run
+    #[serde(default = "default_true")]
+    pub pager: bool,
+    /// Set by `--quiet` and `--verbose`, never read from the file
+    #[serde(skip)]
+    pub verbosity: crate::core::output::Verbosity,
+    /// Set by `--output json`, never read from the file
+    #[serde(skip)]
+    pub json: bool,
+}
+
+impl Default for UiPreferences {
+    fn default() -> Self {
+        Self { color: true, emoji: true, plain: false, non_interactive: Default::default(), pager: true, verbosity: Default::default(), json: false }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NonInteractive {
+    /// Fail and ask for `--yes`, instead of waiting for input that never comes
+    #[default]
+    Fail,
+    /// Confirm, as if `--yes` was given
+    Yes,
+    /// Decline, cancelling the command
+    No,
+}
+
+impl std::str::FromStr for NonInteractive {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            | "fail" => Ok(Self::Fail),
+            | "yes" => Ok(Self::Yes),
+            | "no" => Ok(Self::No),
+            | _ => anyhow::bail!("invalid {} `{}`, expected fail, yes or no", NON_INTERACTIVE_ENV, s),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Command run once a subsystem command finished, with `QOP_STATUS` and `QOP_ELAPSED` set
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct NotifyConfig {
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Only notify about commands that ran at least this many seconds (default 10)
+    #[serde(default, deserialize_with = "crate::core::duration::deserialize_seconds")]
+    pub min_duration: Option<u64>,
+}
+
+impl UserConfig {
+    /// `$QOP_USER_CONFIG`, else `$XDG_CONFIG_HOME/qop/config.toml`, else `~/.config/qop/config.toml`
+    pub fn path() -> Option<std::path::PathBuf> {
+        let env = |name: &str| std::env::var_os(name).filter(|v| !v.is_empty()).map(std::path::PathBuf::from);
+        if let Some(path) = env(USER_CONFIG_ENV) {
+            return Some(path);
+        }
+        env("XDG_CONFIG_HOME")
+            .or_else(|| env("HOME").map(|home| home.join(".config")))
+            .or_else(|| env("APPDATA"))
+            .map(|dir| dir.join("qop").join("config.toml"))
+    }
+
+    /// Load the user config; a missing file yields the defaults
+    pub fn load() -> anyhow::Result<Self> {
+        use anyhow::Context;
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read user config: {}", path.display()))?;
+        let mut value: toml::Value = toml::from_str(&content).with_context(|| format!("Failed to parse user config: {}", path.display()))?;
+        interpolate_env(&mut value, "").with_context(|| format!("Failed to interpolate user config: {}", path.display()))?;
+        value.try_into().with_context(|| format!("Invalid user config: {}", path.display()))
+    }
+
+    /// Merge a config table over the subsystem defaults of its kind
+    fn apply_defaults(&self, config: &mut toml::Table) {
+        let Some(toml::Value::Table(subsystems)) = config.get_mut("subsystem") else {
+            return;
+        };
+        for (kind, settings) in subsystems.iter_mut() {
+            if let (toml::Value::Table(settings), Some(toml::Value::Table(defaults))) = (settings, self.subsystem.get(kind)) {
+                let mut merged = defaults.clone();
+                merge_tables(&mut merged, settings);
+                *settings = merged;
+            }
+        }
+    }
+}
+
+/// A command target: the top-level subsystem (without a name) or one of `[targets.<name>]`
+pub struct Target {
+    pub name: Option<String>,
+    /// Config path the target's migrations and relative paths resolve against; for targets with
+    /// a `dir`, a path of the same file name inside that directory
+    pub path: std::path::PathBuf,
+    pub config: Config,
+    /// Profile merged into the config, if any
+    pub profile: Option<String>,
+}
+
+impl Config {
+    /// Load the config file, with the `[profiles.<name>]` table of the selected profile merged
+    /// over the top level. Tables are merged key by key; `connection` and all other values are
+    /// replaced as a whole.
+    ///
+    /// Without `target` or `all_targets` the top-level subsystem is used. Targets have the same
+    /// shape as the top level plus an optional `dir` holding their migrations; the top-level
+    /// `version` applies unless a target sets its own.
+    pub fn load_targets(path: &std::path::Path, profile: Option<&str>, target: Option<&str>, all_targets: bool, user: &UserConfig) -> anyhow::Result<Vec<Target>> {
+        use anyhow::Context;
+        let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read config file: {}", path.display()))?;
+        let mut root: toml::Table = toml::from_str(&content).with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+        let profiles = match root.remove("profiles") {
+            | Some(toml::Value::Table(profiles)) => profiles,
+            | Some(_) => anyhow::bail!("`profiles` in {} must be a table", path.display()),
+            | None => toml::Table::new(),
+        };
+        // The default profile of the user config only applies to projects that define it
+        let profile = profile.or_else(|| user.profile.as_deref().filter(|p| profiles.contains_key(*p)));
+        if let Some(profile) = profile {
+            let overrides = match profiles.get(profile) {
+                | Some(toml::Value::Table(overrides)) => overrides,
+                | Some(_) => anyhow::bail!("profile {} in {} must be a table", profile, path.display()),
+                | None => {
+                    let available: Vec<&str> = profiles.keys().map(|k| k.as_str()).collect();
+                    anyhow::bail!(
+                        "profile {} not found in {} (available: {})",
+                        profile,
+                        path.display(),
+                        if available.is_empty() { "none".to_string() } else { available.join(", ") }
+                    );
+                },
+            };
+            merge_tables(&mut root, overrides);
+        }
+
+        let targets = match root.remove("targets") {
+            | Some(toml::Value::Table(targets)) => targets,
+            | Some(_) => anyhow::bail!("`targets` in {} must be a table", path.display()),
+            | None => toml::Table::new(),
+        };
+        let names: Vec<String> = match target {
+            | Some(target) => vec![target.to_string()],
+            | None if all_targets => {
+                if targets.is_empty() {
+                    anyhow::bail!("no targets defined in {}", path.display());
+                }
+                targets.keys().cloned().collect()
+            },
+            | None => {
+                if !root.contains_key("subsystem") && !targets.is_empty() {
+                    let available: Vec<&str> = targets.keys().map(|k| k.as_str()).collect();
+                    anyhow::bail!("{} only defines targets ({}); select one with --target or use --all-targets", path.display(), available.join(", "));
+                }
+                let config = Self::from_table(root, "", path, user)?;
+                return Ok(vec![Target { name: None, path: path.to_path_buf(), config, profile: profile.map(String::from) }]);
+            },
+        };
+
+        names
+            .into_iter()
+            .map(|name| {
+                let mut table = match targets.get(&name) {
+                    | Some(toml::Value::Table(table)) => table.clone(),
+                    | Some(_) => anyhow::bail!("target {} in {} must be a table", name, path.display()),
+                    | None => {
+                        let available: Vec<&str> = targets.keys().map(|k| k.as_str()).collect();
+                        anyhow::bail!(
+                            "target {} not found in {} (available: {})",
+                            name,
+                            path.display(),
+                            if available.is_empty() { "none".to_string() } else { available.join(", ") }
+                        );
+                    },
+                };
+                if let Some(version) = root.get("version") {
+                    table.entry("version").or_insert_with(|| version.clone());
+                }
+                let dir = match table.remove("dir") {
+                    | Some(toml::Value::String(dir)) => Some(interpolate_str(&dir, &format!("targets.{}.dir", name))?),
+                    | Some(_) => anyhow::bail!("`dir` of target {} in {} must be a string", name, path.display()),
+                    | None => None,
+                };
+                let target_path = match (dir, path.parent(), path.file_name()) {
+                    | (Some(dir), Some(parent), Some(file_name)) => parent.join(dir).join(file_name),
+                    | _ => path.to_path_buf(),
+                };
+                let config = Self::from_table(table, &format!("targets.{}", name), path, user)?;
+                Ok(Target { name: Some(name), path: target_path, config, profile: profile.map(String::from) })
+            })
+            .collect()
+    }
+
+    fn from_table(mut table: toml::Table, key: &str, path: &std::path::Path, user: &UserConfig) -> anyhow::Result<Self> {
+        use anyhow::Context;
+        user.apply_defaults(&mut table);
+        let mut value = toml::Value::Table(table);
+        interpolate_env(&mut value, key).with_context(|| format!("Failed to interpolate config file: {}", path.display()))?;
+        match key {
+            | "" => value.try_into().with_context(|| format!("Invalid config file: {}", path.display())),
+            | key => value.try_into().with_context(|| format!("Invalid {} in config file: {}", key, path.display())),
+        }
+    }
+}
+
+/// Replace `${VAR}` (or `${VAR:-default}`) in every string of the config with the environment
+/// variable; `$${` is a literal `${`. `key` is the dotted path of `value`, for error messages.
+fn interpolate_env(value: &mut toml::Value, key: &str) -> anyhow::Result<()> {
+    match value {
+        | toml::Value::String(s) => *s = interpolate_str(s, key)?,
+        | toml::Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                interpolate_env(item, &format!("{}[{}]", key, i))?;
+            }
+        },
+        | toml::Value::Table(table) => {
+            for (name, item) in table.iter_mut() {
+                let key = if key.is_empty() { name.clone() } else { format!("{}.{}", key, name) };
+                interpolate_env(item, &key)?;
+            }
+        },
+        | _ => {},
+    }
+    Ok(())
+}
+
+fn interpolate_str(s: &str, key: &str) -> anyhow::Result<String> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(escaped) = rest.strip_prefix("$${") {
+            out.push_str("${");
+            rest = escaped;
+        } else if let Some(reference) = rest.strip_prefix("${") {
+            let end = reference.find('}').ok_or_else(|| anyhow::anyhow!("unterminated ${{ in {}", key))?;
+            let (name, default) = match reference[..end].split_once(":-") {
+                | Some((name, default)) => (name, Some(default)),
+                | None => (&reference[..end], None),
+            };
+            match (std::env::var(name), default) {
+                | (Ok(value), _) => out.push_str(&value),
+                | (Err(_), Some(default)) => out.push_str(default),
+                | (Err(_), None) => anyhow::bail!("environment variable {} referenced by {} is not set", name, key),
+            }
+            rest = &reference[end + 1..];
+        } else {
+            out.push('$');
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn merge_tables(base: &mut toml::Table, overrides: &toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(key), value) {
+            // Data sources are externally tagged enums, merging two variants would yield an invalid table
+            | (Some(toml::Value::Table(base)), toml::Value::Table(value)) if key != "connection" => merge_tables(base, value),
+            | _ => {
+                base.insert(key.clone(), value.clone());
+            },
+        }
+    }
+}
+
+
+/// Prefix of the tracking tables when neither `tables.prefix` nor the table names are given
+pub const DEFAULT_TABLE_PREFIX: &str = "__qop";
+
+/// Names of the tracking tables. `prefix` derives `<prefix>_migrations`, `<prefix>_log`,
+/// `<prefix>_seeds` and `<prefix>_repeatables`; names that are given explicitly take precedence.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", from = "TablesConfig")]
+pub struct Tables {
+    pub migrations: String,
+    pub log: String,
+    pub seeds: String,
+    pub repeatables: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+struct TablesConfig {
+    prefix: Option<String>,
+    migrations: Option<String>,
+    log: Option<String>,
+    seeds: Option<String>,
+    repeatables: Option<String>,
+}
+
+impl Tables {
+    pub fn with_prefix(prefix: &str) -> Self {
+        Self {
+            migrations: format!("{}_migrations", prefix),
+            log: format!("{}_log", prefix),
+            seeds: format!("{}_seeds", prefix),
+            repeatables: format!("{}_repeatables", prefix),
+        }
+    }
+}
+
+impl Default for Tables {
+    fn default() -> Self {
+        Self::with_prefix(DEFAULT_TABLE_PREFIX)
+    }
+}
+
+impl From<TablesConfig> for Tables {
+    fn from(config: TablesConfig) -> Self {
+        let derived = Self::with_prefix(config.prefix.as_deref().unwrap_or(DEFAULT_TABLE_PREFIX));
+        Self {
+            migrations: config.migrations.unwrap_or(derived.migrations),
+            log: config.log.unwrap_or(derived.log),
+            seeds: config.seeds.unwrap_or(derived.seeds),
+            repeatables: config.repeatables.unwrap_or(derived.repeatables),
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -50,8 +544,55 @@ pub struct Config {
 pub enum DataSource<T: Serialize + DeserializeOwned> {
     Static(T),
     FromEnv(String),
+    /// Path of a file holding the value, e.g. a mounted secret; relative to the config file
+    File(String),
+    /// External command printing the value to stdout, e.g. a secret store CLI
+    Command(CommandSource),
+    /// Entry of the OS credential store
+    #[cfg(feature = "keyring")]
+    Keyring(KeyringSource),
+    /// Secret read from HashiCorp Vault at runtime
+    #[cfg(feature = "vault")]
+    Vault(VaultSource),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct CommandSource {
+    pub program: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Seconds to wait for the command (default 30)
+    #[serde(default, deserialize_with = "crate::core::duration::deserialize_seconds", skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+#[cfg(feature = "keyring")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct KeyringSource {
+    pub service: String,
+    pub user: String,
+}
+
+impl<T: Serialize + DeserializeOwned> DataSource<T> {
+    /// Where the value is taken from, without revealing it
+    pub fn describe(&self) -> String {
+        match self {
+            | DataSource::Static(_) => "static value in config".to_string(),
+            | DataSource::FromEnv(var) => format!("environment variable {}", var),
+            | DataSource::File(file) => format!("file {}", file),
+            | DataSource::Command(command) => format!("command {}", command.program),
+            #[cfg(feature = "keyring")]
+            | DataSource::Keyring(keyring) => format!("keyring entry {}/{}", keyring.service, keyring.user),
+            #[cfg(feature = "vault")]
+            | DataSource::Vault(vault) => format!("Vault secret {}", vault.path),
+        }
+    }
 }
 
+// One per config, the size of the variants does not matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum Subsystem {
@@ -0,0 +1,339 @@
+use {
+    crate::core::{
+        bootstrap,
+        import::{self, Conversion, ForeignMigration},
+        migration::MigrationMeta,
+        migration_diff,
+        repo::Dialect,
+    },
+    anyhow::{Context, Result},
+    std::{
+        collections::{HashMap, HashSet},
+        path::{Path, PathBuf},
+    },
+};
+
+/// Name of the tool in the source label of imported migrations
+pub const TOOL: &str = "alembic";
+
+/// Table Alembic keeps its current revisions in
+pub const HISTORY_TABLE: &str = "alembic_version";
+
+/// Columns of the tracking table, in the order `convert` expects the rows
+pub const HISTORY_COLUMNS: [&str; 1] = ["version_num"];
+
+/// A revision as far as the offline scripts and revision files tell
+#[derive(Default)]
+struct Revision {
+    /// Revisions it upgrades from, several for a merge
+    parents: Vec<String>,
+    up: Option<String>,
+    down: Option<String>,
+    message: Option<String>,
+}
+
+/// Convert the revisions in Alembic offline SQL, as written by `alembic upgrade head --sql` and
+/// `alembic downgrade head:base --sql`. `path` is such a script or a directory with scripts, where
+/// the revision files of a versions directory add their messages. Revisions in `history`, the rows of
+/// alembic_version, and their ancestors are marked as applied. Statements maintaining `table` are dropped.
+pub fn convert(path: &Path, dialect: Dialect, history: &[Vec<Option<String>>], table: &str) -> Result<Conversion> {
+    let files: Vec<PathBuf> = match path.is_dir() {
+        | true => {
+            let mut files = Vec::new();
+            for entry in std::fs::read_dir(path).with_context(|| format!("Failed to read directory: {}", path.display()))? {
+                let file = entry?.path();
+                if file.is_file() && file.extension().is_some_and(|ext| ext == "sql" || ext == "py") {
+                    files.push(file);
+                }
+            }
+            files.sort();
+            files
+        },
+        | false => vec![path.to_path_buf()],
+    };
+
+    let mut order: Vec<String> = Vec::new();
+    let mut revisions: HashMap<String, Revision> = HashMap::new();
+    for file in &files {
+        let text = std::fs::read_to_string(file).with_context(|| format!("Failed to read {}", file.display()))?;
+        if file.extension().is_some_and(|ext| ext == "py") {
+            if let Some((id, parents, message)) = revision_file(&text) {
+                let revision = revisions.entry(id.clone()).or_default();
+                revision.message = message;
+                if revision.parents.is_empty() {
+                    revision.parents = parents;
+                }
+            }
+            continue;
+        }
+        for (upgrade, from, to, sql) in sections(&text) {
+            let sql = strip_bookkeeping(&sql, table);
+            match upgrade {
+                | true => {
+                    if !order.contains(&to) {
+                        order.push(to.clone());
+                    }
+                    let revision = revisions.entry(to).or_default();
+                    revision.parents = from;
+                    revision.up = Some(sql);
+                },
+                // Downgrading a revision runs its downgrade() towards its parents
+                | false => {
+                    if let Some(id) = from.into_iter().next() {
+                        revisions.entry(id).or_default().down = Some(sql);
+                    }
+                },
+            }
+        }
+    }
+    if order.is_empty() {
+        anyhow::bail!("{} has no upgrades; write them with `alembic upgrade head --sql > upgrade.sql`", path.display());
+    }
+
+    let mut conversion = Conversion::default();
+    // alembic_version only holds the heads, everything they descend from is applied as well
+    let mut applied = HashSet::new();
+    let mut pending: Vec<String> = history.iter().filter_map(|row| row.first().cloned().flatten()).collect();
+    while let Some(id) = pending.pop() {
+        match revisions.get(&id) {
+            | Some(revision) if applied.insert(id.clone()) => pending.extend(revision.parents.iter().cloned()),
+            | Some(_) => {},
+            | None => conversion.skipped.push((id, format!("{} lists it, but the scripts do not contain it", table))),
+        }
+    }
+
+    for id in sort_parents_first(&order, &revisions) {
+        let revision = &revisions[&id];
+        let Some(up_sql) = revision.up.clone() else { continue };
+        let down_sql = match &revision.down {
+            | Some(down) => down.clone(),
+            | None => {
+                conversion.notes.push((id.clone(), "the scripts have no downgrade for it, down.sql was generated from up.sql".to_string()));
+                bootstrap::generate_migration_down_sql(&up_sql, dialect)
+            },
+        };
+        if revision.parents.len() > 1 {
+            conversion.notes.push((id.clone(), format!("it merges {}; qop orders the migrations by id only", revision.parents.join(", "))));
+        }
+        let mut meta = MigrationMeta { comment: Some(revision.message.clone().unwrap_or_else(|| format!("Alembic revision {}", id))), ..Default::default() };
+        meta.labels.insert(import::SOURCE_LABEL.to_string(), import::source(TOOL, &id));
+        conversion.migrations.push(ForeignMigration { applied: applied.contains(&id), key: id, up_sql, down_sql, meta });
+    }
+    Ok(conversion)
+}
+
+/// (upgrade, from, to, sql) of each `-- Running upgrade a -> b` and `-- Running downgrade b -> a` section
+fn sections(text: &str) -> Vec<(bool, Vec<String>, String, String)> {
+    let mut sections: Vec<(bool, Vec<String>, String, String)> = Vec::new();
+    let mut current: Option<(bool, Vec<String>, String, String)> = None;
+    for line in text.lines() {
+        let marker = line.trim_start().strip_prefix("--").map(str::trim).and_then(|rest| {
+            let (upgrade, rest) = match rest.strip_prefix("Running upgrade") {
+                | Some(rest) => (true, rest),
+                | None => (false, rest.strip_prefix("Running downgrade")?),
+            };
+            let (from, to) = rest.split_once("->")?;
+            let list = |ids: &str| ids.split(',').map(|id| id.trim().to_string()).filter(|id| !id.is_empty()).collect::<Vec<_>>();
+            Some((upgrade, list(from), list(to)))
+        });
+        match marker {
+            | Some((upgrade, from, to)) => {
+                sections.extend(current.take());
+                // Upgrades go from the parents to one revision, downgrades from one revision to its parents
+                current = Some((upgrade, from, to.join(", "), String::new()));
+            },
+            | None => {
+                if let Some((.., sql)) = current.as_mut() {
+                    sql.push_str(line);
+                    sql.push('\n');
+                }
+            },
+        }
+    }
+    sections.extend(current);
+    sections
+}
+
+/// `sql` without transaction control and the statements maintaining the version table
+fn strip_bookkeeping(sql: &str, table: &str) -> String {
+    let table = table.rsplit('.').next().unwrap_or(table).to_ascii_lowercase();
+    let statements: Vec<String> = migration_diff::split_statements(sql)
+        .into_iter()
+        .filter(|statement| {
+            let words: Vec<String> = statement.split_whitespace().take(3).map(|word| word.to_ascii_lowercase()).collect();
+            let target = |word: Option<&String>| word.is_some_and(|word| word.split('(').next().unwrap_or_default().rsplit('.').next().map(|name| name.trim_matches('"')) == Some(table.as_str()));
+            match words.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+                | ["begin" | "commit", ..] | ["start", "transaction", ..] => false,
+                | ["insert", "into", ..] | ["delete", "from", ..] | ["create", "table", ..] | ["drop", "table", ..] => !target(words.get(2)),
+                | ["update", ..] => !target(words.get(1)),
+                | _ => true,
+            }
+        })
+        .collect();
+    statements.iter().map(|statement| format!("{};\n", statement)).collect::<Vec<_>>().join("\n")
+}
+
+/// Revisions in the order of the scripts, but never before their parents
+fn sort_parents_first(order: &[String], revisions: &HashMap<String, Revision>) -> Vec<String> {
+    let mut sorted: Vec<String> = Vec::with_capacity(order.len());
+    let mut placed: HashSet<&str> = HashSet::new();
+    let mut remaining: Vec<&String> = order.iter().collect();
+    while !remaining.is_empty() {
+        let ready = remaining
+            .iter()
+            .position(|id| revisions[*id].parents.iter().all(|parent| placed.contains(parent.as_str()) || !order.contains(parent)))
+            // A cycle cannot be resolved, keep the order of the scripts
+            .unwrap_or(0);
+        let id = remaining.remove(ready);
+        placed.insert(id);
+        sorted.push(id.clone());
+    }
+    sorted
+}
+
+/// (revision, down revisions, message) of a revision file from the versions directory
+fn revision_file(text: &str) -> Option<(String, Vec<String>, Option<String>)> {
+    let value = |name: &str| {
+        text.lines().find_map(|line| {
+            let rest = line.strip_prefix(name)?.trim_start();
+            // `revision = "..."` or with a type annotation, `revision: str = "..."`
+            let value = match rest.strip_prefix(':') {
+                | Some(annotated) => annotated.split_once('=')?.1,
+                | None => rest.strip_prefix('=')?,
+            };
+            Some(value.trim().to_string())
+        })
+    };
+    let quoted = |value: &str| value.split(['\'', '"']).skip(1).step_by(2).map(str::to_string).filter(|id| !id.is_empty()).collect::<Vec<_>>();
+    let id = quoted(&value("revision")?).into_iter().next()?;
+    let parents = value("down_revision").map(|value| quoted(&value)).unwrap_or_default();
+    let message = text
+        .trim_start()
+        .strip_prefix("\"\"\"")
+        .and_then(|docstring| docstring.lines().next())
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty());
+    Some((id, parents, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::core::import::Fixture,
+    };
+
+    const UPGRADE: &str = "BEGIN;
+
+CREATE TABLE alembic_version (
+    version_num VARCHAR(32) NOT NULL,
+    CONSTRAINT alembic_version_pkc PRIMARY KEY (version_num)
+);
+
+-- Running upgrade  -> 1a2b
+
+CREATE TABLE users (id INTEGER);
+
+INSERT INTO alembic_version (version_num) VALUES ('1a2b') RETURNING alembic_version.version_num;
+
+-- Running upgrade 1a2b -> 3c4d
+
+ALTER TABLE users ADD COLUMN name TEXT;
+
+UPDATE alembic_version SET version_num='3c4d' WHERE alembic_version.version_num = '1a2b';
+
+COMMIT;
+";
+
+    const DOWNGRADE: &str = "BEGIN;
+
+-- Running downgrade 3c4d -> 1a2b
+
+ALTER TABLE users DROP COLUMN name;
+
+UPDATE alembic_version SET version_num='1a2b' WHERE alembic_version.version_num = '3c4d';
+
+-- Running downgrade 1a2b -> 
+
+DROP TABLE users;
+
+DELETE FROM alembic_version WHERE alembic_version.version_num = '1a2b';
+
+DROP TABLE alembic_version;
+
+COMMIT;
+";
+
+    const REVISION: &str = "\"\"\"add the name column
+
+Revision ID: 3c4d
+Revises: 1a2b
+\"\"\"
+from alembic import op
+
+revision: str = '3c4d'
+down_revision: Union[str, None] = '1a2b'
+";
+
+    fn convert_dir(fixture: &Fixture, history: &[&str]) -> Conversion {
+        let history: Vec<Vec<Option<String>>> = history.iter().map(|id| vec![Some(id.to_string())]).collect();
+        convert(&fixture.path(""), Dialect::Postgres, &history, HISTORY_TABLE).unwrap()
+    }
+
+    #[test]
+    fn converts_upgrades_and_downgrades() {
+        let fixture = Fixture::new(&[("upgrade.sql", UPGRADE), ("downgrade.sql", DOWNGRADE), ("3c4d_add_name.py", REVISION)]);
+        let conversion = convert_dir(&fixture, &["3c4d"]);
+        assert!(conversion.skipped.is_empty());
+        assert!(conversion.notes.is_empty());
+
+        let [first, second] = conversion.migrations.as_slice() else { panic!("expected two migrations") };
+        assert_eq!(first.key, "1a2b");
+        assert_eq!(first.up_sql, "CREATE TABLE users (id INTEGER);\n");
+        assert_eq!(first.down_sql, "DROP TABLE users;\n");
+        assert_eq!(first.meta.comment.as_deref(), Some("Alembic revision 1a2b"));
+        // The head is recorded, its ancestors are applied as well
+        assert!(first.applied);
+
+        assert_eq!(second.key, "3c4d");
+        assert_eq!(second.up_sql, "ALTER TABLE users ADD COLUMN name TEXT;\n");
+        assert_eq!(second.down_sql, "ALTER TABLE users DROP COLUMN name;\n");
+        assert_eq!(second.meta.comment.as_deref(), Some("add the name column"));
+        assert_eq!(second.meta.labels.get(import::SOURCE_LABEL).map(String::as_str), Some("alembic:3c4d"));
+        assert!(second.applied);
+    }
+
+    #[test]
+    fn merges_follow_their_parents() {
+        let upgrade = "-- Running upgrade 1a2b, 5e6f -> 7a8b\nSELECT 'merge';\n-- Running upgrade  -> 1a2b\nSELECT 1;\n-- Running upgrade  -> 5e6f\nSELECT 2;\n";
+        let fixture = Fixture::new(&[("upgrade.sql", upgrade)]);
+        let conversion = convert_dir(&fixture, &["5e6f", "9999"]);
+
+        let order: Vec<(&str, bool)> = conversion.migrations.iter().map(|migration| (migration.key.as_str(), migration.applied)).collect();
+        assert_eq!(order, vec![("1a2b", false), ("5e6f", true), ("7a8b", false)]);
+        assert_eq!(conversion.skipped, vec![("9999".to_string(), "alembic_version lists it, but the scripts do not contain it".to_string())]);
+        assert!(conversion.notes.contains(&("7a8b".to_string(), "it merges 1a2b, 5e6f; qop orders the migrations by id only".to_string())));
+        assert!(conversion.notes.contains(&("1a2b".to_string(), "the scripts have no downgrade for it, down.sql was generated from up.sql".to_string())));
+    }
+
+    #[test]
+    fn strips_the_version_table() {
+        let sql = "BEGIN;\nCREATE TABLE public.alembic_version(version_num VARCHAR(32));\nCREATE TABLE kept (id INTEGER);\nINSERT INTO \"alembic_version\" (version_num) VALUES ('x');\nUPDATE alembic_version SET version_num = 'y';\nDROP TABLE alembic_version;\nCOMMIT;\n";
+        assert_eq!(strip_bookkeeping(sql, "public.alembic_version"), "CREATE TABLE kept (id INTEGER);\n");
+    }
+
+    #[test]
+    fn reads_revision_files() {
+        assert_eq!(revision_file(REVISION), Some(("3c4d".to_string(), vec!["1a2b".to_string()], Some("add the name column".to_string()))));
+        let merge = "revision = \"7a8b\"\ndown_revision = ('1a2b', '5e6f')\n";
+        assert_eq!(revision_file(merge), Some(("7a8b".to_string(), vec!["1a2b".to_string(), "5e6f".to_string()], None)));
+        assert_eq!(revision_file("print('not a revision')\n"), None);
+    }
+
+    #[test]
+    fn requires_upgrades() {
+        let fixture = Fixture::new(&[("downgrade.sql", DOWNGRADE)]);
+        let error = convert(&fixture.path(""), Dialect::Postgres, &[], HISTORY_TABLE).unwrap_err();
+        assert!(format!("{:#}", error).contains("has no upgrades"));
+    }
+}
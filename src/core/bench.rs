@@ -0,0 +1,129 @@
+use {
+    anyhow::{Context, Result},
+    comfy_table::{Cell, CellAlignment, ContentArrangement},
+    serde::{Deserialize, Serialize},
+    std::{collections::BTreeMap, path::Path, time::Duration},
+};
+
+/// Timing statistics of a single migration across all benchmark iterations (milliseconds)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchStats {
+    pub min_ms: f64,
+    pub mean_ms: f64,
+    pub max_ms: f64,
+}
+
+impl BenchStats {
+    pub fn from_samples(samples: &[Duration]) -> Self {
+        if samples.is_empty() {
+            return Self { min_ms: 0.0, mean_ms: 0.0, max_ms: 0.0 };
+        }
+        let ms: Vec<f64> = samples.iter().map(|d| d.as_secs_f64() * 1000.0).collect();
+        Self {
+            min_ms: ms.iter().cloned().fold(f64::INFINITY, f64::min),
+            mean_ms: ms.iter().sum::<f64>() / ms.len() as f64,
+            max_ms: ms.iter().cloned().fold(0.0, f64::max),
+        }
+    }
+}
+
+/// Benchmark results, also used as the on-disk baseline format
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BenchReport {
+    pub version: String,
+    pub iterations: usize,
+    pub migrations: BTreeMap<String, BenchStats>,
+}
+
+impl BenchReport {
+    pub fn from_samples(iterations: usize, samples: &BTreeMap<String, Vec<Duration>>) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            iterations,
+            migrations: samples.iter().map(|(id, s)| (id.clone(), BenchStats::from_samples(s))).collect(),
+        }
+    }
+
+    /// Read a baseline report from a JSON file
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read bench baseline: {}", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse bench baseline: {}", path.display()))
+    }
+
+    /// Write this report as a JSON baseline file
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)
+            .with_context(|| format!("Failed to write bench baseline: {}", path.display()))
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BenchRegression {
+    pub id: String,
+    pub baseline_ms: f64,
+    pub current_ms: f64,
+    pub change_pct: f64,
+}
+
+/// Compare mean timings against a baseline; migrations unknown to the baseline are ignored
+pub fn find_regressions(current: &BenchReport, baseline: &BenchReport, threshold_pct: f64) -> Vec<BenchRegression> {
+    current
+        .migrations
+        .iter()
+        .filter_map(|(id, stats)| {
+            let base = baseline.migrations.get(id)?;
+            if base.mean_ms <= 0.0 {
+                return None;
+            }
+            let change_pct = (stats.mean_ms - base.mean_ms) / base.mean_ms * 100.0;
+            if change_pct > threshold_pct {
+                Some(BenchRegression { id: id.clone(), baseline_ms: base.mean_ms, current_ms: stats.mean_ms, change_pct })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Render benchmark results as a table, including the baseline comparison when available
+pub fn render_bench_table(report: &BenchReport, baseline: Option<&BenchReport>, threshold_pct: f64) {
+    let mut header = vec![Cell::new("Migration ID"), Cell::new("Min (ms)"), Cell::new("Mean (ms)"), Cell::new("Max (ms)")];
+    if baseline.is_some() {
+        header.push(Cell::new("Baseline (ms)"));
+        header.push(Cell::new("Change"));
+    }
+
+    let mut table = crate::core::output::table();
+    table
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(header);
+
+    for (id, stats) in &report.migrations {
+        let mut row = vec![
+            Cell::new(id),
+            Cell::new(format!("{:.2}", stats.min_ms)).set_alignment(CellAlignment::Right),
+            Cell::new(format!("{:.2}", stats.mean_ms)).set_alignment(CellAlignment::Right),
+            Cell::new(format!("{:.2}", stats.max_ms)).set_alignment(CellAlignment::Right),
+        ];
+        if let Some(baseline) = baseline {
+            match baseline.migrations.get(id) {
+                Some(base) if base.mean_ms > 0.0 => {
+                    let change_pct = (stats.mean_ms - base.mean_ms) / base.mean_ms * 100.0;
+                    let marker = if change_pct > threshold_pct { " ⚠️" } else { "" };
+                    row.push(Cell::new(format!("{:.2}", base.mean_ms)).set_alignment(CellAlignment::Right));
+                    row.push(Cell::new(crate::core::output::ascii(&format!("{:+.1}%{}", change_pct, marker))).set_alignment(CellAlignment::Right));
+                }
+                _ => {
+                    row.push(Cell::new("-").set_alignment(CellAlignment::Center));
+                    row.push(Cell::new("new").set_alignment(CellAlignment::Center));
+                }
+            }
+        }
+        table.add_row(row);
+    }
+
+    println!("{table}");
+}
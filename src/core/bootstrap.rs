@@ -0,0 +1,173 @@
+use crate::core::{
+    migration_diff::{self, AlterAction, MigrationOperation},
+    repo::Dialect,
+};
+
+/// Generate down SQL that drops every object created by a schema dump, in reverse order.
+/// Indexes and triggers are dropped implicitly with their tables; routines whose signature
+/// cannot be determined are left as comments.
+pub fn generate_down_sql(schema: &str, dialect: Dialect) -> String {
+    let cascade = if dialect == Dialect::Postgres { " CASCADE" } else { "" };
+    let mut lines = vec!["-- generated by qop bootstrap, review before relying on it".to_string()];
+    for statement in migration_diff::split_statements(schema).iter().rev() {
+        let drop = match migration_diff::parse_statement(statement) {
+            | MigrationOperation::CreateTable { name, .. } => Some(format!("DROP TABLE IF EXISTS {}{};", name, cascade)),
+            | MigrationOperation::CreateView { name, .. } if starts_with_words(statement, &["CREATE", "MATERIALIZED", "VIEW"]) => {
+                Some(format!("DROP MATERIALIZED VIEW IF EXISTS {}{};", name, cascade))
+            },
+            | MigrationOperation::CreateView { name, .. } => Some(format!("DROP VIEW IF EXISTS {}{};", name, cascade)),
+            | MigrationOperation::Other { statement } => drop_for_other(&statement, cascade),
+            | _ => None,
+        };
+        if let Some(drop) = drop {
+            lines.push(drop);
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Generate best-effort down SQL for a migration by inverting its statements in reverse order.
+/// Statements that cannot be inverted, e.g. data changes or drops, become TODO comments.
+pub fn generate_migration_down_sql(up_sql: &str, dialect: Dialect) -> String {
+    let mut lines = vec!["-- generated by qop from up.sql, review before relying on it".to_string()];
+    for statement in migration_diff::split_statements(up_sql).iter().rev() {
+        lines.extend(invert_statement(statement, dialect));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Statements undoing a single statement, or a TODO comment if it cannot be inverted
+pub fn invert_statement(statement: &str, dialect: Dialect) -> Vec<String> {
+    let todo = || vec![format!("-- TODO: revert manually: {};", statement.split_whitespace().collect::<Vec<_>>().join(" "))];
+    match migration_diff::parse_statement(statement) {
+        | MigrationOperation::CreateTable { name, .. } => vec![format!("DROP TABLE {};", name)],
+        | MigrationOperation::CreateView { or_replace: true, .. } => todo(),
+        | MigrationOperation::CreateView { name, .. } if starts_with_words(statement, &["CREATE", "MATERIALIZED", "VIEW"]) => {
+            vec![format!("DROP MATERIALIZED VIEW {};", name)]
+        },
+        | MigrationOperation::CreateView { name, .. } => vec![format!("DROP VIEW {};", name)],
+        | MigrationOperation::CreateIndex { name: Some(name), table, concurrently, .. } => {
+            // Postgres creates the index in the schema of its table
+            let name = match table.rsplit_once('.') {
+                | Some((schema, _)) if dialect == Dialect::Postgres && !name.contains('.') => format!("{}.{}", schema, name),
+                | _ => name,
+            };
+            let concurrently = if concurrently && dialect == Dialect::Postgres { " CONCURRENTLY" } else { "" };
+            vec![format!("DROP INDEX{} {};", concurrently, name)]
+        },
+        | MigrationOperation::AlterTable { name, actions } => {
+            let mut inverted = Vec::new();
+            for action in actions.iter().rev() {
+                match action {
+                    | AlterAction::AddColumn { column } => inverted.push(format!("ALTER TABLE {} DROP COLUMN {};", name, column)),
+                    | AlterAction::RenameColumn { from, to } => inverted.push(format!("ALTER TABLE {} RENAME COLUMN {} TO {};", name, to, from)),
+                    | AlterAction::RenameTo { name: new_name } => {
+                        let (schema, old_name) = match name.rsplit_once('.') {
+                            | Some((schema, old_name)) => (format!("{}.", schema), old_name),
+                            | None => (String::new(), name.as_str()),
+                        };
+                        inverted.push(format!("ALTER TABLE {}{} RENAME TO {};", schema, new_name, old_name));
+                    },
+                    | AlterAction::Other { clause } if starts_with_words(clause, &["ADD", "CONSTRAINT"]) => match clause.split_whitespace().nth(2) {
+                        | Some(constraint) => inverted.push(format!("ALTER TABLE {} DROP CONSTRAINT {};", name, constraint)),
+                        | None => return todo(),
+                    },
+                    | AlterAction::DropColumn { .. } | AlterAction::Other { .. } => return todo(),
+                }
+            }
+            inverted
+        },
+        | MigrationOperation::Other { statement } => drop_for_other(&statement, "").map(|drop| vec![drop]).unwrap_or_else(todo),
+        | _ => todo(),
+    }
+}
+
+fn drop_for_other(statement: &str, cascade: &str) -> Option<String> {
+    let words: Vec<&str> = statement.split_whitespace().collect();
+    let name_after = |n: usize| words.get(n).map(|w| w.split('(').next().unwrap_or_default());
+    if starts_with_words(statement, &["CREATE", "SEQUENCE"]) {
+        return name_after(2).map(|name| format!("DROP SEQUENCE IF EXISTS {}{};", name, cascade));
+    }
+    if starts_with_words(statement, &["CREATE", "TYPE"]) {
+        return name_after(2).map(|name| format!("DROP TYPE IF EXISTS {}{};", name, cascade));
+    }
+    if starts_with_words(statement, &["CREATE", "EXTENSION"]) {
+        let offset = if starts_with_words(statement, &["CREATE", "EXTENSION", "IF", "NOT", "EXISTS"]) { 5 } else { 2 };
+        return name_after(offset).map(|name| format!("DROP EXTENSION IF EXISTS {};", name));
+    }
+    for kind in ["FUNCTION", "PROCEDURE"] {
+        if starts_with_words(statement, &["CREATE", kind]) || starts_with_words(statement, &["CREATE", "OR", "REPLACE", kind]) {
+            let offset = if words.get(1).is_some_and(|w| w.eq_ignore_ascii_case("OR")) { 4 } else { 2 };
+            let name = name_after(offset)?;
+            return Some(match routine_signature(statement) {
+                | Some(args) => format!("DROP {} IF EXISTS {}({}){};", kind, name, args, cascade),
+                | None => format!("-- DROP {} IF EXISTS {}(...); -- add the argument types", kind, name),
+            });
+        }
+    }
+    None
+}
+
+/// Argument list of a CREATE FUNCTION/PROCEDURE statement without defaults, as accepted by DROP
+fn routine_signature(statement: &str) -> Option<String> {
+    let start = statement.find('(')?;
+    let mut depth = 0;
+    let mut end = None;
+    for (i, c) in statement[start..].char_indices() {
+        match c {
+            | '(' => depth += 1,
+            | ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    end = Some(start + i);
+                    break;
+                }
+            },
+            | _ => {},
+        }
+    }
+    let args = &statement[start + 1..end?];
+
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    depth = 0;
+    for c in args.chars() {
+        match c {
+            | '(' => depth += 1,
+            | ')' => depth -= 1,
+            | ',' if depth == 0 => {
+                parts.push(std::mem::take(&mut current));
+                continue;
+            },
+            | _ => {},
+        }
+        current.push(c);
+    }
+    parts.push(current);
+
+    let args: Vec<String> = parts
+        .iter()
+        .map(|arg| {
+            let lower = arg.to_ascii_lowercase();
+            let cut = lower.find(" default ").or_else(|| lower.find('=')).unwrap_or(arg.len());
+            arg[..cut].trim().to_string()
+        })
+        .filter(|arg| !arg.is_empty())
+        .collect();
+    Some(args.join(", "))
+}
+
+fn starts_with_words(statement: &str, keywords: &[&str]) -> bool {
+    let mut words = statement.split_whitespace();
+    keywords.iter().all(|kw| words.next().is_some_and(|w| w.eq_ignore_ascii_case(kw)))
+}
+
+/// Drop session settings emitted by pg_dump (e.g. the empty search_path) from a schema dump
+pub fn strip_session_settings(schema: &str) -> String {
+    let statements: Vec<String> = migration_diff::split_statements(schema)
+        .into_iter()
+        .filter(|s| !starts_with_words(s, &["SET"]) && !s.to_lowercase().starts_with("select pg_catalog.set_config"))
+        .map(|s| format!("{};", s))
+        .collect();
+    statements.join("\n\n") + "\n"
+}
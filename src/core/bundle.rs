@@ -0,0 +1,165 @@
+use {
+    anyhow::{Context, Result},
+    chrono::{DateTime, Utc},
+    serde::{Deserialize, Serialize},
+    sha2::{Digest, Sha256},
+    std::{
+        collections::{BTreeMap, HashSet},
+        io::Read,
+        path::Path,
+    },
+};
+
+pub const MANIFEST_NAME: &str = "manifest.json";
+const FORMAT_VERSION: u32 = 1;
+const MIGRATION_FILES: [&str; 3] = ["up.sql", "down.sql", "meta.toml"];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub format_version: u32,
+    pub qop_version: String,
+    pub created_at: DateTime<Utc>,
+    /// Last applied migration of the database the bundle was exported from
+    pub last_applied: Option<String>,
+    pub migrations: Vec<BundleMigration>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundleMigration {
+    pub id: String,
+    /// Whether the migration was applied in the database the bundle was exported from
+    pub applied: bool,
+    /// file name -> sha256
+    pub files: BTreeMap<String, String>,
+}
+
+/// Outcome of extracting a bundle into a migration directory
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub created: Vec<String>,
+    pub unchanged: Vec<String>,
+    pub overwritten: Vec<String>,
+}
+
+pub fn sha256_hex(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// Package the given migration directories and a manifest into a gzipped tarball
+pub fn export_bundle(migration_dir: &Path, ids: &[String], applied: &HashSet<String>, last_applied: Option<&str>, out: &Path) -> Result<BundleManifest> {
+    let file = std::fs::File::create(out).with_context(|| format!("Failed to create bundle: {}", out.display()))?;
+    let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(file, flate2::Compression::default()));
+
+    let mut migrations = Vec::new();
+    for id in ids {
+        let mut files = BTreeMap::new();
+        for name in MIGRATION_FILES {
+            let path = migration_dir.join(format!("id={}", id)).join(name);
+            if !path.exists() {
+                continue;
+            }
+            let content = std::fs::read(&path).with_context(|| format!("Failed to read migration file: {}", path.display()))?;
+            append_file(&mut builder, &format!("id={}/{}", id, name), &content)?;
+            files.insert(name.to_string(), sha256_hex(&content));
+        }
+        migrations.push(BundleMigration { id: id.clone(), applied: applied.contains(id), files });
+    }
+
+    let manifest = BundleManifest {
+        format_version: FORMAT_VERSION,
+        qop_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: Utc::now(),
+        last_applied: last_applied.map(|s| s.to_string()),
+        migrations,
+    };
+    append_file(&mut builder, MANIFEST_NAME, serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+    builder.into_inner()?.finish().with_context(|| format!("Failed to write bundle: {}", out.display()))?;
+    Ok(manifest)
+}
+
+fn append_file<W: std::io::Write>(builder: &mut tar::Builder<W>, name: &str, content: &[u8]) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(content.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp() as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, name, content).with_context(|| format!("Failed to add {} to bundle", name))
+}
+
+/// Read a bundle into memory and verify every file against the manifest checksums
+pub fn read_bundle(bundle: &Path) -> Result<(BundleManifest, BTreeMap<String, Vec<u8>>)> {
+    let file = std::fs::File::open(bundle).with_context(|| format!("Failed to open bundle: {}", bundle.display()))?;
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+    let mut entries = BTreeMap::new();
+    for entry in archive.entries().with_context(|| format!("Failed to read bundle: {}", bundle.display()))? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let mut content = Vec::new();
+        entry.read_to_end(&mut content)?;
+        entries.insert(name, content);
+    }
+
+    let manifest: BundleManifest = serde_json::from_slice(entries.get(MANIFEST_NAME).ok_or_else(|| anyhow::anyhow!("bundle contains no {}", MANIFEST_NAME))?)
+        .with_context(|| format!("Failed to parse {}", MANIFEST_NAME))?;
+    if manifest.format_version != FORMAT_VERSION {
+        anyhow::bail!("unsupported bundle format version {} (expected {})", manifest.format_version, FORMAT_VERSION);
+    }
+
+    let mut expected: HashSet<String> = HashSet::from([MANIFEST_NAME.to_string()]);
+    for migration in &manifest.migrations {
+        // Entries are written relative to the migration directory, never outside of it
+        if migration.id.is_empty() || migration.id.contains(['/', '\\']) || migration.id.contains("..") {
+            anyhow::bail!("bundle contains an invalid migration id: {}", migration.id);
+        }
+        if let Some(name) = migration.files.keys().find(|name| !MIGRATION_FILES.contains(&name.as_str())) {
+            anyhow::bail!("bundle contains an unexpected file for migration {}: {}", migration.id, name);
+        }
+        for (name, checksum) in &migration.files {
+            let path = format!("id={}/{}", migration.id, name);
+            let content = entries.get(&path).ok_or_else(|| anyhow::anyhow!("bundle is missing {}", path))?;
+            if sha256_hex(content) != *checksum {
+                anyhow::bail!("checksum mismatch for {}; the bundle was modified after export", path);
+            }
+            expected.insert(path);
+        }
+    }
+    if let Some(extra) = entries.keys().find(|name| !expected.contains(*name)) {
+        anyhow::bail!("bundle contains {} which is not listed in the manifest", extra);
+    }
+    Ok((manifest, entries))
+}
+
+/// Write the migrations of a verified bundle into the migration directory.
+/// Existing migrations with different content are only replaced with `force`.
+pub fn import_bundle(migration_dir: &Path, manifest: &BundleManifest, entries: &BTreeMap<String, Vec<u8>>, force: bool) -> Result<ImportReport> {
+    let mut report = ImportReport::default();
+    let mut pending_writes = Vec::new();
+    for migration in &manifest.migrations {
+        let target = migration_dir.join(format!("id={}", migration.id));
+        let differs = migration.files.iter().any(|(name, checksum)| {
+            std::fs::read(target.join(name)).map(|content| sha256_hex(&content) != *checksum).unwrap_or(true)
+        });
+        if !target.exists() {
+            report.created.push(migration.id.clone());
+        } else if !differs {
+            report.unchanged.push(migration.id.clone());
+            continue;
+        } else if force {
+            report.overwritten.push(migration.id.clone());
+        } else {
+            anyhow::bail!("migration {} already exists locally with different content; pass --force to overwrite it", migration.id);
+        }
+        pending_writes.push((target, migration));
+    }
+
+    // Only write once all migrations passed the checks so a failed import leaves no partial state
+    for (target, migration) in pending_writes {
+        std::fs::create_dir_all(&target).with_context(|| format!("Failed to create directory: {}", target.display()))?;
+        for name in migration.files.keys() {
+            let content = &entries[&format!("id={}/{}", migration.id, name)];
+            let path = target.join(name);
+            std::fs::write(&path, content).with_context(|| format!("Failed to write migration file: {}", path.display()))?;
+        }
+    }
+    Ok(report)
+}
@@ -0,0 +1,33 @@
+/// Why `--check` failed, which qop reports through its exit code instead of the usual 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckFailure {
+    /// Local migrations are not applied yet
+    Pending,
+    /// Applied migrations were modified or are missing locally
+    Drift,
+}
+
+impl CheckFailure {
+    pub fn exit_code(self) -> u8 {
+        match self {
+            | Self::Pending => 2,
+            | Self::Drift => 3,
+        }
+    }
+}
+
+impl std::fmt::Display for CheckFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            | Self::Pending => write!(f, "migrations are pending"),
+            | Self::Drift => write!(f, "applied migrations do not match the local files"),
+        }
+    }
+}
+
+impl std::error::Error for CheckFailure {}
+
+/// The check failure behind `error`, also through added context
+pub fn failure(error: &anyhow::Error) -> Option<CheckFailure> {
+    error.chain().find_map(|cause| cause.downcast_ref::<CheckFailure>()).copied()
+}
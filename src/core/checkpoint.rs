@@ -0,0 +1,62 @@
+use {
+    anyhow::{Context, Result},
+    serde::{Deserialize, Serialize},
+    std::{collections::BTreeMap, path::{Path, PathBuf}},
+};
+
+/// Where a failed `up` stopped, so `up --resume` can continue from there
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Last migration applied before the failure
+    pub last_applied: Option<String>,
+    /// Migration that failed and is still pending
+    pub failed: String,
+    /// Statements of the failed migration that ran and were kept; only migrations with
+    /// `transaction = false` keep any, all others are rolled back as a whole
+    pub statements_done: usize,
+    pub error: String,
+    pub at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Checkpoints of all databases of a config by their scope, next to the config file
+fn file(path: &Path) -> PathBuf {
+    path.with_file_name(".qop-checkpoint.json")
+}
+
+fn read_all(path: &Path) -> Result<BTreeMap<String, Checkpoint>> {
+    let file = file(path);
+    if !file.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let content = std::fs::read_to_string(&file).with_context(|| format!("Failed to read checkpoint file: {}", file.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse checkpoint file: {}", file.display()))
+}
+
+fn write_all(path: &Path, checkpoints: &BTreeMap<String, Checkpoint>) -> Result<()> {
+    let file = file(path);
+    if checkpoints.is_empty() {
+        if file.exists() {
+            std::fs::remove_file(&file).with_context(|| format!("Failed to remove checkpoint file: {}", file.display()))?;
+        }
+        return Ok(());
+    }
+    std::fs::write(&file, serde_json::to_string_pretty(checkpoints)?).with_context(|| format!("Failed to write checkpoint file: {}", file.display()))
+}
+
+pub fn load(path: &Path, scope: &str) -> Result<Option<Checkpoint>> {
+    Ok(read_all(path)?.remove(scope))
+}
+
+pub fn save(path: &Path, scope: &str, checkpoint: Checkpoint) -> Result<()> {
+    let mut checkpoints = read_all(path)?;
+    checkpoints.insert(scope.to_string(), checkpoint);
+    write_all(path, &checkpoints)
+}
+
+pub fn clear(path: &Path, scope: &str) -> Result<()> {
+    let mut checkpoints = read_all(path)?;
+    if checkpoints.remove(scope).is_some() {
+        write_all(path, &checkpoints)?;
+    }
+    Ok(())
+}
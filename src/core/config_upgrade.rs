@@ -0,0 +1,91 @@
+use {
+    anyhow::{Context, Result},
+    crate::config::DEFAULT_TABLE_PREFIX,
+    semver::{Version, VersionReq},
+    toml_edit::{value, DocumentMut, InlineTable, Item, TableLike},
+};
+
+/// Rewrite a qop.toml written for an older qop version to the current format.
+/// Comments and layout are kept; returns the upgraded file and a description of every change.
+pub fn upgrade_config(content: &str, cli_version: &str) -> Result<(String, Vec<String>)> {
+    let mut doc: DocumentMut = content.parse().context("Failed to parse config")?;
+    let mut changes = Vec::new();
+
+    if let Some(subsystems) = doc.get_mut("subsystem").and_then(Item::as_table_like_mut) {
+        for (kind, subsystem) in subsystems.iter_mut() {
+            if let Some(subsystem) = subsystem.as_table_like_mut() {
+                upgrade_subsystem(&format!("subsystem.{}", kind), &kind, subsystem, true, &mut changes);
+            }
+        }
+    }
+    // Profiles only hold overrides, so missing fields are not filled in there
+    if let Some(profiles) = doc.get_mut("profiles").and_then(Item::as_table_like_mut) {
+        for (name, profile) in profiles.iter_mut() {
+            let Some(subsystems) = profile.get_mut("subsystem").and_then(Item::as_table_like_mut) else {
+                continue;
+            };
+            for (kind, subsystem) in subsystems.iter_mut() {
+                if let Some(subsystem) = subsystem.as_table_like_mut() {
+                    upgrade_subsystem(&format!("profiles.{}.subsystem.{}", name, kind), &kind, subsystem, false, &mut changes);
+                }
+            }
+        }
+    }
+
+    // The upgraded file uses the current format, so older CLIs must refuse it
+    let requirement = format!(">={}", cli_version);
+    let current = doc.get("version").and_then(Item::as_str).map(|v| v.to_string());
+    if needs_version_bump(current.as_deref(), cli_version)? {
+        doc["version"] = value(&requirement);
+        changes.push(format!("version: {} -> \"{}\"", current.map(|v| format!("\"{}\"", v)).unwrap_or_else(|| "missing".to_string()), requirement));
+    }
+
+    Ok((doc.to_string(), changes))
+}
+
+fn needs_version_bump(requirement: Option<&str>, cli_version: &str) -> Result<bool> {
+    let cli = Version::parse(cli_version)?;
+    let Some(requirement) = requirement.and_then(|r| VersionReq::parse(r).ok()) else {
+        return Ok(true);
+    };
+    let previous = match (cli.major, cli.minor, cli.patch) {
+        | (major, minor, patch) if patch > 0 => Some(Version::new(major, minor, patch - 1)),
+        | (major, minor, _) if minor > 0 => Some(Version::new(major, minor - 1, u64::MAX)),
+        | (major, ..) if major > 0 => Some(Version::new(major - 1, u64::MAX, u64::MAX)),
+        | _ => None,
+    };
+    Ok(!requirement.matches(&cli) || previous.is_some_and(|previous| requirement.matches(&previous)))
+}
+
+fn upgrade_subsystem(key: &str, kind: &str, subsystem: &mut dyn TableLike, root: bool, changes: &mut Vec<String>) {
+    if let Some(prefix) = subsystem.remove("table_prefix") {
+        let prefix = prefix.as_str().unwrap_or(DEFAULT_TABLE_PREFIX).to_string();
+        match subsystem.get_mut("tables").and_then(Item::as_table_like_mut) {
+            | Some(tables) if tables.contains_key("prefix") => {
+                changes.push(format!("{}: removed table_prefix, superseded by tables.prefix", key));
+            },
+            | Some(tables) => {
+                tables.insert("prefix", value(&prefix));
+                changes.push(format!("{}: moved table_prefix = \"{}\" to tables.prefix", key, prefix));
+            },
+            | None => {
+                let mut tables = InlineTable::new();
+                tables.insert("prefix", prefix.as_str().into());
+                subsystem.insert("tables", value(tables));
+                changes.push(format!("{}: replaced table_prefix = \"{}\" with tables = {{ prefix = \"{}\" }}", key, prefix, prefix));
+            },
+        }
+    }
+    if root && kind == "postgres" && !subsystem.contains_key("schema") {
+        subsystem.insert("schema", value("public"));
+        changes.push(format!("{}: added schema = \"public\"", key));
+    }
+    if let Some(connection) = subsystem.get_mut("connection")
+        && let Some(plain) = connection.as_str().map(|s| s.to_string())
+    {
+        let mut source = InlineTable::new();
+        source.insert("static", plain.into());
+        *connection = value(source);
+        changes.push(format!("{}: wrapped the plain connection string as connection = {{ static = ... }}", key));
+    }
+}
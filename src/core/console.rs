@@ -0,0 +1,70 @@
+use {
+    anyhow::{Context, Result},
+    std::process::Command,
+};
+
+/// Replace the current process with the native database client.
+/// Only returns if the client could not be started (or, off unix, once it exited).
+pub fn exec_client(program: &str, args: &[String], envs: &[(&str, String)]) -> Result<()> {
+    let mut command = Command::new(program);
+    command.args(args);
+    for (key, value) in envs {
+        command.env(key, value);
+    }
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let err = command.exec();
+        Err(err).with_context(|| format!("Failed to start {}; is it installed and on PATH?", program))
+    }
+    #[cfg(not(unix))]
+    {
+        let status = command.status().with_context(|| format!("Failed to start {}; is it installed and on PATH?", program))?;
+        if !status.success() {
+            anyhow::bail!("{} exited with {}", program, status);
+        }
+        Ok(())
+    }
+}
+
+/// Split the password out of a connection URI so it can be handed over via environment instead of argv
+pub fn split_password(uri: &str) -> (String, Option<String>) {
+    let Some(scheme_end) = uri.find("://") else {
+        return (uri.to_string(), None);
+    };
+    let rest_start = scheme_end + 3;
+    let authority_end = uri[rest_start..].find(['/', '?']).map(|i| rest_start + i).unwrap_or(uri.len());
+    let Some(at) = uri[rest_start..authority_end].rfind('@').map(|i| rest_start + i) else {
+        return (uri.to_string(), None);
+    };
+    let Some(colon) = uri[rest_start..at].find(':').map(|i| rest_start + i) else {
+        return (uri.to_string(), None);
+    };
+    let password = percent_decode(&uri[colon + 1..at]);
+    (format!("{}{}", &uri[..colon], &uri[at..]), Some(password))
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(byte) = value.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            out.push(byte);
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// File path of a sqlite connection string (`sqlite://file.db?mode=rwc`, `sqlite:file.db` or `file.db`)
+pub fn sqlite_file(uri: &str) -> String {
+    let path = uri.strip_prefix("sqlite://").or_else(|| uri.strip_prefix("sqlite:")).unwrap_or(uri);
+    path.split('?').next().unwrap_or_default().to_string()
+}
@@ -0,0 +1,188 @@
+use {
+    crate::core::{
+        bootstrap,
+        import::{self, Conversion, ForeignMigration},
+        migration::MigrationMeta,
+        repo::Dialect,
+    },
+    anyhow::{Context, Result},
+    std::{collections::HashSet, path::Path},
+};
+
+/// Name of the tool in the source label of imported migrations
+pub const TOOL: &str = "dbmate";
+
+/// Table dbmate tracks applied migrations in
+pub const HISTORY_TABLE: &str = "schema_migrations";
+
+/// Columns of the tracking table, in the order `convert` expects the rows
+pub const HISTORY_COLUMNS: [&str; 1] = ["version"];
+
+const UP: &str = "migrate:up";
+const DOWN: &str = "migrate:down";
+
+/// A `-- migrate:up` or `-- migrate:down` section of a migration file
+#[derive(Default)]
+struct Section {
+    sql: String,
+    /// `transaction:false` after the marker
+    no_transaction: bool,
+}
+
+/// Convert the migrations in a dbmate `db/migrations` directory. Each `<version>_<name>.sql` holds
+/// a `-- migrate:up` and a `-- migrate:down` section, which become up.sql and down.sql. Versions
+/// in `history`, the rows of `schema_migrations`, are marked as applied.
+pub fn convert(dir: &Path, dialect: Dialect, history: &[Vec<Option<String>>]) -> Result<Conversion> {
+    let applied: HashSet<String> = history.iter().filter_map(|row| Some(row.first()?.as_deref()?.trim().to_string())).collect();
+
+    let mut names: Vec<String> = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read directory: {}", dir.display()))? {
+        let path = entry?.path();
+        let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        if path.is_file() && name.ends_with(".sql") {
+            names.push(name);
+        }
+    }
+    // dbmate applies the files in the order of their names
+    names.sort();
+
+    let mut conversion = Conversion::default();
+    let mut versions: HashSet<String> = HashSet::new();
+    for name in names {
+        let stem = name.trim_end_matches(".sql");
+        let version: String = stem.chars().take_while(char::is_ascii_digit).collect();
+        if version.is_empty() {
+            conversion.skipped.push((name.clone(), "its name does not start with a version".to_string()));
+            continue;
+        }
+        if !versions.insert(version.clone()) {
+            conversion.skipped.push((name.clone(), format!("version {} is used twice", version)));
+            continue;
+        }
+        let text = std::fs::read_to_string(dir.join(&name)).with_context(|| format!("Failed to read migration file: {}", dir.join(&name).display()))?;
+        let (up, down) = sections(&text);
+        let Some(up) = up.filter(|up| !up.sql.trim().is_empty()) else {
+            conversion.skipped.push((version, "it has no `-- migrate:up` section with SQL".to_string()));
+            continue;
+        };
+        let up_sql = format!("{}\n", up.sql.trim());
+
+        let down_sql = match down.as_ref().filter(|down| !down.sql.trim().is_empty()) {
+            | Some(down) => format!("{}\n", down.sql.trim()),
+            | None => {
+                conversion.notes.push((version.clone(), "its `-- migrate:down` section is empty, down.sql was generated from up.sql".to_string()));
+                bootstrap::generate_migration_down_sql(&up_sql, dialect)
+            },
+        };
+        let description = stem[version.len()..].trim_start_matches('_').replace('_', " ");
+        let mut meta = MigrationMeta { comment: Some(description).filter(|description| !description.is_empty()), ..Default::default() };
+        if up.no_transaction {
+            meta.transaction = Some(false);
+        } else if down.is_some_and(|down| down.no_transaction) {
+            conversion.notes.push((version.clone(), "only its down section runs outside a transaction, the migration now runs in one".to_string()));
+        }
+        meta.labels.insert(import::SOURCE_LABEL.to_string(), import::source(TOOL, &version));
+        conversion.migrations.push(ForeignMigration { applied: applied.contains(&version), key: version, up_sql, down_sql, meta });
+    }
+    Ok(conversion)
+}
+
+/// The up and down sections of a migration file; text before the first marker is ignored
+fn sections(text: &str) -> (Option<Section>, Option<Section>) {
+    let (mut up, mut down): (Option<Section>, Option<Section>) = (None, None);
+    let mut current: Option<&mut Section> = None;
+    for line in text.lines() {
+        let marker = line.trim_start().strip_prefix("--").map(str::trim).and_then(|rest| {
+            let mut words = rest.split_whitespace();
+            let kind = words.next().filter(|word| *word == UP || *word == DOWN)?;
+            // Options follow the marker, e.g. `-- migrate:up transaction:false`
+            Some((kind == UP, words.any(|option| option == "transaction:false")))
+        });
+        match marker {
+            | Some((is_up, no_transaction)) => {
+                let slot = if is_up { &mut up } else { &mut down };
+                current = Some(slot.insert(Section { sql: String::new(), no_transaction }));
+            },
+            | None => {
+                if let Some(section) = current.as_mut() {
+                    section.sql.push_str(line);
+                    section.sql.push('\n');
+                }
+            },
+        }
+    }
+    (up, down)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::core::import::Fixture,
+    };
+
+    #[test]
+    fn converts_sections() {
+        let fixture = Fixture::new(&[
+            ("20240101000000_create_users.sql", "-- migrate:up\nCREATE TABLE users (id INTEGER);\n\n-- migrate:down\nDROP TABLE users;\n"),
+            ("20240102000000_add_index.sql", "-- header before the first marker\n--  migrate:up transaction:false\nCREATE INDEX CONCURRENTLY users_id ON users (id);\n-- migrate:down\n"),
+            ("20240103000000_down_only.sql", "-- migrate:up\nSELECT 1;\n-- migrate:down transaction:false\nSELECT 2;\n"),
+            ("schema.txt", "not a migration"),
+        ]);
+        let history = [vec![Some("20240101000000".to_string())], vec![None]];
+        let conversion = convert(&fixture.path(""), Dialect::Postgres, &history).unwrap();
+        assert!(conversion.skipped.is_empty());
+
+        let [users, index, down_only] = conversion.migrations.as_slice() else { panic!("expected three migrations") };
+        assert_eq!(users.key, "20240101000000");
+        assert_eq!(users.up_sql, "CREATE TABLE users (id INTEGER);\n");
+        assert_eq!(users.down_sql, "DROP TABLE users;\n");
+        assert_eq!(users.meta.comment.as_deref(), Some("create users"));
+        assert_eq!(users.meta.labels.get(import::SOURCE_LABEL).map(String::as_str), Some("dbmate:20240101000000"));
+        assert!(users.applied);
+
+        assert_eq!(index.up_sql, "CREATE INDEX CONCURRENTLY users_id ON users (id);\n");
+        assert!(index.down_sql.contains("DROP INDEX"), "{}", index.down_sql);
+        assert_eq!(index.meta.transaction, Some(false));
+        assert!(!index.applied);
+
+        assert_eq!(down_only.meta.transaction, None);
+        assert_eq!(
+            conversion.notes,
+            vec![
+                ("20240102000000".to_string(), "its `-- migrate:down` section is empty, down.sql was generated from up.sql".to_string()),
+                ("20240103000000".to_string(), "only its down section runs outside a transaction, the migration now runs in one".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_files_without_version_or_up() {
+        let fixture = Fixture::new(&[
+            ("1_first.sql", "-- migrate:up\nSELECT 1;\n"),
+            ("1_second.sql", "-- migrate:up\nSELECT 2;\n"),
+            ("2_empty.sql", "-- migrate:up\n\n-- migrate:down\nSELECT 3;\n"),
+            ("3_unmarked.sql", "SELECT 4;\n"),
+            ("seed.sql", "-- migrate:up\nSELECT 5;\n"),
+        ]);
+        let conversion = convert(&fixture.path(""), Dialect::Sqlite, &[]).unwrap();
+        assert_eq!(
+            conversion.skipped,
+            vec![
+                ("1_second.sql".to_string(), "version 1 is used twice".to_string()),
+                ("2".to_string(), "it has no `-- migrate:up` section with SQL".to_string()),
+                ("3".to_string(), "it has no `-- migrate:up` section with SQL".to_string()),
+                ("seed.sql".to_string(), "its name does not start with a version".to_string()),
+            ]
+        );
+        let [first] = conversion.migrations.as_slice() else { panic!("expected one migration") };
+        assert_eq!(first.up_sql, "SELECT 1;\n");
+    }
+
+    #[test]
+    fn markers_need_their_own_comment() {
+        let (up, down) = sections("-- migrate:up\nSELECT '-- migrate:down';\n-- migrate:upgrade\n-- migrate:down\nSELECT 2;\n");
+        assert_eq!(up.unwrap().sql, "SELECT '-- migrate:down';\n-- migrate:upgrade\n");
+        assert_eq!(down.unwrap().sql, "SELECT 2;\n");
+    }
+}
@@ -0,0 +1,184 @@
+use {
+    crate::core::{
+        bootstrap,
+        migration_diff::{self, MigrationOperation},
+        repo::Dialect,
+        snapshot,
+    },
+    std::path::{Path, PathBuf},
+};
+
+/// Declarative schema next to qop.toml that `generate` treats as the source of truth
+pub const SCHEMA_FILE: &str = "schema.sql";
+
+/// The declarative schema: `--schema` as given, else the configured file (default `schema.sql`)
+/// relative to the config file
+pub fn schema_file(path: &Path, configured: Option<&str>, explicit: Option<&Path>) -> PathBuf {
+    if let Some(explicit) = explicit {
+        return explicit.to_path_buf();
+    }
+    let file = configured.unwrap_or(SCHEMA_FILE);
+    path.parent().map(|dir| dir.join(file)).unwrap_or_else(|| file.into())
+}
+
+/// Statements turning one schema into another, and back
+#[derive(Debug, Default)]
+pub struct SchemaChange {
+    pub up: Vec<String>,
+    pub down: Vec<String>,
+}
+
+impl SchemaChange {
+    pub fn is_empty(&self) -> bool {
+        self.up.is_empty()
+    }
+}
+
+/// Diff two schema dumps object by object. New objects are created and missing ones dropped,
+/// changed tables are altered column by column and other changed objects are dropped and
+/// recreated. Changes that cannot be expressed, e.g. a changed column type, become TODO comments.
+pub fn diff_schemas(current: &str, desired: &str, dialect: Dialect) -> SchemaChange {
+    let current = snapshot::schema_objects(current);
+    let desired = snapshot::schema_objects(desired);
+    let mut up = Vec::new();
+    // Down statements per object in up order, reversed at the end
+    let mut down: Vec<Vec<String>> = Vec::new();
+
+    // Objects missing from the desired schema are dropped first, dependents before their dependencies
+    for (key, statements) in current.iter().rev() {
+        if desired.iter().any(|(desired_key, _)| desired_key == key) {
+            continue;
+        }
+        up.extend(drop_object(statements, dialect));
+        down.push(terminated(statements));
+    }
+    for (key, statements) in &desired {
+        match current.iter().find(|(current_key, _)| current_key == key) {
+            | None => {
+                up.extend(terminated(statements));
+                down.push(drop_object(statements, dialect));
+            },
+            | Some((_, existing)) if existing == statements => {},
+            | Some((_, existing)) if key.starts_with("table ") => {
+                let (table_up, table_down) = diff_table(existing, statements, dialect);
+                up.extend(table_up);
+                down.push(table_down);
+            },
+            | Some((_, existing)) => {
+                up.extend(drop_object(existing, dialect));
+                up.extend(terminated(statements));
+                let mut object_down = drop_object(statements, dialect);
+                object_down.extend(terminated(existing));
+                down.push(object_down);
+            },
+        }
+    }
+    SchemaChange { up, down: down.into_iter().rev().flatten().collect() }
+}
+
+fn terminated(statements: &[String]) -> Vec<String> {
+    statements.iter().map(|statement| format!("{};", statement)).collect()
+}
+
+/// Statements dropping an object; a table is dropped as a whole instead of constraint by constraint
+fn drop_object(statements: &[String], dialect: Dialect) -> Vec<String> {
+    match statements.iter().find(|s| matches!(migration_diff::parse_statement(s), MigrationOperation::CreateTable { .. })) {
+        | Some(create) => bootstrap::invert_statement(create, dialect),
+        | None => statements.iter().rev().flat_map(|s| bootstrap::invert_statement(s, dialect)).collect(),
+    }
+}
+
+/// Alter a table in place: columns are added and dropped, other statements of the table such as
+/// constraints and unnamed indexes are added or reverted
+fn diff_table(existing: &[String], desired: &[String], dialect: Dialect) -> (Vec<String>, Vec<String>) {
+    let (mut up, mut down) = (Vec::new(), Vec::new());
+    let create = |statements: &[String]| {
+        statements.iter().find_map(|s| match migration_diff::parse_statement(s) {
+            | MigrationOperation::CreateTable { name, .. } => Some((name, table_elements(s))),
+            | _ => None,
+        })
+    };
+    match (create(existing), create(desired)) {
+        | (Some((_, (old_columns, old_constraints))), Some((table, (new_columns, new_constraints)))) => {
+            for (column, definition) in &new_columns {
+                match old_columns.iter().find(|(old, _)| old == column) {
+                    | None => {
+                        up.push(format!("ALTER TABLE {} ADD COLUMN {};", table, definition));
+                        down.push(format!("ALTER TABLE {} DROP COLUMN {};", table, column));
+                    },
+                    | Some((_, old)) if old != definition => {
+                        up.push(format!("-- TODO: change column {}.{} from `{}` to `{}`", table, column, old, definition));
+                        down.push(format!("-- TODO: change column {}.{} from `{}` to `{}`", table, column, definition, old));
+                    },
+                    | Some(_) => {},
+                }
+            }
+            for (column, definition) in old_columns.iter().filter(|(old, _)| !new_columns.iter().any(|(new, _)| new == old)) {
+                up.push(format!("ALTER TABLE {} DROP COLUMN {};", table, column));
+                down.push(format!("ALTER TABLE {} ADD COLUMN {};", table, definition));
+            }
+            if old_constraints != new_constraints {
+                up.push(format!("-- TODO: change the constraints of {} from `{}` to `{}`", table, old_constraints.join(", "), new_constraints.join(", ")));
+                down.push(format!("-- TODO: change the constraints of {} from `{}` to `{}`", table, new_constraints.join(", "), old_constraints.join(", ")));
+            }
+        },
+        | _ => {
+            up.push("-- TODO: table definition changed, could not compare the CREATE TABLE statements".to_string());
+            down.push("-- TODO: table definition changed, could not compare the CREATE TABLE statements".to_string());
+        },
+    }
+
+    let is_create = |s: &&String| matches!(migration_diff::parse_statement(s), MigrationOperation::CreateTable { .. });
+    for statement in desired.iter().filter(|s| !is_create(s) && !existing.contains(s)) {
+        up.push(format!("{};", statement));
+        down.splice(0..0, bootstrap::invert_statement(statement, dialect));
+    }
+    for statement in existing.iter().filter(|s| !is_create(s) && !desired.contains(s)) {
+        up.extend(bootstrap::invert_statement(statement, dialect));
+        down.insert(0, format!("{};", statement));
+    }
+    (up, down)
+}
+
+/// Columns as `(name, definition)` and table constraints of a CREATE TABLE statement
+fn table_elements(statement: &str) -> (Vec<(String, String)>, Vec<String>) {
+    let (mut columns, mut constraints) = (Vec::new(), Vec::new());
+    let Some(start) = statement.find('(') else {
+        return (columns, constraints);
+    };
+    let mut depth = 0;
+    let mut quote: Option<char> = None;
+    let mut current = String::new();
+    let mut elements = Vec::new();
+    for c in statement[start + 1..].chars() {
+        match (quote, c) {
+            | (Some(q), c) if c == q => quote = None,
+            | (Some(_), _) => {},
+            | (None, '\'' | '"' | '`') => quote = Some(c),
+            | (None, '(') => depth += 1,
+            | (None, ')') if depth == 0 => break,
+            | (None, ')') => depth -= 1,
+            | (None, ',') if depth == 0 => {
+                elements.push(std::mem::take(&mut current));
+                continue;
+            },
+            | _ => {},
+        }
+        current.push(c);
+    }
+    elements.push(current);
+
+    for element in elements.iter().map(|e| e.trim()).filter(|e| !e.is_empty()) {
+        let first = element.split(|c: char| c.is_whitespace() || c == '(').next().unwrap_or_default().to_uppercase();
+        if ["CONSTRAINT", "PRIMARY", "FOREIGN", "UNIQUE", "CHECK", "EXCLUDE"].contains(&first.as_str()) {
+            constraints.push(element.to_string());
+            continue;
+        }
+        let name = match element.chars().next() {
+            | Some(q @ ('"' | '`')) => element[1..].find(q).map(|end| &element[..end + 2]).unwrap_or(element),
+            | _ => element.split_whitespace().next().unwrap_or_default(),
+        };
+        columns.push((name.to_string(), element.to_string()));
+    }
+    (columns, constraints)
+}
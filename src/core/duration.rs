@@ -0,0 +1,66 @@
+use {
+    anyhow::Result,
+    serde::{Deserialize, Deserializer},
+    std::time::Duration,
+};
+
+/// Parse a duration such as `120s`, `2m`, `500ms`, `1h` or `1h30m`; a bare number is taken as seconds
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let invalid = || anyhow::anyhow!("invalid duration: {} (expected e.g. 500ms, 30s, 2m, 1h)", value);
+    if value.is_empty() {
+        return Err(invalid());
+    }
+    let mut rest = value;
+    let mut total = Duration::ZERO;
+    while !rest.is_empty() {
+        let split = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let (amount, tail) = rest.split_at(split);
+        let amount: u64 = amount.parse().map_err(|_| invalid())?;
+        let unit_end = tail.find(|c: char| c.is_ascii_digit()).unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_end);
+        total += match unit.trim() {
+            | "ms" => Duration::from_millis(amount),
+            | "" | "s" => Duration::from_secs(amount),
+            | "m" => Duration::from_secs(amount * 60),
+            | "h" => Duration::from_secs(amount * 3600),
+            | unit => anyhow::bail!("invalid duration unit: {} (expected ms, s, m or h)", unit),
+        };
+        rest = tail;
+    }
+    Ok(total)
+}
+
+/// Parse a duration of whole seconds, as timeouts are given in; see `parse_duration`
+pub fn parse_seconds(value: &str) -> Result<u64> {
+    let duration = parse_duration(value)?;
+    if duration.subsec_nanos() != 0 {
+        anyhow::bail!("invalid duration: {} (must be whole seconds)", value.trim());
+    }
+    Ok(duration.as_secs())
+}
+
+/// Deserialize an optional number of seconds given either as a number or as a duration string
+/// such as `"5m"`
+pub fn deserialize_seconds<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Option<u64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Seconds {
+        Number(u64),
+        Text(String),
+    }
+    Ok(match Option::<Seconds>::deserialize(deserializer)? {
+        | Some(Seconds::Number(seconds)) => Some(seconds),
+        | Some(Seconds::Text(text)) => Some(parse_seconds(&text).map_err(serde::de::Error::custom)?),
+        | None => None,
+    })
+}
+
+/// Render milliseconds for tables, e.g. `850 ms`, `12.3 s` or `4m 05s`
+pub fn format_millis(ms: i64) -> String {
+    match ms {
+        | ..1_000 => format!("{} ms", ms),
+        | 1_000..60_000 => format!("{:.1} s", ms as f64 / 1000.0),
+        | _ => format!("{}m {:02}s", ms / 60_000, ms % 60_000 / 1000),
+    }
+}
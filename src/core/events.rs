@@ -0,0 +1,87 @@
+use {
+    crate::core::{log, output, telemetry::{self, MigrationRun}},
+    anyhow::{Context, Result},
+    chrono::{SecondsFormat, Utc},
+    serde_json::{json, Value},
+    std::{
+        fs::{File, OpenOptions},
+        io::Write,
+        path::PathBuf,
+        sync::Mutex,
+        time::Duration,
+    },
+};
+
+/// Where `--events` writes its JSON lines, if it was given
+static SINK: Mutex<Option<File>> = Mutex::new(None);
+
+/// Open the destination of `--events`: `fd:<N>` for a file descriptor the calling tool passed down,
+/// such as a pipe, or else a file the events are appended to
+pub fn open(dest: &str) -> Result<()> {
+    let path = match dest.strip_prefix("fd:") {
+        | Some(fd) => PathBuf::from(format!("/dev/fd/{}", fd.parse::<u32>().with_context(|| format!("invalid file descriptor in --events {}", dest))?)),
+        | None => PathBuf::from(dest),
+    };
+    let file = OpenOptions::new().create(true).append(true).open(&path).with_context(|| format!("Failed to open {} for events", path.display()))?;
+    *SINK.lock().unwrap_or_else(|e| e.into_inner()) = Some(file);
+    // Rows changed by the migrations are only counted while telemetry records
+    telemetry::enable();
+    Ok(())
+}
+
+/// Write one event as a line of JSON; a failing destination only produces a warning and no more events
+fn emit(event: &str, fields: Value) {
+    let mut sink = SINK.lock().unwrap_or_else(|e| e.into_inner());
+    let Some(file) = sink.as_mut() else { return };
+    let mut line = json!({ "event": event, "timestamp": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true) });
+    let job = output::job();
+    if let Some(object) = line.as_object_mut() {
+        if !job.is_empty() {
+            object.insert("job".to_string(), Value::String(job));
+        }
+        if let Value::Object(fields) = fields {
+            object.extend(fields);
+        }
+    }
+    if let Err(e) = writeln!(file, "{}", line).and_then(|_| file.flush()) {
+        *sink = None;
+        crate::esay!("⚠️  Failed to write events, no more are sent: {}", e);
+    }
+}
+
+pub fn run_started(targets: &[String]) {
+    emit("run_started", json!({ "version": env!("CARGO_PKG_VERSION"), "command": log::invocation(), "targets": targets }));
+}
+
+pub fn migration_started(id: &str, direction: &str) {
+    emit("migration_started", json!({ "id": id, "direction": direction }));
+}
+
+/// Statement `number` of `total` of migration `id` ran, changing `rows` rows
+pub fn statement_executed(id: &str, number: usize, total: usize, line: usize, rows: u64, elapsed: Duration) {
+    emit("statement_executed", json!({ "id": id, "statement": number, "total": total, "line": line, "rows": rows, "duration_ms": elapsed.as_millis() as u64 }));
+}
+
+/// `migration_applied`, `migration_reverted` or `migration_failed`
+pub fn migration_finished(run: &MigrationRun) {
+    let event = match (&run.error, run.direction) {
+        | (Some(_), _) => "migration_failed",
+        | (None, "down") => "migration_reverted",
+        | (None, _) => "migration_applied",
+    };
+    emit(
+        event,
+        json!({
+            "id": run.id,
+            "direction": run.direction,
+            "duration_ms": run.duration.as_millis() as u64,
+            "rows": run.rows,
+            "dry_run": run.dry_run,
+            "error": run.error,
+        }),
+    );
+}
+
+pub fn run_finished(elapsed: Duration, error: Option<&anyhow::Error>) {
+    emit("run_finished", json!({ "success": error.is_none(), "duration_ms": elapsed.as_millis() as u64, "error": error.map(|e| format!("{:#}", e)) }));
+}
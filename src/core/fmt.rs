@@ -0,0 +1,76 @@
+use {
+    crate::core::migration as util,
+    anyhow::{Context, Result},
+    serde::{Deserialize, Serialize},
+    std::{collections::HashSet, path::Path},
+};
+
+/// Formatter settings, configured per subsystem in the `fmt` table of qop.toml
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", default)]
+pub struct FormatConfig {
+    /// Number of spaces per indentation level (ignored with `tabs`)
+    pub indent: u8,
+    pub tabs: bool,
+    /// Upper-case reserved keywords
+    pub uppercase: bool,
+    pub lines_between_queries: u8,
+}
+
+impl Default for FormatConfig {
+    fn default() -> Self {
+        Self { indent: 2, tabs: false, uppercase: true, lines_between_queries: 2 }
+    }
+}
+
+/// Format SQL with the given settings; the result always ends with a newline
+pub fn format_sql(sql: &str, config: &FormatConfig) -> String {
+    let options = sqlformat::FormatOptions {
+        indent: if config.tabs { sqlformat::Indent::Tabs } else { sqlformat::Indent::Spaces(config.indent) },
+        uppercase: config.uppercase,
+        lines_between_queries: config.lines_between_queries,
+    };
+    let mut formatted = sqlformat::format(sql, &sqlformat::QueryParams::None, options);
+    formatted.push('\n');
+    formatted
+}
+
+/// Format up.sql/down.sql of all local migrations except `skip` (the applied ones, unless
+/// `--all` is given). In check mode nothing is written and an error is returned when any
+/// file is not formatted.
+pub fn format_migrations(path: &Path, config: &FormatConfig, check: bool, skip: &HashSet<String>) -> Result<()> {
+    let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+    let mut ids: Vec<String> = util::get_local_migrations(path)?.into_iter().filter(|id| !skip.contains(id)).collect();
+    util::sort_ids(&mut ids);
+
+    let mut changed = 0usize;
+    for id in &ids {
+        for file in ["up.sql", "down.sql"] {
+            let file_path = migration_dir.join(format!("id={}", id)).join(file);
+            let content = std::fs::read_to_string(&file_path)
+                .with_context(|| format!("Failed to read migration file: {}", file_path.display()))?;
+            let formatted = format_sql(&content, config);
+            if formatted == content {
+                continue;
+            }
+            changed += 1;
+            if check {
+                crate::say!("❌ {}", file_path.display());
+            } else {
+                std::fs::write(&file_path, &formatted)
+                    .with_context(|| format!("Failed to write migration file: {}", file_path.display()))?;
+                crate::say!("✏️  {}", file_path.display());
+            }
+        }
+    }
+
+    if check && changed > 0 {
+        anyhow::bail!("{} file(s) are not formatted; run fmt without --check to fix them", changed);
+    }
+    if changed == 0 {
+        crate::summary!("✅ All {} migration(s) are formatted.", ids.len());
+    } else {
+        crate::summary!("\n🎉 Formatted {} file(s)!", changed);
+    }
+    Ok(())
+}
@@ -0,0 +1,258 @@
+use {
+    crate::core::migration as util,
+    chrono::NaiveDateTime,
+    comfy_table::{Cell, CellAlignment, ContentArrangement},
+    serde::Serialize,
+    std::collections::{BTreeMap, BTreeSet, HashSet},
+};
+
+#[derive(Debug, Clone, Copy)]
+pub enum GraphFormat {
+    Ascii,
+    Dot,
+}
+
+/// The `pre` linkage of applied migrations with its irregularities
+pub struct ChainGraph {
+    /// pre -> successors, both sorted by id
+    children: BTreeMap<String, Vec<String>>,
+    /// Migrations without a `pre`, the first one is the regular root
+    roots: Vec<String>,
+    /// (id, pre) where `pre` references a migration that is not applied
+    breaks: Vec<(String, String)>,
+    /// Migrations with more than one successor
+    forks: BTreeSet<String>,
+    /// Migrations that are not reachable from any root, i.e. part of a cycle
+    unreachable: Vec<String>,
+    len: usize,
+}
+
+impl ChainGraph {
+    pub fn new(chain: &[(String, Option<String>)]) -> Self {
+        let ids: HashSet<&str> = chain.iter().map(|(id, _)| id.as_str()).collect();
+        let mut children: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut roots = Vec::new();
+        let mut breaks = Vec::new();
+        for (id, pre) in chain {
+            match pre {
+                | None => roots.push(id.clone()),
+                | Some(pre) if !ids.contains(pre.as_str()) => breaks.push((id.clone(), pre.clone())),
+                | Some(pre) => children.entry(pre.clone()).or_default().push(id.clone()),
+            }
+        }
+        util::sort_ids(&mut roots);
+        breaks.sort_by(|a, b| util::compare_ids(&a.0, &b.0));
+        for successors in children.values_mut() {
+            util::sort_ids(successors);
+        }
+        let forks = children.iter().filter(|(_, c)| c.len() > 1).map(|(id, _)| id.clone()).collect();
+
+        let mut graph = Self { children, roots, breaks, forks, unreachable: Vec::new(), len: chain.len() };
+        let mut visited = HashSet::new();
+        let starts: Vec<String> = graph.roots.iter().cloned().chain(graph.breaks.iter().map(|(id, _)| id.clone())).collect();
+        let mut stack = starts;
+        while let Some(id) = stack.pop() {
+            if visited.insert(id.clone()) {
+                stack.extend(graph.successors(&id).iter().cloned());
+            }
+        }
+        let mut unreachable: Vec<String> = chain.iter().map(|(id, _)| id.clone()).filter(|id| !visited.contains(id)).collect();
+        util::sort_ids(&mut unreachable);
+        graph.unreachable = unreachable;
+        graph
+    }
+
+    fn successors(&self, id: &str) -> &[String] {
+        self.children.get(id).map(|c| c.as_slice()).unwrap_or_default()
+    }
+
+    pub fn is_linear(&self) -> bool {
+        self.roots.len() <= 1 && self.breaks.is_empty() && self.forks.is_empty() && self.unreachable.is_empty()
+    }
+
+    /// Render the chain as an ASCII tree; linear stretches stay at the same indentation
+    pub fn render_ascii(&self) -> String {
+        let mut out = String::new();
+        let mut visited = HashSet::new();
+        for (i, root) in self.roots.iter().enumerate() {
+            let note = if i == 0 { "(root)".to_string() } else { "⚠️  additional root without pre".to_string() };
+            self.render_node(root, &note, "", "", &mut visited, &mut out);
+        }
+        for (id, pre) in &self.breaks {
+            let note = format!("⚠️  break: pre {} is not applied", pre);
+            self.render_node(id, &note, "", "", &mut visited, &mut out);
+        }
+        for id in &self.unreachable {
+            if !visited.contains(id) {
+                self.render_node(id, "⚠️  cycle: not reachable from any root", "", "", &mut visited, &mut out);
+            }
+        }
+        crate::core::output::ascii(&out)
+    }
+
+    fn render_node(&self, id: &str, note: &str, first_prefix: &str, prefix: &str, visited: &mut HashSet<String>, out: &mut String) {
+        // Linear stretches are walked iteratively, only forks recurse
+        let (mut id, mut note, mut first_prefix) = (id.to_string(), note.to_string(), first_prefix.to_string());
+        loop {
+            if !visited.insert(id.clone()) {
+                out.push_str(&format!("{}↺ {} (already shown)\n", first_prefix, id));
+                return;
+            }
+            let successors = self.successors(&id);
+            let mut line = format!("{}● {}", first_prefix, id);
+            if !note.is_empty() {
+                line.push_str(&format!("  {}", note));
+            }
+            if self.forks.contains(&id) {
+                line.push_str(&format!("  ⚠️  fork: {} successors", successors.len()));
+            }
+            out.push_str(&line);
+            out.push('\n');
+
+            if let [next] = successors {
+                (id, note, first_prefix) = (next.clone(), String::new(), prefix.to_string());
+                continue;
+            }
+            for (i, child) in successors.iter().enumerate() {
+                let last = i + 1 == successors.len();
+                let branch = format!("{}{}", prefix, if last { "└── " } else { "├── " });
+                let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+                self.render_node(child, "", &branch, &child_prefix, visited, out);
+            }
+            return;
+        }
+    }
+
+    /// Render the chain in Graphviz DOT format; irregular nodes and edges are highlighted
+    pub fn render_dot(&self) -> String {
+        let mut out = String::new();
+        out.push_str("digraph qop {\n");
+        out.push_str("    rankdir=TB;\n");
+        out.push_str("    node [shape=box, fontname=\"monospace\"];\n");
+        for (i, root) in self.roots.iter().enumerate() {
+            let color = if i == 0 { "black" } else { "red" };
+            out.push_str(&format!("    \"{}\" [color={}];\n", root, color));
+        }
+        for fork in &self.forks {
+            out.push_str(&format!("    \"{}\" [color=orange, style=bold];\n", fork));
+        }
+        for id in &self.unreachable {
+            out.push_str(&format!("    \"{}\" [color=red];\n", id));
+        }
+        for (id, pre) in &self.breaks {
+            out.push_str(&format!("    \"{}\" [label=\"{} (missing)\", style=dashed, color=red];\n", pre, pre));
+            out.push_str(&format!("    \"{}\" -> \"{}\" [style=dashed, color=red];\n", pre, id));
+        }
+        for (pre, successors) in &self.children {
+            for id in successors {
+                out.push_str(&format!("    \"{}\" -> \"{}\";\n", pre, id));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn print_summary(&self) {
+        if self.len == 0 {
+            println!("No applied migrations.");
+        } else if self.is_linear() {
+            crate::summary!("✅ Linear chain of {} migration(s).", self.len);
+        } else {
+            crate::say!(
+                "⚠️  Non-linear chain of {} migration(s): {} root(s), {} break(s), {} fork(s), {} unreachable.",
+                self.len,
+                self.roots.len(),
+                self.breaks.len(),
+                self.forks.len(),
+                self.unreachable.len()
+            );
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainIssueKind {
+    /// `pre` references a migration that is not in the tracking table
+    Gap,
+    /// Another record already links to the same `pre`
+    Fork,
+    /// `pre` differs from the migration applied immediately before
+    Mismatch,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChainIssue {
+    pub id: String,
+    pub kind: ChainIssueKind,
+    pub expected_pre: Option<String>,
+    pub actual_pre: Option<String>,
+}
+
+/// Order records as `(id, pre, created_at)` by application time, with the id breaking ties
+fn applied_order(chain: &[(String, Option<String>, NaiveDateTime)]) -> Vec<&(String, Option<String>, NaiveDateTime)> {
+    let mut ordered: Vec<_> = chain.iter().collect();
+    ordered.sort_by(|a, b| a.2.cmp(&b.2).then_with(|| a.0.cmp(&b.0)));
+    ordered
+}
+
+/// Check every record's `pre` against the migration applied before it
+pub fn verify_chain(chain: &[(String, Option<String>, NaiveDateTime)]) -> Vec<ChainIssue> {
+    let ids: HashSet<&str> = chain.iter().map(|(id, ..)| id.as_str()).collect();
+    let mut linked: HashSet<Option<&str>> = HashSet::new();
+    let mut issues = Vec::new();
+    let mut expected: Option<&str> = None;
+    for (id, pre, _) in applied_order(chain) {
+        let kind = match pre.as_deref() {
+            | Some(pre) if !ids.contains(pre) => Some(ChainIssueKind::Gap),
+            | pre if linked.contains(&pre) => Some(ChainIssueKind::Fork),
+            | pre if pre != expected => Some(ChainIssueKind::Mismatch),
+            | _ => None,
+        };
+        linked.insert(pre.as_deref());
+        if let Some(kind) = kind {
+            issues.push(ChainIssue {
+                id: id.clone(),
+                kind,
+                expected_pre: expected.map(|s| s.to_string()),
+                actual_pre: pre.clone(),
+            });
+        }
+        expected = Some(id);
+    }
+    issues
+}
+
+/// The `(id, pre)` links of a chain rebuilt from application order
+pub fn rebuild_chain(chain: &[(String, Option<String>, NaiveDateTime)]) -> Vec<(String, Option<String>)> {
+    let mut links = Vec::new();
+    let mut previous: Option<String> = None;
+    for (id, ..) in applied_order(chain) {
+        links.push((id.clone(), previous.clone()));
+        previous = Some(id.clone());
+    }
+    links
+}
+
+pub fn render_issue_table(issues: &[ChainIssue]) {
+    let mut table = crate::core::output::table();
+    table
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec![Cell::new("Migration"), Cell::new("Issue"), Cell::new("Expected Pre"), Cell::new("Actual Pre")]);
+
+    for issue in issues {
+        let kind = match issue.kind {
+            | ChainIssueKind::Gap => "🕳️ gap",
+            | ChainIssueKind::Fork => "🔀 fork",
+            | ChainIssueKind::Mismatch => "⚠️ mismatch",
+        };
+        table.add_row(vec![
+            Cell::new(&issue.id),
+            Cell::new(crate::core::output::ascii(kind)).set_alignment(CellAlignment::Center),
+            Cell::new(issue.expected_pre.as_deref().unwrap_or("-")),
+            Cell::new(issue.actual_pre.as_deref().unwrap_or("-")),
+        ]);
+    }
+
+    println!("{table}");
+}
@@ -0,0 +1,131 @@
+use crate::core::{migration_diff, output};
+
+const RESET: &str = "\x1b[0m";
+const KEYWORD: &str = "\x1b[1;34m";
+const TYPE: &str = "\x1b[36m";
+const STRING: &str = "\x1b[32m";
+const NUMBER: &str = "\x1b[35m";
+const COMMENT: &str = "\x1b[2;37m";
+
+/// Keywords and types are sorted for the binary search
+const KEYWORDS: &[&str] = &[
+    "ADD", "AFTER", "ALL", "ALTER", "AND", "AS", "ASC", "AUTOINCREMENT", "BEFORE", "BEGIN", "BETWEEN", "BY", "CASCADE", "CASE", "CHECK", "COLLATE", "COLUMN",
+    "COMMENT", "COMMIT", "CONCURRENTLY", "CONFLICT", "CONSTRAINT", "CREATE", "CROSS", "DEFAULT", "DEFERRABLE", "DELETE", "DESC", "DISTINCT", "DO", "DROP",
+    "EACH", "ELSE", "END", "EXECUTE", "EXISTS", "EXTENSION", "FALSE", "FOR", "FOREIGN", "FROM", "FULL", "FUNCTION", "GRANT", "GROUP", "HAVING", "IF", "IN",
+    "INDEX", "INNER", "INSERT", "INSTEAD", "INTO", "IS", "JOIN", "KEY", "LANGUAGE", "LEFT", "LIKE", "LIMIT", "NOT", "NOTHING", "NULL", "OF", "OFFSET", "ON",
+    "OR", "ORDER", "OUTER", "OWNER", "PRAGMA", "PRIMARY", "PROCEDURE", "REFERENCES", "RENAME", "REPLACE", "RESTRICT", "RETURN", "RETURNING", "RETURNS",
+    "REVOKE", "RIGHT", "ROLLBACK", "ROW", "SCHEMA", "SELECT", "SEQUENCE", "SET", "TABLE", "TEMP", "TEMPORARY", "THEN", "TO", "TRANSACTION", "TRIGGER",
+    "TRUE", "TRUNCATE", "TYPE", "UNION", "UNIQUE", "UPDATE", "USING", "VALUES", "VIEW", "WHEN", "WHERE", "WITH", "WITHOUT",
+];
+
+const TYPES: &[&str] = &[
+    "BIGINT", "BIGSERIAL", "BLOB", "BOOL", "BOOLEAN", "BYTEA", "CHAR", "CHARACTER", "DATE", "DECIMAL", "DOUBLE", "FLOAT", "INT", "INTEGER", "INTERVAL",
+    "JSON", "JSONB", "NUMERIC", "PRECISION", "REAL", "SERIAL", "SMALLINT", "TEXT", "TIME", "TIMESTAMP", "TIMESTAMPTZ", "UUID", "VARCHAR", "VARYING",
+];
+
+/// `sql` with keywords, types, strings, numbers and comments colored, when colors are enabled;
+/// unchanged otherwise
+pub fn sql(sql: &str) -> String {
+    if !output::preferences().color || output::plain() {
+        return sql.to_string();
+    }
+    let chars: Vec<char> = sql.chars().collect();
+    let mut out = String::with_capacity(sql.len() * 2);
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        let color = match c {
+            | '-' if chars.get(i + 1) == Some(&'-') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                Some(COMMENT)
+            },
+            | '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i = (i + 2).min(chars.len());
+                Some(COMMENT)
+            },
+            | '\'' | '"' | '`' => {
+                i += 1;
+                while i < chars.len() {
+                    if chars[i] == c {
+                        // doubled quote is an escaped quote
+                        if chars.get(i + 1) == Some(&c) {
+                            i += 2;
+                            continue;
+                        }
+                        break;
+                    }
+                    i += 1;
+                }
+                i = (i + 1).min(chars.len());
+                // Quoted identifiers stay uncolored, like unquoted ones
+                if c == '\'' { Some(STRING) } else { None }
+            },
+            | '$' => match migration_diff::dollar_tag_len(&chars[i..]) {
+                | Some(tag_len) => {
+                    let tag = &chars[i..i + tag_len];
+                    i += tag_len;
+                    while i < chars.len() && !chars[i..].starts_with(tag) {
+                        i += 1;
+                    }
+                    i = (i + tag_len).min(chars.len());
+                    Some(STRING)
+                },
+                | None => {
+                    i += 1;
+                    None
+                },
+            },
+            | c if c.is_ascii_digit() => {
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                    i += 1;
+                }
+                Some(NUMBER)
+            },
+            | c if c.is_alphabetic() || c == '_' => {
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '$') {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect::<String>().to_ascii_uppercase();
+                // Qualified names such as `public.user` are never keywords
+                let qualified = start > 0 && chars[start - 1] == '.';
+                match qualified {
+                    | true => None,
+                    | false if KEYWORDS.binary_search(&word.as_str()).is_ok() => Some(KEYWORD),
+                    | false if TYPES.binary_search(&word.as_str()).is_ok() => Some(TYPE),
+                    | false => None,
+                }
+            },
+            | _ => {
+                i += 1;
+                None
+            },
+        };
+        let token: String = chars[start..i].iter().collect();
+        match color {
+            | Some(color) => paint(&mut out, &token, color),
+            | None => out.push_str(&token),
+        }
+    }
+    out
+}
+
+/// Color every line of `token` on its own, so pagers that render line by line keep the color
+fn paint(out: &mut String, token: &str, color: &str) {
+    for (n, line) in token.split('\n').enumerate() {
+        if n > 0 {
+            out.push('\n');
+        }
+        if !line.is_empty() {
+            out.push_str(color);
+            out.push_str(line);
+            out.push_str(RESET);
+        }
+    }
+}
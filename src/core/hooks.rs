@@ -0,0 +1,157 @@
+use {
+    crate::core::repo::MigrationRepository,
+    anyhow::{Context, Result},
+    serde::{Deserialize, Serialize},
+    std::process::Stdio,
+};
+
+/// Hooks run around `up`, `down` and `apply`, configured in qop.toml
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct Hooks {
+    /// Once before the first migration of a run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub before_run: Vec<Hook>,
+    /// Once after all migrations of a run succeeded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub after_run: Vec<Hook>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub before_each: Vec<Hook>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub after_each: Vec<Hook>,
+}
+
+impl Hooks {
+    pub fn is_empty(&self) -> bool {
+        self.before_run.is_empty() && self.after_run.is_empty() && self.before_each.is_empty() && self.after_each.is_empty()
+    }
+
+    pub fn for_stage(&self, stage: HookStage) -> &[Hook] {
+        match stage {
+            | HookStage::BeforeRun => &self.before_run,
+            | HookStage::AfterRun => &self.after_run,
+            | HookStage::BeforeEach => &self.before_each,
+            | HookStage::AfterEach => &self.after_each,
+        }
+    }
+}
+
+/// Hooks of a single migration from its meta.toml, run after the ones from qop.toml
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub struct MigrationHooks {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub before_each: Vec<Hook>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub after_each: Vec<Hook>,
+}
+
+impl MigrationHooks {
+    pub fn for_stage(&self, stage: HookStage) -> &[Hook] {
+        match stage {
+            | HookStage::BeforeEach => &self.before_each,
+            | HookStage::AfterEach => &self.after_each,
+            | HookStage::BeforeRun | HookStage::AfterRun => &[],
+        }
+    }
+}
+
+/// A SQL snippet executed in its own transaction, or a program run from the config directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Hook {
+    Sql {
+        sql: String,
+    },
+    Command {
+        program: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        args: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookStage {
+    BeforeRun,
+    AfterRun,
+    BeforeEach,
+    AfterEach,
+}
+
+impl HookStage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            | HookStage::BeforeRun => "before_run",
+            | HookStage::AfterRun => "after_run",
+            | HookStage::BeforeEach => "before_each",
+            | HookStage::AfterEach => "after_each",
+        }
+    }
+}
+
+/// What a hook runs for; commands see it as `QOP_*` environment variables, SQL as `{{...}}` placeholders
+#[derive(Debug, Clone, Copy)]
+pub struct HookContext<'a> {
+    pub stage: HookStage,
+    /// `up` or `down`
+    pub direction: &'a str,
+    /// The migration of `before_each`/`after_each` hooks
+    pub id: Option<&'a str>,
+    /// All migrations of the run, in execution order
+    pub ids: &'a [String],
+}
+
+impl<'a> HookContext<'a> {
+    pub fn run(stage: HookStage, direction: &'a str, ids: &'a [String]) -> Self {
+        Self { stage, direction, id: None, ids }
+    }
+
+    pub fn each(stage: HookStage, direction: &'a str, id: &'a str, ids: &'a [String]) -> Self {
+        Self { stage, direction, id: Some(id), ids }
+    }
+}
+
+/// Run the configured hooks of a stage followed by the migration's own ones. SQL hooks are rolled
+/// back in dry runs, commands are skipped.
+pub async fn run_hooks<R: MigrationRepository>(repo: &R, ctx: HookContext<'_>, migration: Option<&MigrationHooks>, timeout: Option<u64>, dry_run: bool) -> Result<()> {
+    let migration_hooks = migration.map(|hooks| hooks.for_stage(ctx.stage)).unwrap_or_default();
+    for hook in repo.hooks().for_stage(ctx.stage).iter().chain(migration_hooks) {
+        let result = match hook {
+            | Hook::Sql { sql } => {
+                let sql = sql.replace("{{id}}", ctx.id.unwrap_or_default()).replace("{{direction}}", ctx.direction);
+                repo.execute_sql(&sql, timeout, dry_run).await
+            },
+            | Hook::Command { program, .. } if dry_run => {
+                crate::say!("⏭️  Skipping {} hook {} (dry run)", ctx.stage.as_str(), program);
+                Ok(())
+            },
+            | Hook::Command { program, args } => run_command(repo, program, args, &ctx),
+        };
+        match ctx.id {
+            | Some(id) => result.with_context(|| format!("{} hook of migration {} failed", ctx.stage.as_str(), id))?,
+            | None => result.with_context(|| format!("{} hook failed", ctx.stage.as_str()))?,
+        }
+    }
+    Ok(())
+}
+
+fn run_command<R: MigrationRepository>(repo: &R, program: &str, args: &[String], ctx: &HookContext<'_>) -> Result<()> {
+    let mut command = std::process::Command::new(program);
+    command
+        .args(args)
+        .env("QOP_HOOK", ctx.stage.as_str())
+        .env("QOP_DIRECTION", ctx.direction)
+        .env("QOP_MIGRATIONS", ctx.ids.join(","))
+        .stdin(Stdio::null());
+    if let Some(id) = ctx.id {
+        command.env("QOP_MIGRATION_ID", id);
+    }
+    if let Some(dir) = repo.get_path().parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        command.current_dir(dir);
+    }
+    let status = command.status().with_context(|| format!("Failed to start {}", program))?;
+    if !status.success() {
+        anyhow::bail!("{} exited with {}", program, status);
+    }
+    Ok(())
+}
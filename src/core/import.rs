@@ -0,0 +1,98 @@
+use {
+    crate::core::migration::{self as util, MigrationMeta},
+    anyhow::{Context, Result},
+    std::{collections::{HashMap, HashSet}, path::Path},
+};
+
+/// Label in meta.toml naming the migration an imported one was converted from, so importing again
+/// recognizes it instead of creating it twice
+pub const SOURCE_LABEL: &str = "imported_from";
+
+/// A migration of another tool, converted to up.sql, down.sql and meta.toml
+#[derive(Debug, Clone)]
+pub struct ForeignMigration {
+    /// How the other tool identifies the migration, e.g. `changelog.xml::1::alice` for a Liquibase changeset
+    pub key: String,
+    pub up_sql: String,
+    pub down_sql: String,
+    pub meta: MigrationMeta,
+    /// Whether the other tool's tracking table records it as applied
+    pub applied: bool,
+}
+
+/// What an import converted and what it could not
+#[derive(Debug, Default)]
+pub struct Conversion {
+    /// Migrations in the order the other tool runs them
+    pub migrations: Vec<ForeignMigration>,
+    /// (key, reason) of migrations that were not converted
+    pub skipped: Vec<(String, String)>,
+    /// (key, note) of converted migrations that need a review
+    pub notes: Vec<(String, String)>,
+}
+
+/// Value of the source label for the migration `key` of `tool`
+pub fn source(tool: &str, key: &str) -> String {
+    format!("{}:{}", tool, key)
+}
+
+/// Local migrations an earlier import created, by the value of their source label
+pub fn imported(migration_dir: &Path, local: &HashSet<String>) -> HashMap<String, String> {
+    local
+        .iter()
+        .filter_map(|id| {
+            let meta = util::read_migration_meta(migration_dir, id).ok()?;
+            Some((meta.labels.get(SOURCE_LABEL)?.clone(), id.clone()))
+        })
+        .collect()
+}
+
+/// Table names are put into queries as they are, so only plain and schema-qualified names are accepted
+pub fn validate_table(table: &str) -> Result<()> {
+    let valid = !table.is_empty() && table.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+    match valid {
+        | true => Ok(()),
+        | false => anyhow::bail!("invalid table name: {}", table),
+    }
+}
+
+/// Write a converted migration as `id=<id>` into the migration directory
+pub fn write(migration_dir: &Path, id: &str, migration: &ForeignMigration) -> Result<()> {
+    let target = migration_dir.join(format!("id={}", id));
+    std::fs::create_dir(&target).with_context(|| format!("Failed to create directory: {}", target.display()))?;
+    let meta = toml::to_string(&migration.meta).with_context(|| format!("Failed to serialize meta.toml for migration: {}", target.display()))?;
+    for (file, content) in [("up.sql", migration.up_sql.as_str()), ("down.sql", migration.down_sql.as_str()), ("meta.toml", meta.as_str())] {
+        let path = target.join(file);
+        std::fs::write(&path, content).with_context(|| format!("Failed to write migration file: {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Files of another tool in a temporary directory, removed when dropped
+#[cfg(test)]
+pub(crate) struct Fixture(std::path::PathBuf);
+
+#[cfg(test)]
+impl Fixture {
+    pub fn new(files: &[(&str, &str)]) -> Self {
+        let dir = std::env::temp_dir().join(format!("qop-import-{}", uuid::Uuid::now_v7()));
+        for (name, content) in files {
+            let path = dir.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    pub fn path(&self, name: &str) -> std::path::PathBuf {
+        self.0.join(name)
+    }
+}
+
+#[cfg(test)]
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
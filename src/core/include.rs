@@ -0,0 +1,40 @@
+use {
+    anyhow::{Context, Result},
+    std::path::{Path, PathBuf},
+};
+
+/// Line comment replaced by the content of the named file
+pub const INCLUDE_DIRECTIVE: &str = "-- qop:include ";
+
+/// Replace every `-- qop:include <file>` line with the content of the file, resolved relative to
+/// `base_dir`. Included files may include further files relative to their own directory.
+pub fn expand(sql: &str, base_dir: &Path) -> Result<String> {
+    if !sql.contains(INCLUDE_DIRECTIVE) {
+        return Ok(sql.to_string());
+    }
+    expand_nested(sql, base_dir, &mut Vec::new())
+}
+
+fn expand_nested(sql: &str, base_dir: &Path, stack: &mut Vec<PathBuf>) -> Result<String> {
+    let mut out = String::with_capacity(sql.len());
+    for line in sql.split_inclusive('\n') {
+        let Some(file) = line.trim().strip_prefix(INCLUDE_DIRECTIVE.trim_end()).map(str::trim).filter(|file| !file.is_empty()) else {
+            out.push_str(line);
+            continue;
+        };
+        let path = base_dir.join(file);
+        let canonical = path.canonicalize().with_context(|| format!("Failed to resolve include {}", path.display()))?;
+        if stack.contains(&canonical) {
+            anyhow::bail!("include cycle: {} includes itself", canonical.display());
+        }
+        let content = std::fs::read_to_string(&canonical).with_context(|| format!("Failed to read include {}", path.display()))?;
+        stack.push(canonical.clone());
+        let expanded = expand_nested(&content, canonical.parent().unwrap_or(base_dir), stack)?;
+        stack.pop();
+        out.push_str(&expanded);
+        if line.ends_with('\n') && !expanded.ends_with('\n') {
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,175 @@
+use {
+    crate::core::migration_diff::{self, AlterAction, MigrationOperation},
+    comfy_table::{Cell, CellAlignment, ContentArrangement},
+    serde::Serialize,
+    std::collections::HashSet,
+};
+
+const PLACEHOLDER: &str = "-- SQL goes here";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintLevel {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LintIssue {
+    pub migration_id: String,
+    pub file: &'static str,
+    pub level: LintLevel,
+    pub rule: &'static str,
+    pub message: String,
+}
+
+/// Tables that get a non-concurrent index in an existing table; their size decides whether this is flagged
+pub fn non_concurrent_index_tables(up_ops: &[MigrationOperation]) -> Vec<String> {
+    let created: Vec<&str> = up_ops
+        .iter()
+        .filter_map(|op| match op {
+            | MigrationOperation::CreateTable { name, .. } => Some(name.as_str()),
+            | _ => None,
+        })
+        .collect();
+    up_ops
+        .iter()
+        .filter_map(|op| match op {
+            | MigrationOperation::CreateIndex { table, concurrently: false, .. }
+                if !created.iter().any(|c| migration_diff::same_object(c, table)) => Some(table.clone()),
+            | _ => None,
+        })
+        .collect()
+}
+
+/// Whether Postgres refuses to run the statement inside a transaction block
+fn needs_no_transaction(statement: &str) -> bool {
+    let words: Vec<String> = statement.split_whitespace().take(4).map(|w| w.to_ascii_uppercase()).collect();
+    let words: Vec<&str> = words.iter().map(|w| w.as_str()).collect();
+    match words.as_slice() {
+        | ["CREATE", "INDEX", "CONCURRENTLY", ..] | ["CREATE", "UNIQUE", "INDEX", "CONCURRENTLY"] => true,
+        | ["DROP", "INDEX", "CONCURRENTLY", ..] | ["REINDEX", _, "CONCURRENTLY", ..] => true,
+        | ["VACUUM", ..] | ["ALTER", "SYSTEM", ..] | ["CREATE", "DATABASE", ..] | ["DROP", "DATABASE", ..] => true,
+        | _ => false,
+    }
+}
+
+/// Lint the up/down SQL of a single migration.
+/// `large_tables` contains the tables (as written in the SQL) known to exceed the size threshold,
+/// `transaction` whether the migration runs in a transaction (`transaction` in meta.toml).
+pub fn lint_migration(migration_id: &str, up_sql: &str, down_sql: &str, large_tables: &HashSet<String>, transaction: bool) -> Vec<LintIssue> {
+    let mut issues = Vec::new();
+    let mut issue = |file: &'static str, level: LintLevel, rule: &'static str, message: String| {
+        issues.push(LintIssue { migration_id: migration_id.to_string(), file, level, rule, message });
+    };
+
+    let up_ops = migration_diff::parse_migration_operations(up_sql);
+    let down_ops = migration_diff::parse_migration_operations(down_sql);
+
+    if up_ops.is_empty() {
+        let message = if up_sql.trim() == PLACEHOLDER { "up.sql still contains the placeholder" } else { "up.sql contains no statements" };
+        issue("up.sql", LintLevel::Error, "placeholder", message.to_string());
+    }
+    if down_ops.is_empty() {
+        let message = if down_sql.trim() == PLACEHOLDER { "down.sql still contains the placeholder" } else { "down.sql contains no statements" };
+        issue("down.sql", LintLevel::Warning, "placeholder", message.to_string());
+    }
+
+    let drops_table = |name: &str| {
+        down_ops.iter().any(|op| matches!(op, MigrationOperation::DropTable { name: n, .. } if migration_diff::same_object(n, name)))
+    };
+    for op in &up_ops {
+        match op {
+            | MigrationOperation::CreateTable { name, .. } if !drops_table(name) => {
+                issue("down.sql", LintLevel::Warning, "missing-down", format!("table {} is created but never dropped", name));
+            },
+            | MigrationOperation::CreateIndex { name: Some(name), table, .. } => {
+                let dropped = drops_table(table)
+                    || down_ops.iter().any(|op| matches!(op, MigrationOperation::DropIndex { name: n, .. } if migration_diff::same_object(n, name)));
+                if !dropped {
+                    issue("down.sql", LintLevel::Warning, "missing-down", format!("index {} is created but never dropped", name));
+                }
+            },
+            | MigrationOperation::CreateView { name, .. } => {
+                let dropped = down_ops.iter().any(|op| matches!(op, MigrationOperation::DropView { name: n, .. } if migration_diff::same_object(n, name)));
+                if !dropped {
+                    issue("down.sql", LintLevel::Warning, "missing-down", format!("view {} is created but never dropped", name));
+                }
+            },
+            | MigrationOperation::AlterTable { name, actions } if !drops_table(name) => {
+                for action in actions {
+                    let AlterAction::AddColumn { column } = action else { continue };
+                    let dropped = down_ops.iter().any(|op| match op {
+                        | MigrationOperation::AlterTable { name: n, actions } => {
+                            migration_diff::same_object(n, name)
+                                && actions.iter().any(|a| matches!(a, AlterAction::DropColumn { column: c } if migration_diff::same_object(c, column)))
+                        },
+                        | _ => false,
+                    });
+                    if !dropped {
+                        issue("down.sql", LintLevel::Warning, "missing-down", format!("column {}.{} is added but never dropped", name, column));
+                    }
+                }
+            },
+            | _ => {},
+        }
+    }
+
+    for (file, ops) in [("up.sql", &up_ops), ("down.sql", &down_ops)] {
+        for op in ops.iter() {
+            if let MigrationOperation::DropTable { name, if_exists: false } = op {
+                issue(file, LintLevel::Warning, "drop-table-if-exists", format!("DROP TABLE {} without IF EXISTS", name));
+            }
+        }
+    }
+
+    if transaction {
+        for (file, sql) in [("up.sql", up_sql), ("down.sql", down_sql)] {
+            for statement in migration_diff::split_statements(sql).into_iter().filter(|s| needs_no_transaction(s)) {
+                let head: String = statement.split_whitespace().take(4).collect::<Vec<_>>().join(" ");
+                issue(file, LintLevel::Error, "needs-no-transaction", format!("`{} ...` cannot run inside a transaction, set `transaction = false` in meta.toml", head));
+            }
+        }
+    }
+
+    for op in &up_ops {
+        if let MigrationOperation::CreateIndex { name, table, concurrently: false, .. } = op
+            && large_tables.contains(table)
+        {
+            let name = name.as_deref().unwrap_or("(unnamed)");
+            issue("up.sql", LintLevel::Warning, "non-concurrent-index", format!("index {} on large table {} is not created CONCURRENTLY", name, table));
+        }
+    }
+
+    issues
+}
+
+/// Render lint issues as a table
+pub fn render_lint_table(issues: &[LintIssue]) {
+    let mut table = crate::core::output::table();
+    table
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec![
+            Cell::new("Migration ID"),
+            Cell::new("File"),
+            Cell::new("Level"),
+            Cell::new("Rule"),
+            Cell::new("Message"),
+        ]);
+
+    for issue in issues {
+        let level = match issue.level {
+            | LintLevel::Error => "❌ error",
+            | LintLevel::Warning => "⚠️ warning",
+        };
+        table.add_row(vec![
+            Cell::new(&issue.migration_id),
+            Cell::new(issue.file),
+            Cell::new(crate::core::output::ascii(level)).set_alignment(CellAlignment::Center),
+            Cell::new(issue.rule),
+            Cell::new(&issue.message),
+        ]);
+    }
+
+    println!("{table}");
+}
@@ -0,0 +1,821 @@
+use {
+    crate::core::{
+        bootstrap,
+        import::{self, Conversion, ForeignMigration},
+        migration::MigrationMeta,
+        repo::Dialect,
+    },
+    anyhow::{Context, Result},
+    serde_json::Value,
+    std::{
+        collections::{BTreeMap, HashSet},
+        path::{Path, PathBuf},
+    },
+};
+
+/// Name of the tool in the source label of imported migrations
+pub const TOOL: &str = "liquibase";
+
+/// Table Liquibase tracks executed changesets in
+pub const HISTORY_TABLE: &str = "databasechangelog";
+
+/// Columns of the tracking table, in the order `convert` expects the rows
+pub const HISTORY_COLUMNS: [&str; 4] = ["id", "author", "filename", "exectype"];
+
+/// Convert the changesets of the changelog at `path` and the changelogs it includes, in the order Liquibase
+/// runs them. Changesets found in `history`, the rows of DATABASECHANGELOG, are marked as applied;
+/// they are matched by id, author and the file name of their changelog.
+pub fn convert(path: &Path, dialect: Dialect, history: &[Vec<Option<String>>]) -> Result<Conversion> {
+    let applied: HashSet<(String, String, String)> = history
+        .iter()
+        .filter_map(|row| {
+            let [id, author, filename, exectype] = row.as_slice() else { return None };
+            // FAILED and SKIPPED changesets left the database unchanged
+            if !matches!(exectype.as_deref(), None | Some("EXECUTED") | Some("MARK_RAN") | Some("RERAN")) {
+                return None;
+            }
+            Some((id.clone()?, author.clone()?, file_name(filename.as_deref()?).to_string()))
+        })
+        .collect();
+
+    let root = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let mut reader = Reader { dialect, root, properties: BTreeMap::new(), changesets: Vec::new(), seen: HashSet::new() };
+    reader.read(path, &path.to_string_lossy(), true)?;
+
+    let mut conversion = Conversion::default();
+    for changeset in &reader.changesets {
+        let applied = applied.contains(&(changeset.id.clone(), changeset.author.clone(), file_name(&changeset.file).to_string()));
+        reader.convert_changeset(changeset, applied, &mut conversion)?;
+    }
+    Ok(conversion)
+}
+
+/// Last component of a changelog path as Liquibase records it, which may use either separator
+fn file_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// An element of a changelog; XML, YAML, JSON and formatted SQL changelogs are all read into this shape
+#[derive(Debug, Default, Clone)]
+struct Node {
+    name: String,
+    attrs: BTreeMap<String, String>,
+    text: String,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn new(name: &str) -> Self {
+        Self { name: name.to_string(), ..Default::default() }
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.get(name).map(|value| value.trim()).filter(|value| !value.is_empty())
+    }
+
+    fn flag(&self, name: &str) -> Option<bool> {
+        self.attr(name).map(|value| value.eq_ignore_ascii_case("true"))
+    }
+}
+
+struct ChangeSet {
+    id: String,
+    author: String,
+    /// Logical path of its changelog, as Liquibase records it in DATABASECHANGELOG
+    file: String,
+    /// Directory of its changelog, for paths relative to it
+    dir: PathBuf,
+    node: Node,
+}
+
+impl ChangeSet {
+    fn key(&self) -> String {
+        format!("{}::{}::{}", self.file, self.id, self.author)
+    }
+}
+
+struct Reader {
+    dialect: Dialect,
+    /// Directory of the root changelog, where paths not relative to their changelog are looked up too
+    root: PathBuf,
+    /// `property` values substituted for `${name}`, the first definition wins as in Liquibase
+    properties: BTreeMap<String, String>,
+    changesets: Vec<ChangeSet>,
+    /// Changelogs already read; Liquibase runs an included changelog only once
+    seen: HashSet<PathBuf>,
+}
+
+impl Reader {
+    /// Read the changelog at `path`, recorded as `logical`. Only included SQL files may be plain SQL.
+    fn read(&mut self, path: &Path, logical: &str, root: bool) -> Result<()> {
+        let canonical = std::fs::canonicalize(path).with_context(|| format!("Failed to read changelog: {}", path.display()))?;
+        if !self.seen.insert(canonical) {
+            return Ok(());
+        }
+        let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read changelog: {}", path.display()))?;
+        let extension = path.extension().map(|ext| ext.to_string_lossy().to_ascii_lowercase()).unwrap_or_default();
+        let changelog = match extension.as_str() {
+            | "xml" => parse_xml(&text),
+            | "yaml" | "yml" => serde_yaml::from_str(&text).map(|value| from_root(&value)).map_err(Into::into),
+            | "json" => serde_json::from_str(&text).map(|value| from_root(&value)).map_err(Into::into),
+            | "sql" => match formatted_sql(&text) {
+                | Some(node) => Ok(node),
+                | None if root => anyhow::bail!("{} is not a formatted SQL changelog; its first line has to be `--liquibase formatted sql`", path.display()),
+                // includeAll runs plain SQL files as a single changeset
+                | None => {
+                    let mut changeset = Node::new("changeSet");
+                    changeset.attrs.insert("id".to_string(), "raw".to_string());
+                    changeset.attrs.insert("author".to_string(), "includeAll".to_string());
+                    let mut sql = Node::new("sql");
+                    sql.text = text.clone();
+                    changeset.children.push(sql);
+                    let mut changelog = Node::new("databaseChangeLog");
+                    changelog.children.push(changeset);
+                    Ok(changelog)
+                },
+            },
+            | _ => anyhow::bail!("unsupported changelog format: {}; expected .xml, .yaml, .yml, .json or .sql", path.display()),
+        }
+        .with_context(|| format!("Failed to parse changelog: {}", path.display()))?;
+
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        let logical = changelog.attr("logicalFilePath").unwrap_or(logical).to_string();
+        for child in &changelog.children {
+            match child.name.as_str() {
+                | "changeSet" => {
+                    let (Some(id), Some(author)) = (child.attr("id"), child.attr("author")) else {
+                        anyhow::bail!("a changeset in {} has no id or author", path.display());
+                    };
+                    self.changesets.push(ChangeSet {
+                        id: id.to_string(),
+                        author: author.to_string(),
+                        file: child.attr("logicalFilePath").unwrap_or(&logical).to_string(),
+                        dir: dir.clone(),
+                        node: child.clone(),
+                    });
+                },
+                | "property" => {
+                    if let (Some(name), Some(value)) = (child.attr("name"), child.attrs.get("value")) {
+                        self.properties.entry(name.to_string()).or_insert_with(|| value.clone());
+                    }
+                },
+                | "include" => {
+                    let file = child.attr("file").ok_or_else(|| anyhow::anyhow!("an include in {} has no file", path.display()))?;
+                    let relative = child.flag("relativeToChangelogFile").unwrap_or(false);
+                    let included = self.resolve(file, &dir, relative);
+                    self.read(&included, &include_logical(&logical, file, relative), false)?;
+                },
+                | "includeAll" => {
+                    let directory = child.attr("path").ok_or_else(|| anyhow::anyhow!("an includeAll in {} has no path", path.display()))?;
+                    let relative = child.flag("relativeToChangelogFile").unwrap_or(false);
+                    let base = self.resolve(directory, &dir, relative);
+                    for file in changelog_files(&base)? {
+                        let name = file.strip_prefix(&base).unwrap_or(&file).to_string_lossy().replace('\\', "/");
+                        let logical = include_logical(&logical, &format!("{}/{}", directory.trim_end_matches('/'), name), relative);
+                        self.read(&file, &logical, false)?;
+                    }
+                },
+                | _ => {},
+            }
+        }
+        Ok(())
+    }
+
+    /// Paths relative to their changelog are resolved against it; other paths against the working
+    /// directory, the directory of the root changelog and that of the changelog, whichever exists
+    fn resolve(&self, path: &str, dir: &Path, relative: bool) -> PathBuf {
+        if relative {
+            return path_clean::clean(dir.join(path));
+        }
+        let candidates = [PathBuf::from(path), self.root.join(path), dir.join(path)];
+        let found = candidates.iter().find(|candidate| candidate.exists()).unwrap_or(&candidates[0]);
+        path_clean::clean(found)
+    }
+
+    fn convert_changeset(&self, changeset: &ChangeSet, applied: bool, conversion: &mut Conversion) -> Result<()> {
+        let key = changeset.key();
+        let node = &changeset.node;
+        if let Some(reason) = self.unconvertible(node) {
+            conversion.skipped.push((key, reason));
+            return Ok(());
+        }
+
+        let mut notes = Vec::new();
+        let mut comment = node.attr("comment").map(str::to_string);
+        let mut changes = Vec::new();
+        let mut rollback: Option<Vec<&Node>> = node.attr("rollback").map(|_| Vec::new());
+        let mut rollback_sql: Vec<String> = node.attr("rollback").map(|sql| vec![terminate(sql)]).unwrap_or_default();
+        for child in &node.children {
+            match child.name.as_str() {
+                | "comment" => comment = Some(child.text.trim().to_string()).filter(|text| !text.is_empty()),
+                | "rollback" => {
+                    if !child.text.trim().is_empty() {
+                        rollback_sql.push(terminate(child.text.trim()));
+                    }
+                    let nested = child.children.iter().flat_map(|change| if change.name == "changes" { change.children.iter().collect() } else { vec![change] });
+                    rollback.get_or_insert_with(Vec::new).extend(nested);
+                    if child.attr("changeSetId").is_some() {
+                        notes.push("its rollback refers to another changeset".to_string());
+                    }
+                },
+                | "preConditions" => notes.push("its preconditions are not checked".to_string()),
+                | "validCheckSum" => {},
+                | "changes" => changes.extend(child.children.iter()),
+                | _ => changes.push(child),
+            }
+        }
+
+        let mut up = Vec::new();
+        let mut unsupported = Vec::new();
+        for change in changes {
+            match self.sql(change, &changeset.dir, &mut notes) {
+                | Ok(Some(sql)) if sql.is_empty() => {},
+                | Ok(Some(sql)) => up.push(sql),
+                | Ok(None) if !unsupported.contains(&change.name.as_str()) => unsupported.push(change.name.as_str()),
+                | Ok(None) => {},
+                | Err(e) => {
+                    conversion.skipped.push((key, format!("{:#}", e)));
+                    return Ok(());
+                },
+            }
+        }
+        if !unsupported.is_empty() {
+            let verb = if unsupported.len() == 1 { "is" } else { "are" };
+            conversion.skipped.push((key, format!("{} {} not SQL; generate the SQL with `liquibase update-sql`", unsupported.join(", "), verb)));
+            return Ok(());
+        }
+        if up.is_empty() {
+            conversion.skipped.push((key, "it has no SQL to run".to_string()));
+            return Ok(());
+        }
+        let up_sql = self.substitute(&up.join("\n\n"), &mut notes) + "\n";
+
+        let generated = || bootstrap::generate_migration_down_sql(&up_sql, self.dialect);
+        let down_sql = match rollback {
+            | None => {
+                notes.push("it has no rollback, down.sql was generated from up.sql".to_string());
+                generated()
+            },
+            | Some(changes) => {
+                let mut converted = Some(rollback_sql);
+                for change in changes {
+                    match self.sql(change, &changeset.dir, &mut notes) {
+                        | Ok(Some(sql)) if sql.is_empty() => {},
+                        | Ok(Some(sql)) => {
+                            if let Some(converted) = converted.as_mut() {
+                                converted.push(sql);
+                            }
+                        },
+                        | _ => converted = None,
+                    }
+                }
+                match converted {
+                    | Some(sql) if sql.is_empty() => "-- Liquibase declares an empty rollback for this changeset\n".to_string(),
+                    | Some(sql) => self.substitute(&sql.join("\n\n"), &mut notes) + "\n",
+                    | None => {
+                        notes.push("its rollback is not plain SQL, down.sql was generated from up.sql".to_string());
+                        generated()
+                    },
+                }
+            },
+        };
+
+        let mut meta = MigrationMeta {
+            comment: Some(comment.map(|comment| self.substitute(&comment, &mut Vec::new())).unwrap_or_else(|| format!("Liquibase changeset {}", key))),
+            author: Some(changeset.author.clone()),
+            ..Default::default()
+        };
+        if node.flag("runInTransaction") == Some(false) {
+            meta.transaction = Some(false);
+        }
+        if let Some(labels) = node.attr("labels") {
+            let tags: Vec<String> = labels.split(',').map(|label| label.trim().to_string()).collect();
+            match tags.iter().all(|tag| !tag.is_empty() && tag.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')) {
+                | true => meta.tags = tags,
+                | false => notes.push(format!("its label expression `{}` is not converted", labels)),
+            }
+        }
+        if let Some(context) = node.attr("contextFilter").or(node.attr("context")) {
+            notes.push(format!("its context `{}` is not checked, it runs everywhere", context));
+        }
+        if node.flag("failOnError") == Some(false) {
+            notes.push("failOnError=false is not supported, errors fail the migration".to_string());
+        }
+        meta.labels.insert(import::SOURCE_LABEL.to_string(), import::source(TOOL, &key));
+
+        conversion.notes.extend(notes.into_iter().map(|note| (key.clone(), note)));
+        conversion.migrations.push(ForeignMigration { key, up_sql, down_sql, meta, applied });
+        Ok(())
+    }
+
+    /// Why the changeset is not converted at all, if it is not
+    fn unconvertible(&self, node: &Node) -> Option<String> {
+        if let Some(dbms) = node.attr("dbms").filter(|dbms| !dbms_matches(dbms, self.dialect)) {
+            return Some(format!("it only runs on {}", dbms));
+        }
+        if node.flag("runAlways") == Some(true) {
+            return Some("it runs on every update; move it to a repeatable".to_string());
+        }
+        if node.flag("runOnChange") == Some(true) {
+            return Some("it runs again whenever it changes; move it to a repeatable".to_string());
+        }
+        None
+    }
+
+    /// SQL of a change: empty for a change meant for another database, None for change types that are not SQL
+    fn sql(&self, change: &Node, dir: &Path, notes: &mut Vec<String>) -> Result<Option<String>> {
+        if change.attr("dbms").is_some_and(|dbms| !dbms_matches(dbms, self.dialect)) {
+            return Ok(Some(String::new()));
+        }
+        let sql = match change.name.as_str() {
+            | "sql" => match change.text.trim() {
+                | "" => change.attr("sql").unwrap_or_default().to_string(),
+                | text => text.to_string(),
+            },
+            | "sqlFile" => {
+                let file = change.attr("path").ok_or_else(|| anyhow::anyhow!("a sqlFile has no path"))?;
+                let path = self.resolve(file, dir, change.flag("relativeToChangelogFile").unwrap_or(false));
+                std::fs::read_to_string(&path).with_context(|| format!("Failed to read sqlFile {}", path.display()))?.trim().to_string()
+            },
+            | _ => return Ok(None),
+        };
+        if let Some(delimiter) = change.attr("endDelimiter").filter(|delimiter| *delimiter != ";") {
+            notes.push(format!("its statements end with `{}`, which qop does not split on", delimiter));
+        }
+        Ok(Some(terminate(&sql)))
+    }
+
+    /// Replace `${name}` with the changelog properties
+    fn substitute(&self, sql: &str, notes: &mut Vec<String>) -> String {
+        let mut sql = sql.to_string();
+        for (name, value) in &self.properties {
+            sql = sql.replace(&format!("${{{}}}", name), value);
+        }
+        if let Some(start) = sql.find("${") {
+            let name: String = sql[start + 2..].chars().take_while(|c| *c != '}').collect();
+            notes.push(format!("it uses the property `{}`, which is not defined in the changelog", name));
+        }
+        sql
+    }
+}
+
+/// `sql` with its last statement terminated, which Liquibase does not require
+fn terminate(sql: &str) -> String {
+    let last_line = sql.lines().last().unwrap_or_default().trim_start();
+    match sql.is_empty() || sql.ends_with(';') || last_line.starts_with("--") {
+        | true => sql.to_string(),
+        | false => format!("{};", sql),
+    }
+}
+
+/// Logical path of an included changelog
+fn include_logical(logical: &str, file: &str, relative: bool) -> String {
+    match (relative, logical.rsplit_once(['/', '\\'])) {
+        | (true, Some((parent, _))) => path_clean::clean(format!("{}/{}", parent, file)).to_string_lossy().replace('\\', "/"),
+        | _ => file.to_string(),
+    }
+}
+
+/// Changelogs in a directory of includeAll and its subdirectories, sorted by path as Liquibase does
+fn changelog_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read directory: {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(changelog_files(&path)?);
+        } else if path.extension().is_some_and(|ext| ["xml", "yaml", "yml", "json", "sql"].contains(&ext.to_string_lossy().to_ascii_lowercase().as_str())) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether a `dbms` attribute such as `postgresql, h2` or `!sqlite` includes the database
+fn dbms_matches(dbms: &str, dialect: Dialect) -> bool {
+    let name = match dialect {
+        | Dialect::Postgres => "postgresql",
+        | Dialect::Sqlite => "sqlite",
+    };
+    let entries: Vec<String> = dbms.split(',').map(|entry| entry.trim().to_ascii_lowercase()).filter(|entry| !entry.is_empty()).collect();
+    if entries.iter().any(|entry| entry == "none" || entry.strip_prefix('!') == Some(name)) {
+        return false;
+    }
+    let positive: Vec<&String> = entries.iter().filter(|entry| !entry.starts_with('!')).collect();
+    positive.is_empty() || positive.iter().any(|entry| *entry == "all" || *entry == name)
+}
+
+/// The `databaseChangeLog` of a YAML or JSON changelog
+fn from_root(value: &Value) -> Node {
+    match value.get("databaseChangeLog") {
+        | Some(changelog) => from_value("databaseChangeLog", changelog),
+        | None => Node::new("databaseChangeLog"),
+    }
+}
+
+/// Scalars of a mapping become attributes, mappings and sequences children; `- changeSet: {...}`
+/// items of a sequence become children named by their key
+fn from_value(name: &str, value: &Value) -> Node {
+    let mut node = Node::new(name);
+    match value {
+        | Value::Object(map) => {
+            for (key, value) in map {
+                match value {
+                    | Value::Object(_) | Value::Array(_) => node.children.push(from_value(key, value)),
+                    | Value::Null => {},
+                    | scalar => {
+                        node.attrs.insert(key.clone(), scalar_text(scalar));
+                    },
+                }
+            }
+        },
+        | Value::Array(items) => {
+            for item in items {
+                match item {
+                    | Value::Object(map) if map.len() == 1 => node.children.extend(map.iter().map(|(key, value)| from_value(key, value))),
+                    | item => node.children.push(from_value("item", item)),
+                }
+            }
+        },
+        | Value::Null => {},
+        | scalar => node.text = scalar_text(scalar),
+    }
+    node
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        | Value::String(text) => text.clone(),
+        | other => other.to_string(),
+    }
+}
+
+/// A changelog in formatted SQL: `--changeset author:id` starts a changeset, followed by its SQL and
+/// `--rollback`, `--comment` and `--precondition-*` lines. None if the first line is not `--liquibase formatted sql`.
+fn formatted_sql(text: &str) -> Option<Node> {
+    let directive = |line: &str| line.trim_start().strip_prefix("--").map(|rest| rest.trim_start().to_string());
+    let first = text.lines().find(|line| !line.trim().is_empty())?;
+    if !directive(first).is_some_and(|rest| rest.to_ascii_lowercase().starts_with("liquibase formatted sql")) {
+        return None;
+    }
+
+    let mut changelog = Node::new("databaseChangeLog");
+    let mut current: Option<(Node, Node, Option<Node>)> = None;
+    let finish = |changelog: &mut Node, current: Option<(Node, Node, Option<Node>)>| {
+        if let Some((mut changeset, sql, rollback)) = current {
+            changeset.children.push(sql);
+            changeset.children.extend(rollback);
+            changelog.children.push(changeset);
+        }
+    };
+    for line in text.lines().skip_while(|line| line.trim().is_empty()).skip(1) {
+        let Some(rest) = directive(line) else {
+            if let Some((_, sql, _)) = current.as_mut() {
+                sql.text.push_str(line);
+                sql.text.push('\n');
+            }
+            continue;
+        };
+        let lower = rest.to_ascii_lowercase();
+        if let Some(definition) = lower.strip_prefix("changeset ").map(|_| rest["changeset ".len()..].trim()) {
+            finish(&mut changelog, current.take());
+            let mut tokens = definition.split_whitespace();
+            let (author, id) = tokens.next().and_then(|token| token.split_once(':')).unwrap_or_default();
+            let mut changeset = Node::new("changeSet");
+            let mut sql = Node::new("sql");
+            changeset.attrs.insert("id".to_string(), id.trim_matches('"').to_string());
+            changeset.attrs.insert("author".to_string(), author.trim_matches('"').to_string());
+            for (name, value) in tokens.filter_map(|token| token.split_once(':')) {
+                let target = if matches!(name, "endDelimiter" | "splitStatements" | "stripComments") { &mut sql } else { &mut changeset };
+                target.attrs.insert(name.to_string(), value.trim_matches('"').to_string());
+            }
+            current = Some((changeset, sql, None));
+        } else if let Some((changeset, sql, rollback)) = current.as_mut() {
+            if lower.starts_with("rollback") {
+                let statement = rest["rollback".len()..].trim();
+                let rollback = rollback.get_or_insert_with(|| Node::new("rollback"));
+                // `--rollback not required` declares that there is nothing to roll back
+                if !statement.eq_ignore_ascii_case("not required") && !statement.eq_ignore_ascii_case("empty") && !statement.is_empty() {
+                    rollback.text.push_str(statement);
+                    rollback.text.push('\n');
+                }
+            } else if lower.starts_with("comment") {
+                changeset.attrs.insert("comment".to_string(), rest["comment".len()..].trim_start_matches(':').trim().to_string());
+            } else if lower.starts_with("precondition") {
+                if !changeset.children.iter().any(|child| child.name == "preConditions") {
+                    changeset.children.push(Node::new("preConditions"));
+                }
+            } else {
+                // Ordinary comments belong to the SQL
+                sql.text.push_str(line);
+                sql.text.push('\n');
+            }
+        } else if let Some(definition) = lower.strip_prefix("property ").map(|_| &rest["property ".len()..]) {
+            let mut property = Node::new("property");
+            for (name, value) in definition.split_whitespace().filter_map(|token| token.split_once(':')) {
+                property.attrs.insert(name.to_string(), value.trim_matches('"').to_string());
+            }
+            changelog.children.push(property);
+        }
+    }
+    finish(&mut changelog, current);
+    Some(changelog)
+}
+
+/// Read an XML changelog into nodes; namespace prefixes are dropped
+fn parse_xml(text: &str) -> Result<Node> {
+    let mut reader = quick_xml::Reader::from_str(text);
+    read_xml(&mut reader).with_context(|| {
+        let position = (reader.buffer_position() as usize).min(text.len());
+        format!("line {}", text.as_bytes()[..position].iter().filter(|b| **b == b'\n').count() + 1)
+    })
+}
+
+fn read_xml(reader: &mut quick_xml::Reader<&[u8]>) -> Result<Node> {
+    use quick_xml::events::{BytesStart, Event};
+    let element = |start: &BytesStart| -> Result<Node> {
+        let mut node = Node::new(&String::from_utf8_lossy(start.local_name().as_ref()));
+        for attr in start.attributes() {
+            let attr = attr?;
+            node.attrs.insert(String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string(), attr.unescape_value()?.to_string());
+        }
+        Ok(node)
+    };
+    // Open elements; an element is added to its parent once it is closed
+    let mut open: Vec<Node> = Vec::new();
+    loop {
+        let node = match reader.read_event()? {
+            | Event::Start(start) => {
+                open.push(element(&start)?);
+                continue;
+            },
+            | Event::Empty(start) => element(&start)?,
+            | Event::End(_) => open.pop().ok_or_else(|| anyhow::anyhow!("closing tag without an element"))?,
+            | Event::Text(text) => {
+                if let Some(node) = open.last_mut() {
+                    node.text.push_str(&text.unescape()?);
+                }
+                continue;
+            },
+            | Event::CData(cdata) => {
+                if let Some(node) = open.last_mut() {
+                    node.text.push_str(&cdata.decode()?);
+                }
+                continue;
+            },
+            | Event::Eof => match open.last() {
+                | Some(node) => anyhow::bail!("<{}> is not closed", node.name),
+                | None => anyhow::bail!("expected an element"),
+            },
+            | _ => continue,
+        };
+        match open.last_mut() {
+            | Some(parent) => parent.children.push(node),
+            | None => return Ok(node),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::core::import::Fixture,
+    };
+
+    fn row(values: &[&str]) -> Vec<Option<String>> {
+        values.iter().map(|value| Some(value.to_string())).collect()
+    }
+
+    /// The converted migration whose key ends with `file::id::author`
+    fn migration<'a>(conversion: &'a Conversion, key: &str) -> &'a ForeignMigration {
+        conversion.migrations.iter().find(|migration| migration.key.ends_with(key)).unwrap_or_else(|| panic!("no migration {}", key))
+    }
+
+    fn skipped<'a>(conversion: &'a Conversion, key: &str) -> &'a str {
+        conversion.skipped.iter().find(|(skipped, _)| skipped.ends_with(key)).map(|(_, reason)| reason.as_str()).unwrap_or_else(|| panic!("{} is not skipped", key))
+    }
+
+    fn notes<'a>(conversion: &'a Conversion, key: &str) -> Vec<&'a str> {
+        conversion.notes.iter().filter(|(noted, _)| noted.ends_with(key)).map(|(_, note)| note.as_str()).collect()
+    }
+
+    fn error(fixture: &Fixture, file: &str) -> String {
+        format!("{:#}", convert(&fixture.path(file), Dialect::Sqlite, &[]).expect_err("the changelog should not convert"))
+    }
+
+    #[test]
+    fn xml_changelog() {
+        let fixture = Fixture::new(&[
+            (
+                "master.xml",
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<databaseChangeLog xmlns="http://www.liquibase.org/xml/ns/dbchangelog" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance">
+    <property name="tbl" value="people"/>
+    <!-- a comment -->
+    <changeSet id="1" author="alice" labels="core">
+        <comment>Create the ${tbl} table</comment>
+        <sql>CREATE TABLE ${tbl} (id INTEGER PRIMARY KEY, name TEXT)</sql>
+        <rollback>DROP TABLE ${tbl};</rollback>
+    </changeSet>
+    <changeSet id="2" author="alice">
+        <createTable tableName="x"><column name="id" type="int"/></createTable>
+    </changeSet>
+    <changeSet id="3" author="bob" dbms="postgresql">
+        <sql>CREATE EXTENSION foo;</sql>
+    </changeSet>
+    <changeSet id="4" author="bob" runInTransaction="false" context="prod">
+        <sqlFile path="sql/four.sql" relativeToChangelogFile="true"/>
+        <rollback/>
+    </changeSet>
+    <changeSet id="5" author="bob"><sql><![CDATA[CREATE VIEW v AS SELECT 1 WHERE 1 < 2]]></sql></changeSet>
+    <changeSet id="6" author="bob"><sql>SELECT 'a &amp; b' WHERE 1 &lt; 2 AND '&#x41;' = 'A'</sql></changeSet>
+    <changeSet id="7" author="bob" runOnChange="true"><sql>SELECT 1</sql></changeSet>
+</databaseChangeLog>
+"#,
+            ),
+            ("sql/four.sql", "CREATE INDEX people_name ON people (name);\n"),
+        ]);
+        let history = [row(&["1", "alice", "master.xml", "EXECUTED"]), row(&["4", "bob", "master.xml", "FAILED"])];
+        let conversion = convert(&fixture.path("master.xml"), Dialect::Sqlite, &history).unwrap();
+
+        let first = migration(&conversion, "master.xml::1::alice");
+        assert_eq!(first.up_sql, "CREATE TABLE people (id INTEGER PRIMARY KEY, name TEXT);\n");
+        assert_eq!(first.down_sql, "DROP TABLE people;\n");
+        assert_eq!(first.meta.comment.as_deref(), Some("Create the people table"));
+        assert_eq!(first.meta.author.as_deref(), Some("alice"));
+        assert_eq!(first.meta.tags, vec!["core".to_string()]);
+        assert!(first.applied);
+
+        assert_eq!(skipped(&conversion, "master.xml::2::alice"), "createTable is not SQL; generate the SQL with `liquibase update-sql`");
+        assert_eq!(skipped(&conversion, "master.xml::3::bob"), "it only runs on postgresql");
+        assert!(skipped(&conversion, "master.xml::7::bob").contains("runs again whenever it changes"));
+
+        let fourth = migration(&conversion, "master.xml::4::bob");
+        assert_eq!(fourth.up_sql, "CREATE INDEX people_name ON people (name);\n");
+        assert_eq!(fourth.down_sql, "-- Liquibase declares an empty rollback for this changeset\n");
+        assert_eq!(fourth.meta.transaction, Some(false));
+        assert!(!fourth.applied);
+        assert_eq!(notes(&conversion, "master.xml::4::bob"), vec!["its context `prod` is not checked, it runs everywhere"]);
+
+        assert_eq!(migration(&conversion, "master.xml::5::bob").up_sql, "CREATE VIEW v AS SELECT 1 WHERE 1 < 2;\n");
+        assert_eq!(migration(&conversion, "master.xml::6::bob").up_sql, "SELECT 'a & b' WHERE 1 < 2 AND 'A' = 'A';\n");
+        assert_eq!(conversion.migrations.len(), 4);
+    }
+
+    #[test]
+    fn yaml_changelog_with_includes() {
+        let fixture = Fixture::new(&[
+            (
+                "master.yaml",
+                r#"databaseChangeLog:
+- include:
+    file: changes/a.json
+    relativeToChangelogFile: true
+- includeAll:
+    path: changes/raw/
+    relativeToChangelogFile: true
+- changeSet:
+    id: "y-1"
+    author: 'erin'   # trailing comment
+    labels: a, b
+    preConditions:
+      - onFail: MARK_RAN
+      - tableExists:
+          tableName: foo
+    changes:
+    - sql:
+        sql: >-
+          INSERT INTO t
+          VALUES (1)
+    rollback: DELETE FROM t WHERE id = 1
+- changeSet:
+    id: 10
+    author: carol
+    changes:
+      - sql:
+          sql: |
+            ALTER TABLE people ADD COLUMN email TEXT;
+            UPDATE people SET email = 'x';
+    rollback:
+      - sql:
+          sql: ALTER TABLE people DROP COLUMN email
+"#,
+            ),
+            (
+                "changes/a.json",
+                r#"{"databaseChangeLog":[{"changeSet":{"id":"j1","author":"fay","changes":[{"sql":{"sql":"CREATE TABLE t (id int)"}}],"rollback":[{"sql":{"sql":"DROP TABLE t"}}]}}]}"#,
+            ),
+            ("changes/raw/002.sql", "CREATE TABLE s (x int);\n"),
+            ("changes/raw/001.sql", "CREATE TABLE r (x int);\n"),
+            ("changes/raw/notes.txt", "not a changelog"),
+        ]);
+        let history = [row(&["j1", "fay", "a.json", "EXECUTED"])];
+        let conversion = convert(&fixture.path("master.yaml"), Dialect::Sqlite, &history).unwrap();
+
+        let keys: Vec<&str> = conversion.migrations.iter().map(|migration| migration.key.rsplit('/').next().unwrap()).collect();
+        assert_eq!(keys, vec!["a.json::j1::fay", "001.sql::raw::includeAll", "002.sql::raw::includeAll", "master.yaml::y-1::erin", "master.yaml::10::carol"]);
+
+        let json = migration(&conversion, "changes/a.json::j1::fay");
+        assert_eq!(json.up_sql, "CREATE TABLE t (id int);\n");
+        assert_eq!(json.down_sql, "DROP TABLE t;\n");
+        assert!(json.applied);
+
+        let raw = migration(&conversion, "changes/raw/001.sql::raw::includeAll");
+        assert_eq!(raw.up_sql, "CREATE TABLE r (x int);\n");
+        assert!(!raw.applied);
+        assert_eq!(notes(&conversion, "changes/raw/001.sql::raw::includeAll"), vec!["it has no rollback, down.sql was generated from up.sql"]);
+
+        let yaml = migration(&conversion, "master.yaml::y-1::erin");
+        assert_eq!(yaml.up_sql, "INSERT INTO t VALUES (1);\n");
+        assert_eq!(yaml.down_sql, "DELETE FROM t WHERE id = 1;\n");
+        assert_eq!(yaml.meta.tags, vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(notes(&conversion, "master.yaml::y-1::erin"), vec!["its preconditions are not checked"]);
+
+        let block = migration(&conversion, "master.yaml::10::carol");
+        assert_eq!(block.up_sql, "ALTER TABLE people ADD COLUMN email TEXT;\nUPDATE people SET email = 'x';\n");
+        assert_eq!(block.down_sql, "ALTER TABLE people DROP COLUMN email;\n");
+    }
+
+    #[test]
+    fn formatted_sql_changelog() {
+        let fixture = Fixture::new(&[(
+            "changelog.sql",
+            "--liquibase formatted sql\n\n--property name:tbl value:audit\n\n--changeset dave:20 labels:x runInTransaction:false\n--comment: formatted one\nCREATE TABLE ${tbl} (id INTEGER);\n--rollback DROP TABLE ${tbl};\n\n--changeset dave:21\n-- plain comment\nINSERT INTO audit VALUES (1);\n--rollback not required\n\n--changeset dave:22\n--precondition-sql-check expectedResult:0 SELECT 1\nCREATE TABLE other (id INTEGER);\n",
+        )]);
+        let conversion = convert(&fixture.path("changelog.sql"), Dialect::Sqlite, &[]).unwrap();
+        assert_eq!(conversion.migrations.len(), 3);
+
+        let first = migration(&conversion, "changelog.sql::20::dave");
+        assert_eq!(first.up_sql, "CREATE TABLE audit (id INTEGER);\n");
+        assert_eq!(first.down_sql, "DROP TABLE audit;\n");
+        assert_eq!(first.meta.comment.as_deref(), Some("formatted one"));
+        assert_eq!(first.meta.tags, vec!["x".to_string()]);
+        assert_eq!(first.meta.transaction, Some(false));
+
+        let second = migration(&conversion, "changelog.sql::21::dave");
+        assert_eq!(second.up_sql, "-- plain comment\nINSERT INTO audit VALUES (1);\n");
+        assert_eq!(second.down_sql, "-- Liquibase declares an empty rollback for this changeset\n");
+
+        let third = migration(&conversion, "changelog.sql::22::dave");
+        assert!(third.down_sql.contains("DROP TABLE"), "{}", third.down_sql);
+        assert_eq!(
+            notes(&conversion, "changelog.sql::22::dave"),
+            vec!["its preconditions are not checked", "it has no rollback, down.sql was generated from up.sql"]
+        );
+    }
+
+    #[test]
+    fn included_changelogs_run_once() {
+        let fixture = Fixture::new(&[
+            ("a.xml", r#"<databaseChangeLog><include file="b.xml" relativeToChangelogFile="true"/><changeSet id="a" author="x"><sql>SELECT 1</sql></changeSet></databaseChangeLog>"#),
+            ("b.xml", r#"<databaseChangeLog><include file="a.xml" relativeToChangelogFile="true"/><changeSet id="b" author="x"><sql>SELECT 2</sql></changeSet></databaseChangeLog>"#),
+        ]);
+        let conversion = convert(&fixture.path("a.xml"), Dialect::Sqlite, &[]).unwrap();
+        let keys: Vec<&str> = conversion.migrations.iter().map(|migration| migration.key.rsplit('/').next().unwrap()).collect();
+        assert_eq!(keys, vec!["b.xml::b::x", "a.xml::a::x"]);
+    }
+
+    #[test]
+    fn undefined_property_is_noted() {
+        let fixture = Fixture::new(&[("c.xml", r#"<databaseChangeLog><changeSet id="1" author="x"><sql>SELECT ${missing}</sql><rollback>SELECT 0</rollback></changeSet></databaseChangeLog>"#)]);
+        let conversion = convert(&fixture.path("c.xml"), Dialect::Postgres, &[]).unwrap();
+        assert_eq!(notes(&conversion, "c.xml::1::x"), vec!["it uses the property `missing`, which is not defined in the changelog"]);
+    }
+
+    #[test]
+    fn malformed_changelogs() {
+        let fixture = Fixture::new(&[
+            ("unclosed.xml", "<databaseChangeLog>\n<changeSet id=\"1\" author=\"x\">\n"),
+            ("mismatched.xml", "<databaseChangeLog>\n<changeSet id=\"1\" author=\"x\">\n</databaseChangeLog>\n"),
+            ("attribute.xml", "<databaseChangeLog><changeSet id=1 author=\"x\"/></databaseChangeLog>"),
+            ("empty.xml", ""),
+            ("broken.yaml", "databaseChangeLog:\n  - changeSet: {id: 1\n"),
+            ("broken.json", r#"{"databaseChangeLog": ["#),
+            ("plain.sql", "CREATE TABLE t (id INTEGER);\n"),
+            ("anonymous.xml", r#"<databaseChangeLog><changeSet id="1"><sql>SELECT 1</sql></changeSet></databaseChangeLog>"#),
+            ("missing.xml", r#"<databaseChangeLog><include file="nowhere.xml" relativeToChangelogFile="true"/></databaseChangeLog>"#),
+            ("changelog.txt", "text"),
+        ]);
+        assert!(error(&fixture, "unclosed.xml").contains("<changeSet> is not closed"));
+        assert!(error(&fixture, "mismatched.xml").contains("line 3"));
+        assert!(error(&fixture, "attribute.xml").contains("Failed to parse changelog"));
+        assert!(error(&fixture, "empty.xml").contains("expected an element"));
+        assert!(error(&fixture, "broken.yaml").contains("Failed to parse changelog"));
+        assert!(error(&fixture, "broken.json").contains("Failed to parse changelog"));
+        assert!(error(&fixture, "plain.sql").contains("is not a formatted SQL changelog"));
+        assert!(error(&fixture, "anonymous.xml").contains("has no id or author"));
+        assert!(error(&fixture, "missing.xml").contains("Failed to read changelog"));
+        assert!(error(&fixture, "changelog.txt").contains("unsupported changelog format"));
+    }
+
+    #[test]
+    fn dbms_expressions() {
+        assert!(dbms_matches("postgresql, h2", Dialect::Postgres));
+        assert!(!dbms_matches("postgresql, h2", Dialect::Sqlite));
+        assert!(dbms_matches("!postgresql", Dialect::Sqlite));
+        assert!(!dbms_matches("!sqlite", Dialect::Sqlite));
+        assert!(dbms_matches("all", Dialect::Sqlite));
+        assert!(!dbms_matches("none", Dialect::Postgres));
+    }
+}
@@ -0,0 +1,292 @@
+use {
+    anyhow::Result,
+    chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc},
+    comfy_table::{Cell, CellAlignment, ContentArrangement},
+    serde::Serialize,
+};
+
+/// A single row of the log table
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub id: String,
+    pub migration_id: String,
+    pub operation: String,
+    pub sql_command: String,
+    pub executed_at: NaiveDateTime,
+    /// How long the SQL took, for `up` and `down` entries
+    pub duration_ms: Option<i64>,
+    /// OS user and host that ran the operation, unknown for entries written by an older qop
+    pub applied_by: Option<String>,
+    pub hostname: Option<String>,
+    /// qop version and command line that wrote the entry, unknown for entries written by an older qop
+    pub version: Option<String>,
+    pub invocation: Option<String>,
+    pub dry_run: Option<bool>,
+    /// `success` or `failed`; older qop releases only logged successful operations
+    pub status: Option<String>,
+    pub error: Option<String>,
+}
+
+/// How a logged operation went
+#[derive(Debug, Clone, Default)]
+pub struct LogOutcome {
+    /// How long the SQL took, for operations that executed SQL
+    pub duration_ms: Option<i64>,
+    pub dry_run: bool,
+    /// Error of a failed attempt
+    pub error: Option<String>,
+}
+
+impl LogOutcome {
+    pub fn status(&self) -> &'static str {
+        match self.error {
+            | Some(_) => "failed",
+            | None => "success",
+        }
+    }
+}
+
+/// The command line of this invocation, with the passwords of connection URIs replaced by `***`
+pub fn invocation() -> String {
+    std::env::args()
+        .map(|arg| match arg.contains("://") {
+            | true => crate::core::ping::redact_connection(&arg),
+            | false => arg,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// JSON representation of a log entry, timestamps are emitted as UTC
+#[derive(Debug, Serialize)]
+pub struct LogEntryOut<'a> {
+    pub id: &'a str,
+    pub migration_id: &'a str,
+    pub operation: &'a str,
+    pub executed_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub applied_by: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<&'a str>,
+    pub status: &'a str,
+    pub dry_run: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invocation: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sql_command: Option<&'a str>,
+}
+
+impl LogEntry {
+    pub fn status(&self) -> &str {
+        self.status.as_deref().unwrap_or("success")
+    }
+
+    pub fn to_out(&self, with_sql: bool) -> LogEntryOut<'_> {
+        LogEntryOut {
+            id: &self.id,
+            migration_id: &self.migration_id,
+            operation: &self.operation,
+            executed_at: Utc.from_utc_datetime(&self.executed_at),
+            duration_ms: self.duration_ms,
+            applied_by: self.applied_by.as_deref(),
+            hostname: self.hostname.as_deref(),
+            status: self.status(),
+            dry_run: self.dry_run.unwrap_or(false),
+            error: self.error.as_deref(),
+            version: self.version.as_deref(),
+            invocation: self.invocation.as_deref(),
+            sql_command: if with_sql { Some(&self.sql_command) } else { None },
+        }
+    }
+}
+
+/// Filters applied when querying the log table; all bounds are inclusive and in UTC
+#[derive(Debug, Default)]
+pub struct LogFilter {
+    pub migration_id: Option<String>,
+    pub operation: Option<String>,
+    pub since: Option<NaiveDateTime>,
+    pub until: Option<NaiveDateTime>,
+    pub limit: Option<usize>,
+}
+
+/// Parse a time bound given as RFC 3339, `YYYY-MM-DD HH:MM:SS` (UTC) or `YYYY-MM-DD` (UTC midnight)
+pub fn parse_time_bound(value: &str) -> Result<NaiveDateTime> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Ok(dt.with_timezone(&Utc).naive_utc());
+    }
+    if let Ok(dt) = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S") {
+        return Ok(dt);
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap());
+    }
+    anyhow::bail!("invalid time '{}', expected RFC 3339, 'YYYY-MM-DD HH:MM:SS' or 'YYYY-MM-DD'", value)
+}
+
+fn format_local(ts: &NaiveDateTime) -> String {
+    Local.from_utc_datetime(ts).format("%Y-%m-%d %H:%M:%S %Z").to_string()
+}
+
+/// Render log entries as a table (without the SQL)
+pub fn render_log_table(entries: &[LogEntry]) {
+    let mut table = crate::core::output::table();
+    table
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec![
+            Cell::new("Log ID"),
+            Cell::new("Migration ID"),
+            Cell::new("Operation"),
+            Cell::new("Executed At"),
+            Cell::new("Duration"),
+            Cell::new("Status"),
+        ]);
+
+    for entry in entries {
+        table.add_row(vec![
+            Cell::new(&entry.id),
+            Cell::new(&entry.migration_id),
+            Cell::new(&entry.operation).set_alignment(CellAlignment::Center),
+            Cell::new(format_local(&entry.executed_at)).set_alignment(CellAlignment::Center),
+            Cell::new(entry.duration_ms.map(crate::core::duration::format_millis).unwrap_or_default()).set_alignment(CellAlignment::Right),
+            Cell::new(status_label(entry)).set_alignment(CellAlignment::Center),
+        ]);
+    }
+
+    println!("{table}");
+}
+
+fn status_label(entry: &LogEntry) -> String {
+    let status = match entry.status() {
+        | "failed" => "❌ failed",
+        | _ => "✅",
+    };
+    let status = crate::core::output::ascii(status);
+    match entry.dry_run.unwrap_or(false) {
+        | true => format!("{} (dry run)", status),
+        | false => status,
+    }
+}
+
+/// A single log entry including the executed SQL, as `log show` prints it
+pub fn format_log_entry(entry: &LogEntry) -> String {
+    let header_line = crate::core::output::rule();
+    let mut lines = vec![
+        format!("Log ID:       {}", entry.id),
+        format!("Migration ID: {}", entry.migration_id),
+        format!("Operation:    {}", entry.operation),
+        format!("Executed At:  {}", format_local(&entry.executed_at)),
+    ];
+    if let Some(duration_ms) = entry.duration_ms {
+        lines.push(format!("Duration:     {}", crate::core::duration::format_millis(duration_ms)));
+    }
+    if entry.applied_by.is_some() || entry.hostname.is_some() {
+        lines.push(format!("Applied By:   {}", crate::core::migration::format_applied_by(entry.applied_by.as_deref(), entry.hostname.as_deref())));
+    }
+    lines.push(format!("Status:       {}", status_label(entry)));
+    if let Some(version) = &entry.version {
+        lines.push(format!("qop Version:  {}", version));
+    }
+    if let Some(invocation) = &entry.invocation {
+        lines.push(format!("Invocation:   {}", invocation));
+    }
+    if let Some(error) = &entry.error {
+        lines.push(format!("Error:        {}", error));
+    }
+    lines.push(header_line.clone());
+    lines.push(entry.sql_command.strip_suffix('\n').unwrap_or(&entry.sql_command).to_string());
+    lines.push(header_line.clone());
+    lines.join("\n") + "\n"
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+/// A log entry joined with the record of its migration, as exported for audits
+#[derive(Debug, Serialize)]
+pub struct AuditEntryOut<'a> {
+    pub id: &'a str,
+    pub migration_id: &'a str,
+    pub operation: &'a str,
+    pub executed_at: DateTime<Utc>,
+    pub duration_ms: Option<i64>,
+    pub applied_by: Option<&'a str>,
+    pub hostname: Option<&'a str>,
+    pub status: &'a str,
+    pub dry_run: bool,
+    pub error: Option<&'a str>,
+    /// qop version that wrote the entry, else the one that recorded the migration
+    pub version: Option<&'a str>,
+    pub invocation: Option<&'a str>,
+    /// Comment and provenance of the migration, unknown once it was reverted or removed
+    pub comment: Option<&'a str>,
+    pub author: Option<&'a str>,
+    pub ticket: Option<&'a str>,
+    pub sql_command: &'a str,
+}
+
+const AUDIT_COLUMNS: [&str; 16] = [
+    "id",
+    "migration_id",
+    "operation",
+    "executed_at",
+    "duration_ms",
+    "applied_by",
+    "hostname",
+    "status",
+    "dry_run",
+    "error",
+    "version",
+    "invocation",
+    "comment",
+    "author",
+    "ticket",
+    "sql_command",
+];
+
+/// Quote a CSV field when it contains a separator, quote or line break (RFC 4180)
+fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n', '\r']) {
+        | true => format!("\"{}\"", value.replace('"', "\"\"")),
+        | false => value.to_string(),
+    }
+}
+
+/// Render audit entries as CSV with a header row
+pub fn render_audit_csv(entries: &[AuditEntryOut]) -> String {
+    let mut out = AUDIT_COLUMNS.join(",");
+    out.push_str("\r\n");
+    for entry in entries {
+        let fields = [
+            entry.id.to_string(),
+            entry.migration_id.to_string(),
+            entry.operation.to_string(),
+            entry.executed_at.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true),
+            entry.duration_ms.map(|ms| ms.to_string()).unwrap_or_default(),
+            entry.applied_by.unwrap_or_default().to_string(),
+            entry.hostname.unwrap_or_default().to_string(),
+            entry.status.to_string(),
+            entry.dry_run.to_string(),
+            entry.error.unwrap_or_default().to_string(),
+            entry.version.unwrap_or_default().to_string(),
+            entry.invocation.unwrap_or_default().to_string(),
+            entry.comment.unwrap_or_default().to_string(),
+            entry.author.unwrap_or_default().to_string(),
+            entry.ticket.unwrap_or_default().to_string(),
+            entry.sql_command.to_string(),
+        ];
+        let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        out.push_str(&fields.join(","));
+        out.push_str("\r\n");
+    }
+    out
+}
@@ -0,0 +1,173 @@
+use {
+    crate::core::{
+        migration::{self as util, MigrationMeta},
+        service::OutputFormat,
+    },
+    anyhow::{Context, Result},
+    serde::Serialize,
+    std::path::Path,
+    toml_edit::DocumentMut,
+};
+
+/// Top-level keys of meta.toml, i.e. the fields of `MigrationMeta`
+pub const META_KEYS: [&str; 14] = ["comment", "locked", "transaction", "timeout", "retries", "isolation", "tags", "depends_on", "hooks", "author", "ticket", "labels", "skip", "batch"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetaIssueKind {
+    /// No meta.toml, the defaults apply
+    Missing,
+    /// Not valid TOML, or a value of the wrong type
+    Invalid,
+    /// A key qop does not know, usually a typo
+    UnknownKey,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MetaIssue {
+    pub id: String,
+    pub kind: MetaIssueKind,
+    pub message: String,
+}
+
+/// Validate the meta.toml of every local migration. Missing files are reported but do not fail.
+pub fn check_metas(path: &Path, output: OutputFormat) -> Result<()> {
+    let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+    let mut ids: Vec<String> = util::get_local_migrations(path)?.into_iter().collect();
+    util::sort_ids(&mut ids);
+    let issues: Vec<MetaIssue> = ids.iter().flat_map(|id| check_meta(migration_dir, id)).collect();
+    let failing = issues.iter().filter(|issue| issue.kind != MetaIssueKind::Missing).count();
+
+    match output {
+        | OutputFormat::Human => {
+            for issue in &issues {
+                let icon = if issue.kind == MetaIssueKind::Missing { "⚠️ " } else { "❌" };
+                crate::summary!("{} {}: {}", icon, issue.id, issue.message);
+            }
+            if failing == 0 {
+                crate::summary!("✅ All meta.toml files are valid.");
+            }
+        },
+        | OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&issues)?),
+    }
+    if failing > 0 {
+        anyhow::bail!("{} meta.toml issue(s) found; run `meta fix` to repair unknown keys and missing files", failing);
+    }
+    Ok(())
+}
+
+/// Create missing meta.toml files with the defaults and rename or remove unknown keys; values of
+/// the wrong type are left for manual fixing
+pub fn fix_metas(path: &Path, yes: bool) -> Result<()> {
+    let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+    let mut ids: Vec<String> = util::get_local_migrations(path)?.into_iter().collect();
+    util::sort_ids(&mut ids);
+
+    let mut fixes = Vec::new();
+    for id in &ids {
+        let meta_path = meta_path(migration_dir, id);
+        let content = match meta_path.exists() {
+            | true => Some(std::fs::read_to_string(&meta_path).with_context(|| format!("Failed to read meta.toml: {}", meta_path.display()))?),
+            | false => None,
+        };
+        match fix_content(content.as_deref()) {
+            | Ok(Some((fixed, changes))) => fixes.push((id, meta_path, fixed, changes)),
+            | Ok(None) => {},
+            | Err(e) => crate::say!("❌ {}: {:#}, fix it by hand", id, e),
+        }
+    }
+    if fixes.is_empty() {
+        crate::summary!("✅ Nothing to fix.");
+        return check_metas(path, OutputFormat::Human);
+    }
+
+    crate::say!("📋 About to fix {} meta.toml file(s):", fixes.len());
+    for (id, _, _, changes) in &fixes {
+        crate::say!("  - {}: {}", id, changes.join(", "));
+    }
+    if !util::prompt_for_confirmation_with_diff("❓ Do you want to write the fixed files?", yes, |_| Ok(()))? {
+        crate::say!("❌ Fix cancelled.");
+        return Ok(())
+    }
+    for (_, meta_path, fixed, _) in &fixes {
+        std::fs::write(meta_path, fixed).with_context(|| format!("Failed to write meta.toml: {}", meta_path.display()))?;
+    }
+    crate::summary!("🎉 Fixed {} meta.toml file(s)!", fixes.len());
+    check_metas(path, OutputFormat::Human)
+}
+
+fn meta_path(migration_dir: &Path, id: &str) -> std::path::PathBuf {
+    migration_dir.join(format!("id={}", id)).join("meta.toml")
+}
+
+fn check_meta(migration_dir: &Path, id: &str) -> Vec<MetaIssue> {
+    let issue = |kind, message: String| MetaIssue { id: id.to_string(), kind, message };
+    let Ok(content) = std::fs::read_to_string(meta_path(migration_dir, id)) else {
+        return vec![issue(MetaIssueKind::Missing, "meta.toml is missing".to_string())];
+    };
+    let doc: DocumentMut = match content.parse() {
+        | Ok(doc) => doc,
+        | Err(e) => return vec![issue(MetaIssueKind::Invalid, e.message().to_string())],
+    };
+    let mut issues: Vec<MetaIssue> = doc
+        .iter()
+        .filter(|(key, _)| !META_KEYS.contains(key))
+        .map(|(key, _)| match suggest(key) {
+            | Some(known) => issue(MetaIssueKind::UnknownKey, format!("unknown key `{}`, did you mean `{}`?", key, known)),
+            | None => issue(MetaIssueKind::UnknownKey, format!("unknown key `{}`", key)),
+        })
+        .collect();
+    if let Err(e) = toml::from_str::<MigrationMeta>(&content) {
+        let line = e.span().map(|span| content[..span.start].lines().count().max(1));
+        let message = match line {
+            | Some(line) => format!("line {}: {}", line, e.message()),
+            | None => e.message().to_string(),
+        };
+        issues.push(issue(MetaIssueKind::Invalid, message));
+    }
+    issues
+}
+
+/// The fixed content of a meta.toml and a description of every change, None if it is fine
+fn fix_content(content: Option<&str>) -> Result<Option<(String, Vec<String>)>> {
+    let Some(content) = content else {
+        let meta = MigrationMeta { locked: Some(false), ..MigrationMeta::default() };
+        return Ok(Some((toml::to_string(&meta)?, vec!["created with defaults".to_string()])));
+    };
+    let mut doc: DocumentMut = content.parse().map_err(|e: toml_edit::TomlError| anyhow::anyhow!("{}", e.message()))?;
+    let unknown: Vec<String> = doc.iter().map(|(key, _)| key.to_string()).filter(|key| !META_KEYS.contains(&key.as_str())).collect();
+    if unknown.is_empty() {
+        return Ok(None);
+    }
+    let mut changes = Vec::new();
+    for key in unknown {
+        let item = doc.remove(&key).unwrap_or_default();
+        match suggest(&key) {
+            | Some(known) if !doc.contains_key(known) => {
+                doc.insert(known, item);
+                changes.push(format!("renamed `{}` to `{}`", key, known));
+            },
+            | _ => changes.push(format!("removed `{}`", key)),
+        }
+    }
+    Ok(Some((doc.to_string(), changes)))
+}
+
+/// The known key closest to a misspelled one, if any is close enough
+fn suggest(key: &str) -> Option<&'static str> {
+    META_KEYS.into_iter().map(|known| (edit_distance(key, known), known)).filter(|(distance, _)| *distance <= 2).min().map(|(_, known)| known)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
@@ -7,7 +7,7 @@ use {
     },
 };
 use std::io::{self, Write};
-use comfy_table::{modifiers::UTF8_ROUND_CORNERS, presets::UTF8_FULL, Cell, ContentArrangement, Table, CellAlignment};
+use comfy_table::{Cell, ContentArrangement, CellAlignment};
 use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
 
@@ -15,11 +15,273 @@ use serde::{Deserialize, Serialize};
 pub struct MigrationMeta {
     pub comment: Option<String>,
     pub locked: Option<bool>,
+    /// `false` runs the statements one by one outside a transaction, e.g. for CREATE INDEX CONCURRENTLY
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transaction: Option<bool>,
+    /// Statement timeout in seconds, overriding --timeout and the config
+    #[serde(default, deserialize_with = "crate::core::duration::deserialize_seconds", skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Additional attempts after a failure
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub isolation: Option<Isolation>,
+    /// Labels selecting this migration with `--tag`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Migrations that have to be applied before this one, regardless of their ids
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// `before_each`/`after_each` hooks of this migration only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<crate::core::hooks::MigrationHooks>,
+    /// Who wrote the migration; `new` fills in the current user
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Issue or ticket the migration belongs to, e.g. `OPS-1234`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ticket: Option<String>,
+    /// Free-form key/value pairs, e.g. `team = "billing"`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    /// Keeps the migration out of `up` and `diff`, everywhere or only for the listed profiles
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skip: Option<Skip>,
+    /// Runs up.sql as a data backfill in batches, committing after each one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub batch: Option<BatchOptions>,
 }
 
 impl Default for MigrationMeta {
     fn default() -> Self {
-        Self { comment: None, locked: None }
+        Self { comment: None, locked: None, transaction: None, timeout: None, retries: None, isolation: None, tags: Vec::new(), depends_on: Vec::new(), hooks: None, author: None, ticket: None, labels: BTreeMap::new(), skip: None, batch: None }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Isolation {
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+impl Isolation {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            | Isolation::ReadCommitted => "READ COMMITTED",
+            | Isolation::RepeatableRead => "REPEATABLE READ",
+            | Isolation::Serializable => "SERIALIZABLE",
+        }
+    }
+}
+
+/// `skip = true` parks a migration everywhere, `skip = ["prod"]` only for the listed profiles
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Skip {
+    Always(bool),
+    Profiles(Vec<String>),
+}
+
+impl Skip {
+    pub fn applies(&self, profile: Option<&str>) -> bool {
+        match self {
+            | Skip::Always(skip) => *skip,
+            | Skip::Profiles(profiles) => profile.is_some_and(|profile| profiles.iter().any(|p| p == profile)),
+        }
+    }
+}
+
+/// Author, ticket and labels of a migration, recorded in the tracking table when it is applied
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Provenance {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ticket: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+}
+
+impl Provenance {
+    /// Labels as stored in the `labels` column: a JSON object, or NULL without labels
+    pub fn labels_json(&self) -> Option<String> {
+        match self.labels.is_empty() {
+            | true => None,
+            | false => serde_json::to_string(&self.labels).ok(),
+        }
+    }
+
+    pub fn from_columns(author: Option<String>, ticket: Option<String>, labels: Option<String>) -> Self {
+        Self { author, ticket, labels: labels.and_then(|labels| serde_json::from_str(&labels).ok()).unwrap_or_default() }
+    }
+
+    fn format_labels(&self) -> String {
+        self.labels.iter().map(|(key, value)| format!("{}={}", key, value)).collect::<Vec<_>>().join(", ")
+    }
+}
+
+/// How `new` names migrations, the `ids` table of the subsystem config
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IdConfig {
+    #[serde(default)]
+    pub scheme: IdScheme,
+    /// Digits of sequential ids, zero-padded (default 4)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdScheme {
+    /// Milliseconds since the epoch, e.g. `1756625168555`
+    #[default]
+    Timestamp,
+    /// Ordinals continuing after the highest id in use, e.g. `0001`
+    Sequential,
+}
+
+impl IdConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The id of the next migration; `taken` holds the local and applied ids
+    pub fn next_id(&self, taken: &HashSet<String>) -> Result<String> {
+        let id = match self.scheme {
+            | IdScheme::Timestamp => Utc::now().timestamp_millis().to_string(),
+            | IdScheme::Sequential => {
+                let next = taken.iter().filter_map(|id| id.parse::<u128>().ok()).max().map_or(1, |max| max + 1);
+                format!("{:0width$}", next, width = self.width.unwrap_or(4))
+            },
+        };
+        if let Some(existing) = taken.iter().find(|existing| compare_ids(existing, &id).is_eq()) {
+            anyhow::bail!("migration id {} collides with existing migration {}", id, existing);
+        }
+        Ok(id)
+    }
+
+    /// `count` consecutive ids for migrations created at once, e.g. by an import
+    pub fn next_ids(&self, taken: &HashSet<String>, count: usize) -> Result<Vec<String>> {
+        if count == 0 {
+            return Ok(Vec::new())
+        }
+        let first: u128 = self.next_id(taken)?.parse().context("migration ids are not numeric")?;
+        let mut ids = Vec::with_capacity(count);
+        for n in 0..count as u128 {
+            let id = match self.scheme {
+                | IdScheme::Timestamp => (first + n).to_string(),
+                | IdScheme::Sequential => format!("{:0width$}", first + n, width = self.width.unwrap_or(4)),
+            };
+            if let Some(existing) = taken.iter().find(|existing| compare_ids(existing, &id).is_eq()) {
+                anyhow::bail!("migration id {} collides with existing migration {}", id, existing);
+            }
+            ids.push(id);
+        }
+        Ok(ids)
+    }
+}
+
+/// Retries of migrations that failed on a transient error such as a serialization failure, a
+/// deadlock or a lock timeout; the `retry` table of the subsystem config
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetryConfig {
+    /// Additional attempts after a transient failure (default 0)
+    #[serde(default)]
+    pub attempts: u32,
+    /// Milliseconds before the first retry, doubled for every further one (default 1000)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backoff_ms: Option<u64>,
+    /// Upper bound of the wait between two attempts in milliseconds (default 30000)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_backoff_ms: Option<u64>,
+}
+
+impl RetryConfig {
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// The wait before the given retry, counting from 1
+    pub fn delay(&self, attempt: u32) -> std::time::Duration {
+        let backoff = self.backoff_ms.unwrap_or(1000).saturating_mul(1u64 << attempt.saturating_sub(1).min(32));
+        std::time::Duration::from_millis(backoff.min(self.max_backoff_ms.unwrap_or(30_000)))
+    }
+}
+
+/// Replaced by the batch size in the up.sql of a batched migration
+pub const BATCH_SIZE_PLACEHOLDER: &str = "{{batch_size}}";
+
+/// `[batch]` of meta.toml: up.sql runs again and again, each time in a transaction of its own,
+/// until a run affects fewer than `size` rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchOptions {
+    /// Rows per batch, substituted for `{{batch_size}}`
+    pub size: u64,
+    /// Pause between batches in milliseconds, to give replicas and vacuum room
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pause_ms: Option<u64>,
+}
+
+impl BatchOptions {
+    /// The SQL of a single batch
+    pub fn render(&self, id: &str, up_sql: &str) -> Result<String> {
+        if self.size == 0 {
+            anyhow::bail!("migration {} has a batch size of 0", id);
+        }
+        if !up_sql.contains(BATCH_SIZE_PLACEHOLDER) {
+            anyhow::bail!("migration {} runs in batches but its up.sql does not use {} to limit the rows per batch", id, BATCH_SIZE_PLACEHOLDER);
+        }
+        Ok(up_sql.replace(BATCH_SIZE_PLACEHOLDER, &self.size.to_string()))
+    }
+
+    /// Report a committed batch; true once the last batch ran
+    pub fn finish_batch(&self, id: &str, number: u64, rows: u64, total: u64) -> bool {
+        crate::say!("  📦 {} batch {}: {} row(s), {} total", id, number, rows, total);
+        rows < self.size
+    }
+
+    pub async fn pause(&self) {
+        if let Some(pause_ms) = self.pause_ms {
+            tokio::time::sleep(std::time::Duration::from_millis(pause_ms)).await;
+        }
+    }
+}
+
+/// How a single migration is executed, resolved from its meta.toml and the run-level defaults
+#[derive(Debug, Clone, Copy)]
+pub struct ExecOptions {
+    pub timeout: Option<u64>,
+    pub transaction: bool,
+    pub isolation: Option<Isolation>,
+    /// Batches of the up direction; down always runs in one go
+    pub batch: Option<BatchOptions>,
+    /// Leading statements that already ran, when resuming a migration with `transaction = false`
+    pub skip_statements: usize,
+}
+
+impl ExecOptions {
+    pub fn with_timeout(timeout: Option<u64>) -> Self {
+        Self { timeout, transaction: true, isolation: None, batch: None, skip_statements: 0 }
+    }
+}
+
+/// OS user and host running qop, recorded as `applied_by` and `hostname` in the tracking and log tables
+pub fn applied_by() -> (String, Option<String>) {
+    (whoami::username(), whoami::fallible::hostname().ok())
+}
+
+/// (OS user, host) that applied the migrations by id, both None for records written by an older qop
+pub type AppliedBy = std::collections::HashMap<String, (Option<String>, Option<String>)>;
+
+/// `user@host` as shown by `list --wide` and `log show`, `-` for records written by an older qop
+pub fn format_applied_by(user: Option<&str>, host: Option<&str>) -> String {
+    match (user, host) {
+        | (Some(user), Some(host)) => format!("{}@{}", user, host),
+        | (Some(user), None) => user.to_string(),
+        | (None, Some(host)) => format!("@{}", host),
+        | (None, None) => "-".to_string(),
     }
 }
 
@@ -29,9 +291,18 @@ impl MigrationMeta {
         let username = whoami::username();
         let timestamp = Utc::now().format("%Y-%m-%d %H:%M:%S UTC");
         let comment = format!("Created by {} at {}", username, timestamp);
-        Self { comment: Some(comment), locked: None }
+        Self { comment: Some(comment), ..Self::default() }
     }
-    
+
+    /// Execution settings of this migration, falling back to the run-level timeout
+    pub fn exec_options(&self, timeout: Option<u64>) -> ExecOptions {
+        ExecOptions { timeout: self.timeout.or(timeout), transaction: self.transaction.unwrap_or(true), isolation: self.isolation, batch: self.batch, skip_statements: 0 }
+    }
+
+    pub fn provenance(&self) -> Provenance {
+        Provenance { author: self.author.clone(), ticket: self.ticket.clone(), labels: self.labels.clone() }
+    }
+
     /// Check if this migration is locked
     pub fn is_locked(&self) -> bool {
         self.locked.unwrap_or(false)
@@ -47,6 +318,37 @@ pub fn normalize_migration_id(id: &str) -> String {
     }
 }
 
+/// Order of migration ids: all-digit ids compare by value, so timestamps and sequential ids
+/// keep their order past the padding width (`9999` before `10000`); other ids compare as text
+pub fn compare_ids(a: &str, b: &str) -> std::cmp::Ordering {
+    let numeric = |id: &str| !id.is_empty() && id.bytes().all(|c| c.is_ascii_digit());
+    if !numeric(a) || !numeric(b) {
+        return a.cmp(b);
+    }
+    let (value_a, value_b) = (a.trim_start_matches('0'), b.trim_start_matches('0'));
+    value_a.len().cmp(&value_b.len()).then_with(|| value_a.cmp(value_b)).then_with(|| a.cmp(b))
+}
+
+/// Sort migration ids oldest first, see `compare_ids`
+pub fn sort_ids(ids: &mut [String]) {
+    ids.sort_by(|a, b| compare_ids(a, b));
+}
+
+/// Whether an id lies within an inclusive range; open ends match everything
+pub fn id_in_range(id: &str, from: Option<&str>, to: Option<&str>) -> bool {
+    from.is_none_or(|from| compare_ids(id, &normalize_migration_id(from)).is_ge()) && to.is_none_or(|to| compare_ids(id, &normalize_migration_id(to)).is_le())
+}
+
+/// Whether the local meta.toml of a migration carries any of the tags; no tags match every migration
+pub fn has_any_tag(migration_dir: &Path, id: &str, tags: &[String]) -> bool {
+    tags.is_empty() || read_migration_meta(migration_dir, id).is_ok_and(|meta| meta.tags.iter().any(|tag| tags.contains(tag)))
+}
+
+/// Whether the local meta.toml of a migration skips it for the profile
+pub fn is_skipped(migration_dir: &Path, id: &str, profile: Option<&str>) -> bool {
+    read_migration_meta(migration_dir, id).is_ok_and(|meta| meta.skip.is_some_and(|skip| skip.applies(profile)))
+}
+
 /// Get local migrations from directory by scanning for "id=" prefixed directories
 pub fn get_local_migrations(path: &Path) -> Result<HashSet<String>> {
     let migration_dir = path
@@ -71,12 +373,61 @@ pub fn get_local_migrations(path: &Path) -> Result<HashSet<String>> {
         .collect())
 }
 
-/// Create a new migration directory with timestamp-based ID
-pub fn create_migration_directory(path: &Path, comment: Option<&str>, locked: bool) -> Result<std::path::PathBuf> {
-    let id = Utc::now().timestamp_millis().to_string();
+/// Directory next to qop.toml holding the templates for `new`, unless configured otherwise
+pub const TEMPLATES_DIR: &str = "templates";
+/// Template used by `new` without `--template`, if it exists
+pub const DEFAULT_TEMPLATE: &str = "default";
+
+/// Skeleton of a new migration; files missing from the template use the built-in skeleton
+#[derive(Debug, Default)]
+pub struct MigrationTemplate {
+    pub up: Option<String>,
+    pub down: Option<String>,
+    pub meta: Option<MigrationMeta>,
+}
+
+/// Load template `name` from `templates/<name>/` (or the configured templates directory, relative
+/// to the config file). Without a name the `default` template is used if there is one.
+pub fn load_template(path: &Path, templates_dir: Option<&str>, name: Option<&str>) -> Result<MigrationTemplate> {
+    let config_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+    let templates_dir = config_dir.join(templates_dir.unwrap_or(TEMPLATES_DIR));
+    let template_dir = templates_dir.join(name.unwrap_or(DEFAULT_TEMPLATE));
+    if !template_dir.is_dir() {
+        let Some(name) = name else {
+            return Ok(MigrationTemplate::default());
+        };
+        let mut available: Vec<String> = std::fs::read_dir(&templates_dir)
+            .map(|entries| entries.filter_map(|e| e.ok()).filter(|e| e.path().is_dir()).map(|e| e.file_name().to_string_lossy().into_owned()).collect())
+            .unwrap_or_default();
+        available.sort();
+        anyhow::bail!(
+            "template {} not found in {} (available: {})",
+            name,
+            templates_dir.display(),
+            if available.is_empty() { "none".to_string() } else { available.join(", ") }
+        );
+    }
+
+    let read = |file: &str| -> Result<Option<String>> {
+        let file_path = template_dir.join(file);
+        match file_path.exists() {
+            | true => std::fs::read_to_string(&file_path).map(Some).with_context(|| format!("Failed to read template file: {}", file_path.display())),
+            | false => Ok(None),
+        }
+    };
+    let meta = match read("meta.toml")? {
+        | Some(content) => Some(toml::from_str(&content).with_context(|| format!("Failed to parse template meta.toml in {}", template_dir.display()))?),
+        | None => None,
+    };
+    Ok(MigrationTemplate { up: read("up.sql")?, down: read("down.sql")?, meta })
+}
+
+/// Create a new migration directory for the ID, failing if it already exists.
+/// `{{id}}`, `{{comment}}` and `{{user}}` in the SQL of the template are replaced.
+pub fn create_migration_directory(path: &Path, id: &str, comment: Option<&str>, locked: bool, template: &MigrationTemplate) -> Result<std::path::PathBuf> {
     let migration_path = path.parent().unwrap();
     let migration_id_path = migration_path.join(format!("id={}", id));
-    std::fs::create_dir_all(&migration_id_path).with_context(|| {
+    std::fs::create_dir(&migration_id_path).with_context(|| {
         format!("Failed to create directory: {}", migration_id_path.display())
     })?;
     
@@ -84,26 +435,34 @@ pub fn create_migration_directory(path: &Path, comment: Option<&str>, locked: bo
     let down_path = migration_id_path.join("down.sql");
     let meta_path = migration_id_path.join("meta.toml");
     
-    std::fs::write(&up_path, "-- SQL goes here").with_context(|| {
+    // Create meta.toml with provided comment, the template's or a default comment including user and timestamp
+    let mut meta = template.meta.clone().unwrap_or_default();
+    match comment {
+        | Some(comment) => meta.comment = Some(comment.to_string()),
+        | None if meta.comment.is_none() => meta.comment = MigrationMeta::new_with_default_comment().comment,
+        | None => {},
+    }
+    if locked {
+        meta.locked = Some(true);
+    }
+    if meta.author.is_none() {
+        meta.author = Some(whoami::username());
+    }
+
+    let render = |sql: &Option<String>| match sql {
+        | Some(sql) => sql
+            .replace("{{id}}", id)
+            .replace("{{comment}}", meta.comment.as_deref().unwrap_or_default())
+            .replace("{{user}}", &whoami::username()),
+        | None => "-- SQL goes here\n".to_string(),
+    };
+    std::fs::write(&up_path, render(&template.up)).with_context(|| {
         format!("Failed to write up migration: {}", up_path.display())
     })?;
-    std::fs::write(&down_path, "-- SQL goes here").with_context(|| {
+    std::fs::write(&down_path, render(&template.down)).with_context(|| {
         format!("Failed to write down migration: {}", down_path.display())
     })?;
-    
-    // Create meta.toml with provided comment or default comment including user and timestamp
-    let meta = if let Some(comment) = comment {
-        MigrationMeta { 
-            comment: Some(comment.to_string()), 
-            locked: if locked { Some(true) } else { None }
-        }
-    } else {
-        let mut meta = MigrationMeta::new_with_default_comment();
-        if locked {
-            meta.locked = Some(true);
-        }
-        meta
-    };
+
     let meta_content = toml::to_string(&meta).with_context(|| {
         format!("Failed to serialize meta.toml for migration: {}", migration_id_path.display())
     })?;
@@ -136,7 +495,7 @@ pub fn read_migration_meta(migration_dir: &Path, migration_id: &str) -> Result<M
     Ok(meta)
 }
 
-/// Read migration SQL files for a given migration ID
+/// Read migration SQL files for a given migration ID, with `-- qop:include` directives expanded
 pub fn read_migration_files(migration_dir: &Path, migration_id: &str) -> Result<(String, String)> {
     // Migration folders always use "id=" prefix
     let migration_path = migration_dir.join(format!("id={}", migration_id));
@@ -155,6 +514,8 @@ pub fn read_migration_files(migration_dir: &Path, migration_id: &str) -> Result<
         },
     )?;
     
+    let up_sql = crate::core::include::expand(&up_sql, &migration_path).with_context(|| format!("Failed to expand includes of {}", up_sql_path.display()))?;
+    let down_sql = crate::core::include::expand(&down_sql, &migration_path).with_context(|| format!("Failed to expand includes of {}", down_sql_path.display()))?;
     Ok((up_sql, down_sql))
 }
 
@@ -165,6 +526,138 @@ pub fn read_migration_with_meta(migration_dir: &Path, migration_id: &str) -> Res
     Ok((up_sql, down_sql, meta))
 }
 
+/// Write meta.toml for an existing migration ID
+pub fn write_migration_meta(migration_dir: &Path, migration_id: &str, meta: &MigrationMeta) -> Result<()> {
+    let meta_path = migration_dir.join(format!("id={}", migration_id)).join("meta.toml");
+    let meta_content = toml::to_string(meta).with_context(|| {
+        format!("Failed to serialize meta.toml for migration: {}", migration_id)
+    })?;
+    std::fs::write(&meta_path, &meta_content).with_context(|| {
+        format!("Failed to write meta.toml: {}", meta_path.display())
+    })?;
+    Ok(())
+}
+
+/// Write up.sql, down.sql and meta.toml for a migration ID, creating the directory if needed
+pub fn write_migration_directory(migration_dir: &Path, migration_id: &str, up_sql: &str, down_sql: &str, meta: &MigrationMeta) -> Result<std::path::PathBuf> {
+    let migration_id_path = migration_dir.join(format!("id={}", migration_id));
+    std::fs::create_dir_all(&migration_id_path).with_context(|| {
+        format!("Failed to create directory: {}", migration_id_path.display())
+    })?;
+
+    let up_path = migration_id_path.join("up.sql");
+    let down_path = migration_id_path.join("down.sql");
+    let meta_path = migration_id_path.join("meta.toml");
+
+    std::fs::write(&up_path, up_sql).with_context(|| {
+        format!("Failed to write up migration: {}", up_path.display())
+    })?;
+    std::fs::write(&down_path, down_sql).with_context(|| {
+        format!("Failed to write down migration: {}", down_path.display())
+    })?;
+    let meta_content = toml::to_string(meta).with_context(|| {
+        format!("Failed to serialize meta.toml for migration: {}", migration_id_path.display())
+    })?;
+    std::fs::write(&meta_path, &meta_content).with_context(|| {
+        format!("Failed to write meta.toml: {}", meta_path.display())
+    })?;
+
+    Ok(migration_id_path)
+}
+
+/// Move migration directories into `archive/<name>` next to the migration directories
+pub fn archive_migration_directories(migration_dir: &Path, migration_ids: &[String], name: &str) -> Result<std::path::PathBuf> {
+    let archive_path = migration_dir.join("archive").join(name);
+    std::fs::create_dir_all(&archive_path).with_context(|| {
+        format!("Failed to create directory: {}", archive_path.display())
+    })?;
+    for id in migration_ids {
+        let from = migration_dir.join(format!("id={}", id));
+        let to = archive_path.join(format!("id={}", id));
+        std::fs::rename(&from, &to).with_context(|| {
+            format!("Failed to archive migration {} to {}", from.display(), to.display())
+        })?;
+    }
+    Ok(archive_path)
+}
+
+/// Rename the directory of a migration to a new ID
+pub fn rename_migration_directory(migration_dir: &Path, migration_id: &str, new_id: &str) -> Result<std::path::PathBuf> {
+    let from = migration_dir.join(format!("id={}", migration_id));
+    let to = migration_dir.join(format!("id={}", new_id));
+    std::fs::rename(&from, &to).with_context(|| {
+        format!("Failed to rename migration {} to {}", from.display(), to.display())
+    })?;
+    Ok(to)
+}
+
+/// Delete the directory of a migration including all of its files
+pub fn remove_migration_directory(migration_dir: &Path, migration_id: &str) -> Result<std::path::PathBuf> {
+    let migration_id_path = migration_dir.join(format!("id={}", migration_id));
+    std::fs::remove_dir_all(&migration_id_path).with_context(|| {
+        format!("Failed to remove migration directory: {}", migration_id_path.display())
+    })?;
+    Ok(migration_id_path)
+}
+
+/// Message for a failed statement of a migration run statement by statement, locating it in the
+/// (include-expanded) SQL
+pub fn statement_error(migration_id: &str, number: usize, total: usize, line: usize, statement: &str, error: impl Into<anyhow::Error>, hint: &str) -> anyhow::Error {
+    let cause = error.into();
+    let message = format!("Statement {}/{} of migration {} failed at line {}: {}\n{}{}", number, total, migration_id, line, cause, statement, hint);
+    anyhow::Error::new(StatementError { message, number, cause })
+}
+
+/// A failed statement of a migration. It reads like the message alone; the database error is kept
+/// so transient failures can be told apart.
+#[derive(Debug)]
+pub struct StatementError {
+    message: String,
+    /// 1-based position of the statement in its migration
+    pub number: usize,
+    pub cause: anyhow::Error,
+}
+
+impl std::fmt::Display for StatementError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for StatementError {}
+
+/// The first error of type `E` behind an error, looking into failed statements as well
+pub fn find_cause<E: std::error::Error + Send + Sync + 'static>(error: &anyhow::Error) -> Option<&E> {
+    error.chain().find_map(|e| e.downcast_ref::<E>().or_else(|| e.downcast_ref::<StatementError>().and_then(|s| s.cause.downcast_ref::<E>())))
+}
+
+/// Show which statement of a migration is running, on its progress bar or else on an interactive
+/// stderr only; `done == total` clears the line again
+pub fn report_statement_progress(migration_id: &str, done: usize, total: usize) {
+    use std::io::IsTerminal;
+    if crate::core::progress::statement(migration_id, done, total) {
+        return;
+    }
+    // Verbose output prints every statement on a line of its own instead
+    if total < 2 || !io::stderr().is_terminal() || crate::core::output::verbosity() != crate::core::output::Verbosity::Normal {
+        return;
+    }
+    match done < total {
+        | true => eprint!("\r\x1b[2K{}", crate::core::output::decorate(format!("⏳ {} statement {}/{}", migration_id, done + 1, total))),
+        | false => eprint!("\r\x1b[2K"),
+    }
+}
+
+/// Concatenate the SQL of several migrations, each prefixed with a marker naming its origin
+pub fn concat_migration_sql(parts: &[(String, String)]) -> String {
+    parts
+        .iter()
+        .map(|(id, sql)| format!("-- squashed from migration {}\n{}", id, sql.trim_end()))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+        + "\n"
+}
+
 /// Check if migration should be warned about for non-linear history
 pub fn check_non_linear_history(
     applied_migrations: &HashSet<String>,
@@ -174,41 +667,145 @@ pub fn check_non_linear_history(
         return Vec::new();
     }
     
-    let max_applied_migration = applied_migrations.iter().max().cloned().unwrap_or_default();
+    let max_applied_migration = applied_migrations.iter().max_by(|a, b| compare_ids(a, b)).cloned().unwrap_or_default();
     
     migrations_to_apply
         .iter()
-        .filter(|id| id.as_str() < max_applied_migration.as_str())
+        .filter(|id| compare_ids(id, &max_applied_migration).is_lt())
         .cloned()
         .collect()
 }
 
+/// Order migrations of a run by id, moving each behind the migrations in its `depends_on`.
+/// Every dependency has to be applied already or be part of the run.
+pub fn order_by_dependencies(migration_dir: &Path, ids: &[String], applied: &HashSet<String>) -> Result<Vec<String>> {
+    let mut remaining: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for id in ids {
+        let meta = read_migration_meta(migration_dir, id)?;
+        let mut deps = Vec::new();
+        for dep in meta.depends_on.iter().map(|dep| normalize_migration_id(dep)) {
+            if ids.contains(&dep) {
+                deps.push(dep);
+            } else if !applied.contains(&dep) {
+                anyhow::bail!("migration {} depends on {}, which is neither applied nor part of this run", id, dep);
+            }
+        }
+        remaining.insert(id.clone(), deps);
+    }
+
+    // Always continue with the lowest id whose dependencies are done, so independent migrations keep id order
+    let mut by_id: Vec<String> = remaining.keys().cloned().collect();
+    sort_ids(&mut by_id);
+    let mut ordered = Vec::with_capacity(remaining.len());
+    while !remaining.is_empty() {
+        let next = by_id
+            .iter()
+            .find(|id| remaining.get(*id).is_some_and(|deps| deps.iter().all(|dep| !remaining.contains_key(dep))))
+            .cloned();
+        let Some(next) = next else {
+            let cycle: Vec<&str> = remaining.keys().map(|id| id.as_str()).collect();
+            anyhow::bail!("depends_on contains a cycle among migrations {}", cycle.join(", "));
+        };
+        remaining.remove(&next);
+        ordered.push(next);
+    }
+    Ok(ordered)
+}
+
 /// Display non-linear history warning and get user confirmation
 pub fn handle_non_linear_warning(out_of_order_migrations: &[String], max_applied: &str) -> Result<bool> {
     if out_of_order_migrations.is_empty() {
         return Ok(true);
     }
-    println!("⚠️  Non-linear history detected!");
-    println!("The following migrations would create a non-linear history:");
+    let mut out = crate::core::output::stream();
+    crate::say!("⚠️  Non-linear history detected!");
+    writeln!(out, "The following migrations would create a non-linear history:")?;
     for migration in out_of_order_migrations {
-        println!("  - {}", migration);
-    }
-    println!("Latest applied migration: {}", max_applied);
-    println!("");
-    println!("This could cause issues with database schema consistency.");
-    println!("Alternatively, you can run history fix to rename out-of-order migrations.");
-    print!("Do you want to continue? [y/N]: ");
-    io::stdout().flush()?;
+        crate::say!("  - {}", migration);
+    }
+    writeln!(out, "Latest applied migration: {}", max_applied)?;
+    writeln!(out)?;
+    writeln!(out, "This could cause issues with database schema consistency.")?;
+    writeln!(out, "Alternatively, you can run history fix to rename out-of-order migrations.")?;
+    if let Some(answer) = non_interactive_answer("Do you want to continue?")? {
+        return Ok(answer);
+    }
+    write!(out, "Do you want to continue? [y/N]: ")?;
+    out.flush()?;
     let mut input = String::new();
     io::stdin().read_line(&mut input)?;
     let input = input.trim().to_lowercase();
     Ok(matches!(input.as_str(), "y" | "yes"))
 }
 
+/// Result of `history fix` with `--output json`
+#[derive(Debug, Serialize)]
+pub struct HistoryFixOut {
+    pub command: &'static str,
+    pub renamed: Vec<RenamedOut>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RenamedOut {
+    pub from: String,
+    pub to: String,
+}
+
+/// Result of `history sync` with `--output json`
+#[derive(Debug, Serialize)]
+pub struct HistorySyncOut {
+    pub command: &'static str,
+    pub synced: Vec<String>,
+}
+
 /// Print migration application results
 pub fn print_migration_results(applied_count: usize, action: &str) {
     if applied_count > 0 {
-        println!("\n🎉 Successfully {} {} migration(s)!", action, applied_count);
+        crate::summary!("\n🎉 Successfully {} {} migration(s)!", action, applied_count);
+    }
+}
+
+/// A prompt needed an answer while stdin is not a terminal, which qop reports with exit code 4
+/// instead of the usual 1 (ADR-0019)
+#[derive(Debug, Clone)]
+pub struct PromptRequired(String);
+
+impl PromptRequired {
+    pub const EXIT_CODE: u8 = 4;
+}
+
+impl std::fmt::Display for PromptRequired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for PromptRequired {}
+
+/// Whether `error` comes from a prompt that could not be asked, also through added context
+pub fn prompt_required(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| cause.is::<PromptRequired>())
+}
+
+/// The answer of the `non_interactive` policy when stdin is not a terminal, None when there is
+/// one to ask. Without this, a CI job that forgot `--yes` waits for input forever.
+fn non_interactive_answer(message: &str) -> Result<Option<bool>> {
+    use std::io::IsTerminal;
+    if io::stdin().is_terminal() {
+        return Ok(None);
+    }
+    match crate::core::output::preferences().non_interactive {
+        | crate::config::NonInteractive::Fail => {
+            Err(PromptRequired(format!("\"{}\" needs an answer, but stdin is not a terminal; pass --yes or set non_interactive in the user config", message.trim_start_matches(['❓', ' ']))).into())
+        },
+        | crate::config::NonInteractive::Yes => {
+            crate::say!("{} yes (non-interactive)", message);
+            Ok(Some(true))
+        },
+        | crate::config::NonInteractive::No => {
+            crate::say!("{} no (non-interactive)", message);
+            Ok(Some(false))
+        },
     }
 }
 
@@ -219,42 +816,115 @@ pub fn prompt_for_confirmation_with_diff<F>(
     diff_fn: F,
 ) -> Result<bool>
 where
-    F: Fn() -> Result<()>,
+    F: Fn(DiffView) -> Result<()>,
 {
     if yes { return Ok(true); }
+    if let Some(answer) = non_interactive_answer(message)? {
+        return Ok(answer);
+    }
+    let mut out = crate::core::output::stream();
     loop {
-        print!("{} [y/N/d]: ", message);
-        io::stdout().flush()?;
+        write!(out, "{} [y/N/d/s]: ", message)?;
+        out.flush()?;
         let mut input = String::new();
         io::stdin().read_line(&mut input)?;
         let input = input.trim().to_lowercase();
         match input.as_str() {
             "y" | "yes" => return Ok(true),
             "n" | "no" | "" => return Ok(false),
-            "d" | "diff" => { println!("\n📋 Migration Details:"); diff_fn()?; println!(""); }
-            _ => println!("Please enter 'y' (yes), 'n' (no), or 'd' (diff)"),
+            "d" | "diff" => {
+                crate::say!("\n📋 Operations:");
+                diff_fn(DiffView::Operations)?;
+                crate::say!("Enter 's' for the full SQL.");
+                writeln!(out)?;
+            }
+            "s" | "sql" => { crate::say!("\n📋 Migration Details:"); diff_fn(DiffView::Sql)?; writeln!(out)?; }
+            _ => writeln!(out, "Please enter 'y' (yes), 'n' (no), 'd' (operations) or 's' (full SQL)")?,
         }
     }
 }
 
-/// Prints a formatted SQL migration diff block to stdout for easy identification
-pub fn display_sql_migration(migration_id: &str, sql: &str, direction: &str) -> Result<()> {
-    let header_line = "────────────────────────────────────────────────────────";
-    println!("");
-    println!("▶ Migration: {} [{}]", migration_id, direction);
-    println!("{}", header_line);
-    print!("{}", sql);
-    if !sql.ends_with('\n') { println!(""); }
-    println!("{}", header_line);
-    println!("");
+/// What the diff callback of a confirmation prompt shows for each migration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffView {
+    /// The full SQL, the `s` option
+    Sql,
+    /// The tables, indexes and other objects it changes, the `d` option
+    Operations,
+}
+
+/// Let the user pick a migration from a fuzzy-searchable list of `(id, comment, timestamp)` entries.
+/// Fails when stdin is not a terminal, in which case the id has to be passed explicitly.
+pub fn pick_migration(prompt: &str, candidates: &[(String, Option<String>, Option<NaiveDateTime>)]) -> Result<Option<String>> {
+    use std::io::IsTerminal;
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        return Err(PromptRequired("no migration id given and no terminal available for interactive selection".to_string()).into());
+    }
+    if candidates.is_empty() {
+        return Ok(None);
+    }
+    let items: Vec<String> = candidates
+        .iter()
+        .map(|(id, comment, ts)| {
+            let ts = ts.map(|ts| Local.from_utc_datetime(&ts).format("%Y-%m-%d %H:%M:%S %Z").to_string()).unwrap_or_else(|| "pending".to_string());
+            format!("{}  {}  {}", id, ts, comment.as_deref().unwrap_or("-"))
+        })
+        .collect();
+    let selection = dialoguer::FuzzySelect::new()
+        .with_prompt(prompt)
+        .items(&items)
+        .default(0)
+        .interact_opt()?;
+    Ok(selection.map(|i| candidates[i].0.clone()))
+}
+
+/// Prints a formatted SQL migration diff block to stdout (stderr with `--output json`) for easy
+/// identification
+pub fn display_sql_migration(migration_id: &str, sql: &str, direction: &str, view: DiffView) -> Result<()> {
+    let text = match view {
+        | DiffView::Operations => format_operation_summary(migration_id, sql, direction),
+        | DiffView::Sql => format_sql_migration(migration_id, sql, direction),
+    };
+    crate::core::progress::suspend(|| write!(crate::core::output::stream(), "{}", text))?;
     Ok(())
 }
 
+/// The tables, indexes and other objects a migration changes, one operation per line
+pub fn format_operation_summary(migration_id: &str, sql: &str, direction: &str) -> String {
+    let operations = crate::core::migration_diff::parse_migration_operations(sql);
+    let mut summary = format!("▶ {} [{}]: {} operation(s)", migration_id, direction, operations.len());
+    for op in &operations {
+        summary.push_str(&format!("\n    {}", crate::core::plan::describe(op)));
+    }
+    crate::core::output::decorate(summary) + "\n"
+}
+
+/// The SQL of a migration between header lines, as `display_sql_migration` prints it
+pub fn format_sql_migration(migration_id: &str, sql: &str, direction: &str) -> String {
+    let header_line = crate::core::output::rule();
+    let mut out = String::from("\n");
+    if crate::core::output::verbosity() > crate::core::output::Verbosity::Quiet {
+        out.push_str(&crate::core::output::decorate(format!("▶ Migration: {} [{}]", migration_id, direction)));
+        out.push('\n');
+    }
+    out.push_str(&header_line);
+    out.push('\n');
+    out.push_str(&crate::core::highlight::sql(sql));
+    if !sql.ends_with('\n') { out.push('\n'); }
+    out.push_str(&header_line);
+    out.push_str("\n\n");
+    out
+}
+
 /// Render a migration table given local and remote data in a unified way
 pub fn render_migration_table(
     local_ids: &std::collections::HashSet<String>,
     remote_history: &[(String, NaiveDateTime, Option<String>, bool)],
+    remote_provenance: &std::collections::HashMap<String, Provenance>,
+    remote_durations: &std::collections::HashMap<String, i64>,
+    remote_applied_by: Option<&AppliedBy>,
     migration_dir: &std::path::Path,
+    profile: Option<&str>,
 ) -> Result<()> {
     let mut all: BTreeMap<String, (Option<NaiveDateTime>, bool, Option<String>, bool)> = BTreeMap::new();
     
@@ -276,37 +946,178 @@ pub fn render_migration_table(
         }
     }
 
-    let mut table = Table::new();
+    // Author, ticket and labels as recorded when applied, else from the local meta.toml
+    let provenance: BTreeMap<&String, Provenance> = all
+        .iter()
+        .map(|(id, (applied_at, ..))| {
+            let provenance = match remote_provenance.get(id) {
+                | Some(provenance) if applied_at.is_some() => provenance.clone(),
+                | _ => read_migration_meta(migration_dir, id).map(|meta| meta.provenance()).unwrap_or_default(),
+            };
+            (id, provenance)
+        })
+        .collect();
+    let show_author = provenance.values().any(|p| p.author.is_some());
+    let show_ticket = provenance.values().any(|p| p.ticket.is_some());
+    let show_labels = provenance.values().any(|p| !p.labels.is_empty());
+    let show_duration = !remote_durations.is_empty();
+
+    let mut header = vec![Cell::new("Migration ID"), Cell::new("Remote"), Cell::new("Local"), Cell::new("Comment"), Cell::new("Locked")];
+    if show_author {
+        header.push(Cell::new("Author"));
+    }
+    if show_ticket {
+        header.push(Cell::new("Ticket"));
+    }
+    if show_labels {
+        header.push(Cell::new("Labels"));
+    }
+    if show_duration {
+        header.push(Cell::new("Duration"));
+    }
+    if remote_applied_by.is_some() {
+        header.push(Cell::new("Applied By"));
+    }
+    let mut table = crate::core::output::table();
     table
-        .load_preset(UTF8_FULL)
-        .apply_modifier(UTF8_ROUND_CORNERS)
         .set_content_arrangement(ContentArrangement::Dynamic)
-        .set_header(vec![
-            Cell::new("Migration ID"),
-            Cell::new("Remote"),
-            Cell::new("Local"),
-            Cell::new("Comment"),
-            Cell::new("Locked"),
-        ]);
+        .set_header(header);
 
-    for (id, (applied_at, is_local, comment, locked)) in all {
+    let mut ids: Vec<&String> = all.keys().collect();
+    ids.sort_by(|a, b| compare_ids(a, b));
+    for id in ids {
+        let (applied_at, is_local, comment, locked) = &all[id];
+        let provenance = &provenance[id];
         let remote_str = if let Some(ts) = applied_at {
-            let utc_dt = Local.from_utc_datetime(&ts);
+            let utc_dt = Local.from_utc_datetime(ts);
             utc_dt.format("%Y-%m-%d %H:%M:%S %Z").to_string()
+        } else if *is_local && is_skipped(migration_dir, id, profile) {
+            "⏭️ skipped".to_string()
         } else { "❌".to_string() };
-        let local_str = if is_local { "✅" } else { "❌" };
-        let comment_str = comment.unwrap_or_else(|| "-".to_string());
-        let locked_str = if locked { "🔒" } else { "" };
+        let local_str = if *is_local { "✅" } else { "❌" };
+        let comment_str = comment.clone().unwrap_or_else(|| "-".to_string());
+        let locked_str = if *locked { "🔒" } else { "" };
         
-        table.add_row(vec![
+        let mut row = vec![
             Cell::new(id),
-            Cell::new(remote_str).set_alignment(CellAlignment::Center),
-            Cell::new(local_str).set_alignment(CellAlignment::Center),
+            Cell::new(crate::core::output::ascii(&remote_str)).set_alignment(CellAlignment::Center),
+            Cell::new(crate::core::output::ascii(local_str)).set_alignment(CellAlignment::Center),
             Cell::new(comment_str),
-            Cell::new(locked_str).set_alignment(CellAlignment::Center),
-        ]);
+            Cell::new(crate::core::output::ascii(locked_str)).set_alignment(CellAlignment::Center),
+        ];
+        if show_author {
+            row.push(Cell::new(provenance.author.as_deref().unwrap_or("-")));
+        }
+        if show_ticket {
+            row.push(Cell::new(provenance.ticket.as_deref().unwrap_or("-")));
+        }
+        if show_labels {
+            row.push(Cell::new(match provenance.labels.is_empty() {
+                | true => "-".to_string(),
+                | false => provenance.format_labels(),
+            }));
+        }
+        if show_duration {
+            let duration = match remote_durations.get(id) {
+                | Some(ms) if applied_at.is_some() => crate::core::duration::format_millis(*ms),
+                | _ => "-".to_string(),
+            };
+            row.push(Cell::new(duration).set_alignment(CellAlignment::Right));
+        }
+        if let Some(remote_applied_by) = remote_applied_by {
+            let applied_by = match remote_applied_by.get(id) {
+                | Some((user, host)) if applied_at.is_some() => format_applied_by(user.as_deref(), host.as_deref()),
+                | _ => "-".to_string(),
+            };
+            row.push(Cell::new(applied_by));
+        }
+        table.add_row(row);
     }
 
     println!("{table}");
     Ok(())
 }
+
+/// Result of comparing an applied migration with its local files
+#[derive(Debug, Serialize)]
+pub struct VerifyResult {
+    pub id: String,
+    pub local: bool,
+    pub up_modified: bool,
+    pub down_modified: bool,
+}
+
+impl VerifyResult {
+    pub fn is_modified(&self) -> bool {
+        self.up_modified || self.down_modified
+    }
+}
+
+/// Compare stored SQL with local SQL, ignoring trailing whitespace differences
+pub fn sql_matches(stored: &str, local: &str) -> bool {
+    stored.trim_end() == local.trim_end()
+}
+
+/// Checksum stored as `up_sha256`/`down_sha256`; like `sql_matches` it ignores trailing whitespace
+pub fn sql_checksum(sql: &str) -> String {
+    crate::core::bundle::sha256_hex(sql.trim_end().as_bytes())
+}
+
+/// Render verification results as a table
+pub fn render_verify_table(results: &[VerifyResult]) {
+    let mut table = crate::core::output::table();
+    table
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec![
+            Cell::new("Migration ID"),
+            Cell::new("Local"),
+            Cell::new("Up"),
+            Cell::new("Down"),
+        ]);
+
+    for result in results {
+        let (up_str, down_str) = if !result.local {
+            ("-", "-")
+        } else {
+            (
+                if result.up_modified { "❌" } else { "✅" },
+                if result.down_modified { "❌" } else { "✅" },
+            )
+        };
+        table.add_row(vec![
+            Cell::new(&result.id),
+            Cell::new(crate::core::output::ascii(if result.local { "✅" } else { "❌" })).set_alignment(CellAlignment::Center),
+            Cell::new(crate::core::output::ascii(up_str)).set_alignment(CellAlignment::Center),
+            Cell::new(crate::core::output::ascii(down_str)).set_alignment(CellAlignment::Center),
+        ]);
+    }
+
+    println!("{table}");
+}
+
+/// Run a migration step, retrying it up to `retries` more times if it fails, and up to
+/// `policy.attempts` more times if it fails on an error `is_transient` accepts, backing off in between
+pub async fn with_retries<T, F, Fut>(id: &str, retries: u32, policy: &RetryConfig, is_transient: impl Fn(&anyhow::Error) -> bool, mut step: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        let e = match step().await {
+            | Ok(value) => return Ok(value),
+            | Err(e) => e,
+        };
+        let limit = match is_transient(&e) {
+            | true => retries.max(policy.attempts),
+            | false => retries,
+        };
+        if attempt >= limit {
+            return Err(e);
+        }
+        attempt += 1;
+        let delay = policy.delay(attempt);
+        crate::say!("⚠️  Migration {} failed, retrying in {:.1}s ({}/{}): {:#}", id, delay.as_secs_f64(), attempt, limit, e);
+        tokio::time::sleep(delay).await;
+    }
+}
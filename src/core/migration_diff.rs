@@ -0,0 +1,397 @@
+use serde::Serialize;
+
+/// A schema or data operation recognised in migration SQL
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MigrationOperation {
+    CreateTable { name: String, if_not_exists: bool },
+    DropTable { name: String, if_exists: bool },
+    AlterTable { name: String, actions: Vec<AlterAction> },
+    CreateIndex { name: Option<String>, table: String, unique: bool, concurrently: bool, if_not_exists: bool },
+    DropIndex { name: String, if_exists: bool, concurrently: bool },
+    CreateView { name: String, or_replace: bool },
+    DropView { name: String, if_exists: bool },
+    Insert { table: String },
+    Update { table: String },
+    Delete { table: String },
+    Truncate { table: String },
+    Other { statement: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AlterAction {
+    AddColumn { column: String },
+    DropColumn { column: String },
+    RenameColumn { from: String, to: String },
+    RenameTo { name: String },
+    Other { clause: String },
+}
+
+/// Split SQL into statements on top-level semicolons, dropping comments and empty statements.
+/// Quoted strings, quoted identifiers and dollar-quoted bodies are kept intact.
+pub fn split_statements(sql: &str) -> Vec<String> {
+    split_statements_with_lines(sql).into_iter().map(|(_, statement)| statement).collect()
+}
+
+/// `split_statements` with the 1-based line each statement starts on
+pub fn split_statements_with_lines(sql: &str) -> Vec<(usize, String)> {
+    let chars: Vec<char> = sql.chars().collect();
+    let line_at = |i: usize| chars[..i].iter().filter(|c| **c == '\n').count() + 1;
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut start = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let comment = (c == '-' && chars.get(i + 1) == Some(&'-')) || (c == '/' && chars.get(i + 1) == Some(&'*'));
+        if current.trim().is_empty() && !c.is_whitespace() && !comment {
+            start = i;
+        }
+        match c {
+            | '-' if chars.get(i + 1) == Some(&'-') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            },
+            | '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 2;
+                current.push(' ');
+                continue;
+            },
+            | '\'' | '"' | '`' => {
+                current.push(c);
+                i += 1;
+                while i < chars.len() {
+                    current.push(chars[i]);
+                    if chars[i] == c {
+                        // doubled quote is an escaped quote
+                        if chars.get(i + 1) == Some(&c) {
+                            current.push(c);
+                            i += 2;
+                            continue;
+                        }
+                        break;
+                    }
+                    i += 1;
+                }
+                i += 1;
+                continue;
+            },
+            | '$' => {
+                if let Some(tag_len) = dollar_tag_len(&chars[i..]) {
+                    let tag: String = chars[i..i + tag_len].iter().collect();
+                    current.push_str(&tag);
+                    i += tag_len;
+                    let rest: String = chars[i..].iter().collect();
+                    match rest.find(&tag) {
+                        | Some(end) => {
+                            current.push_str(&rest[..end + tag.len()]);
+                            i += rest[..end + tag.len()].chars().count();
+                        },
+                        | None => {
+                            current.push_str(&rest);
+                            i = chars.len();
+                        },
+                    }
+                    continue;
+                }
+                current.push(c);
+            },
+            // Trigger bodies (`BEGIN ... END`) contain semicolons of their own
+            | ';' if in_compound_body(&current) => current.push(c),
+            | ';' => {
+                let statement = current.trim();
+                if !statement.is_empty() {
+                    statements.push((line_at(start), statement.to_string()));
+                }
+                current.clear();
+            },
+            | _ => current.push(c),
+        }
+        i += 1;
+    }
+    let statement = current.trim();
+    if !statement.is_empty() {
+        statements.push((line_at(start), statement.to_string()));
+    }
+    statements
+}
+
+/// Whether a CREATE statement has opened a `BEGIN` block that has not reached its `END` yet
+fn in_compound_body(statement: &str) -> bool {
+    let words: Vec<String> = statement.split(|c: char| !c.is_alphanumeric() && c != '_').filter(|w| !w.is_empty()).map(|w| w.to_ascii_uppercase()).collect();
+    words.first().is_some_and(|w| w == "CREATE") && words.iter().any(|w| w == "BEGIN") && words.last().is_some_and(|w| w != "END")
+}
+
+/// Length of a dollar-quote tag (`$$` or `$tag$`) starting at the beginning of `chars`
+pub fn dollar_tag_len(chars: &[char]) -> Option<usize> {
+    let mut len = 1;
+    while len < chars.len() {
+        match chars[len] {
+            | '$' => return Some(len + 1),
+            | c if c.is_alphanumeric() || c == '_' => len += 1,
+            | _ => return None,
+        }
+    }
+    None
+}
+
+/// Split a statement into words, keeping quoted identifiers and parenthesised groups as single tokens
+fn tokenize(statement: &str) -> Vec<String> {
+    let chars: Vec<char> = statement.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' {
+            let start = i;
+            let mut depth = 0;
+            while i < chars.len() {
+                match chars[i] {
+                    | '(' => depth += 1,
+                    | ')' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    },
+                    | _ => {},
+                }
+                i += 1;
+            }
+            i = (i + 1).min(chars.len());
+            tokens.push(chars[start..i].iter().collect());
+        } else if c == ',' || c == ')' {
+            tokens.push(c.to_string());
+            i += 1;
+        } else {
+            let start = i;
+            let mut quote: Option<char> = None;
+            while i < chars.len() {
+                let ch = chars[i];
+                match quote {
+                    | Some(q) if ch == q => quote = None,
+                    | Some(_) => {},
+                    | None if ch == '"' || ch == '`' || ch == '\'' => quote = Some(ch),
+                    | None if ch.is_whitespace() || ch == '(' || ch == ',' => break,
+                    | None => {},
+                }
+                i += 1;
+            }
+            tokens.push(chars[start..i].iter().collect());
+        }
+    }
+    tokens
+}
+
+/// Normalise an identifier: unquoted parts are lower-cased, quotes are removed
+pub fn normalize_ident(ident: &str) -> String {
+    ident
+        .split('.')
+        .map(|part| {
+            if part.len() >= 2 && ((part.starts_with('"') && part.ends_with('"')) || (part.starts_with('`') && part.ends_with('`'))) {
+                part[1..part.len() - 1].to_string()
+            } else {
+                part.to_lowercase()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Check whether two identifiers name the same object, ignoring a schema qualifier on either side
+pub fn same_object(a: &str, b: &str) -> bool {
+    let a = normalize_ident(a);
+    let b = normalize_ident(b);
+    a == b || a.rsplit('.').next() == b.rsplit('.').next()
+}
+
+struct Cursor {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Cursor {
+    fn new(statement: &str) -> Self {
+        Self { tokens: tokenize(statement), pos: 0 }
+    }
+
+    fn peek_is(&self, keyword: &str) -> bool {
+        self.tokens.get(self.pos).is_some_and(|t| t.eq_ignore_ascii_case(keyword))
+    }
+
+    /// Consume the keywords if they all match in sequence
+    fn eat(&mut self, keywords: &[&str]) -> bool {
+        let matches = keywords
+            .iter()
+            .enumerate()
+            .all(|(offset, kw)| self.tokens.get(self.pos + offset).is_some_and(|t| t.eq_ignore_ascii_case(kw)));
+        if matches {
+            self.pos += keywords.len();
+        }
+        matches
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// Next token as an identifier (strips a trailing column list glued to the name)
+    fn ident(&mut self) -> Option<String> {
+        self.next().map(|t| t.split('(').next().unwrap_or_default().to_string()).filter(|t| !t.is_empty())
+    }
+
+    fn rest(&self) -> &[String] {
+        &self.tokens[self.pos.min(self.tokens.len())..]
+    }
+}
+
+/// Parse a single statement into an operation; unrecognised statements become `Other`
+pub fn parse_statement(statement: &str) -> MigrationOperation {
+    parse_known(statement).unwrap_or_else(|| MigrationOperation::Other { statement: statement.to_string() })
+}
+
+fn parse_known(statement: &str) -> Option<MigrationOperation> {
+    let mut c = Cursor::new(statement);
+    if c.eat(&["CREATE"]) {
+        let or_replace = c.eat(&["OR", "REPLACE"]);
+        c.eat(&["TEMPORARY"]);
+        c.eat(&["TEMP"]);
+        c.eat(&["UNLOGGED"]);
+        if c.eat(&["TABLE"]) {
+            let if_not_exists = c.eat(&["IF", "NOT", "EXISTS"]);
+            return Some(MigrationOperation::CreateTable { name: c.ident()?, if_not_exists });
+        }
+        if c.eat(&["MATERIALIZED", "VIEW"]) || c.eat(&["VIEW"]) {
+            c.eat(&["IF", "NOT", "EXISTS"]);
+            return Some(MigrationOperation::CreateView { name: c.ident()?, or_replace });
+        }
+        let unique = c.eat(&["UNIQUE"]);
+        if c.eat(&["INDEX"]) {
+            let concurrently = c.eat(&["CONCURRENTLY"]);
+            let if_not_exists = c.eat(&["IF", "NOT", "EXISTS"]);
+            let name = if c.peek_is("ON") { None } else { c.ident() };
+            if !c.eat(&["ON"]) {
+                return None;
+            }
+            c.eat(&["ONLY"]);
+            return Some(MigrationOperation::CreateIndex { name, table: c.ident()?, unique, concurrently, if_not_exists });
+        }
+        return None;
+    }
+    if c.eat(&["DROP"]) {
+        if c.eat(&["TABLE"]) {
+            let if_exists = c.eat(&["IF", "EXISTS"]);
+            return Some(MigrationOperation::DropTable { name: c.ident()?, if_exists });
+        }
+        if c.eat(&["INDEX"]) {
+            let concurrently = c.eat(&["CONCURRENTLY"]);
+            let if_exists = c.eat(&["IF", "EXISTS"]);
+            return Some(MigrationOperation::DropIndex { name: c.ident()?, if_exists, concurrently });
+        }
+        if c.eat(&["MATERIALIZED", "VIEW"]) || c.eat(&["VIEW"]) {
+            let if_exists = c.eat(&["IF", "EXISTS"]);
+            return Some(MigrationOperation::DropView { name: c.ident()?, if_exists });
+        }
+        return None;
+    }
+    if c.eat(&["ALTER", "TABLE"]) {
+        c.eat(&["IF", "EXISTS"]);
+        c.eat(&["ONLY"]);
+        let name = c.ident()?;
+        let mut actions = Vec::new();
+        for clause in c.rest().split(|t| t == ",") {
+            actions.push(parse_alter_action(clause));
+        }
+        return Some(MigrationOperation::AlterTable { name, actions });
+    }
+    if c.eat(&["INSERT", "INTO"]) || c.eat(&["INSERT", "OR", "REPLACE", "INTO"]) || c.eat(&["INSERT", "OR", "IGNORE", "INTO"]) {
+        return Some(MigrationOperation::Insert { table: c.ident()? });
+    }
+    if c.eat(&["UPDATE"]) {
+        c.eat(&["ONLY"]);
+        return Some(MigrationOperation::Update { table: c.ident()? });
+    }
+    if c.eat(&["DELETE", "FROM"]) {
+        c.eat(&["ONLY"]);
+        return Some(MigrationOperation::Delete { table: c.ident()? });
+    }
+    if c.eat(&["TRUNCATE"]) {
+        c.eat(&["TABLE"]);
+        c.eat(&["ONLY"]);
+        return Some(MigrationOperation::Truncate { table: c.ident()? });
+    }
+    None
+}
+
+fn parse_alter_action(clause: &[String]) -> AlterAction {
+    let mut c = Cursor { tokens: clause.to_vec(), pos: 0 };
+    if c.eat(&["ADD"]) {
+        if c.eat(&["CONSTRAINT"]) || c.peek_is("PRIMARY") || c.peek_is("FOREIGN") || c.peek_is("UNIQUE") || c.peek_is("CHECK") {
+            return AlterAction::Other { clause: clause.join(" ") };
+        }
+        c.eat(&["COLUMN"]);
+        c.eat(&["IF", "NOT", "EXISTS"]);
+        if let Some(column) = c.ident() {
+            return AlterAction::AddColumn { column };
+        }
+    } else if c.eat(&["DROP"]) {
+        if c.eat(&["COLUMN"]) || !(c.peek_is("CONSTRAINT") || c.peek_is("DEFAULT") || c.peek_is("NOT")) {
+            c.eat(&["IF", "EXISTS"]);
+            if let Some(column) = c.ident() {
+                return AlterAction::DropColumn { column };
+            }
+        }
+    } else if c.eat(&["RENAME", "TO"]) {
+        if let Some(name) = c.ident() {
+            return AlterAction::RenameTo { name };
+        }
+    } else if c.eat(&["RENAME"]) {
+        c.eat(&["COLUMN"]);
+        if let Some(from) = c.ident()
+            && c.eat(&["TO"])
+            && let Some(to) = c.ident()
+        {
+            return AlterAction::RenameColumn { from, to };
+        }
+    }
+    AlterAction::Other { clause: clause.join(" ") }
+}
+
+/// Parse all statements of a migration into operations
+pub fn parse_migration_operations(sql: &str) -> Vec<MigrationOperation> {
+    split_statements(sql).iter().map(|s| parse_statement(s)).collect()
+}
+
+/// Existing tables the DDL of a migration takes locks on; tables it creates itself are left out
+pub fn locked_tables(ops: &[MigrationOperation]) -> Vec<String> {
+    let created: Vec<&str> = ops
+        .iter()
+        .filter_map(|op| match op {
+            | MigrationOperation::CreateTable { name, .. } => Some(name.as_str()),
+            | _ => None,
+        })
+        .collect();
+    let mut tables: Vec<String> = Vec::new();
+    for op in ops {
+        let table = match op {
+            | MigrationOperation::DropTable { name, .. } | MigrationOperation::AlterTable { name, .. } => name,
+            | MigrationOperation::CreateIndex { table, concurrently: false, .. } | MigrationOperation::Truncate { table } => table,
+            | _ => continue,
+        };
+        if !created.iter().any(|c| same_object(c, table)) && !tables.iter().any(|t| same_object(t, table)) {
+            tables.push(table.clone());
+        }
+    }
+    tables
+}
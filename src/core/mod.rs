@@ -1,3 +1,46 @@
 pub mod repo;
 pub mod service;
 pub mod migration;
+pub mod bench;
+pub mod log;
+pub mod snapshot;
+pub mod migration_diff;
+pub mod lint;
+pub mod plan;
+pub mod fmt;
+pub mod tui;
+pub mod graph;
+pub mod bundle;
+pub mod import;
+pub mod alembic;
+pub mod dbmate;
+pub mod liquibase;
+pub mod sqlx_migrate;
+pub mod seed;
+pub mod repeatable;
+pub mod hooks;
+pub mod bootstrap;
+pub mod declarative;
+pub mod meta;
+pub mod include;
+pub mod wait;
+pub mod duration;
+pub mod ping;
+pub mod console;
+pub mod config_upgrade;
+pub mod output;
+pub mod progress;
+pub mod pager;
+pub mod highlight;
+pub mod parallel;
+pub mod window;
+pub mod checkpoint;
+pub mod check;
+pub mod telemetry;
+pub mod prometheus;
+pub mod report;
+pub mod events;
+#[cfg(feature = "vault")]
+pub mod vault;
+#[cfg(feature = "otel")]
+pub mod otel;
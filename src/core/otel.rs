@@ -0,0 +1,244 @@
+use {
+    crate::core::telemetry::{self, MigrationRun},
+    anyhow::{Context, Result},
+    serde_json::{json, Value},
+    std::time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Upper bounds in milliseconds of the buckets of the `qop.migration.duration` histogram
+const DURATION_BOUNDS: [f64; 12] = [10.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0, 30000.0, 60000.0, 300000.0];
+
+/// Environment variables that configure the export, any of them enables it
+const ENDPOINT_ENVS: [&str; 3] = ["OTEL_EXPORTER_OTLP_ENDPOINT", "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT", "OTEL_EXPORTER_OTLP_METRICS_ENDPOINT"];
+
+/// Whether an OTLP endpoint is configured
+pub fn configured() -> bool {
+    ENDPOINT_ENVS.iter().any(|var| env(var).is_some())
+}
+
+/// Send the migrations of this run to the configured OTLP/HTTP endpoint: a span for the run with a
+/// span per migration, plus the `qop.migrations`, `qop.migration.rows` and `qop.migration.duration`
+/// metrics. A run without migrations sends nothing; failures only produce a warning.
+pub fn export(elapsed: Duration, success: bool) {
+    let runs = telemetry::runs();
+    if !configured() || runs.is_empty() {
+        return;
+    }
+    let end = SystemTime::now();
+    let start = end - elapsed;
+    let resource = resource();
+    let scope = json!({ "name": "qop", "version": env!("CARGO_PKG_VERSION") });
+    let exports = [
+        ("traces", json!({ "resourceSpans": [{ "resource": resource, "scopeSpans": [{ "scope": scope, "spans": spans(&runs, start, end, success) }] }] })),
+        ("metrics", json!({ "resourceMetrics": [{ "resource": resource, "scopeMetrics": [{ "scope": scope, "metrics": metrics(&runs, start, end) }] }] })),
+    ];
+    for (signal, body) in exports {
+        if let Err(e) = send(signal, &body) {
+            crate::esay!("⚠️  Failed to export OpenTelemetry {}: {:#}", signal, e);
+        }
+    }
+}
+
+fn send(signal: &str, body: &Value) -> Result<()> {
+    let specific = format!("OTEL_EXPORTER_OTLP_{}_ENDPOINT", signal.to_uppercase());
+    let url = match env(&specific) {
+        | Some(url) => url,
+        | None => match env("OTEL_EXPORTER_OTLP_ENDPOINT") {
+            | Some(base) => format!("{}/v1/{}", base.trim_end_matches('/'), signal),
+            | None => return Ok(()),
+        },
+    };
+    let timeout = env("OTEL_EXPORTER_OTLP_TIMEOUT").and_then(|ms| ms.parse().ok()).unwrap_or(10_000);
+    let agent: ureq::Agent = ureq::Agent::config_builder().timeout_global(Some(Duration::from_millis(timeout))).http_status_as_error(false).build().into();
+    let mut request = agent.post(&url);
+    for (name, value) in pairs(env("OTEL_EXPORTER_OTLP_HEADERS").as_deref().unwrap_or_default()) {
+        request = request.header(name, value);
+    }
+    let response = request.send_json(body).with_context(|| format!("request to {} failed", url))?;
+    if !response.status().is_success() {
+        anyhow::bail!("{} returned {}", url, response.status());
+    }
+    Ok(())
+}
+
+fn env(var: &str) -> Option<String> {
+    std::env::var(var).ok().filter(|value| !value.is_empty())
+}
+
+/// `key=value` pairs separated by commas, as in `OTEL_RESOURCE_ATTRIBUTES` and `OTEL_EXPORTER_OTLP_HEADERS`
+fn pairs(list: &str) -> Vec<(String, String)> {
+    list.split(',').filter_map(|pair| pair.split_once('=')).map(|(key, value)| (key.trim().to_string(), value.trim().to_string())).collect()
+}
+
+fn resource() -> Value {
+    let mut attributes = vec![attribute("service.name", env("OTEL_SERVICE_NAME").unwrap_or_else(|| "qop".to_string()))];
+    for (key, value) in pairs(env("OTEL_RESOURCE_ATTRIBUTES").as_deref().unwrap_or_default()) {
+        if key != "service.name" || env("OTEL_SERVICE_NAME").is_none() {
+            attributes.retain(|attribute| attribute["key"] != key.as_str());
+            attributes.push(attribute(&key, value));
+        }
+    }
+    json!({ "attributes": attributes })
+}
+
+fn attribute(key: &str, value: impl Into<AttributeValue>) -> Value {
+    let value = match value.into() {
+        | AttributeValue::String(value) => json!({ "stringValue": value }),
+        | AttributeValue::Int(value) => json!({ "intValue": value.to_string() }),
+        | AttributeValue::Bool(value) => json!({ "boolValue": value }),
+    };
+    json!({ "key": key, "value": value })
+}
+
+enum AttributeValue {
+    String(String),
+    Int(i64),
+    Bool(bool),
+}
+
+impl From<String> for AttributeValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&str> for AttributeValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<i64> for AttributeValue {
+    fn from(value: i64) -> Self {
+        Self::Int(value)
+    }
+}
+
+impl From<bool> for AttributeValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+fn nanos(time: SystemTime) -> String {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos().to_string()
+}
+
+/// 16 random bytes for a trace id, or 8 for a span id, in hex
+fn random_id(bytes: usize) -> String {
+    uuid::Uuid::now_v7().as_bytes()[16 - bytes..].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Trace and span id of the W3C `TRACEPARENT` a CI system or deploy tool passed down, so the run shows
+/// up within the trace of the deploy that triggered it
+fn parent() -> Option<(String, String)> {
+    let traceparent = env("TRACEPARENT")?;
+    let parts: Vec<&str> = traceparent.split('-').collect();
+    match parts.as_slice() {
+        | [_, trace, span, _] if trace.len() == 32 && span.len() == 16 => Some((trace.to_string(), span.to_string())),
+        | _ => None,
+    }
+}
+
+fn spans(runs: &[MigrationRun], start: SystemTime, end: SystemTime, success: bool) -> Vec<Value> {
+    let (trace_id, parent_id) = match parent() {
+        | Some((trace, span)) => (trace, Some(span)),
+        | None => (random_id(16), None),
+    };
+    let run_id = random_id(8);
+    let command: Vec<String> = std::env::args().collect();
+    let mut spans = vec![json!({
+        "traceId": trace_id,
+        "spanId": run_id,
+        "parentSpanId": parent_id.unwrap_or_default(),
+        "name": "qop run",
+        "kind": 1,
+        "startTimeUnixNano": nanos(start),
+        "endTimeUnixNano": nanos(end),
+        "attributes": [attribute("process.command_args", command.join(" ")), attribute("qop.migrations", runs.len() as i64)],
+        "status": { "code": if success { 1 } else { 2 } },
+    })];
+    for run in runs {
+        let mut attributes = vec![
+            attribute("qop.migration.id", run.id.as_str()),
+            attribute("qop.migration.direction", run.direction),
+            attribute("qop.migration.duration_ms", run.duration.as_millis() as i64),
+            attribute("qop.migration.rows_affected", run.rows as i64),
+            attribute("qop.dry_run", run.dry_run),
+        ];
+        if !run.job.is_empty() {
+            attributes.push(attribute("qop.job", run.job.as_str()));
+        }
+        let status = match &run.error {
+            | Some(error) => json!({ "code": 2, "message": error }),
+            | None => json!({ "code": 1 }),
+        };
+        spans.push(json!({
+            "traceId": trace_id,
+            "spanId": random_id(8),
+            "parentSpanId": run_id,
+            "name": format!("{} {}", run.direction, run.id),
+            "kind": 1,
+            "startTimeUnixNano": nanos(run.start),
+            "endTimeUnixNano": nanos(run.start + run.duration),
+            "attributes": attributes,
+            "status": status,
+        }));
+    }
+    spans
+}
+
+fn metrics(runs: &[MigrationRun], start: SystemTime, end: SystemTime) -> Vec<Value> {
+    // One data point per direction and outcome
+    let mut groups: Vec<((&str, bool), Vec<&MigrationRun>)> = Vec::new();
+    for run in runs {
+        let group = (run.direction, run.error.is_none());
+        match groups.iter_mut().find(|(key, _)| *key == group) {
+            | Some((_, members)) => members.push(run),
+            | None => groups.push((group, vec![run])),
+        }
+    }
+    let (start, end) = (nanos(start), nanos(end));
+    let point = |(direction, ok): (&str, bool)| {
+        json!({
+            "attributes": [attribute("qop.migration.direction", direction), attribute("qop.migration.status", if ok { "ok" } else { "error" })],
+            "startTimeUnixNano": start,
+            "timeUnixNano": end,
+        })
+    };
+    let with = |mut point: Value, fields: Value| {
+        if let (Some(point), Some(fields)) = (point.as_object_mut(), fields.as_object()) {
+            point.extend(fields.clone());
+        }
+        point
+    };
+
+    let counts: Vec<Value> = groups.iter().map(|(key, members)| with(point(*key), json!({ "asInt": members.len().to_string() }))).collect();
+    let rows: Vec<Value> = groups.iter().map(|(key, members)| with(point(*key), json!({ "asInt": members.iter().map(|run| run.rows).sum::<u64>().to_string() }))).collect();
+    let durations: Vec<Value> = groups
+        .iter()
+        .map(|(key, members)| {
+            let millis: Vec<f64> = members.iter().map(|run| run.duration.as_secs_f64() * 1000.0).collect();
+            let mut buckets = vec![0u64; DURATION_BOUNDS.len() + 1];
+            for ms in &millis {
+                buckets[DURATION_BOUNDS.iter().position(|bound| ms <= bound).unwrap_or(DURATION_BOUNDS.len())] += 1;
+            }
+            with(
+                point(*key),
+                json!({
+                    "count": millis.len().to_string(),
+                    "sum": millis.iter().sum::<f64>(),
+                    "bucketCounts": buckets.iter().map(|count| count.to_string()).collect::<Vec<_>>(),
+                    "explicitBounds": DURATION_BOUNDS,
+                }),
+            )
+        })
+        .collect();
+
+    // Delta temporality, every run reports only its own migrations
+    vec![
+        json!({ "name": "qop.migrations", "description": "Migrations run", "unit": "{migration}", "sum": { "aggregationTemporality": 1, "isMonotonic": true, "dataPoints": counts } }),
+        json!({ "name": "qop.migration.rows", "description": "Rows changed by migrations", "unit": "{row}", "sum": { "aggregationTemporality": 1, "isMonotonic": true, "dataPoints": rows } }),
+        json!({ "name": "qop.migration.duration", "description": "Time migrations took", "unit": "ms", "histogram": { "aggregationTemporality": 1, "dataPoints": durations } }),
+    ]
+}
@@ -0,0 +1,205 @@
+use {
+    crate::config::UiPreferences,
+    std::sync::OnceLock,
+};
+
+static PREFERENCES: OnceLock<UiPreferences> = OnceLock::new();
+
+/// How much is printed besides errors and the output of the command itself
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Only the final summary of a command
+    Quiet,
+    #[default]
+    Normal,
+    /// Also the executed SQL, timings and connection details
+    Verbose,
+}
+
+tokio::task_local! {
+    /// Name of the target or tenant a status message belongs to, while several run in parallel
+    static PREFIX: String;
+}
+
+/// Set the output preferences of this run; later calls have no effect
+pub fn init(preferences: UiPreferences) {
+    let _ = PREFERENCES.set(preferences);
+}
+
+pub fn preferences() -> UiPreferences {
+    PREFERENCES.get().copied().unwrap_or_default()
+}
+
+pub fn verbosity() -> Verbosity {
+    preferences().verbosity
+}
+
+/// Whether `--output json` was given, which moves status messages to stderr
+pub fn json() -> bool {
+    preferences().json
+}
+
+/// Whether `--plain` asked for ASCII output, without emoji and box drawing
+pub fn plain() -> bool {
+    preferences().plain
+}
+
+/// Print the JSON result of a command on stdout with `--output json`, nothing otherwise
+pub fn result<T: serde::Serialize>(result: &T) -> anyhow::Result<()> {
+    if json() {
+        println!("{}", serde_json::to_string_pretty(result)?);
+    }
+    Ok(())
+}
+
+/// Print a status message on stdout, or on stderr when stdout carries JSON
+pub fn print(message: String) {
+    match json() {
+        | true => eprintln!("{}", message),
+        | false => println!("{}", message),
+    }
+}
+
+/// Where prompts and SQL previews go: stdout, or stderr when stdout carries JSON
+pub fn stream() -> Box<dyn std::io::Write> {
+    match json() {
+        | true => Box::new(std::io::stderr()),
+        | false => Box::new(std::io::stdout()),
+    }
+}
+
+/// Prefix the status messages of `job` with `name`, nested within the prefix of an outer job
+pub async fn prefixed<F: std::future::Future>(name: String, job: F) -> F::Output {
+    let prefix = PREFIX.try_with(|outer| format!("{}/{}", outer, name)).unwrap_or(name);
+    PREFIX.scope(prefix, job).await
+}
+
+/// Name of the target or tenant the current job runs for, empty outside of parallel runs
+pub fn job() -> String {
+    PREFIX.try_with(|prefix| prefix.clone()).unwrap_or_default()
+}
+
+/// A status message as configured, i.e. without emoji when they are disabled, and with the prefix
+/// of the job it belongs to on every line
+pub fn decorate(message: String) -> String {
+    let message = match preferences().emoji && !plain() {
+        | true => message,
+        | false => ascii(&strip_emoji(message)),
+    };
+    PREFIX
+        .try_with(|prefix| message.split('\n').map(|line| if line.is_empty() { line.to_string() } else { format!("[{}] {}", prefix, line) }).collect::<Vec<_>>().join("\n"))
+        .unwrap_or(message)
+}
+
+fn strip_emoji(message: String) -> String {
+    // Emoji usually lead the message, separated by one or two spaces
+    let indent = message.len() - message.trim_start_matches([' ', '\n']).len();
+    let mut body = &message[indent..];
+    if body.starts_with(is_emoji) {
+        body = body.trim_start_matches(|c: char| c == ' ' || is_emoji(c));
+    }
+    let body: String = body.chars().filter(|c| !is_emoji(*c)).collect();
+    format!("{}{}", &message[..indent], body.trim_end_matches(' '))
+}
+
+fn is_emoji(c: char) -> bool {
+    matches!(c as u32, 0x2300..=0x23FF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0x1F000..=0x1FAFF | 0xFE0F | 0x200D | 0x20E3)
+}
+
+/// ASCII stand-ins for the symbols in tables and trees
+const ASCII: [(char, &str); 16] = [
+    ('✅', "OK"),
+    ('❌', "X"),
+    ('⚠', "!"),
+    ('🔒', "locked"),
+    ('⏭', ">>"),
+    ('⏳', "..."),
+    ('▶', ">"),
+    ('●', "*"),
+    ('↺', "^"),
+    ('…', "..."),
+    ('→', "->"),
+    ('←', "<-"),
+    ('↑', "up"),
+    ('↓', "down"),
+    ('└', "`"),
+    ('—', "-"),
+];
+
+/// `text` in ASCII with `--plain`: symbols get a stand-in, box drawing becomes `-`, `|` and `+`, and
+/// other emoji are dropped; unchanged otherwise
+pub fn ascii(text: &str) -> String {
+    if !plain() {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match ASCII.iter().find(|(symbol, _)| *symbol == c) {
+            | Some((_, stand_in)) => out.push_str(stand_in),
+            | None => match c as u32 {
+                | 0x2500 | 0x2501 | 0x2504 | 0x2505 | 0x2550 => out.push('-'),
+                | 0x2502 | 0x2503 | 0x2506 | 0x2507 | 0x2551 => out.push('|'),
+                | 0x2500..=0x257F => out.push('+'),
+                | _ if is_emoji(c) => {},
+                | _ => out.push(c),
+            },
+        }
+    }
+    // A dropped leading emoji leaves the space that separated it behind
+    match text.starts_with(' ') {
+        | true => out,
+        | false => out.trim_start_matches(' ').to_string(),
+    }
+}
+
+/// The line around SQL previews and log entries
+pub fn rule() -> String {
+    ascii(&"─".repeat(56))
+}
+
+/// A table with rounded borders, or ASCII ones with `--plain`
+pub fn table() -> comfy_table::Table {
+    let mut table = comfy_table::Table::new();
+    match plain() {
+        | true => table.load_preset(comfy_table::presets::ASCII_FULL),
+        | false => table.load_preset(comfy_table::presets::UTF8_FULL).apply_modifier(comfy_table::modifiers::UTF8_ROUND_CORNERS),
+    };
+    table
+}
+
+/// `println!` for status messages, honoring the emoji preference; silent with `--quiet` and on stderr
+/// with `--output json`
+#[macro_export]
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if $crate::core::output::verbosity() > $crate::core::output::Verbosity::Quiet {
+            $crate::core::progress::suspend(|| $crate::core::output::print($crate::core::output::decorate(format!($($arg)*))))
+        }
+    };
+}
+
+/// `say!` for the final summary of a command, which `--quiet` still prints
+#[macro_export]
+macro_rules! summary {
+    ($($arg:tt)*) => {
+        $crate::core::progress::suspend(|| $crate::core::output::print($crate::core::output::decorate(format!($($arg)*))))
+    };
+}
+
+/// `eprintln!` for the details only `--verbose` prints: executed SQL, timings and connections
+#[macro_export]
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        if $crate::core::output::verbosity() == $crate::core::output::Verbosity::Verbose {
+            $crate::core::progress::suspend(|| eprintln!("{}", $crate::core::output::decorate(format!($($arg)*))))
+        }
+    };
+}
+
+/// `eprintln!` for status messages, honoring the emoji preference
+#[macro_export]
+macro_rules! esay {
+    ($($arg:tt)*) => {
+        $crate::core::progress::suspend(|| eprintln!("{}", $crate::core::output::decorate(format!($($arg)*))))
+    };
+}
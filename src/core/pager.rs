@@ -0,0 +1,64 @@
+use {
+    crate::core::output,
+    anyhow::{Context, Result},
+    std::{
+        io::{IsTerminal, Write},
+        process::{Command, Stdio},
+    },
+};
+
+/// Pager used when neither `QOP_PAGER` nor `PAGER` is set
+const DEFAULT_PAGER: &str = "less";
+
+/// Options for less unless `LESS` is set, as git sets them: keep colors, quit on output that fits the
+/// screen and leave the output on the screen after quitting
+const DEFAULT_LESS: &str = "FRX";
+
+/// Print `text` to stdout, through the pager when it is longer than the terminal is high, like git does.
+/// `--no-pager`, `pager = false` in the user config, JSON output and stdout not being a terminal print it directly.
+pub fn page(text: &str) -> Result<()> {
+    match pager(text) {
+        | Some(pager) => run(&pager, text).with_context(|| format!("Failed to run pager `{}`", pager.join(" "))),
+        | None => {
+            let mut out = output::stream();
+            write!(out, "{}", text)?;
+            out.flush()?;
+            Ok(())
+        },
+    }
+}
+
+/// The pager command for `text`, if it should be paged at all
+fn pager(text: &str) -> Option<Vec<String>> {
+    if !output::preferences().pager || output::json() || !std::io::stdout().is_terminal() {
+        return None;
+    }
+    let (_, rows) = ratatui::crossterm::terminal::size().ok()?;
+    if text.lines().count() < rows as usize {
+        return None;
+    }
+    let command = ["QOP_PAGER", "PAGER"].iter().find_map(|var| std::env::var(var).ok()).unwrap_or_else(|| DEFAULT_PAGER.to_string());
+    let command: Vec<String> = command.split_whitespace().map(|part| part.to_string()).collect();
+    match command.first().map(|program| program.as_str()) {
+        // An empty pager or `cat` turns paging off, as with git
+        | None | Some("cat") => None,
+        | Some(_) => Some(command),
+    }
+}
+
+fn run(command: &[String], text: &str) -> Result<()> {
+    let mut child = Command::new(&command[0])
+        .args(&command[1..])
+        .env("LESS", std::env::var("LESS").unwrap_or_else(|_| DEFAULT_LESS.to_string()))
+        .stdin(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager closes its input when quit early, which is no error
+        match stdin.write_all(text.as_bytes()) {
+            | Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e.into()),
+            | _ => {},
+        }
+    }
+    child.wait()?;
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use {
+    crate::core::check,
+    anyhow::Result,
+    std::{future::Future, pin::Pin, task::Poll},
+};
+
+/// A named unit of work, e.g. one target or one tenant
+pub type Job<'a> = (String, Pin<Box<dyn Future<Output = Result<()>> + 'a>>);
+
+/// Run the jobs with at most `parallel` of them at a time, all on the current task. Status messages
+/// of a job are prefixed with its name when more than one runs at a time. Returns the result of
+/// every job in the order given.
+pub async fn run(jobs: Vec<Job<'_>>, parallel: usize) -> Vec<(String, Result<()>)> {
+    let parallel = parallel.max(1);
+    let names: Vec<String> = jobs.iter().map(|(name, _)| name.clone()).collect();
+    let mut results: Vec<Option<Result<()>>> = names.iter().map(|_| None).collect();
+    let mut queued = jobs.into_iter().enumerate();
+    let mut running = Vec::new();
+
+    std::future::poll_fn(|cx| loop {
+        while running.len() < parallel {
+            let Some((index, (name, job))) = queued.next() else { break };
+            let job: Pin<Box<dyn Future<Output = Result<()>> + '_>> = match parallel {
+                | 1 => job,
+                | _ => Box::pin(crate::core::output::prefixed(name, job)),
+            };
+            running.push((index, job));
+        }
+        if running.is_empty() {
+            return Poll::Ready(());
+        }
+        let before = running.len();
+        running.retain_mut(|(index, job)| match job.as_mut().poll(cx) {
+            | Poll::Ready(result) => {
+                results[*index] = Some(result);
+                false
+            },
+            | Poll::Pending => true,
+        });
+        // Finished jobs make room for queued ones, which need a first poll
+        if running.len() == before {
+            return Poll::Pending;
+        }
+    })
+    .await;
+
+    names.into_iter().zip(results).map(|(name, result)| (name, result.unwrap_or_else(|| Err(anyhow::anyhow!("job did not run"))))).collect()
+}
+
+/// Print how many of the jobs succeeded and fail with the names of the ones that did not; `kind`
+/// names a job in messages, e.g. "tenant". When all of them failed a `--check`, so does the run, with
+/// the most severe of their failures.
+pub fn report(kind: &str, results: &[(String, Result<()>)]) -> Result<()> {
+    let failed: Vec<&str> = results.iter().filter(|(_, result)| result.is_err()).map(|(name, _)| name.as_str()).collect();
+    crate::esay!("\n📊 {} of {} {}(s) succeeded", results.len() - failed.len(), results.len(), kind);
+    if failed.is_empty() {
+        return Ok(());
+    }
+    let message = format!("{} {}(s) failed: {}", failed.len(), kind, failed.join(", "));
+    let checks: Option<Vec<check::CheckFailure>> = results.iter().filter_map(|(_, result)| result.as_ref().err()).map(check::failure).collect();
+    match checks.and_then(|checks| checks.into_iter().max()) {
+        | Some(worst) => Err(anyhow::Error::new(worst).context(message)),
+        | None => Err(anyhow::anyhow!(message)),
+    }
+}
@@ -0,0 +1,73 @@
+use {
+    serde::Serialize,
+    std::time::Duration,
+};
+
+#[derive(Debug, Serialize)]
+pub struct PingReport {
+    /// Where the connection string was taken from
+    pub source: String,
+    /// Connection string with credentials redacted
+    pub target: String,
+    pub connect_ms: f64,
+    /// Round trip of `SELECT 1`
+    pub query_ms: f64,
+    pub tables: Vec<TableStatus>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TableStatus {
+    pub name: String,
+    pub exists: bool,
+}
+
+impl PingReport {
+    pub fn new(source: &str, target: &str, connect: Duration, query: Duration, tables: Vec<(String, bool)>) -> Self {
+        Self {
+            source: source.to_string(),
+            target: redact_connection(target),
+            connect_ms: connect.as_secs_f64() * 1000.0,
+            query_ms: query.as_secs_f64() * 1000.0,
+            tables: tables.into_iter().map(|(name, exists)| TableStatus { name, exists }).collect(),
+        }
+    }
+
+    pub fn print(&self) {
+        crate::say!("🔌 Source:  {}", self.source);
+        crate::say!("🎯 Target:  {}", self.target);
+        crate::say!("⏱️  Connect: {:.1} ms", self.connect_ms);
+        crate::say!("⏱️  Query:   {:.1} ms (SELECT 1)", self.query_ms);
+        for table in &self.tables {
+            let status = if table.exists { "✅ present" } else { "❌ missing" };
+            crate::say!("📋 {}: {}", table.name, status);
+        }
+        if self.tables.iter().any(|t| !t.exists) {
+            crate::say!("ℹ️  Tracking tables are missing; run `init` to create them.");
+        }
+    }
+}
+
+/// Replace the password of a connection URI (userinfo or `password` parameter) with `***`
+pub fn redact_connection(uri: &str) -> String {
+    let mut redacted = uri.to_string();
+    if let Some(scheme_end) = redacted.find("://") {
+        let rest_start = scheme_end + 3;
+        let authority_end = redacted[rest_start..].find(['/', '?']).map(|i| rest_start + i).unwrap_or(redacted.len());
+        if let Some(at) = redacted[rest_start..authority_end].rfind('@').map(|i| rest_start + i)
+            && let Some(colon) = redacted[rest_start..at].find(':').map(|i| rest_start + i)
+        {
+            redacted.replace_range(colon + 1..at, "***");
+        }
+    }
+    if let Some(query_start) = redacted.find('?') {
+        let params: Vec<String> = redacted[query_start + 1..]
+            .split('&')
+            .map(|param| match param.split_once('=') {
+                | Some((key, _)) if key.eq_ignore_ascii_case("password") => format!("{}=***", key),
+                | _ => param.to_string(),
+            })
+            .collect();
+        redacted = format!("{}?{}", &redacted[..query_start], params.join("&"));
+    }
+    redacted
+}
@@ -0,0 +1,165 @@
+use {
+    crate::core::{
+        migration::MigrationMeta,
+        migration_diff::{self, AlterAction, MigrationOperation},
+    },
+    comfy_table::{Cell, ContentArrangement},
+    serde::Serialize,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RiskLevel {
+    Low,
+    Medium,
+    High,
+}
+
+/// Something about a pending migration worth a second look before it runs
+#[derive(Debug, Serialize)]
+pub struct Risk {
+    pub level: RiskLevel,
+    pub flag: &'static str,
+    pub message: String,
+}
+
+/// A pending migration with the operations its up.sql performs
+#[derive(Debug, Serialize)]
+pub struct PlannedMigration {
+    pub id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    pub transaction: bool,
+    pub operations: Vec<MigrationOperation>,
+    pub risks: Vec<Risk>,
+}
+
+impl PlannedMigration {
+    pub fn new(id: &str, up_sql: &str, meta: &MigrationMeta) -> Self {
+        let operations = migration_diff::parse_migration_operations(up_sql);
+        let transaction = meta.transaction.unwrap_or(true);
+        let risks = risks(&operations, transaction);
+        Self { id: id.to_string(), comment: meta.comment.clone(), transaction, operations, risks }
+    }
+
+    /// The highest risk level of the migration, if it has any risks
+    pub fn level(&self) -> Option<RiskLevel> {
+        self.risks.iter().map(|risk| risk.level).max()
+    }
+}
+
+fn risks(ops: &[MigrationOperation], transaction: bool) -> Vec<Risk> {
+    let mut risks = Vec::new();
+    let mut risk = |level: RiskLevel, flag: &'static str, message: String| risks.push(Risk { level, flag, message });
+    for op in ops {
+        match op {
+            | MigrationOperation::DropTable { name, .. } => risk(RiskLevel::High, "drop_table", format!("drops table {} and its data", name)),
+            | MigrationOperation::Truncate { table } => risk(RiskLevel::High, "truncate", format!("deletes all rows of {}", table)),
+            | MigrationOperation::AlterTable { name, actions } => {
+                for action in actions {
+                    match action {
+                        | AlterAction::DropColumn { column } => risk(RiskLevel::High, "drop_column", format!("drops column {} of {}", column, name)),
+                        | AlterAction::RenameColumn { from, to } => {
+                            risk(RiskLevel::Medium, "rename", format!("renames column {} of {} to {}, breaking code that still uses the old name", from, name, to))
+                        },
+                        | AlterAction::RenameTo { name: to } => risk(RiskLevel::Medium, "rename", format!("renames table {} to {}, breaking code that still uses the old name", name, to)),
+                        | _ => {},
+                    }
+                }
+            },
+            | MigrationOperation::DropView { name, .. } => risk(RiskLevel::Medium, "drop_view", format!("drops view {}", name)),
+            | MigrationOperation::DropIndex { name, .. } => risk(RiskLevel::Low, "drop_index", format!("drops index {}, queries using it may slow down", name)),
+            | MigrationOperation::Update { table } | MigrationOperation::Delete { table } => {
+                risk(RiskLevel::Medium, "data_change", format!("changes existing rows of {}", table))
+            },
+            | _ => {},
+        }
+    }
+    for table in migration_diff::locked_tables(ops) {
+        risk(RiskLevel::Low, "table_lock", format!("locks existing table {} while it runs", table));
+    }
+    let unknown = ops.iter().filter(|op| matches!(op, MigrationOperation::Other { .. })).count();
+    if unknown > 0 {
+        risk(RiskLevel::Low, "unrecognized", format!("{} statement(s) qop cannot analyse", unknown));
+    }
+    if !transaction {
+        risk(RiskLevel::Medium, "no_transaction", "runs outside a transaction, a failure leaves it partially applied".to_string());
+    }
+    risks
+}
+
+/// One line per operation, e.g. `alter table users: add column email`
+pub fn describe(op: &MigrationOperation) -> String {
+    match op {
+        | MigrationOperation::CreateTable { name, .. } => format!("create table {}", name),
+        | MigrationOperation::DropTable { name, .. } => format!("drop table {}", name),
+        | MigrationOperation::AlterTable { name, actions } => {
+            let actions: Vec<String> = actions
+                .iter()
+                .map(|action| match action {
+                    | AlterAction::AddColumn { column } => format!("add column {}", column),
+                    | AlterAction::DropColumn { column } => format!("drop column {}", column),
+                    | AlterAction::RenameColumn { from, to } => format!("rename column {} to {}", from, to),
+                    | AlterAction::RenameTo { name } => format!("rename to {}", name),
+                    | AlterAction::Other { clause } => clause.to_lowercase(),
+                })
+                .collect();
+            format!("alter table {}: {}", name, actions.join(", "))
+        },
+        | MigrationOperation::CreateIndex { name, table, unique, concurrently, .. } => format!(
+            "create {}index {}on {}{}",
+            if *unique { "unique " } else { "" },
+            name.as_ref().map(|name| format!("{} ", name)).unwrap_or_default(),
+            table,
+            if *concurrently { " concurrently" } else { "" }
+        ),
+        | MigrationOperation::DropIndex { name, .. } => format!("drop index {}", name),
+        | MigrationOperation::CreateView { name, .. } => format!("create view {}", name),
+        | MigrationOperation::DropView { name, .. } => format!("drop view {}", name),
+        | MigrationOperation::Insert { table } => format!("insert into {}", table),
+        | MigrationOperation::Update { table } => format!("update {}", table),
+        | MigrationOperation::Delete { table } => format!("delete from {}", table),
+        | MigrationOperation::Truncate { table } => format!("truncate {}", table),
+        | MigrationOperation::Other { statement } => {
+            let statement = statement.split_whitespace().collect::<Vec<_>>().join(" ");
+            match statement.chars().count() > 60 {
+                | true => format!("{}…", statement.chars().take(60).collect::<String>()),
+                | false => statement,
+            }
+        },
+    }
+}
+
+pub fn render_plan_table(plan: &[PlannedMigration]) {
+    let mut table = crate::core::output::table();
+    table
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec![Cell::new("Migration ID"), Cell::new("Operations"), Cell::new("Risks")]);
+
+    for migration in plan {
+        let id = match &migration.comment {
+            | Some(comment) => format!("{}\n{}", migration.id, comment),
+            | None => migration.id.clone(),
+        };
+        let operations: Vec<String> = migration.operations.iter().map(describe).collect();
+        let risks: Vec<String> = migration
+            .risks
+            .iter()
+            .map(|risk| {
+                let level = match risk.level {
+                    | RiskLevel::High => "high",
+                    | RiskLevel::Medium => "medium",
+                    | RiskLevel::Low => "low",
+                };
+                format!("[{}] {}: {}", level, risk.flag, risk.message)
+            })
+            .collect();
+        table.add_row(vec![
+            Cell::new(id),
+            Cell::new(if operations.is_empty() { "(empty)".to_string() } else { operations.join("\n") }),
+            Cell::new(if risks.is_empty() { "-".to_string() } else { risks.join("\n") }),
+        ]);
+    }
+
+    println!("{table}");
+}
@@ -0,0 +1,106 @@
+use {
+    crate::core::output::{self, Verbosity},
+    indicatif::{MultiProgress, ProgressBar, ProgressStyle},
+    std::{
+        io::IsTerminal,
+        sync::{Mutex, OnceLock},
+    },
+};
+
+static BARS: OnceLock<MultiProgress> = OnceLock::new();
+
+/// Bars of the migrations running right now with the job and id of the migration and their message,
+/// so the executors can show statement progress on them
+static RUNNING: Mutex<Vec<(String, ProgressBar, String)>> = Mutex::new(Vec::new());
+
+/// Run `print` without tearing the progress bars, which are redrawn below its output
+pub fn suspend<R>(print: impl FnOnce() -> R) -> R {
+    match BARS.get() {
+        | Some(bars) => bars.suspend(print),
+        | None => print(),
+    }
+}
+
+fn key(migration_id: &str) -> String {
+    format!("{}/{}", output::job(), migration_id)
+}
+
+/// Progress through the migrations of a run: a bar on an interactive stderr, a line per migration
+/// otherwise; the bar is removed again when dropped
+pub struct Progress {
+    verb: &'static str,
+    total: usize,
+    done: usize,
+    bar: Option<ProgressBar>,
+    current: Option<String>,
+}
+
+impl Progress {
+    pub fn new(verb: &'static str, total: usize) -> Self {
+        // Verbose output prints every statement on a line of its own, a bar would only get in the way
+        let bar = match std::io::stderr().is_terminal() && output::verbosity() == Verbosity::Normal {
+            | true => {
+                let bar = BARS.get_or_init(MultiProgress::new).add(ProgressBar::new(total as u64));
+                let style = ProgressStyle::with_template("{prefix}[{bar:30}] {pos}/{len} {wide_msg} {elapsed}").unwrap_or_else(|_| ProgressStyle::default_bar());
+                bar.set_style(style.progress_chars("=> "));
+                let job = output::job();
+                if !job.is_empty() {
+                    bar.set_prefix(format!("[{}] ", job));
+                }
+                Some(bar)
+            },
+            | false => None,
+        };
+        Self { verb, total, done: 0, bar, current: None }
+    }
+
+    /// Migration `id` starts running
+    pub fn start(&mut self, id: &str) {
+        self.finish();
+        match &self.bar {
+            | Some(bar) => {
+                let message = format!("{} {}", self.verb, id);
+                bar.set_message(message.clone());
+                RUNNING.lock().unwrap_or_else(|e| e.into_inner()).push((key(id), bar.clone(), message));
+            },
+            | None => crate::say!("⏳ [{}/{}] {} {}", self.done + 1, self.total, self.verb, id),
+        }
+        self.current = Some(id.to_string());
+    }
+
+    /// The migration started last is done
+    pub fn finish(&mut self) {
+        let Some(id) = self.current.take() else { return };
+        let key = key(&id);
+        RUNNING.lock().unwrap_or_else(|e| e.into_inner()).retain(|(running, ..)| running != &key);
+        self.done += 1;
+        if let Some(bar) = &self.bar {
+            bar.inc(1);
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if let Some(id) = self.current.take() {
+            let key = key(&id);
+            RUNNING.lock().unwrap_or_else(|e| e.into_inner()).retain(|(running, ..)| running != &key);
+        }
+        if let (Some(bar), Some(bars)) = (self.bar.take(), BARS.get()) {
+            bar.finish_and_clear();
+            bars.remove(&bar);
+        }
+    }
+}
+
+/// Show which statement of a migration is running on its bar; false when the migration has none
+pub fn statement(migration_id: &str, done: usize, total: usize) -> bool {
+    let key = key(migration_id);
+    let running = RUNNING.lock().unwrap_or_else(|e| e.into_inner());
+    let Some((_, bar, message)) = running.iter().find(|(running, ..)| running == &key) else { return false };
+    match done < total {
+        | true => bar.set_message(format!("{} (statement {}/{})", message, done + 1, total)),
+        | false => bar.set_message(message.clone()),
+    }
+    true
+}
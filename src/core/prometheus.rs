@@ -0,0 +1,152 @@
+use {
+    crate::core::telemetry::{self, MigrationRun},
+    anyhow::{Context, Result},
+    std::{
+        path::{Path, PathBuf},
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+};
+
+/// Where `--metrics-file` and `--metrics-push` send the Prometheus metrics of a run
+#[derive(Debug, Clone, Default)]
+pub struct MetricsOutput {
+    /// Textfile for the node exporter, rewritten after every run
+    pub file: Option<PathBuf>,
+    /// Pushgateway URL including the grouping key, e.g. `http://pushgateway:9091/metrics/job/qop`
+    pub push: Option<String>,
+}
+
+impl MetricsOutput {
+    pub fn is_enabled(&self) -> bool {
+        self.file.is_some() || self.push.is_some()
+    }
+}
+
+/// Counters that keep adding up over the runs writing the same textfile
+const COUNTERS: [&str; 2] = ["qop_migrations_applied_total", "qop_migrations_reverted_total"];
+
+/// Write and push the metrics of this run; failures only produce a warning, the migrations ran either way
+pub fn export(output: &MetricsOutput, elapsed: Duration, success: bool) {
+    let runs = telemetry::runs();
+    let pending = telemetry::pending();
+    if let Some(file) = &output.file {
+        let previous = std::fs::read_to_string(file).unwrap_or_default();
+        let metrics = render(&runs, &pending, &previous, elapsed, success);
+        if let Err(e) = write(file, &metrics) {
+            crate::esay!("⚠️  Failed to write metrics to {}: {:#}", file.display(), e);
+        }
+    }
+    if let Some(url) = &output.push {
+        // A push replaces the group, so its counters only count this run
+        let metrics = render(&runs, &pending, "", elapsed, success);
+        if let Err(e) = push(url, &metrics) {
+            crate::esay!("⚠️  Failed to push metrics to {}: {:#}", url, e);
+        }
+    }
+}
+
+/// Replace the textfile in one step, the node exporter must never read half of it
+fn write(file: &Path, metrics: &str) -> Result<()> {
+    let name = file.file_name().ok_or_else(|| anyhow::anyhow!("invalid metrics file: {}", file.display()))?;
+    let temp = file.with_file_name(format!(".{}.tmp", name.to_string_lossy()));
+    std::fs::write(&temp, metrics).with_context(|| format!("Failed to write {}", temp.display()))?;
+    std::fs::rename(&temp, file).with_context(|| format!("Failed to replace {}", file.display()))
+}
+
+#[cfg(feature = "pushgateway")]
+fn push(url: &str, metrics: &str) -> Result<()> {
+    let agent: ureq::Agent = ureq::Agent::config_builder().timeout_global(Some(Duration::from_secs(10))).http_status_as_error(false).build().into();
+    let response = agent
+        .put(url)
+        .header("Content-Type", "text/plain; version=0.0.4")
+        .send(metrics)
+        .with_context(|| format!("request to {} failed", url))?;
+    if !response.status().is_success() {
+        anyhow::bail!("{} returned {}", url, response.status());
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "pushgateway"))]
+fn push(_url: &str, _metrics: &str) -> Result<()> {
+    anyhow::bail!("qop was built without the pushgateway feature")
+}
+
+fn labels(job: &str, extra: &[(&str, &str)]) -> String {
+    let mut labels: Vec<(&str, &str)> = Vec::new();
+    if !job.is_empty() {
+        labels.push(("target", job));
+    }
+    labels.extend_from_slice(extra);
+    match labels.is_empty() {
+        | true => String::new(),
+        | false => {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(name, value)| format!("{}=\"{}\"", name, value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")))
+                .collect();
+            format!("{{{}}}", labels.join(","))
+        },
+    }
+}
+
+/// Values of the counters in a previous textfile, by series, e.g. `qop_migrations_applied_total{target="eu"}`
+fn previous_counters(previous: &str) -> Vec<(String, f64)> {
+    previous
+        .lines()
+        .filter(|line| COUNTERS.iter().any(|counter| line.starts_with(counter)))
+        .filter_map(|line| line.rsplit_once(' '))
+        .filter_map(|(series, value)| Some((series.to_string(), value.parse().ok()?)))
+        .collect()
+}
+
+fn render(runs: &[MigrationRun], pending: &[(String, usize)], previous: &str, elapsed: Duration, success: bool) -> String {
+    let mut counters = previous_counters(previous);
+    for run in runs.iter().filter(|run| run.error.is_none() && !run.dry_run) {
+        let counter = match run.direction {
+            | "down" => COUNTERS[1],
+            | _ => COUNTERS[0],
+        };
+        let series = format!("{}{}", counter, labels(&run.job, &[]));
+        match counters.iter_mut().find(|(existing, _)| existing == &series) {
+            | Some((_, value)) => *value += 1.0,
+            | None => counters.push((series, 1.0)),
+        }
+    }
+    // Every counter gets a series, so rate() works from the first run on
+    for counter in COUNTERS {
+        if !counters.iter().any(|(series, _)| series.starts_with(counter)) {
+            counters.push((counter.to_string(), 0.0));
+        }
+    }
+
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, samples: Vec<(String, f64)>| {
+        out.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n", name, help, name, kind));
+        for (series, value) in samples {
+            out.push_str(&format!("{} {}\n", series, value));
+        }
+    };
+    let (applied, reverted): (Vec<_>, Vec<_>) = counters.into_iter().partition(|(series, _)| series.starts_with(COUNTERS[0]));
+    metric(COUNTERS[0], "counter", "Migrations applied by qop.", applied);
+    metric(COUNTERS[1], "counter", "Migrations reverted by qop.", reverted);
+    metric(
+        "qop_migration_duration_seconds",
+        "gauge",
+        "Time the migrations of the last run took.",
+        runs.iter()
+            .map(|run| (format!("qop_migration_duration_seconds{}", labels(&run.job, &[("migration", &run.id), ("direction", run.direction)])), run.duration.as_secs_f64()))
+            .collect(),
+    );
+    metric(
+        "qop_migrations_pending",
+        "gauge",
+        "Migrations not applied after the last run.",
+        pending.iter().map(|(job, count)| (format!("qop_migrations_pending{}", labels(job, &[])), *count as f64)).collect(),
+    );
+    metric("qop_last_run_duration_seconds", "gauge", "Time the last run took.", vec![("qop_last_run_duration_seconds".to_string(), elapsed.as_secs_f64())]);
+    metric("qop_last_run_failed", "gauge", "Whether the last run failed.", vec![("qop_last_run_failed".to_string(), if success { 0.0 } else { 1.0 })]);
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    metric("qop_last_run_timestamp_seconds", "gauge", "When the last run finished, as a Unix timestamp.", vec![("qop_last_run_timestamp_seconds".to_string(), now as f64)]);
+    out
+}
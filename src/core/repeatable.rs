@@ -0,0 +1,94 @@
+use {
+    crate::core::bundle::sha256_hex,
+    anyhow::{Context, Result},
+    chrono::{Local, NaiveDateTime, TimeZone},
+    comfy_table::{Cell, CellAlignment, ContentArrangement},
+    serde::Serialize,
+    std::path::Path,
+};
+
+/// Prefix of the directories holding repeatable migrations, e.g. `repeat=views/up.sql`
+pub const REPEATABLE_PREFIX: &str = "repeat=";
+
+/// A migration that runs again whenever its SQL changes, e.g. views, functions and grants
+#[derive(Debug)]
+pub struct LocalRepeatable {
+    pub name: String,
+    pub sql: String,
+    pub checksum: String,
+}
+
+/// Repeatable migrations next to qop.toml, sorted by name
+pub fn local_repeatables(migration_dir: &Path) -> Result<Vec<LocalRepeatable>> {
+    let mut dirs: Vec<_> = std::fs::read_dir(migration_dir)
+        .with_context(|| format!("Failed to read migration directory: {}", migration_dir.display()))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().to_string_lossy().strip_prefix(REPEATABLE_PREFIX).map(|name| (name.to_string(), entry.path())))
+        .collect();
+    dirs.sort();
+
+    dirs.into_iter()
+        .map(|(name, dir)| {
+            let path = dir.join("up.sql");
+            let sql = std::fs::read_to_string(&path).with_context(|| format!("Failed to read repeatable migration: {}", path.display()))?;
+            let sql = crate::core::include::expand(&sql, &dir).with_context(|| format!("Failed to expand includes of {}", path.display()))?;
+            let checksum = sha256_hex(sql.as_bytes());
+            Ok(LocalRepeatable { name, sql, checksum })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepeatableStatus {
+    Pending,
+    Applied,
+    /// Applied, but the SQL changed since; runs again with the next `up`
+    Modified,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RepeatableRow {
+    pub name: String,
+    pub status: RepeatableStatus,
+    pub applied_at: Option<NaiveDateTime>,
+}
+
+/// Compare local repeatable migrations with the tracking records as `(name, checksum, applied_at)`
+pub fn repeatable_rows(local: &[LocalRepeatable], applied: &[(String, String, NaiveDateTime)]) -> Vec<RepeatableRow> {
+    local
+        .iter()
+        .map(|repeatable| {
+            let record = applied.iter().find(|(name, ..)| *name == repeatable.name);
+            let status = match record {
+                | None => RepeatableStatus::Pending,
+                | Some((_, checksum, _)) if *checksum == repeatable.checksum => RepeatableStatus::Applied,
+                | Some(_) => RepeatableStatus::Modified,
+            };
+            RepeatableRow { name: repeatable.name.clone(), status, applied_at: record.map(|(_, _, ts)| *ts) }
+        })
+        .collect()
+}
+
+pub fn render_repeatable_table(rows: &[RepeatableRow]) {
+    let mut table = crate::core::output::table();
+    table
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec![Cell::new("Repeatable"), Cell::new("Status"), Cell::new("Last Run At")]);
+
+    for row in rows {
+        let status = match row.status {
+            | RepeatableStatus::Pending => "⏳ pending",
+            | RepeatableStatus::Applied => "✅ applied",
+            | RepeatableStatus::Modified => "🔁 modified",
+        };
+        let applied_at = row
+            .applied_at
+            .map(|ts| Local.from_utc_datetime(&ts).format("%Y-%m-%d %H:%M:%S %Z").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        table.add_row(vec![Cell::new(&row.name), Cell::new(status).set_alignment(CellAlignment::Center), Cell::new(applied_at)]);
+    }
+
+    println!("{table}");
+}
@@ -1,17 +1,69 @@
 use anyhow::Result;
+use crate::core::hooks::Hooks;
+use crate::core::log::{LogEntry, LogFilter, LogOutcome};
+use crate::core::migration::{ExecOptions, MigrationMeta, Provenance};
 use chrono::NaiveDateTime;
-use std::{collections::HashSet, path::Path};
+use std::{collections::HashSet, path::Path, time::Duration};
+
+/// SQL dialect of a repository, for generated SQL that differs between databases
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    Postgres,
+    Sqlite,
+}
 
 #[async_trait::async_trait(?Send)]
 pub trait MigrationRepository {
     async fn init_store(&self) -> Result<()>;
+    async fn acquire_run_lock(&self) -> Result<()>; // keeps concurrent up/down runs apart until the repo is dropped, waits up to run_lock_timeout; no-op with --no-lock
+    async fn release_run_lock(&self) -> Result<()>; // releases the run lock early, for sessions that run several commands like the TUI
+    async fn ping(&self) -> Result<()>; // SELECT 1
+    async fn fetch_table_status(&self) -> Result<Vec<(String, bool)>>; // (table, exists) for each qop tracking table
     async fn fetch_applied_ids(&self) -> Result<HashSet<String>>;
     async fn fetch_last_id(&self) -> Result<Option<String>>;
-    async fn apply_migration(&self, id: &str, up_sql: &str, down_sql: &str, comment: Option<&str>, pre: Option<&str>, timeout: Option<u64>, dry_run: bool, locked: bool) -> Result<()>;
-    async fn revert_migration(&self, id: &str, down_sql: &str, timeout: Option<u64>, dry_run: bool, unlock: bool) -> Result<()>;
+    async fn apply_migration(&self, id: &str, up_sql: &str, down_sql: &str, meta: &MigrationMeta, pre: Option<&str>, exec: &ExecOptions, dry_run: bool, locked: bool) -> Result<()>;
+    async fn apply_migrations(&self, migrations: &[(String, String, String, MigrationMeta)], pre: Option<&str>, timeout: Option<u64>, keep_going: bool, dry_run: bool) -> Result<Vec<(String, anyhow::Error)>>; // (id, up, down, meta) applied and recorded in order in one transaction, each behind a savepoint; with keep_going a failing migration is rolled back to its savepoint and returned instead
+    async fn check_idempotency(&self, migrations: &[(String, String, MigrationMeta)], timeout: Option<u64>) -> Result<Vec<(String, anyhow::Error)>>; // (id, up, meta) each run twice in one rolled-back transaction; returns the migrations whose second run failed
+    async fn revert_migration(&self, id: &str, down_sql: &str, exec: &ExecOptions, dry_run: bool, unlock: bool) -> Result<()>;
     async fn fetch_history(&self) -> Result<Vec<(String, NaiveDateTime, Option<String>, bool)>>;
     async fn fetch_recent_for_revert_remote(&self) -> Result<Vec<(String, String)>>; // id, down
     async fn fetch_down_sql(&self, id: &str) -> Result<Option<String>>;
     async fn fetch_all_migrations(&self) -> Result<Vec<(String, String, String, Option<String>)>>; // id, up, down, comment
+    async fn fetch_provenance(&self) -> Result<std::collections::HashMap<String, Provenance>>; // author, ticket and labels of applied migrations by id
+    async fn fetch_durations(&self) -> Result<std::collections::HashMap<String, i64>>; // milliseconds the applied migrations took by id, missing for records written by an older qop
+    async fn fetch_applied_by(&self) -> Result<crate::core::migration::AppliedBy>; // (OS user, host) that applied the migrations by id
+    async fn fetch_versions(&self) -> Result<std::collections::HashMap<String, (String, Option<String>)>>; // (qop version that recorded it, comment) of applied migrations by id
+    async fn fetch_migration_checksums(&self) -> Result<Vec<(String, Option<String>, Option<String>)>>; // (id, up_sha256, down_sha256) ordered by id, None for records written by an older qop
+    async fn fetch_chain(&self) -> Result<Vec<(String, Option<String>, NaiveDateTime)>>; // (id, pre, created_at) ordered by id
+    async fn relink_migrations(&self, links: &[(String, Option<String>)]) -> Result<()>; // (id, pre) overwrites the `pre` of each record, no SQL is executed
+    async fn bench_migrations(&self, migrations: &[(String, String, ExecOptions)]) -> Result<Vec<Duration>>; // (id, up, exec) -> duration per migration, committed like `up` but not recorded; only for scratch copies
+    async fn squash_migrations(&self, ids: &[String], id: &str, up_sql: &str, down_sql: &str, meta: &MigrationMeta, pre: Option<&str>, locked: bool) -> Result<()>; // replaces the records of `ids` with a single record, no SQL is executed
+    async fn baseline_migrations(&self, migrations: &[(String, String, String, MigrationMeta, bool)]) -> Result<()>; // (id, up, down, meta, locked) recorded as applied, no SQL is executed
+    async fn rename_migration(&self, id: &str, new_id: &str) -> Result<()>; // rewrites the record, `pre` references and log entries
+    async fn set_migration_locked(&self, id: &str, locked: bool) -> Result<bool>; // false if the migration is not applied
+    async fn forget_migration(&self, id: &str) -> Result<()>; // deletes the record and its log entries, no SQL is executed
+    async fn prune_migrations(&self, ids: &[String]) -> Result<()>; // deletes the records, log entries are kept
+    async fn repair_migration(&self, id: &str, up_sql: Option<&str>, down_sql: Option<&str>) -> Result<()>; // overwrites the stored SQL, no SQL is executed
+    async fn dump_schema(&self) -> Result<String>; // schema DDL without the qop tables
+    async fn estimate_rows(&self, table: &str) -> Result<Option<i64>>; // None if unknown or not supported
+    async fn fetch_foreign_rows(&self, table: &str, columns: &[&str]) -> Result<Option<Vec<Vec<Option<String>>>>>; // columns of another tool's tracking table as text, None if the table does not exist
+    async fn fetch_seeds(&self) -> Result<Vec<(String, String, NaiveDateTime)>>; // (name, checksum, applied_at) ordered by name
+    async fn run_seed(&self, name: &str, sql: &str, checksum: &str, timeout: Option<u64>, dry_run: bool) -> Result<()>; // executes the SQL and records the seed in one transaction
+    async fn reset_seeds(&self, names: &[String]) -> Result<()>; // deletes the tracking records, seeded data is kept
+    async fn fetch_repeatables(&self) -> Result<Vec<(String, String, NaiveDateTime)>>; // (name, checksum, applied_at) ordered by name
+    async fn run_repeatable(&self, name: &str, sql: &str, checksum: &str, timeout: Option<u64>, dry_run: bool) -> Result<()>; // executes the SQL and records its checksum in one transaction
+    async fn execute_sql(&self, sql: &str, timeout: Option<u64>, dry_run: bool) -> Result<()>; // executes the SQL in its own transaction, nothing is recorded
+    async fn log_attempt(&self, migration_id: &str, operation: &str, sql: &str, outcome: &LogOutcome) -> Result<()>; // writes a log entry on its own, for dry runs and failed attempts whose transaction left none
+    async fn fetch_log(&self, filter: &LogFilter) -> Result<Vec<LogEntry>>; // newest first
+    async fn fetch_log_entry(&self, id: &str) -> Result<Option<LogEntry>>;
     fn get_path(&self) -> &Path;
+    fn profile(&self) -> Option<&str>; // profile the config was loaded with, for per-profile skip markers
+    fn scope(&self) -> String; // database and tracking table this repo works on, without credentials, to key local state such as checkpoints
+    fn snapshot_file(&self) -> Option<std::path::PathBuf>; // configured snapshot, resolved against the config file
+    fn seeds_dir(&self) -> std::path::PathBuf; // configured seeds directory, resolved against the config file
+    fn hooks(&self) -> &Hooks;
+    fn ids(&self) -> &crate::core::migration::IdConfig; // naming scheme of `new`
+    fn retry(&self) -> &crate::core::migration::RetryConfig; // retries of migrations failing on a transient error
+    fn is_transient(&self, error: &anyhow::Error) -> bool; // serialization failures, deadlocks, lock timeouts and the like, worth another attempt
+    fn dialect(&self) -> Dialect;
 }
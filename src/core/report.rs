@@ -0,0 +1,193 @@
+use {
+    crate::core::{
+        duration, log,
+        migration::{self as util, MigrationMeta},
+        migration_diff,
+        plan::{self, PlannedMigration, RiskLevel},
+        telemetry::{self, MigrationRun},
+    },
+    anyhow::Context,
+    std::{path::Path, time::Duration},
+};
+
+/// Write the report of `--report` after the run: Markdown, or HTML when the file ends in `.html`.
+/// Failures only produce a warning, the migrations ran either way.
+pub fn write(path: &Path, elapsed: Duration, success: bool) {
+    let report = Report::new(elapsed, success);
+    let html = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"));
+    let text = match html {
+        | true => report.html(),
+        | false => report.markdown(),
+    };
+    match std::fs::write(path, text).with_context(|| format!("Failed to write {}", path.display())) {
+        | Ok(()) => crate::say!("📝 Wrote the report to {}", path.display()),
+        | Err(e) => crate::esay!("⚠️  Failed to write the report: {:#}", e),
+    }
+}
+
+/// A migration of the run with what it changed and what deserves a second look
+struct Entry {
+    run: MigrationRun,
+    operations: Vec<String>,
+    warnings: Vec<String>,
+}
+
+struct Report {
+    /// Label and value of the facts about the run as a whole
+    facts: Vec<(&'static str, String)>,
+    entries: Vec<Entry>,
+}
+
+impl Report {
+    fn new(elapsed: Duration, success: bool) -> Self {
+        let (user, host) = util::applied_by();
+        let facts = vec![
+            ("Result", if success { "succeeded" } else { "failed" }.to_string()),
+            ("Finished", chrono::Local::now().format("%Y-%m-%d %H:%M:%S %Z").to_string()),
+            ("Duration", duration::format_millis(elapsed.as_millis() as i64)),
+            ("Run by", util::format_applied_by(Some(&user), host.as_deref())),
+            ("qop version", env!("CARGO_PKG_VERSION").to_string()),
+            ("Command", log::invocation()),
+        ];
+        let entries = telemetry::runs()
+            .into_iter()
+            .map(|run| {
+                let operations = migration_diff::parse_migration_operations(&run.sql).iter().map(plan::describe).collect();
+                let mut warnings: Vec<String> = PlannedMigration::new(&run.id, &run.sql, &MigrationMeta::default())
+                    .risks
+                    .iter()
+                    .filter(|risk| risk.level > RiskLevel::Low)
+                    .map(|risk| format!("[{}] {}", if risk.level == RiskLevel::High { "high" } else { "medium" }, risk.message))
+                    .collect();
+                if let Some(error) = &run.error {
+                    warnings.insert(0, format!("failed: {}", error));
+                }
+                Entry { run, operations, warnings }
+            })
+            .collect();
+        Self { facts, entries }
+    }
+
+    fn status(run: &MigrationRun) -> &'static str {
+        match (&run.error, run.dry_run, run.direction) {
+            | (Some(_), ..) => "failed",
+            | (None, true, _) => "dry run",
+            | (None, false, "down") => "reverted",
+            | (None, false, _) => "applied",
+        }
+    }
+
+    /// Whether the runs went to several targets or tenants, which gets them a column of their own
+    fn jobs(&self) -> bool {
+        self.entries.iter().any(|entry| !entry.run.job.is_empty())
+    }
+
+    fn rows(&self) -> (Vec<&'static str>, Vec<Vec<String>>) {
+        let jobs = self.jobs();
+        let mut header = vec!["Migration", "Direction", "Status", "Duration", "Rows"];
+        if jobs {
+            header.insert(0, "Target");
+        }
+        let rows = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let run = &entry.run;
+                let mut row = vec![run.id.clone(), run.direction.to_string(), Self::status(run).to_string(), duration::format_millis(run.duration.as_millis() as i64), run.rows.to_string()];
+                if jobs {
+                    row.insert(0, run.job.clone());
+                }
+                row
+            })
+            .collect();
+        (header, rows)
+    }
+
+    fn title(&self, entry: &Entry) -> String {
+        match entry.run.job.is_empty() {
+            | true => format!("{} ({})", entry.run.id, entry.run.direction),
+            | false => format!("{}: {} ({})", entry.run.job, entry.run.id, entry.run.direction),
+        }
+    }
+
+    fn markdown(&self) -> String {
+        let cell = |text: &str| text.replace('|', "\\|").replace('\n', " ");
+        let mut out = String::from("# qop migration report\n\n| | |\n|---|---|\n");
+        for (label, value) in &self.facts {
+            out.push_str(&format!("| {} | {} |\n", label, cell(value)));
+        }
+
+        out.push_str("\n## Migrations\n\n");
+        if self.entries.is_empty() {
+            out.push_str("No migrations ran.\n");
+            return out;
+        }
+        let (header, rows) = self.rows();
+        out.push_str(&format!("| {} |\n|{}\n", header.join(" | "), "---|".repeat(header.len())));
+        for row in rows {
+            out.push_str(&format!("| {} |\n", row.iter().map(|value| cell(value)).collect::<Vec<_>>().join(" | ")));
+        }
+
+        out.push_str("\n## Operations\n");
+        for entry in &self.entries {
+            out.push_str(&format!("\n### {}\n\n", self.title(entry)));
+            match entry.operations.is_empty() {
+                | true => out.push_str("(empty)\n"),
+                | false => entry.operations.iter().for_each(|op| out.push_str(&format!("- `{}`\n", op.replace('`', "'")))),
+            }
+        }
+
+        out.push_str("\n## Warnings\n\n");
+        let warnings: Vec<String> = self.entries.iter().flat_map(|entry| entry.warnings.iter().map(move |warning| format!("- **{}**: {}\n", self.title(entry), cell(warning)))).collect();
+        match warnings.is_empty() {
+            | true => out.push_str("None.\n"),
+            | false => out.push_str(&warnings.concat()),
+        }
+        out
+    }
+
+    fn html(&self) -> String {
+        let mut out = String::from(concat!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>qop migration report</title>\n",
+            "<style>body{font-family:sans-serif;margin:2em}table{border-collapse:collapse}th,td{border:1px solid #ccc;padding:4px 8px;text-align:left}code{background:#f4f4f4}</style>\n",
+            "</head>\n<body>\n<h1>qop migration report</h1>\n<table>\n",
+        ));
+        for (label, value) in &self.facts {
+            out.push_str(&format!("<tr><th>{}</th><td>{}</td></tr>\n", label, escape(value)));
+        }
+        out.push_str("</table>\n<h2>Migrations</h2>\n");
+        if self.entries.is_empty() {
+            out.push_str("<p>No migrations ran.</p>\n</body>\n</html>\n");
+            return out;
+        }
+        let (header, rows) = self.rows();
+        out.push_str(&format!("<table>\n<tr>{}</tr>\n", header.iter().map(|label| format!("<th>{}</th>", label)).collect::<String>()));
+        for row in rows {
+            out.push_str(&format!("<tr>{}</tr>\n", row.iter().map(|value| format!("<td>{}</td>", escape(value))).collect::<String>()));
+        }
+        out.push_str("</table>\n<h2>Operations</h2>\n");
+        for entry in &self.entries {
+            out.push_str(&format!("<h3>{}</h3>\n", escape(&self.title(entry))));
+            match entry.operations.is_empty() {
+                | true => out.push_str("<p>(empty)</p>\n"),
+                | false => out.push_str(&format!("<ul>\n{}</ul>\n", entry.operations.iter().map(|op| format!("<li><code>{}</code></li>\n", escape(op))).collect::<String>())),
+            }
+        }
+        out.push_str("<h2>Warnings</h2>\n");
+        let warnings: String = self
+            .entries
+            .iter()
+            .flat_map(|entry| entry.warnings.iter().map(move |warning| format!("<li><strong>{}</strong>: {}</li>\n", escape(&self.title(entry)), escape(warning))))
+            .collect();
+        match warnings.is_empty() {
+            | true => out.push_str("<p>None.</p>\n"),
+            | false => out.push_str(&format!("<ul>\n{}</ul>\n", warnings)),
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,121 @@
+use {
+    crate::core::bundle::sha256_hex,
+    anyhow::{Context, Result},
+    chrono::{Local, NaiveDateTime, TimeZone},
+    comfy_table::{Cell, CellAlignment, ContentArrangement},
+    serde::Serialize,
+    std::path::Path,
+};
+
+/// Directory next to qop.toml holding the seed files, unless configured otherwise
+pub const SEEDS_DIR: &str = "seeds";
+
+/// A seed file; shared seeds live directly in `seeds/`, profile seeds in `seeds/<profile>/`
+#[derive(Debug)]
+pub struct LocalSeed {
+    /// Path relative to the seeds directory, used as the tracking key
+    pub name: String,
+    pub profile: Option<String>,
+    pub sql: String,
+    pub checksum: String,
+}
+
+/// Shared seeds followed by the seeds of the given profile, each sorted by file name
+pub fn local_seeds(seeds_dir: &Path, profile: Option<&str>) -> Result<Vec<LocalSeed>> {
+    if !seeds_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut seeds = read_seed_files(seeds_dir, None)?;
+    if let Some(profile) = profile {
+        let profile_dir = seeds_dir.join(profile);
+        if !profile_dir.is_dir() {
+            anyhow::bail!("no seeds found for profile {} (expected directory {})", profile, profile_dir.display());
+        }
+        seeds.extend(read_seed_files(&profile_dir, Some(profile))?);
+    }
+    Ok(seeds)
+}
+
+fn read_seed_files(dir: &Path, profile: Option<&str>) -> Result<Vec<LocalSeed>> {
+    let mut files: Vec<_> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read seeds directory: {}", dir.display()))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "sql"))
+        .collect();
+    files.sort();
+
+    files
+        .into_iter()
+        .map(|path| {
+            let sql = std::fs::read_to_string(&path).with_context(|| format!("Failed to read seed file: {}", path.display()))?;
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            let name = match profile {
+                Some(profile) => format!("{}/{}", profile, file_name),
+                None => file_name,
+            };
+            let checksum = sha256_hex(sql.as_bytes());
+            Ok(LocalSeed { name, profile: profile.map(|p| p.to_string()), sql, checksum })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SeedStatus {
+    Pending,
+    Applied,
+    /// Applied, but the file changed since
+    Modified,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SeedRow {
+    pub name: String,
+    pub profile: Option<String>,
+    pub status: SeedStatus,
+    pub applied_at: Option<NaiveDateTime>,
+}
+
+/// Compare local seeds with the tracking records as `(name, checksum, applied_at)`
+pub fn seed_rows(local: &[LocalSeed], applied: &[(String, String, NaiveDateTime)]) -> Vec<SeedRow> {
+    local
+        .iter()
+        .map(|seed| {
+            let record = applied.iter().find(|(name, ..)| *name == seed.name);
+            let status = match record {
+                None => SeedStatus::Pending,
+                Some((_, checksum, _)) if *checksum == seed.checksum => SeedStatus::Applied,
+                Some(_) => SeedStatus::Modified,
+            };
+            SeedRow { name: seed.name.clone(), profile: seed.profile.clone(), status, applied_at: record.map(|(_, _, ts)| *ts) }
+        })
+        .collect()
+}
+
+pub fn render_seed_table(rows: &[SeedRow]) {
+    let mut table = crate::core::output::table();
+    table
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec![Cell::new("Seed"), Cell::new("Profile"), Cell::new("Status"), Cell::new("Applied At")]);
+
+    for row in rows {
+        let status = match row.status {
+            SeedStatus::Pending => "⏳ pending",
+            SeedStatus::Applied => "✅ applied",
+            SeedStatus::Modified => "⚠️ modified",
+        };
+        let applied_at = row
+            .applied_at
+            .map(|ts| Local.from_utc_datetime(&ts).format("%Y-%m-%d %H:%M:%S %Z").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        table.add_row(vec![
+            Cell::new(&row.name),
+            Cell::new(row.profile.as_deref().unwrap_or("-")),
+            Cell::new(status).set_alignment(CellAlignment::Center),
+            Cell::new(applied_at),
+        ]);
+    }
+
+    println!("{table}");
+}
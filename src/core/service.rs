@@ -1,9 +1,9 @@
 use std::collections::BTreeMap;
 use chrono::{DateTime, TimeZone, Utc};
 use {
-    crate::core::migration as util,
+    crate::core::{alembic, bench::{self, BenchReport}, bootstrap, bundle, check::CheckFailure, checkpoint, dbmate, declarative, events, graph, hooks::{self, HookContext, HookStage}, import, lint, liquibase, log, migration as util, migration_diff, output, ping, plan, progress::Progress, repeatable, seed, snapshot, sqlx_migrate, telemetry, tui},
     super::repo::MigrationRepository,
-    anyhow::Result,
+    anyhow::{Context, Result},
     std::path::Path,
 };
 
@@ -13,6 +13,96 @@ pub enum OutputFormat {
     Json,
 }
 
+/// Migrations picked by `apply`: explicit ids, an inclusive id range, or neither to prompt for one
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Selection<'a> {
+    pub ids: &'a [String],
+    pub from: Option<&'a str>,
+    pub to: Option<&'a str>,
+}
+
+/// Where `new` takes the content of a migration from; `up`/`stdin` and `down` override the template
+#[derive(Debug, Clone, Copy)]
+pub struct NewSources<'a> {
+    pub up: Option<&'a Path>,
+    pub down: Option<&'a Path>,
+    pub stdin: bool,
+    pub template: &'a util::MigrationTemplate,
+    /// Generate down.sql from up.sql, even if the template has a down.sql
+    pub auto_down: bool,
+}
+
+/// What `up`, `down` and `apply` did, printed on stdout with `--output json`
+#[derive(Debug, serde::Serialize)]
+struct RunOut {
+    command: &'static str,
+    /// Target or tenant of the run, in runs over several of them
+    #[serde(skip_serializing_if = "String::is_empty")]
+    job: String,
+    dry_run: bool,
+    cancelled: bool,
+    migrations: Vec<RunMigrationOut>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    failed: Vec<RunFailureOut>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    repeatables: Vec<String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct RunMigrationOut {
+    id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u128>,
+}
+
+#[derive(Debug, serde::Serialize)]
+struct RunFailureOut {
+    id: String,
+    error: String,
+}
+
+impl RunOut {
+    fn new(command: &'static str, dry_run: bool) -> Self {
+        Self { command, job: output::job(), dry_run, cancelled: false, migrations: Vec::new(), failed: Vec::new(), repeatables: Vec::new() }
+    }
+
+    fn cancelled(command: &'static str, dry_run: bool) -> Self {
+        Self { cancelled: true, ..Self::new(command, dry_run) }
+    }
+
+    fn push(&mut self, id: &str, started: Option<std::time::Instant>) {
+        self.migrations.push(RunMigrationOut { id: id.to_string(), duration_ms: started.map(|started| started.elapsed().as_millis()) });
+    }
+}
+
+/// The migrations previewed by `diff --output json`
+#[derive(Debug, serde::Serialize)]
+struct DiffOut {
+    command: &'static str,
+    direction: &'static str,
+    migrations: Vec<DiffMigrationOut>,
+}
+
+/// A previewed migration with the operations its SQL performs
+#[derive(Debug, serde::Serialize)]
+struct DiffMigrationOut {
+    id: String,
+    sql: String,
+    operations: Vec<migration_diff::MigrationOperation>,
+}
+
+/// Result of `--check`
+#[derive(Debug, serde::Serialize)]
+struct CheckOut {
+    command: &'static str,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    job: String,
+    status: &'static str,
+    pending: Vec<String>,
+    modified: Vec<String>,
+    missing: Vec<String>,
+}
+
 pub struct MigrationService<R: MigrationRepository> {
     repo: R,
 }
@@ -20,65 +110,275 @@ pub struct MigrationService<R: MigrationRepository> {
 impl<R: MigrationRepository> MigrationService<R> {
     pub fn new(repo: R) -> Self { Self { repo } }
 
+    pub(crate) fn repo(&self) -> &R { &self.repo }
+
     pub async fn init(&self) -> Result<()> {
-        self.repo.init_store().await
+        self.repo.init_store().await?;
+        output::result(&serde_json::json!({ "command": "init", "initialized": true }))
     }
 
-    pub async fn new_migration(&self, path: &Path, comment: Option<&str>, locked: bool) -> Result<()> {
-        let migration_id_path = util::create_migration_directory(path, comment, locked)?;
-        println!("Created new migration: {}", migration_id_path.display());
-        Ok(())
+    pub async fn new_migration(&self, path: &Path, comment: Option<&str>, locked: bool, sources: NewSources<'_>) -> Result<()> {
+        // Read all sources before creating anything so a bad path leaves no half-filled migration behind
+        let up_sql = match sources.up {
+            Some(up) => Some(std::fs::read_to_string(up).with_context(|| format!("Failed to read up SQL: {}", up.display()))?),
+            None if sources.stdin => {
+                let mut sql = String::new();
+                std::io::Read::read_to_string(&mut std::io::stdin(), &mut sql).context("Failed to read up SQL from stdin")?;
+                if sql.trim().is_empty() {
+                    anyhow::bail!("no SQL received on stdin");
+                }
+                Some(sql)
+            },
+            None => None,
+        };
+        let down_sql = sources.down.map(|down| std::fs::read_to_string(down).with_context(|| format!("Failed to read down SQL: {}", down.display()))).transpose()?;
+        // Without --auto-down, down.sql is only generated where it would otherwise be the placeholder
+        let has_up = up_sql.is_some() || sources.template.up.is_some();
+        if sources.auto_down && !has_up {
+            anyhow::bail!("--auto-down needs up SQL from --up, --stdin or a template");
+        }
+        let generate_down = sources.auto_down || (has_up && down_sql.is_none() && sources.template.down.is_none());
+
+        let id = self.next_id(path).await?;
+        let migration_id_path = util::create_migration_directory(path, &id, comment, locked, sources.template)?;
+        for (file, sql) in [("up.sql", up_sql), ("down.sql", down_sql)] {
+            if let Some(sql) = sql {
+                let target = migration_id_path.join(file);
+                std::fs::write(&target, sql).with_context(|| format!("Failed to write migration file: {}", target.display()))?;
+            }
+        }
+        if generate_down {
+            // Generated from the written up.sql, so template placeholders are already replaced
+            let up_sql = std::fs::read_to_string(migration_id_path.join("up.sql")).context("Failed to read the new up.sql")?;
+            let target = migration_id_path.join("down.sql");
+            std::fs::write(&target, bootstrap::generate_migration_down_sql(&up_sql, self.repo.dialect()))
+                .with_context(|| format!("Failed to write migration file: {}", target.display()))?;
+        }
+        crate::summary!("Created new migration: {}", migration_id_path.display());
+        output::result(&serde_json::json!({ "command": "new", "id": id, "path": migration_id_path }))
+    }
+
+    /// Id for a new migration in the configured scheme; sequential ids also avoid applied ids
+    /// whose directories are not checked out
+    async fn next_id(&self, path: &Path) -> Result<String> {
+        self.repo.ids().next_id(&self.taken_ids(path).await?)
+    }
+
+    /// Ids a new migration must not use
+    async fn taken_ids(&self, path: &Path) -> Result<std::collections::HashSet<String>> {
+        let mut taken = util::get_local_migrations(path)?;
+        if self.repo.ids().scheme == util::IdScheme::Sequential {
+            // A database without tracking table has nothing applied yet
+            taken.extend(self.repo.fetch_applied_ids().await.unwrap_or_default());
+        }
+        Ok(taken)
     }
 
-    pub async fn apply_up(&self, path: &Path, id: &str, timeout: Option<u64>, yes: bool, dry_run: bool, locked: bool) -> Result<()> {
+    pub async fn apply_up(&self, path: &Path, selection: Selection<'_>, timeout: Option<u64>, yes: bool, dry_run: bool, locked: bool) -> Result<()> {
+        self.repo.acquire_run_lock().await?;
         let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
-        let target_id = util::normalize_migration_id(id);
-        let (up_sql, down_sql, meta) = util::read_migration_with_meta(migration_dir, &target_id)?;
+        let applied = self.repo.fetch_applied_ids().await?;
+        let local = util::get_local_migrations(path)?;
+        let mut pending: Vec<String> = local.difference(&applied).cloned().collect();
+        util::sort_ids(&mut pending);
 
-        let diff_fn = || -> Result<()> { util::display_sql_migration(&target_id, &up_sql, "UP") };
-        if !util::prompt_for_confirmation_with_diff(&format!("❓ Do you want to apply migration '{}'?",&target_id), yes, diff_fn)? {
-            println!("❌ Migration cancelled.");
-            return Ok(())
+        let Selection { ids, from, to } = selection;
+        let targets: Vec<String> = if !ids.is_empty() {
+            let mut targets: Vec<String> = ids.iter().map(|id| util::normalize_migration_id(id)).collect();
+            util::sort_ids(&mut targets);
+            targets.dedup();
+            for id in &targets {
+                if applied.contains(id) {
+                    anyhow::bail!("migration {} is already applied", id);
+                }
+                if !local.contains(id) {
+                    anyhow::bail!("migration {} not found locally", id);
+                }
+            }
+            targets
+        } else if from.is_some() || to.is_some() {
+            let (skipped, targets): (Vec<String>, Vec<String>) = pending
+                .into_iter()
+                .filter(|id| util::id_in_range(id, from, to))
+                .partition(|id| util::is_skipped(migration_dir, id, self.repo.profile()));
+            Self::note_skipped(&skipped);
+            if targets.is_empty() {
+                crate::summary!("No pending migrations in the given range.");
+                return output::result(&RunOut::new("apply up", dry_run))
+            }
+            targets
+        } else {
+            let candidates: Vec<_> = pending
+                .into_iter()
+                .map(|id| {
+                    let comment = util::read_migration_meta(migration_dir, &id).ok().and_then(|m| m.comment);
+                    (id, comment, None)
+                })
+                .collect();
+            if candidates.is_empty() {
+                crate::summary!("All migrations are up to date.");
+                return output::result(&RunOut::new("apply up", dry_run))
+            }
+            let Some(id) = util::pick_migration("Select a migration to apply", &candidates)? else {
+                crate::say!("❌ Migration cancelled.");
+                return output::result(&RunOut::cancelled("apply up", dry_run))
+            };
+            vec![id]
+        };
+
+        let targets = util::order_by_dependencies(migration_dir, &targets, &applied)?;
+        let mut migrations = Vec::with_capacity(targets.len());
+        for id in &targets {
+            let (up_sql, down_sql, meta) = util::read_migration_with_meta(migration_dir, id)?;
+            migrations.push((id.clone(), up_sql, down_sql, meta));
         }
 
-        let pre = self.repo.fetch_last_id().await?;
-        self.repo.apply_migration(&target_id, &up_sql, &down_sql, meta.comment.as_deref(), pre.as_deref(), timeout, dry_run, locked).await?;
-        util::print_migration_results(1, "applied");
-        Ok(())
+        let diff_fn = |view: util::DiffView| -> Result<()> {
+            for (id, up_sql, ..) in &migrations {
+                util::display_sql_migration(id, up_sql, "UP", view)?;
+            }
+            Ok(())
+        };
+        let question = match targets.as_slice() {
+            [id] => format!("❓ Do you want to apply migration '{}'?", id),
+            _ => format!("❓ Do you want to apply {} migrations ({})?", targets.len(), targets.join(", ")),
+        };
+        if !util::prompt_for_confirmation_with_diff(&question, yes, diff_fn)? {
+            crate::say!("❌ Migration cancelled.");
+            return output::result(&RunOut::cancelled("apply up", dry_run))
+        }
+
+        hooks::run_hooks(&self.repo, HookContext::run(HookStage::BeforeRun, "up", &targets), None, timeout, dry_run).await?;
+        let mut out = RunOut::new("apply up", dry_run);
+        let mut pre = self.repo.fetch_last_id().await?;
+        for (id, up_sql, down_sql, meta) in &migrations {
+            let exec = meta.exec_options(timeout);
+            hooks::run_hooks(&self.repo, HookContext::each(HookStage::BeforeEach, "up", id, &targets), meta.hooks.as_ref(), timeout, dry_run).await?;
+            events::migration_started(id, "up");
+            let started = std::time::Instant::now();
+            let applied = self.with_retries(id, meta, &exec, || self.repo.apply_migration(id, up_sql, down_sql, meta, pre.as_deref(), &exec, dry_run, locked)).await;
+            telemetry::record(id, "up", up_sql, started, dry_run, applied.as_ref().err());
+            self.log_attempt(id, "up", up_sql, started, dry_run, applied.as_ref().err()).await;
+            applied?;
+            out.push(id, Some(started));
+            hooks::run_hooks(&self.repo, HookContext::each(HookStage::AfterEach, "up", id, &targets), meta.hooks.as_ref(), timeout, dry_run).await?;
+            pre = Some(id.clone());
+        }
+        hooks::run_hooks(&self.repo, HookContext::run(HookStage::AfterRun, "up", &targets), None, timeout, dry_run).await?;
+        util::print_migration_results(migrations.len(), "applied");
+        self.record_snapshot(dry_run).await;
+        output::result(&out)
     }
 
-    pub async fn apply_down(&self, path: &Path, id: &str, timeout: Option<u64>, remote: bool, yes: bool, dry_run: bool, unlock: bool) -> Result<()> {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn apply_down(&self, path: &Path, selection: Selection<'_>, timeout: Option<u64>, remote: bool, yes: bool, dry_run: bool, unlock: bool) -> Result<()> {
+        self.repo.acquire_run_lock().await?;
         let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
-        let target_id = util::normalize_migration_id(id);
-        let down_sql = if remote {
-            self.repo.fetch_down_sql(&target_id).await?.unwrap_or_default()
+        // Newest first, migrations are reverted in the opposite order they were applied in
+        let mut history = self.repo.fetch_history().await?;
+        history.sort_by(|a, b| util::compare_ids(&b.0, &a.0));
+
+        let Selection { ids, from, to } = selection;
+        let targets: Vec<String> = if !ids.is_empty() {
+            let mut targets: Vec<String> = ids.iter().map(|id| util::normalize_migration_id(id)).collect();
+            targets.sort_by(|a, b| util::compare_ids(b, a));
+            targets.dedup();
+            if let Some(id) = targets.iter().find(|id| !history.iter().any(|(applied, ..)| applied == *id)) {
+                anyhow::bail!("migration {} is not applied", id);
+            }
+            targets
+        } else if from.is_some() || to.is_some() {
+            let targets: Vec<String> = history.into_iter().map(|(id, ..)| id).filter(|id| util::id_in_range(id, from, to)).collect();
+            if targets.is_empty() {
+                crate::summary!("No applied migrations in the given range.");
+                return output::result(&RunOut::new("apply down", dry_run))
+            }
+            targets
         } else {
-            let (_up_sql, down_sql) = util::read_migration_files(migration_dir, &target_id)?;
-            down_sql
+            let candidates: Vec<_> = history.into_iter().map(|(id, ts, comment, _)| (id, comment, Some(ts))).collect();
+            if candidates.is_empty() {
+                crate::summary!("No applied migrations to revert.");
+                return output::result(&RunOut::new("apply down", dry_run))
+            }
+            let Some(id) = util::pick_migration("Select a migration to revert", &candidates)? else {
+                crate::say!("❌ Revert cancelled.");
+                return output::result(&RunOut::cancelled("apply down", dry_run))
+            };
+            vec![id]
         };
 
-        let diff_fn = || -> Result<()> { util::display_sql_migration(&target_id, &down_sql, "DOWN") };
-        if !util::prompt_for_confirmation_with_diff(&format!("❓ Do you want to revert migration '{}'?",&target_id), yes, diff_fn)? {
-            println!("❌ Revert cancelled.");
-            return Ok(())
+        let mut migrations = Vec::with_capacity(targets.len());
+        for id in &targets {
+            let down_sql = if remote {
+                self.repo.fetch_down_sql(id).await?.unwrap_or_default()
+            } else {
+                let (_up_sql, down_sql) = util::read_migration_files(migration_dir, id)?;
+                down_sql
+            };
+            // Remote reverts may target migrations whose local files are gone
+            let meta = util::read_migration_meta(migration_dir, id).unwrap_or_default();
+            migrations.push((id.clone(), down_sql, meta));
         }
 
-        self.repo.revert_migration(&target_id, &down_sql, timeout, dry_run, unlock).await?;
-        util::print_migration_results(1, "reverted");
-        Ok(())
+        let diff_fn = |view: util::DiffView| -> Result<()> {
+            for (id, down_sql, _) in &migrations {
+                util::display_sql_migration(id, down_sql, "DOWN", view)?;
+            }
+            Ok(())
+        };
+        let question = match targets.as_slice() {
+            [id] => format!("❓ Do you want to revert migration '{}'?", id),
+            _ => format!("❓ Do you want to revert {} migrations ({})?", targets.len(), targets.join(", ")),
+        };
+        if !util::prompt_for_confirmation_with_diff(&question, yes, diff_fn)? {
+            crate::say!("❌ Revert cancelled.");
+            return output::result(&RunOut::cancelled("apply down", dry_run))
+        }
+
+        hooks::run_hooks(&self.repo, HookContext::run(HookStage::BeforeRun, "down", &targets), None, timeout, dry_run).await?;
+        let mut out = RunOut::new("apply down", dry_run);
+        for (id, down_sql, meta) in &migrations {
+            let exec = meta.exec_options(timeout);
+            hooks::run_hooks(&self.repo, HookContext::each(HookStage::BeforeEach, "down", id, &targets), meta.hooks.as_ref(), timeout, dry_run).await?;
+            events::migration_started(id, "down");
+            let started = std::time::Instant::now();
+            let reverted = self.with_retries(id, meta, &exec, || self.repo.revert_migration(id, down_sql, &exec, dry_run, unlock)).await;
+            telemetry::record(id, "down", down_sql, started, dry_run, reverted.as_ref().err());
+            self.log_attempt(id, "down", down_sql, started, dry_run, reverted.as_ref().err()).await;
+            reverted?;
+            out.push(id, Some(started));
+            hooks::run_hooks(&self.repo, HookContext::each(HookStage::AfterEach, "down", id, &targets), meta.hooks.as_ref(), timeout, dry_run).await?;
+        }
+        hooks::run_hooks(&self.repo, HookContext::run(HookStage::AfterRun, "down", &targets), None, timeout, dry_run).await?;
+        util::print_migration_results(migrations.len(), "reverted");
+        self.record_snapshot(dry_run).await;
+        output::result(&out)
     }
 
-    pub async fn list(&self, output: OutputFormat) -> Result<()> {
-        let history = self.repo.fetch_history().await?;
-        let local = util::get_local_migrations(self.repo.get_path())?;
+    pub async fn list(&self, tags: &[String], wide: bool, output: OutputFormat) -> Result<()> {
+        let migration_dir = self.repo.get_path().parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", self.repo.get_path().display()))?;
+        let mut history = self.repo.fetch_history().await?;
+        let mut local = util::get_local_migrations(self.repo.get_path())?;
+        if !tags.is_empty() {
+            history.retain(|(id, ..)| util::has_any_tag(migration_dir, id, tags));
+            local.retain(|id| util::has_any_tag(migration_dir, id, tags));
+        }
         match output {
             OutputFormat::Human => {
                 if history.is_empty() && local.is_empty() {
                     println!("No migrations found.");
                     return Ok(())
                 }
-                let migration_dir = self.repo.get_path().parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", self.repo.get_path().display()))?;
-                util::render_migration_table(&local, &history, migration_dir)?;
+                let applied_by = match wide {
+                    | true => Some(self.repo.fetch_applied_by().await?),
+                    | false => None,
+                };
+                util::render_migration_table(&local, &history, &self.repo.fetch_provenance().await?, &self.repo.fetch_durations().await?, applied_by.as_ref(), migration_dir, self.repo.profile())?;
+                Self::warn_modified(&self.verify_checksums(self.repo.get_path()).await?);
+                let repeatables = repeatable::local_repeatables(migration_dir)?;
+                if !repeatables.is_empty() && tags.is_empty() {
+                    repeatable::render_repeatable_table(&repeatable::repeatable_rows(&repeatables, &self.repo.fetch_repeatables().await?));
+                }
                 Ok(())
             }
             OutputFormat::Json => {
@@ -89,10 +389,27 @@ impl<R: MigrationRepository> MigrationService<R> {
                     local: bool,
                     comment: Option<String>,
                     locked: bool,
+                    /// Local files differ from the applied SQL
+                    modified: bool,
+                    /// Pending, but excluded from `up` by a skip marker
+                    skipped: bool,
+                    /// How long applying took, unknown for migrations applied by an older qop
+                    #[serde(skip_serializing_if = "Option::is_none")]
+                    duration_ms: Option<i64>,
+                    /// OS user and host that applied the migration, unknown for migrations applied by an older qop
+                    #[serde(skip_serializing_if = "Option::is_none")]
+                    applied_by: Option<String>,
+                    #[serde(skip_serializing_if = "Option::is_none")]
+                    hostname: Option<String>,
+                    #[serde(flatten)]
+                    provenance: util::Provenance,
                 }
+                let provenance = self.repo.fetch_provenance().await?;
+                let durations = self.repo.fetch_durations().await?;
+                let applied_by = self.repo.fetch_applied_by().await?;
+                let modified: std::collections::HashSet<String> = self.verify_checksums(self.repo.get_path()).await?.into_iter().filter(|r| r.is_modified()).map(|r| r.id).collect();
                 let mut all: BTreeMap<String, (Option<chrono::NaiveDateTime>, bool, Option<String>, bool)> = BTreeMap::new();
-                let migration_dir = self.repo.get_path().parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", self.repo.get_path().display()))?;
-                
+
                 for id in &local {
                     let entry = all.entry(id.clone()).or_default();
                     entry.1 = true;
@@ -112,7 +429,19 @@ impl<R: MigrationRepository> MigrationService<R> {
                 }
                 let mut rows: Vec<RowOut> = Vec::new();
                 for (id, (applied_at, is_local, comment, locked)) in all {
+                    // Recorded when applied, else from the local meta.toml
+                    let provenance = match provenance.get(&id) {
+                        | Some(provenance) if applied_at.is_some() => provenance.clone(),
+                        | _ => util::read_migration_meta(migration_dir, &id).map(|meta| meta.provenance()).unwrap_or_default(),
+                    };
+                    let (user, host) = applied_by.get(&id).filter(|_| applied_at.is_some()).cloned().unwrap_or_default();
                     rows.push(RowOut { 
+                        applied_by: user,
+                        hostname: host,
+                        modified: modified.contains(&id),
+                        skipped: applied_at.is_none() && is_local && util::is_skipped(migration_dir, &id, self.repo.profile()),
+                        duration_ms: applied_at.and(durations.get(&id).copied()),
+                        provenance,
                         id, 
                         remote: applied_at.map(|naive| Utc.from_utc_datetime(&naive)), 
                         local: is_local,
@@ -120,82 +449,276 @@ impl<R: MigrationRepository> MigrationService<R> {
                         locked,
                     });
                 }
+                rows.sort_by(|a, b| util::compare_ids(&a.id, &b.id));
                 println!("{}", serde_json::to_string_pretty(&rows)?);
                 Ok(())
             }
         }
     }
 
-    pub async fn up(&self, path: &Path, timeout: Option<u64>, count: Option<usize>, yes: bool, dry_run: bool) -> Result<()> {
+    /// Apply the pending migrations for real to a shadow copy of the database; the copy is
+    /// thrown away afterwards, so the outcome is all that is left
+    #[allow(clippy::too_many_arguments)]
+    pub async fn shadow_up(&self, path: &Path, shadow: &str, timeout: Option<u64>, count: Option<usize>, tags: &[String], single_transaction: bool, keep_going: bool) -> Result<()> {
+        crate::say!("🌘 Running against shadow database {}, the target database stays untouched", shadow);
+        self.up(path, timeout, count, tags, true, false, single_transaction, keep_going, false)
+            .await
+            .context("Shadow run failed, the target database was not changed")?;
+        crate::summary!("✅ Shadow run succeeded, the pending migrations apply cleanly.");
+        Ok(())
+    }
+
+    /// Run every pending migration twice in one transaction that is rolled back afterwards; a
+    /// failing second run means the migration cannot be re-run after a partial failure
+    pub async fn assert_idempotent(&self, path: &Path, timeout: Option<u64>, count: Option<usize>, tags: &[String]) -> Result<()> {
         let local = util::get_local_migrations(path)?;
         let applied = self.repo.fetch_applied_ids().await?;
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
 
-        let mut to_apply: Vec<String> = local.difference(&applied).cloned().collect();
-        to_apply.sort();
-        if let Some(c) = count { to_apply.truncate(c); }
-
-        if to_apply.is_empty() {
+        let mut pending: Vec<String> = local.difference(&applied).filter(|id| util::has_any_tag(migration_dir, id, tags)).cloned().collect();
+        util::sort_ids(&mut pending);
+        let (skipped, pending): (Vec<String>, Vec<String>) = pending.into_iter().partition(|id| util::is_skipped(migration_dir, id, self.repo.profile()));
+        Self::note_skipped(&skipped);
+        let mut to_check = util::order_by_dependencies(migration_dir, &pending, &applied)?;
+        if let Some(c) = count { to_check.truncate(c); }
+        if to_check.is_empty() {
             println!("All migrations are up to date.");
             return Ok(())
         }
 
+        let mut migrations = Vec::with_capacity(to_check.len());
+        for id in &to_check {
+            let (up_sql, _down_sql, meta) = util::read_migration_with_meta(migration_dir, id)?;
+            let exec = meta.exec_options(timeout);
+            if !exec.transaction || exec.batch.is_some() {
+                crate::say!("⏭️  Skipping {} (runs outside a single transaction)", id);
+                continue;
+            }
+            migrations.push((id.clone(), up_sql, meta));
+        }
+        crate::say!("🔂 Running {} pending migration(s) twice, nothing is committed", migrations.len());
+        let failed = self.repo.check_idempotency(&migrations, timeout).await?;
+        for (id, e) in &failed {
+            crate::say!("❌ {} fails when run again: {:#}", id, e);
+        }
+        if !failed.is_empty() {
+            anyhow::bail!("{} of {} migration(s) are not idempotent; guard them with IF NOT EXISTS, IF EXISTS or OR REPLACE", failed.len(), migrations.len());
+        }
+        crate::summary!("✅ All {} migration(s) can be run again.", migrations.len());
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn up(&self, path: &Path, timeout: Option<u64>, count: Option<usize>, tags: &[String], yes: bool, dry_run: bool, single_transaction: bool, keep_going: bool, resume: bool) -> Result<()> {
+        self.repo.acquire_run_lock().await?;
+        let local = util::get_local_migrations(path)?;
+        let applied = self.repo.fetch_applied_ids().await?;
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+
+        let mut pending: Vec<String> = local.difference(&applied).filter(|id| util::has_any_tag(migration_dir, id, tags)).cloned().collect();
+        util::sort_ids(&mut pending);
+        let (skipped, pending): (Vec<String>, Vec<String>) = pending.into_iter().partition(|id| util::is_skipped(migration_dir, id, self.repo.profile()));
+        Self::note_skipped(&skipped);
+        let mut to_apply = util::order_by_dependencies(migration_dir, &pending, &applied)?;
+        let pending = to_apply.len();
+        if let Some(c) = count { to_apply.truncate(c); }
+        Self::warn_modified(&self.verify_checksums(path).await?);
+        let resumed = match resume {
+            | true => Some(self.resume_checkpoint(path, &to_apply).await?),
+            | false => None,
+        };
+
+        // Repeatable migrations run after all versioned migrations, so not when --count or --tag leave some pending
+        let repeatables = match to_apply.len() < pending || !tags.is_empty() {
+            | true => Vec::new(),
+            | false => self.changed_repeatables(migration_dir).await?,
+        };
+
+        if to_apply.is_empty() && repeatables.is_empty() {
+            crate::summary!("All migrations are up to date.");
+            return output::result(&RunOut::new("up", dry_run))
+        }
+
         // Non-linear warning
         let out_of_order = util::check_non_linear_history(&applied, &to_apply);
         if !out_of_order.is_empty() {
-            let max_applied = applied.iter().max().cloned().unwrap_or_default();
-            if !util::handle_non_linear_warning(&out_of_order, &max_applied)? { 
-                println!("Operation cancelled.");
-                return Ok(())
+            let max_applied = applied.iter().max_by(|a, b| util::compare_ids(a, b)).cloned().unwrap_or_default();
+            if !util::handle_non_linear_warning(&out_of_order, &max_applied)? {
+                crate::summary!("Operation cancelled.");
+                return output::result(&RunOut::cancelled("up", dry_run))
             }
         }
 
+        // Checked before confirming, a run that cannot share a transaction should not get that far
+        let batch = match single_transaction {
+            | true => Some(self.single_transaction_batch(migration_dir, &to_apply, timeout)?),
+            | false => None,
+        };
+
         // Confirm
-        println!("\n📋 About to apply {} migration(s):", to_apply.len());
-        for id in &to_apply { println!("  - {}", id); }
-        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        if !to_apply.is_empty() {
+            crate::say!("\n📋 About to apply {} migration(s):", to_apply.len());
+            for id in &to_apply { crate::say!("  - {}", id); }
+        }
+        if !repeatables.is_empty() {
+            crate::say!("\n🔁 About to run {} repeatable migration(s):", repeatables.len());
+            for repeatable in &repeatables { crate::say!("  - {}", repeatable.name); }
+        }
         let to_apply_for_diff = to_apply.clone();
-        let diff_fn = move || -> Result<()> {
+        let repeatables_for_diff = &repeatables;
+        let diff_fn = move |view: util::DiffView| -> Result<()> {
             for id in &to_apply_for_diff {
                 let (up_sql, _down) = util::read_migration_files(migration_dir, id)?;
-                util::display_sql_migration(id, &up_sql, "UP")?;
+                util::display_sql_migration(id, &up_sql, "UP", view)?;
+            }
+            for repeatable in repeatables_for_diff {
+                util::display_sql_migration(&repeatable.name, &repeatable.sql, "REPEAT", view)?;
             }
             Ok(())
         };
         if !util::prompt_for_confirmation_with_diff("❓ Do you want to proceed with applying these migrations?", yes, diff_fn)? {
-            println!("❌ Migration cancelled.");
-            return Ok(())
+            crate::say!("❌ Migration cancelled.");
+            return output::result(&RunOut::cancelled("up", dry_run))
         }
 
+        hooks::run_hooks(&self.repo, HookContext::run(HookStage::BeforeRun, "up", &to_apply), None, timeout, dry_run).await?;
+        let mut out = RunOut::new("up", dry_run);
         let mut previous: Option<String> = self.repo.fetch_last_id().await?;
         let mut applied_count = 0usize;
-        for id in to_apply {
-            let (up_sql, down_sql, meta) = util::read_migration_with_meta(migration_dir, &id)?;
-            self.repo.apply_migration(&id, &up_sql, &down_sql, meta.comment.as_deref(), previous.as_deref(), timeout, dry_run, meta.is_locked()).await?;
-            previous = Some(id.clone());
-            applied_count += 1;
+        match &batch {
+            | Some(batch) if batch.is_empty() => {},
+            | Some(batch) => {
+                for (id, ..) in batch {
+                    events::migration_started(id, "up");
+                }
+                // Retried as a whole on transient errors, the transaction leaves nothing behind
+                let started = std::time::Instant::now();
+                let failed = util::with_retries(&to_apply.join(", "), 0, self.repo.retry(), |e| self.repo.is_transient(e), || {
+                    self.repo.apply_migrations(batch, previous.as_deref(), timeout, keep_going, dry_run)
+                })
+                .await;
+                // The migrations share one transaction, each span covers all of it
+                for (id, up_sql, ..) in batch {
+                    let error = match &failed {
+                        | Ok(failed) => failed.iter().find(|(failed, _)| failed == id).map(|(_, e)| e),
+                        | Err(e) => Some(e),
+                    };
+                    telemetry::record(id, "up", up_sql, started, dry_run, error);
+                    self.log_attempt(id, "up", up_sql, started, dry_run, error).await;
+                }
+                let failed = failed?;
+                for (id, e) in &failed {
+                    crate::say!("⚠️  Migration {} failed and was rolled back to its savepoint: {:#}", id, e);
+                    out.failed.push(RunFailureOut { id: id.clone(), error: format!("{:#}", e) });
+                }
+                for id in to_apply.iter().filter(|id| !failed.iter().any(|(failed, _)| failed == *id)) {
+                    out.push(id, None);
+                }
+                applied_count = batch.len() - failed.len();
+            },
+            | None => {
+                let mut progress = Progress::new("Applying", to_apply.len());
+                for id in &to_apply {
+                    progress.start(id);
+                    let (up_sql, down_sql, meta) = util::read_migration_with_meta(migration_dir, id)?;
+                    let mut exec = meta.exec_options(timeout);
+                    if let Some(resumed) = resumed.as_ref().filter(|resumed| &resumed.failed == id && !exec.transaction) {
+                        exec.skip_statements = resumed.statements_done;
+                    }
+                    hooks::run_hooks(&self.repo, HookContext::each(HookStage::BeforeEach, "up", id, &to_apply), meta.hooks.as_ref(), timeout, dry_run).await?;
+                    events::migration_started(id, "up");
+                    let started = std::time::Instant::now();
+                    let applied = self.with_retries(id, &meta, &exec, || self.repo.apply_migration(id, &up_sql, &down_sql, &meta, previous.as_deref(), &exec, dry_run, meta.is_locked())).await;
+                    telemetry::record(id, "up", &up_sql, started, dry_run, applied.as_ref().err());
+                    self.log_attempt(id, "up", &up_sql, started, dry_run, applied.as_ref().err()).await;
+                    crate::verbose!("⏱️  Migration {} took {:.2}s", id, started.elapsed().as_secs_f64());
+                    if let Err(e) = applied {
+                        if !dry_run {
+                            self.save_checkpoint(path, previous.as_deref(), id, &exec, &e)?;
+                        }
+                        return Err(e);
+                    }
+                    out.push(id, Some(started));
+                    hooks::run_hooks(&self.repo, HookContext::each(HookStage::AfterEach, "up", id, &to_apply), meta.hooks.as_ref(), timeout, dry_run).await?;
+                    previous = Some(id.clone());
+                    applied_count += 1;
+                    progress.finish();
+                }
+            },
+        }
+        for repeatable in &repeatables {
+            self.repo.run_repeatable(&repeatable.name, &repeatable.sql, &repeatable.checksum, timeout, dry_run).await?;
+            crate::say!("🔁 Ran repeatable migration {}", repeatable.name);
+            out.repeatables.push(repeatable.name.clone());
+        }
+        hooks::run_hooks(&self.repo, HookContext::run(HookStage::AfterRun, "up", &to_apply), None, timeout, dry_run).await?;
+        if !dry_run {
+            self.clear_checkpoint(path, &to_apply)?;
         }
 
         util::print_migration_results(applied_count, "applied");
+        if !repeatables.is_empty() {
+            crate::summary!("\n🎉 Successfully ran {} repeatable migration(s)!", repeatables.len());
+        }
+        self.record_snapshot(dry_run).await;
+        output::result(&out)?;
+        // The others were committed, but the run must not pass with migrations left behind
+        if !out.failed.is_empty() {
+            let failed: Vec<&str> = out.failed.iter().map(|failed| failed.id.as_str()).collect();
+            anyhow::bail!(
+                "{} migration(s) failed and were rolled back, the others were committed: {}; they are now older than applied migrations, apply them once fixed",
+                failed.len(),
+                failed.join(", ")
+            );
+        }
         Ok(())
     }
 
-    pub async fn down(&self, path: &Path, timeout: Option<u64>, count: usize, remote: bool, yes: bool, dry_run: bool, unlock: bool) -> Result<()> {
+    /// Repeatable migrations that never ran or whose SQL changed since
+    async fn changed_repeatables(&self, migration_dir: &Path) -> Result<Vec<repeatable::LocalRepeatable>> {
+        let local = repeatable::local_repeatables(migration_dir)?;
+        if local.is_empty() {
+            return Ok(local);
+        }
+        let rows = repeatable::repeatable_rows(&local, &self.repo.fetch_repeatables().await?);
+        Ok(local
+            .into_iter()
+            .zip(rows)
+            .filter(|(_, row)| row.status != repeatable::RepeatableStatus::Applied)
+            .map(|(repeatable, _)| repeatable)
+            .collect())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn down(&self, path: &Path, timeout: Option<u64>, count: Option<usize>, to: Option<&str>, tags: &[String], remote: bool, yes: bool, dry_run: bool, unlock: bool) -> Result<()> {
+        self.repo.acquire_run_lock().await?;
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
         let applied = self.repo.fetch_applied_ids().await?;
         if applied.is_empty() {
-            println!("No migrations applied.");
-            return Ok(())
+            crate::summary!("No migrations applied.");
+            return output::result(&RunOut::new("down", dry_run))
         }
         let mut applied_sorted: Vec<String> = applied.into_iter().collect();
-        applied_sorted.sort();
+        util::sort_ids(&mut applied_sorted);
         applied_sorted.reverse();
-        let targets: Vec<String> = applied_sorted.into_iter().take(count).collect();
+        let targets: Vec<String> = match to {
+            Some(to) => {
+                if !applied_sorted.iter().any(|id| id == to) {
+                    anyhow::bail!("target migration {} is not applied", to);
+                }
+                applied_sorted.into_iter().take_while(|id| util::compare_ids(id, to).is_gt()).filter(|id| util::has_any_tag(migration_dir, id, tags)).collect()
+            }
+            None => applied_sorted.into_iter().filter(|id| util::has_any_tag(migration_dir, id, tags)).take(count.unwrap_or(1)).collect(),
+        };
 
-        if targets.is_empty() { println!("Nothing to revert."); return Ok(()) }
+        if targets.is_empty() {
+            crate::summary!("Nothing to revert.");
+            return output::result(&RunOut::new("down", dry_run))
+        }
 
-        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
         let diff_fn = {
             let targets = targets.clone();
-            move || -> Result<()> {
+            move |view: util::DiffView| -> Result<()> {
                 for id in &targets {
                     let down_sql = if remote {
                         String::from("-- remote down sql omitted in preview")
@@ -203,29 +726,1484 @@ impl<R: MigrationRepository> MigrationService<R> {
                         let (_up_sql, down_sql) = util::read_migration_files(migration_dir, id)?;
                         down_sql
                     };
-                    util::display_sql_migration(id, &down_sql, "DOWN")?;
+                    util::display_sql_migration(id, &down_sql, "DOWN", view)?;
                 }
                 Ok(())
             }
         };
         if !util::prompt_for_confirmation_with_diff("❓ Do you want to proceed with reverting these migrations?", yes, diff_fn)? {
-            println!("❌ Revert cancelled.");
-            return Ok(())
+            crate::say!("❌ Revert cancelled.");
+            return output::result(&RunOut::cancelled("down", dry_run))
         }
 
+        hooks::run_hooks(&self.repo, HookContext::run(HookStage::BeforeRun, "down", &targets), None, timeout, dry_run).await?;
+        let mut out = RunOut::new("down", dry_run);
         let mut reverted = 0usize;
-        for id in targets {
+        let mut progress = Progress::new("Reverting", targets.len());
+        for id in &targets {
+            progress.start(id);
             let down_sql = if remote {
-                self.repo.fetch_down_sql(&id).await?.unwrap_or_default()
+                self.repo.fetch_down_sql(id).await?.unwrap_or_default()
             } else {
-                let (_up_sql, down_sql) = util::read_migration_files(migration_dir, &id)?;
+                let (_up_sql, down_sql) = util::read_migration_files(migration_dir, id)?;
                 down_sql
             };
-                            self.repo.revert_migration(&id, &down_sql, timeout, dry_run, unlock).await?;
+            let meta = util::read_migration_meta(migration_dir, id).unwrap_or_default();
+            let exec = meta.exec_options(timeout);
+            hooks::run_hooks(&self.repo, HookContext::each(HookStage::BeforeEach, "down", id, &targets), meta.hooks.as_ref(), timeout, dry_run).await?;
+            events::migration_started(id, "down");
+            let started = std::time::Instant::now();
+            let result = self.with_retries(id, &meta, &exec, || self.repo.revert_migration(id, &down_sql, &exec, dry_run, unlock)).await;
+            telemetry::record(id, "down", &down_sql, started, dry_run, result.as_ref().err());
+            self.log_attempt(id, "down", &down_sql, started, dry_run, result.as_ref().err()).await;
+            result?;
+            crate::verbose!("⏱️  Migration {} took {:.2}s", id, started.elapsed().as_secs_f64());
+            out.push(id, Some(started));
+            hooks::run_hooks(&self.repo, HookContext::each(HookStage::AfterEach, "down", id, &targets), meta.hooks.as_ref(), timeout, dry_run).await?;
             reverted += 1;
+            progress.finish();
         }
+        drop(progress);
+        hooks::run_hooks(&self.repo, HookContext::run(HookStage::AfterRun, "down", &targets), None, timeout, dry_run).await?;
 
         util::print_migration_results(reverted, "reverted");
+        self.record_snapshot(dry_run).await;
+        output::result(&out)
+    }
+
+    pub async fn squash(&self, path: &Path, from: &str, to: &str, comment: Option<&str>, yes: bool) -> Result<()> {
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        let from = util::normalize_migration_id(from);
+        let to = util::normalize_migration_id(to);
+        if util::compare_ids(&from, &to).is_ge() {
+            anyhow::bail!("--from ({}) must be older than --to ({})", from, to);
+        }
+
+        let local = util::get_local_migrations(path)?;
+        for id in [&from, &to] {
+            if !local.contains(id) {
+                anyhow::bail!("migration {} does not exist locally", id);
+            }
+        }
+        let mut range: Vec<String> = local.into_iter().filter(|id| util::id_in_range(id, Some(&from), Some(&to))).collect();
+        util::sort_ids(&mut range);
+        if range.len() < 2 {
+            println!("Nothing to squash.");
+            return Ok(())
+        }
+
+        let applied = self.repo.fetch_applied_ids().await?;
+        let applied_in_range = range.iter().filter(|id| applied.contains(*id)).count();
+        if applied_in_range != 0 && applied_in_range != range.len() {
+            anyhow::bail!("cannot squash a partially applied range ({} of {} migrations applied)", applied_in_range, range.len());
+        }
+
+        let mut ups = Vec::with_capacity(range.len());
+        let mut downs = Vec::with_capacity(range.len());
+        let mut locked = false;
+        for id in &range {
+            let (up_sql, down_sql, meta) = util::read_migration_with_meta(migration_dir, id)?;
+            locked |= meta.is_locked();
+            ups.push((id.clone(), up_sql));
+            downs.push((id.clone(), down_sql));
+        }
+        downs.reverse();
+        let up_sql = util::concat_migration_sql(&ups);
+        let down_sql = util::concat_migration_sql(&downs);
+        // The squashed migration takes over the newest ID so its position in the chain is unchanged
+        let meta = util::MigrationMeta {
+            comment: Some(comment.map(|c| c.to_string()).unwrap_or_else(|| format!("Squashed {} migrations ({}..{})", range.len(), from, to))),
+            locked: if locked { Some(true) } else { None },
+            ..util::MigrationMeta::default()
+        };
+
+        crate::say!("📋 About to squash {} migration(s) into {}:", range.len(), to);
+        for id in &range {
+            crate::say!("  - {}", id);
+        }
+        let diff_fn = |view: util::DiffView| -> Result<()> {
+            util::display_sql_migration(&to, &up_sql, "UP", view)?;
+            util::display_sql_migration(&to, &down_sql, "DOWN", view)
+        };
+        if !util::prompt_for_confirmation_with_diff("❓ Do you want to proceed with squashing these migrations?", yes, diff_fn)? {
+            crate::say!("❌ Squash cancelled.");
+            return Ok(())
+        }
+
+        if applied_in_range == range.len() {
+            let pre = applied.iter().filter(|id| util::compare_ids(id, &from).is_lt()).max_by(|a, b| util::compare_ids(a, b)).cloned();
+            self.repo.squash_migrations(&range, &to, &up_sql, &down_sql, &meta, pre.as_deref(), locked).await?;
+        }
+        let archive = util::archive_migration_directories(migration_dir, &range, &format!("squash={}", to))?;
+        let squashed = util::write_migration_directory(migration_dir, &to, &up_sql, &down_sql, &meta)?;
+
+        crate::say!("📦 Archived {} migration(s) to {}", range.len(), archive.display());
+        crate::summary!("🎉 Squashed into {}", squashed.display());
+        Ok(())
+    }
+
+    pub async fn baseline(&self, path: &Path, id: &str, yes: bool) -> Result<()> {
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        let target_id = util::normalize_migration_id(id);
+        let local = util::get_local_migrations(path)?;
+        if !local.contains(&target_id) {
+            anyhow::bail!("migration {} does not exist locally", target_id);
+        }
+        let applied = self.repo.fetch_applied_ids().await?;
+        let mut targets: Vec<String> = local.into_iter().filter(|id| util::compare_ids(id, &target_id).is_le() && !applied.contains(id)).collect();
+        util::sort_ids(&mut targets);
+
+        if targets.is_empty() {
+            println!("Nothing to baseline.");
+            return Ok(())
+        }
+
+        let mut migrations = Vec::with_capacity(targets.len());
+        for id in targets {
+            let (up_sql, down_sql, meta) = util::read_migration_with_meta(migration_dir, &id)?;
+            let locked = meta.is_locked();
+            migrations.push((id, up_sql, down_sql, meta, locked));
+        }
+
+        crate::say!("📋 About to mark {} migration(s) as applied without executing them:", migrations.len());
+        for (id, ..) in &migrations {
+            crate::say!("  - {}", id);
+        }
+        let diff_fn = |view: util::DiffView| -> Result<()> {
+            for (id, up_sql, ..) in &migrations {
+                util::display_sql_migration(id, up_sql, "UP", view)?;
+            }
+            Ok(())
+        };
+        if !util::prompt_for_confirmation_with_diff("❓ Do you want to proceed with the baseline?", yes, diff_fn)? {
+            crate::say!("❌ Baseline cancelled.");
+            return Ok(())
+        }
+
+        self.repo.baseline_migrations(&migrations).await?;
+        util::print_migration_results(migrations.len(), "baselined");
+        Ok(())
+    }
+
+    pub async fn log_list(&self, migration_id: Option<&str>, operation: Option<&str>, since: Option<&str>, until: Option<&str>, limit: Option<usize>, output: OutputFormat) -> Result<()> {
+        let filter = log::LogFilter {
+            migration_id: migration_id.map(util::normalize_migration_id),
+            operation: operation.map(|s| s.to_string()),
+            since: since.map(log::parse_time_bound).transpose()?,
+            until: until.map(log::parse_time_bound).transpose()?,
+            limit,
+        };
+        let entries = self.repo.fetch_log(&filter).await?;
+        match output {
+            OutputFormat::Human => {
+                if entries.is_empty() {
+                    println!("No log entries found.");
+                    return Ok(())
+                }
+                log::render_log_table(&entries);
+            }
+            OutputFormat::Json => {
+                let rows: Vec<_> = entries.iter().map(|e| e.to_out(false)).collect();
+                println!("{}", serde_json::to_string_pretty(&rows)?);
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn log_show(&self, id: &str, output: OutputFormat) -> Result<()> {
+        let entry = self.repo.fetch_log_entry(id).await?.ok_or_else(|| anyhow::anyhow!("log entry {} not found", id))?;
+        match output {
+            OutputFormat::Human => crate::core::pager::page(&log::format_log_entry(&entry))?,
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&entry.to_out(true))?),
+        }
+        Ok(())
+    }
+
+    /// Dump the log, oldest first, with the comment, qop version and provenance of each migration,
+    /// so audits need no access to the database
+    pub async fn log_export(&self, format: log::ExportFormat, since: Option<&str>, until: Option<&str>, out: Option<&Path>) -> Result<()> {
+        let filter = log::LogFilter {
+            since: since.map(log::parse_time_bound).transpose()?,
+            until: until.map(log::parse_time_bound).transpose()?,
+            ..Default::default()
+        };
+        let mut entries = self.repo.fetch_log(&filter).await?;
+        entries.reverse();
+        let versions = self.repo.fetch_versions().await?;
+        let provenance = self.repo.fetch_provenance().await?;
+        let rows: Vec<log::AuditEntryOut> = entries
+            .iter()
+            .map(|entry| {
+                let record = versions.get(&entry.migration_id);
+                let provenance = provenance.get(&entry.migration_id);
+                log::AuditEntryOut {
+                    id: &entry.id,
+                    migration_id: &entry.migration_id,
+                    operation: &entry.operation,
+                    executed_at: Utc.from_utc_datetime(&entry.executed_at),
+                    duration_ms: entry.duration_ms,
+                    applied_by: entry.applied_by.as_deref(),
+                    hostname: entry.hostname.as_deref(),
+                    status: entry.status(),
+                    dry_run: entry.dry_run.unwrap_or(false),
+                    error: entry.error.as_deref(),
+                    version: entry.version.as_deref().or(record.map(|(version, _)| version.as_str())),
+                    invocation: entry.invocation.as_deref(),
+                    comment: record.and_then(|(_, comment)| comment.as_deref()),
+                    author: provenance.and_then(|p| p.author.as_deref()),
+                    ticket: provenance.and_then(|p| p.ticket.as_deref()),
+                    sql_command: &entry.sql_command,
+                }
+            })
+            .collect();
+        let rendered = match format {
+            | log::ExportFormat::Csv => log::render_audit_csv(&rows),
+            | log::ExportFormat::Json => format!("{}\n", serde_json::to_string_pretty(&rows)?),
+        };
+        match out {
+            | Some(out) => {
+                std::fs::write(out, rendered).with_context(|| format!("Failed to write {}", out.display()))?;
+                crate::say!("📝 Exported {} log entries to {}", rows.len(), out.display());
+            },
+            | None => print!("{}", rendered),
+        }
+        Ok(())
+    }
+
+    pub async fn rename(&self, path: &Path, id: &str, new_id: &str, comment: Option<&str>, yes: bool) -> Result<()> {
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        let id = util::normalize_migration_id(id);
+        let new_id = util::normalize_migration_id(new_id);
+        if id == new_id && comment.is_none() {
+            println!("Nothing to rename.");
+            return Ok(())
+        }
+
+        let local = util::get_local_migrations(path)?;
+        if !local.contains(&id) {
+            anyhow::bail!("migration {} does not exist locally", id);
+        }
+        let applied = self.repo.fetch_applied_ids().await?;
+        if id != new_id && (local.contains(&new_id) || applied.contains(&new_id)) {
+            anyhow::bail!("migration {} already exists", new_id);
+        }
+
+        let (low, high) = if util::compare_ids(&id, &new_id).is_lt() { (&id, &new_id) } else { (&new_id, &id) };
+        if local.iter().chain(applied.iter()).any(|other| util::compare_ids(other, low).is_gt() && util::compare_ids(other, high).is_lt()) {
+            crate::say!("⚠️  Renaming {} to {} changes its position in the migration chain.", id, new_id);
+        }
+
+        let is_applied = applied.contains(&id);
+        let diff_fn = |_: util::DiffView| -> Result<()> {
+            println!("  id={} -> id={}", id, new_id);
+            if is_applied {
+                println!("  tracking record, pre references and log entries will be updated");
+            }
+            if let Some(comment) = comment {
+                println!("  comment -> {}", comment);
+            }
+            Ok(())
+        };
+        if !util::prompt_for_confirmation_with_diff(&format!("❓ Do you want to rename migration '{}' to '{}'?", id, new_id), yes, diff_fn)? {
+            crate::say!("❌ Rename cancelled.");
+            return Ok(())
+        }
+
+        if is_applied && id != new_id {
+            self.repo.rename_migration(&id, &new_id).await?;
+        }
+        if id != new_id {
+            util::rename_migration_directory(migration_dir, &id, &new_id)?;
+        }
+        if let Some(comment) = comment {
+            let mut meta = util::read_migration_meta(migration_dir, &new_id)?;
+            meta.comment = Some(comment.to_string());
+            util::write_migration_meta(migration_dir, &new_id, &meta)?;
+        }
+
+        crate::summary!("✅ Renamed migration {} to {}", id, new_id);
+        Ok(())
+    }
+
+    pub async fn set_locked(&self, path: &Path, id: &str, locked: bool) -> Result<()> {
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        let target_id = util::normalize_migration_id(id);
+        let is_local = util::get_local_migrations(path)?.contains(&target_id);
+        if is_local {
+            let mut meta = util::read_migration_meta(migration_dir, &target_id)?;
+            meta.locked = if locked { Some(true) } else { None };
+            util::write_migration_meta(migration_dir, &target_id, &meta)?;
+        }
+        let is_applied = self.repo.set_migration_locked(&target_id, locked).await?;
+        if !is_local && !is_applied {
+            anyhow::bail!("migration {} does not exist locally or remotely", target_id);
+        }
+
+        let state = if locked { "🔒 Locked" } else { "🔓 Unlocked" };
+        let scope = match (is_local, is_applied) {
+            (true, true) => "meta.toml and tracking table",
+            (true, false) => "meta.toml",
+            _ => "tracking table",
+        };
+        println!("{} migration {} ({})", state, target_id, scope);
+        Ok(())
+    }
+
+    pub async fn remove(&self, path: &Path, id: &str, force: bool, also_remote: bool, yes: bool) -> Result<()> {
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        let target_id = util::normalize_migration_id(id);
+        let is_local = util::get_local_migrations(path)?.contains(&target_id);
+        let is_applied = self.repo.fetch_applied_ids().await?.contains(&target_id);
+        if !is_local && !is_applied {
+            anyhow::bail!("migration {} does not exist locally or remotely", target_id);
+        }
+        if is_applied && !(force && also_remote) {
+            anyhow::bail!("migration {} is applied; revert it first or pass --force --also-remote to drop its tracking record without running the down SQL", target_id);
+        }
+
+        let diff_fn = |_: util::DiffView| -> Result<()> {
+            if is_local {
+                println!("  delete {}", migration_dir.join(format!("id={}", target_id)).display());
+            }
+            if is_applied {
+                println!("  delete tracking record and log entries of {} (down SQL is NOT executed)", target_id);
+            }
+            Ok(())
+        };
+        if !util::prompt_for_confirmation_with_diff(&format!("❓ Do you want to remove migration '{}'?", target_id), yes, diff_fn)? {
+            crate::say!("❌ Removal cancelled.");
+            return Ok(())
+        }
+
+        if is_applied {
+            self.repo.forget_migration(&target_id).await?;
+            crate::say!("🗑️  Removed tracking record and log entries of {}", target_id);
+        }
+        if is_local {
+            let removed = util::remove_migration_directory(migration_dir, &target_id)?;
+            crate::say!("🗑️  Removed {}", removed.display());
+        }
+        Ok(())
+    }
+
+    pub async fn prune(&self, path: &Path, yes: bool) -> Result<()> {
+        let local = util::get_local_migrations(path)?;
+        let mut ghosts: Vec<String> = self.repo.fetch_applied_ids().await?.into_iter().filter(|id| !local.contains(id)).collect();
+        util::sort_ids(&mut ghosts);
+
+        if ghosts.is_empty() {
+            println!("No remote-only migrations found.");
+            return Ok(())
+        }
+
+        crate::say!("📋 Found {} applied migration(s) without a local directory:", ghosts.len());
+        for id in &ghosts {
+            crate::say!("  - {}", id);
+        }
+        if !util::prompt_for_confirmation_with_diff("❓ Do you want to delete these tracking records (down SQL is NOT executed)?", yes, |_| Ok(()))? {
+            crate::say!("❌ Prune cancelled.");
+            return Ok(())
+        }
+
+        self.repo.prune_migrations(&ghosts).await?;
+        util::print_migration_results(ghosts.len(), "pruned");
+        Ok(())
+    }
+
+    pub async fn repair(&self, path: &Path, id: &str, up: bool, down: bool, yes: bool) -> Result<()> {
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        let target_id = util::normalize_migration_id(id);
+        // Without an explicit selection both directions are repaired
+        let (up, down) = if !up && !down { (true, true) } else { (up, down) };
+
+        let (stored_up, stored_down) = self.repo.fetch_all_migrations().await?
+            .into_iter()
+            .find(|(id, ..)| *id == target_id)
+            .map(|(_, up_sql, down_sql, _)| (up_sql, down_sql))
+            .ok_or_else(|| anyhow::anyhow!("migration {} is not applied", target_id))?;
+        let (local_up, local_down) = util::read_migration_files(migration_dir, &target_id)?;
+
+        let up_sql = if up && !util::sql_matches(&stored_up, &local_up) { Some(local_up) } else { None };
+        let down_sql = if down && !util::sql_matches(&stored_down, &local_down) { Some(local_down) } else { None };
+        if up_sql.is_none() && down_sql.is_none() {
+            crate::summary!("✅ Nothing to repair, the selected stored SQL of {} matches the local files.", target_id);
+            return Ok(())
+        }
+
+        let diff_fn = |view: util::DiffView| -> Result<()> {
+            if let Some(sql) = &up_sql {
+                util::display_sql_migration(&target_id, sql, "UP", view)?;
+            }
+            if let Some(sql) = &down_sql {
+                util::display_sql_migration(&target_id, sql, "DOWN", view)?;
+            }
+            Ok(())
+        };
+        if !util::prompt_for_confirmation_with_diff(&format!("❓ Do you want to overwrite the stored SQL of migration '{}'?", target_id), yes, diff_fn)? {
+            crate::say!("❌ Repair cancelled.");
+            return Ok(())
+        }
+
+        self.repo.repair_migration(&target_id, up_sql.as_deref(), down_sql.as_deref()).await?;
+        let parts: Vec<&str> = [("up", up_sql.is_some()), ("down", down_sql.is_some())].into_iter().filter(|(_, b)| *b).map(|(p, _)| p).collect();
+        crate::say!("🔧 Repaired stored {} SQL of {}", parts.join(" and "), target_id);
+        Ok(())
+    }
+
+    /// Rewrite the configured snapshot after the schema changed. Failures only warn, the
+    /// migrations are already committed at this point.
+    async fn record_snapshot(&self, dry_run: bool) {
+        let Some(file) = self.repo.snapshot_file() else { return };
+        if dry_run {
+            return;
+        }
+        let result = async {
+            let schema = self.repo.dump_schema().await?;
+            let last = self.repo.fetch_last_id().await?;
+            snapshot::write_snapshot(&file, &snapshot::render_snapshot(last.as_deref(), &schema))
+        }
+        .await;
+        match result {
+            Ok(()) => crate::say!("📸 Recorded schema snapshot to {}", file.display()),
+            Err(e) => crate::esay!("⚠️  Failed to record schema snapshot to {}: {:#}", file.display(), e),
+        }
+    }
+
+    pub async fn drift(&self, snapshot_file: Option<&Path>, output: OutputFormat) -> Result<()> {
+        let file = match snapshot_file {
+            Some(file) => file.to_path_buf(),
+            None => self.repo.snapshot_file().ok_or_else(|| anyhow::anyhow!("no snapshot configured; set `snapshot` in the subsystem config or pass --snapshot"))?,
+        };
+        let (recorded_at, recorded) = snapshot::read_snapshot(&file)?;
+        let last = self.repo.fetch_last_id().await?;
+        let live = self.repo.dump_schema().await?;
+        let entries = snapshot::schema_drift(&recorded, &live);
+        match output {
+            OutputFormat::Human => {
+                if recorded_at != last {
+                    crate::say!(
+                        "⚠️  Snapshot was recorded at migration {} but the database is at {}; differences may stem from migrations.",
+                        recorded_at.as_deref().unwrap_or("none"),
+                        last.as_deref().unwrap_or("none")
+                    );
+                }
+                if entries.is_empty() {
+                    crate::summary!("✅ No drift: the live schema matches {}.", file.display());
+                } else {
+                    snapshot::render_drift_table(&entries);
+                    crate::say!("⚠️  Found {} object(s) changed outside of migrations.", entries.len());
+                }
+            }
+            OutputFormat::Json => {
+                #[derive(serde::Serialize)]
+                struct DriftOut<'a> {
+                    snapshot: String,
+                    recorded_at: Option<String>,
+                    last_applied: Option<String>,
+                    drift: &'a [snapshot::DriftEntry],
+                }
+                let out = DriftOut { snapshot: file.display().to_string(), recorded_at, last_applied: last, drift: &entries };
+                println!("{}", serde_json::to_string_pretty(&out)?);
+            }
+        }
+        if !entries.is_empty() {
+            anyhow::bail!("schema drift detected");
+        }
+        Ok(())
+    }
+
+    pub async fn snapshot(&self, out: Option<&Path>) -> Result<()> {
+        let schema = self.repo.dump_schema().await?;
+        let last = self.repo.fetch_last_id().await?;
+        let content = snapshot::render_snapshot(last.as_deref(), &schema);
+        match out {
+            Some(out) => {
+                snapshot::write_snapshot(out, &content)?;
+                crate::say!("📸 Wrote schema snapshot at migration {} to {}", last.as_deref().unwrap_or("none"), out.display());
+            }
+            None => print!("{}", content),
+        }
+        Ok(())
+    }
+
+    /// Apply all local migrations and revert them again; meant to run against an ephemeral database
+    pub async fn test(&self, path: &Path, timeout: Option<u64>) -> Result<()> {
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        let mut ids: Vec<String> = util::get_local_migrations(path)?.into_iter().collect();
+        util::sort_ids(&mut ids);
+        if ids.is_empty() {
+            println!("No migrations found.");
+            return Ok(())
+        }
+
+        self.repo.init_store().await?;
+        let before = self.repo.dump_schema().await?;
+        crate::say!("🧪 Testing {} migration(s) against an ephemeral database", ids.len());
+
+        let mut failure: Option<String> = None;
+        let mut applied: Vec<String> = Vec::with_capacity(ids.len());
+        for id in &ids {
+            let (up_sql, down_sql, meta) = util::read_migration_with_meta(migration_dir, id)?;
+            let pre = self.repo.fetch_last_id().await?;
+            match self.repo.apply_migration(id, &up_sql, &down_sql, &meta, pre.as_deref(), &meta.exec_options(timeout), false, false).await {
+                Ok(()) => {
+                    crate::say!("  ✅ up   {}", id);
+                    applied.push(id.clone());
+                }
+                Err(e) => {
+                    crate::say!("  ❌ up   {}: {:#}", id, e);
+                    failure = Some(format!("up of {} failed", id));
+                    break;
+                }
+            }
+        }
+
+        for id in applied.iter().rev() {
+            let (_up_sql, down_sql, meta) = util::read_migration_with_meta(migration_dir, id)?;
+            match self.repo.revert_migration(id, &down_sql, &meta.exec_options(timeout), false, true).await {
+                Ok(()) => crate::say!("  ✅ down {}", id),
+                Err(e) => {
+                    crate::say!("  ❌ down {}: {:#}", id, e);
+                    failure.get_or_insert_with(|| format!("down of {} failed", id));
+                    break;
+                }
+            }
+        }
+
+        // Down migrations that run cleanly can still leave objects behind or miss some
+        if failure.is_none() {
+            let leftovers = snapshot::schema_drift(&before, &self.repo.dump_schema().await?);
+            if !leftovers.is_empty() {
+                snapshot::render_drift_table(&leftovers);
+                failure = Some(format!("the schema after reverting all migrations differs from the one before in {} object(s)", leftovers.len()));
+            }
+        }
+
+        match failure {
+            Some(failure) => anyhow::bail!("migration test failed: {}", failure),
+            None => {
+                crate::summary!("\n🎉 All {} migration(s) applied and reverted successfully!", ids.len());
+                Ok(())
+            }
+        }
+    }
+
+    /// Replay all local migrations into this (ephemeral) database, load the declarative schema into
+    /// `desired` and write the difference between both as a new migration
+    pub async fn generate(&self, path: &Path, desired: &R, schema_file: &Path, comment: Option<&str>, yes: bool) -> Result<()> {
+        let schema = std::fs::read_to_string(schema_file).with_context(|| format!("Failed to read declarative schema: {}", schema_file.display()))?;
+        let current = self.replay_migrations(path, None).await?;
+        desired.execute_sql(&schema, None, false).await.with_context(|| format!("Failed to load {}", schema_file.display()))?;
+        let change = declarative::diff_schemas(&current, &desired.dump_schema().await?, self.repo.dialect());
+        if change.is_empty() {
+            crate::summary!("✅ The migrations already match {}, nothing to generate.", schema_file.display());
+            return Ok(())
+        }
+        self.write_generated(path, change, comment.unwrap_or("Generated from the declarative schema"), yes).await?;
+        Ok(())
+    }
+
+    /// Replay the migrations applied to `live` into this (ephemeral) database and write the
+    /// difference to the live schema, i.e. changes made outside of migrations, as a new migration
+    pub async fn generate_from_db(&self, path: &Path, live: &R, comment: Option<&str>, yes: bool) -> Result<()> {
+        let applied = live.fetch_applied_ids().await?;
+        let local = util::get_local_migrations(path)?;
+        if let Some(missing) = applied.iter().find(|id| !local.contains(*id)) {
+            anyhow::bail!("applied migration {} has no local files, run `history sync` first", missing);
+        }
+        let current = self.replay_migrations(path, Some(&applied)).await?;
+        let change = declarative::diff_schemas(&current, &live.dump_schema().await?, self.repo.dialect());
+        if change.is_empty() {
+            crate::summary!("✅ The live schema matches the applied migrations, nothing to generate.");
+            return Ok(())
+        }
+        if let Some(id) = self.write_generated(path, change, comment.unwrap_or("Generated from changes made outside of migrations"), yes).await? {
+            crate::say!("💡 The changes already exist in the database, run `baseline {}` to mark the migration as applied.", id);
+        }
+        Ok(())
+    }
+
+    /// Apply all local migrations, or only the given ones, in dependency order and return the resulting schema
+    async fn replay_migrations(&self, path: &Path, only: Option<&std::collections::HashSet<String>>) -> Result<String> {
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        let ids: Vec<String> = util::get_local_migrations(path)?.into_iter().filter(|id| only.is_none_or(|only| only.contains(id))).collect();
+        let ids = util::order_by_dependencies(migration_dir, &ids, &std::collections::HashSet::new())?;
+        self.repo.init_store().await?;
+        crate::say!("🧪 Replaying {} migration(s) in an ephemeral database", ids.len());
+        for id in &ids {
+            let (up_sql, down_sql, meta) = util::read_migration_with_meta(migration_dir, id)?;
+            let pre = self.repo.fetch_last_id().await?;
+            self.repo
+                .apply_migration(id, &up_sql, &down_sql, &meta, pre.as_deref(), &meta.exec_options(None), false, false)
+                .await
+                .with_context(|| format!("Failed to replay migration {}", id))?;
+        }
+        self.repo.dump_schema().await
+    }
+
+    /// Write a generated migration after confirmation and return its id
+    async fn write_generated(&self, path: &Path, change: declarative::SchemaChange, comment: &str, yes: bool) -> Result<Option<String>> {
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        let header = "-- generated by qop generate, review before relying on it";
+        let up_sql = std::iter::once(header.to_string()).chain(change.up.iter().cloned()).collect::<Vec<_>>().join("\n") + "\n";
+        let down_sql = std::iter::once(header.to_string()).chain(change.down.iter().cloned()).collect::<Vec<_>>().join("\n") + "\n";
+
+        let diff_fn = |view: util::DiffView| -> Result<()> {
+            util::display_sql_migration("generated", &up_sql, "UP", view)?;
+            util::display_sql_migration("generated", &down_sql, "DOWN", view)
+        };
+        if !util::prompt_for_confirmation_with_diff(&format!("❓ Do you want to create the generated migration ({} statement(s))?", change.up.len()), yes, diff_fn)? {
+            crate::say!("❌ Generate cancelled.");
+            return Ok(None)
+        }
+
+        let id = self.next_id(path).await?;
+        let meta = util::MigrationMeta { comment: Some(comment.to_string()), ..util::MigrationMeta::default() };
+        let created = util::write_migration_directory(migration_dir, &id, &up_sql, &down_sql, &meta)?;
+        println!("Created generated migration: {}", created.display());
+        crate::say!("💡 Review the generated SQL, statements marked TODO need manual changes.");
+        Ok(Some(id))
+    }
+
+    pub async fn lint(&self, path: &Path, all: bool, strict: bool, large_table_rows: i64, output: OutputFormat) -> Result<()> {
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        let local = util::get_local_migrations(path)?;
+        let mut ids: Vec<String> = if all {
+            local.into_iter().collect()
+        } else {
+            let applied = self.repo.fetch_applied_ids().await?;
+            local.into_iter().filter(|id| !applied.contains(id)).collect()
+        };
+        util::sort_ids(&mut ids);
+
+        let mut issues = Vec::new();
+        for id in &ids {
+            let (up_sql, down_sql) = util::read_migration_files(migration_dir, id)?;
+            let up_ops = migration_diff::parse_migration_operations(&up_sql);
+            let mut large_tables = std::collections::HashSet::new();
+            for table in lint::non_concurrent_index_tables(&up_ops) {
+                if self.repo.estimate_rows(&table).await?.is_some_and(|n| n >= large_table_rows) {
+                    large_tables.insert(table);
+                }
+            }
+            let transaction = util::read_migration_meta(migration_dir, id)?.transaction.unwrap_or(true);
+            issues.extend(lint::lint_migration(id, &up_sql, &down_sql, &large_tables, transaction));
+        }
+
+        let errors = issues.iter().filter(|i| i.level == lint::LintLevel::Error).count();
+        let warnings = issues.len() - errors;
+        match output {
+            OutputFormat::Human => {
+                if ids.is_empty() {
+                    println!("No migrations to lint.");
+                    return Ok(())
+                }
+                if issues.is_empty() {
+                    crate::summary!("✅ {} migration(s) passed linting.", ids.len());
+                } else {
+                    lint::render_lint_table(&issues);
+                    println!("{} error(s), {} warning(s) in {} migration(s).", errors, warnings, ids.len());
+                }
+            }
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&issues)?),
+        }
+
+        if errors > 0 || (strict && warnings > 0) {
+            anyhow::bail!("linting failed with {} error(s) and {} warning(s)", errors, warnings);
+        }
+        Ok(())
+    }
+
+    /// The pending migrations `up` would apply, with the operations they perform and their risks
+    pub async fn plan(&self, path: &Path, count: Option<usize>, tags: &[String], output: OutputFormat) -> Result<()> {
+        let local = util::get_local_migrations(path)?;
+        let applied = self.repo.fetch_applied_ids().await?;
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+
+        let mut pending: Vec<String> = local.difference(&applied).filter(|id| util::has_any_tag(migration_dir, id, tags)).cloned().collect();
+        util::sort_ids(&mut pending);
+        pending.retain(|id| !util::is_skipped(migration_dir, id, self.repo.profile()));
+        let mut to_apply = util::order_by_dependencies(migration_dir, &pending, &applied)?;
+        if let Some(c) = count { to_apply.truncate(c); }
+
+        let mut planned = Vec::with_capacity(to_apply.len());
+        for id in &to_apply {
+            let (up_sql, _down_sql, meta) = util::read_migration_with_meta(migration_dir, id)?;
+            planned.push(plan::PlannedMigration::new(id, &up_sql, &meta));
+        }
+        match output {
+            OutputFormat::Human => {
+                if planned.is_empty() {
+                    println!("All migrations are up to date.");
+                    return Ok(())
+                }
+                plan::render_plan_table(&planned);
+                let high = planned.iter().filter(|m| m.level() == Some(plan::RiskLevel::High)).count();
+                let operations: usize = planned.iter().map(|m| m.operations.len()).sum();
+                crate::summary!("📋 {} pending migration(s) with {} operation(s), {} of them high risk.", planned.len(), operations, high);
+            }
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&planned)?),
+        }
+        Ok(())
+    }
+
+    pub async fn tui(&self, path: &Path, timeout: Option<u64>) -> Result<()> {
+        tui::run(self, path, timeout).await
+    }
+
+    pub async fn graph(&self, format: graph::GraphFormat) -> Result<()> {
+        let chain: Vec<(String, Option<String>)> = self.repo.fetch_chain().await?.into_iter().map(|(id, pre, _)| (id, pre)).collect();
+        let graph = graph::ChainGraph::new(&chain);
+        match format {
+            graph::GraphFormat::Ascii => {
+                print!("{}", graph.render_ascii());
+                graph.print_summary();
+            }
+            graph::GraphFormat::Dot => print!("{}", graph.render_dot()),
+        }
+        Ok(())
+    }
+
+    pub async fn history_verify(&self, fix: bool, yes: bool, output: OutputFormat) -> Result<()> {
+        let chain = self.repo.fetch_chain().await?;
+        let issues = graph::verify_chain(&chain);
+        match output {
+            OutputFormat::Human if issues.is_empty() => crate::summary!("✅ History chain of {} migration(s) is consistent.", chain.len()),
+            OutputFormat::Human => {
+                graph::render_issue_table(&issues);
+                crate::say!("⚠️  Found {} issue(s) in the history chain of {} migration(s).", issues.len(), chain.len());
+            }
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&issues)?),
+        }
+        if issues.is_empty() {
+            return Ok(())
+        }
+        if !fix {
+            anyhow::bail!("history chain is inconsistent; run with --fix to rebuild it from the application order");
+        }
+
+        let links: Vec<(String, Option<String>)> = graph::rebuild_chain(&chain)
+            .into_iter()
+            .filter(|(id, pre)| chain.iter().any(|(cid, cpre, _)| cid == id && cpre != pre))
+            .collect();
+        let diff_fn = |_: util::DiffView| -> Result<()> {
+            for (id, pre) in &links {
+                println!("  {} -> pre {}", id, pre.as_deref().unwrap_or("-"));
+            }
+            Ok(())
+        };
+        if !util::prompt_for_confirmation_with_diff(&format!("❓ Do you want to relink {} migration(s) in application order?", links.len()), yes, diff_fn)? {
+            crate::say!("❌ Fix cancelled.");
+            return Ok(())
+        }
+        self.repo.relink_migrations(&links).await?;
+        crate::say!("🔗 Relinked {} migration(s).", links.len());
+        Ok(())
+    }
+
+    pub async fn export(&self, path: &Path, out: &Path) -> Result<()> {
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        let mut ids: Vec<String> = util::get_local_migrations(path)?.into_iter().collect();
+        util::sort_ids(&mut ids);
+        if ids.is_empty() {
+            anyhow::bail!("no local migrations to export");
+        }
+        let applied = self.repo.fetch_applied_ids().await?;
+        let last = self.repo.fetch_last_id().await?;
+
+        let manifest = bundle::export_bundle(migration_dir, &ids, &applied, last.as_deref(), out)?;
+        let applied_count = manifest.migrations.iter().filter(|m| m.applied).count();
+        crate::say!("📦 Exported {} migration(s) ({} applied, {} pending) to {}", manifest.migrations.len(), applied_count, manifest.migrations.len() - applied_count, out.display());
+        Ok(())
+    }
+
+    pub async fn import(&self, path: &Path, bundle_path: &Path, force: bool, yes: bool) -> Result<()> {
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        let (manifest, entries) = bundle::read_bundle(bundle_path)?;
+        crate::say!("📦 Bundle {} ({} migration(s), exported at {} by qop {}, last applied at source: {})",
+            bundle_path.display(),
+            manifest.migrations.len(),
+            manifest.created_at.to_rfc3339(),
+            manifest.qop_version,
+            manifest.last_applied.as_deref().unwrap_or("none"));
+
+        let diff_fn = |_: util::DiffView| -> Result<()> {
+            for migration in &manifest.migrations {
+                crate::say!("  - {} ({})", migration.id, if migration.applied { "applied at source" } else { "pending at source" });
+            }
+            Ok(())
+        };
+        if !util::prompt_for_confirmation_with_diff(&format!("❓ Do you want to import {} migration(s) into {}?", manifest.migrations.len(), migration_dir.display()), yes, diff_fn)? {
+            crate::say!("❌ Import cancelled.");
+            return Ok(())
+        }
+
+        let report = bundle::import_bundle(migration_dir, &manifest, &entries, force)?;
+        crate::summary!("✅ Imported bundle: {} created, {} overwritten, {} unchanged", report.created.len(), report.overwritten.len(), report.unchanged.len());
+
+        let applied = self.repo.fetch_applied_ids().await?;
+        let bundled: std::collections::HashSet<&str> = manifest.migrations.iter().map(|m| m.id.as_str()).collect();
+        let pending = manifest.migrations.iter().filter(|m| !applied.contains(&m.id)).count();
+        if pending > 0 {
+            crate::say!("📋 {} migration(s) from the bundle are not applied here yet; run `up` to apply them.", pending);
+        }
+        let mut foreign: Vec<&String> = applied.iter().filter(|id| !bundled.contains(id.as_str())).collect();
+        foreign.sort_by(|a, b| util::compare_ids(a, b));
+        if !foreign.is_empty() {
+            crate::say!("⚠️  {} applied migration(s) are not part of the bundle:", foreign.len());
+            for id in foreign {
+                crate::say!("  - {}", id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert a Liquibase changelog into migrations and record the changesets DATABASECHANGELOG lists as applied
+    pub async fn import_liquibase(&self, path: &Path, changelog: &Path, history_table: Option<&str>, history: bool, yes: bool) -> Result<()> {
+        let rows = match history {
+            | true => self.foreign_history(history_table.unwrap_or(liquibase::HISTORY_TABLE), &liquibase::HISTORY_COLUMNS).await?,
+            | false => Vec::new(),
+        };
+        let conversion = liquibase::convert(changelog, self.repo.dialect(), &rows)?;
+        self.import_conversion(path, "Liquibase", liquibase::TOOL, conversion, yes).await
+    }
+
+    /// Convert an sqlx migrations directory into migrations and record the versions _sqlx_migrations lists as applied
+    pub async fn import_sqlx(&self, path: &Path, dir: &Path, history_table: Option<&str>, history: bool, yes: bool) -> Result<()> {
+        let rows = match history {
+            | true => self.foreign_history(history_table.unwrap_or(sqlx_migrate::HISTORY_TABLE), &sqlx_migrate::HISTORY_COLUMNS).await?,
+            | false => Vec::new(),
+        };
+        let conversion = sqlx_migrate::convert(dir, self.repo.dialect(), &rows)?;
+        self.import_conversion(path, "sqlx", sqlx_migrate::TOOL, conversion, yes).await
+    }
+
+    /// Convert Alembic offline SQL into migrations and record the revisions alembic_version descends from as applied
+    pub async fn import_alembic(&self, path: &Path, source: &Path, history_table: Option<&str>, history: bool, yes: bool) -> Result<()> {
+        let table = history_table.unwrap_or(alembic::HISTORY_TABLE);
+        let rows = match history {
+            | true => self.foreign_history(table, &alembic::HISTORY_COLUMNS).await?,
+            | false => Vec::new(),
+        };
+        let conversion = alembic::convert(source, self.repo.dialect(), &rows, table)?;
+        self.import_conversion(path, "Alembic", alembic::TOOL, conversion, yes).await
+    }
+
+    /// Convert a dbmate migrations directory into migrations and record those in schema_migrations as applied
+    pub async fn import_dbmate(&self, path: &Path, dir: &Path, history_table: Option<&str>, history: bool, yes: bool) -> Result<()> {
+        let rows = match history {
+            | true => self.foreign_history(history_table.unwrap_or(dbmate::HISTORY_TABLE), &dbmate::HISTORY_COLUMNS).await?,
+            | false => Vec::new(),
+        };
+        let conversion = dbmate::convert(dir, self.repo.dialect(), &rows)?;
+        self.import_conversion(path, "dbmate", dbmate::TOOL, conversion, yes).await
+    }
+
+    /// Rows of another tool's tracking table, none if it does not exist
+    async fn foreign_history(&self, table: &str, columns: &[&str]) -> Result<Vec<Vec<Option<String>>>> {
+        import::validate_table(table)?;
+        match self.repo.fetch_foreign_rows(table, columns).await? {
+            | Some(rows) => Ok(rows),
+            | None => {
+                crate::say!("📋 There is no {} table, so no migration is recorded as applied.", table);
+                Ok(Vec::new())
+            },
+        }
+    }
+
+    /// Write the migrations of a conversion that an earlier import did not create, and record those the
+    /// other tool applied as applied here, without executing them
+    async fn import_conversion(&self, path: &Path, name: &str, tool: &str, conversion: import::Conversion, yes: bool) -> Result<()> {
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        let local = util::get_local_migrations(path)?;
+        let imported = import::imported(migration_dir, &local);
+        let applied = self.repo.fetch_applied_ids().await?;
+        let (existing, new): (Vec<_>, Vec<_>) = conversion.migrations.iter().partition(|migration| imported.contains_key(&import::source(tool, &migration.key)));
+        // Imported earlier, but applied by the other tool only since then
+        let mut record: Vec<String> = existing
+            .iter()
+            .filter(|migration| migration.applied)
+            .map(|migration| imported[&import::source(tool, &migration.key)].clone())
+            .filter(|id| !applied.contains(id))
+            .collect();
+
+        crate::say!("📥 {}: {} migration(s) to import, {} imported before, {} not convertible", name, new.len(), existing.len(), conversion.skipped.len());
+        for migration in &new {
+            crate::say!("  - {}{}", migration.key, if migration.applied { " (applied)" } else { "" });
+        }
+        if !conversion.skipped.is_empty() {
+            crate::say!("⚠️  {} migration(s) could not be converted:", conversion.skipped.len());
+            for (key, reason) in &conversion.skipped {
+                crate::say!("  - {}: {}", key, reason);
+            }
+        }
+        if new.is_empty() && record.is_empty() {
+            crate::say!("Nothing to import.");
+            return Ok(())
+        }
+
+        let diff_fn = |view: util::DiffView| -> Result<()> {
+            for migration in &new {
+                util::display_sql_migration(&migration.key, &migration.up_sql, "UP", view)?;
+            }
+            Ok(())
+        };
+        let prompt = format!("❓ Do you want to import {} migration(s) into {} and record {} as applied?", new.len(), migration_dir.display(), record.len() + new.iter().filter(|m| m.applied).count());
+        if !util::prompt_for_confirmation_with_diff(&prompt, yes, diff_fn)? {
+            crate::say!("❌ Import cancelled.");
+            return Ok(())
+        }
+
+        let ids = self.repo.ids().next_ids(&self.taken_ids(path).await?, new.len())?;
+        for (id, migration) in ids.iter().zip(&new) {
+            import::write(migration_dir, id, migration)?;
+            if migration.applied {
+                record.push(id.clone());
+            }
+        }
+        util::sort_ids(&mut record);
+        let mut migrations = Vec::with_capacity(record.len());
+        for id in record {
+            let (up_sql, down_sql, meta) = util::read_migration_with_meta(migration_dir, &id)?;
+            let locked = meta.is_locked();
+            migrations.push((id, up_sql, down_sql, meta, locked));
+        }
+        self.repo.baseline_migrations(&migrations).await?;
+        crate::summary!("✅ Imported {} migration(s) from {}, recorded {} as applied", new.len(), name, migrations.len());
+
+        let keys: std::collections::HashMap<&str, &str> = new.iter().map(|m| m.key.as_str()).zip(ids.iter().map(String::as_str)).collect();
+        let notes: Vec<_> = conversion.notes.iter().filter_map(|(key, note)| Some((keys.get(key.as_str())?, key, note))).collect();
+        if !notes.is_empty() {
+            crate::say!("📋 Review these migrations:");
+            for (id, key, note) in notes {
+                crate::say!("  - {} ({}): {}", id, key, note);
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn seed_run(&self, profile: Option<&str>, allowed: Option<&[String]>, timeout: Option<u64>, force: bool, yes: bool, dry_run: bool) -> Result<()> {
+        let mut local = seed::local_seeds(&self.repo.seeds_dir(), profile)?;
+        if let Some(allowed) = allowed {
+            let (permitted, refused): (Vec<_>, Vec<_>) = local.into_iter().partition(|seed| allowed.contains(&seed.name));
+            if !refused.is_empty() {
+                crate::say!("🔒 Skipping {} seed(s) that are not in allowed_seeds of this protected config:", refused.len());
+                for seed in &refused {
+                    crate::say!("  - {}", seed.name);
+                }
+            }
+            local = permitted;
+        }
+        let rows = seed::seed_rows(&local, &self.repo.fetch_seeds().await?);
+
+        let modified: Vec<&seed::SeedRow> = rows.iter().filter(|r| r.status == seed::SeedStatus::Modified).collect();
+        if !modified.is_empty() && !force {
+            crate::say!("⚠️  Skipping {} seed(s) that changed since they were run (pass --force to run them again):", modified.len());
+            for row in &modified {
+                crate::say!("  - {}", row.name);
+            }
+        }
+        let to_run: Vec<&seed::LocalSeed> = local
+            .iter()
+            .zip(&rows)
+            .filter(|(_, row)| row.status == seed::SeedStatus::Pending || (force && row.status == seed::SeedStatus::Modified))
+            .map(|(seed, _)| seed)
+            .collect();
+        if to_run.is_empty() {
+            println!("All seeds are up to date.");
+            return Ok(())
+        }
+
+        let diff_fn = |view: util::DiffView| -> Result<()> {
+            for seed in &to_run {
+                util::display_sql_migration(&seed.name, &seed.sql, "SEED", view)?;
+            }
+            Ok(())
+        };
+        if !util::prompt_for_confirmation_with_diff(&format!("❓ Do you want to run {} seed(s)?", to_run.len()), yes, diff_fn)? {
+            crate::say!("❌ Seeding cancelled.");
+            return Ok(())
+        }
+
+        for seed in &to_run {
+            self.repo.run_seed(&seed.name, &seed.sql, &seed.checksum, timeout, dry_run).await?;
+            crate::say!("🌱 Seeded {}", seed.name);
+        }
+        crate::summary!("\n🎉 Successfully ran {} seed(s)!", to_run.len());
+        Ok(())
+    }
+
+    pub async fn seed_list(&self, profile: Option<&str>, output: OutputFormat) -> Result<()> {
+        let seeds_dir = self.repo.seeds_dir();
+        let local = seed::local_seeds(&seeds_dir, profile)?;
+        let rows = seed::seed_rows(&local, &self.repo.fetch_seeds().await?);
+        match output {
+            OutputFormat::Human => {
+                if rows.is_empty() {
+                    println!("No seeds found in {}.", seeds_dir.display());
+                } else {
+                    seed::render_seed_table(&rows);
+                }
+            }
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&rows)?),
+        }
+        Ok(())
+    }
+
+    pub async fn seed_reset(&self, profile: Option<&str>, yes: bool) -> Result<()> {
+        // Shared seeds plus the seeds of the selected profile, including records whose file was deleted
+        let names: Vec<String> = self.repo.fetch_seeds().await?
+            .into_iter()
+            .map(|(name, ..)| name)
+            .filter(|name| match name.split_once('/') {
+                None => true,
+                Some((p, _)) => Some(p) == profile,
+            })
+            .collect();
+        if names.is_empty() {
+            println!("No seeds have been run.");
+            return Ok(())
+        }
+
+        let diff_fn = |_: util::DiffView| -> Result<()> {
+            for name in &names {
+                crate::say!("  - {}", name);
+            }
+            Ok(())
+        };
+        if !util::prompt_for_confirmation_with_diff(&format!("❓ Do you want to reset {} seed(s) so they run again (seeded data is NOT removed)?", names.len()), yes, diff_fn)? {
+            crate::say!("❌ Reset cancelled.");
+            return Ok(())
+        }
+
+        self.repo.reset_seeds(&names).await?;
+        crate::say!("🔄 Reset {} seed(s).", names.len());
+        Ok(())
+    }
+
+    pub async fn bootstrap(&self, path: &Path, comment: Option<&str>, baseline: bool, yes: bool) -> Result<()> {
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        if !self.repo.fetch_applied_ids().await?.is_empty() {
+            anyhow::bail!("the database already has applied migrations; bootstrap is meant for databases not yet managed by qop");
+        }
+        if !util::get_local_migrations(path)?.is_empty() {
+            anyhow::bail!("local migrations already exist; bootstrap generates the first migration");
+        }
+
+        let schema = bootstrap::strip_session_settings(&self.repo.dump_schema().await?);
+        if migration_diff::split_statements(&schema).is_empty() {
+            anyhow::bail!("the database schema is empty, nothing to bootstrap");
+        }
+        let up_sql = schema;
+        let down_sql = bootstrap::generate_down_sql(&up_sql, self.repo.dialect());
+
+        let diff_fn = |view: util::DiffView| -> Result<()> {
+            util::display_sql_migration("bootstrap", &up_sql, "UP", view)?;
+            util::display_sql_migration("bootstrap", &down_sql, "DOWN", view)
+        };
+        let action = if baseline { "create and baseline" } else { "create" };
+        if !util::prompt_for_confirmation_with_diff(&format!("❓ Do you want to {} the bootstrap migration ({} statement(s))?", action, migration_diff::split_statements(&up_sql).len()), yes, diff_fn)? {
+            crate::say!("❌ Bootstrap cancelled.");
+            return Ok(())
+        }
+
+        let id = self.next_id(path).await?;
+        let meta = util::MigrationMeta {
+            comment: Some(comment.unwrap_or("Bootstrapped from the existing schema").to_string()),
+            ..util::MigrationMeta::default()
+        };
+        let created = util::write_migration_directory(migration_dir, &id, &up_sql, &down_sql, &meta)?;
+        println!("Created bootstrap migration: {}", created.display());
+
+        if baseline {
+            self.repo.baseline_migrations(&[(id.clone(), up_sql, down_sql, meta, false)]).await?;
+            crate::say!("📌 Baselined {}, it is recorded as applied without being executed.", id);
+        } else {
+            crate::say!("💡 Review the generated SQL, then run `baseline {}` to mark it as applied.", id);
+        }
+        Ok(())
+    }
+
+    pub async fn ping(&self, source: &str, target: &str, connect: std::time::Duration, output: OutputFormat) -> Result<()> {
+        let start = std::time::Instant::now();
+        self.repo.ping().await?;
+        let query = start.elapsed();
+        let report = ping::PingReport::new(source, target, connect, query, self.repo.fetch_table_status().await?);
+        match output {
+            OutputFormat::Human => report.print(),
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        }
+        Ok(())
+    }
+
+    /// Preview SQL without running it: pending ups by default, the downs of the newest applied
+    /// migrations with `down`, or a single migration with `id`
+    pub async fn diff(&self, path: &Path, down: bool, count: Option<usize>, id: Option<&str>, output: OutputFormat) -> Result<()> {
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        let direction = if down { "DOWN" } else { "UP" };
+        let targets: Vec<String> = match id {
+            Some(id) => vec![util::normalize_migration_id(id)],
+            None if down => {
+                let mut applied: Vec<String> = self.repo.fetch_applied_ids().await?.into_iter().collect();
+                applied.sort_by(|a, b| util::compare_ids(b, a));
+                applied.into_iter().take(count.unwrap_or(1)).collect()
+            },
+            None => {
+                let applied = self.repo.fetch_applied_ids().await?;
+                let mut pending: Vec<String> = util::get_local_migrations(path)?.difference(&applied).cloned().collect();
+                util::sort_ids(&mut pending);
+                let (skipped, mut pending): (Vec<String>, Vec<String>) = pending.into_iter().partition(|id| util::is_skipped(migration_dir, id, self.repo.profile()));
+                Self::note_skipped(&skipped);
+                pending.truncate(count.unwrap_or(pending.len()));
+                pending
+            },
+        };
+        if targets.is_empty() && matches!(output, OutputFormat::Human) {
+            crate::summary!("{}", if down { "No migrations applied." } else { "All migrations are up to date." });
+        }
+
+        let mut migrations = Vec::with_capacity(targets.len());
+        // Buffered to go through the pager at once, pending diffs can run to thousands of lines
+        let mut text = String::new();
+        for target in &targets {
+            let local = migration_dir.join(format!("id={}", target));
+            let sql = if local.exists() {
+                let (up_sql, down_sql) = util::read_migration_files(migration_dir, target)?;
+                if down { down_sql } else { up_sql }
+            } else if down {
+                // Fall back to the SQL stored on apply, which is what `down --remote` would run
+                self.repo.fetch_down_sql(target).await?.ok_or_else(|| anyhow::anyhow!("migration {} not found locally or remotely", target))?
+            } else {
+                anyhow::bail!("migration {} not found locally", target);
+            };
+            match output {
+                | OutputFormat::Json => {
+                    let operations = migration_diff::parse_migration_operations(&sql);
+                    migrations.push(DiffMigrationOut { id: target.clone(), sql, operations });
+                },
+                | OutputFormat::Human => text.push_str(&util::format_sql_migration(target, &sql, direction)),
+            }
+        }
+        match output {
+            | OutputFormat::Json => {
+                let out = DiffOut { command: "diff", direction: if down { "down" } else { "up" }, migrations };
+                println!("{}", serde_json::to_string_pretty(&out)?);
+            },
+            | OutputFormat::Human => crate::core::pager::page(&text)?,
+        }
+        Ok(())
+    }
+
+    /// Compare the checksums stored for every applied migration with its local files. The stored SQL
+    /// is only fetched for records without checksums.
+    async fn verify_checksums(&self, path: &Path) -> Result<Vec<util::VerifyResult>> {
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        let local = util::get_local_migrations(path)?;
+        let checksums = self.repo.fetch_migration_checksums().await?;
+        let stored: std::collections::HashMap<String, (String, String)> = match checksums.iter().any(|(_, up, down)| up.is_none() || down.is_none()) {
+            | true => self.repo.fetch_all_migrations().await?.into_iter().map(|(id, up, down, _)| (id, (util::sql_checksum(&up), util::sql_checksum(&down)))).collect(),
+            | false => Default::default(),
+        };
+
+        let mut results: Vec<util::VerifyResult> = Vec::with_capacity(checksums.len());
+        for (id, up_sha256, down_sha256) in checksums {
+            if !local.contains(&id) {
+                results.push(util::VerifyResult { id, local: false, up_modified: false, down_modified: false });
+                continue;
+            }
+            let up_sha256 = up_sha256.or_else(|| stored.get(&id).map(|(up, _)| up.clone())).unwrap_or_default();
+            let down_sha256 = down_sha256.or_else(|| stored.get(&id).map(|(_, down)| down.clone())).unwrap_or_default();
+            let (local_up, local_down) = util::read_migration_files(migration_dir, &id)?;
+            results.push(util::VerifyResult {
+                up_modified: up_sha256 != util::sql_checksum(&local_up),
+                down_modified: down_sha256 != util::sql_checksum(&local_down),
+                id,
+                local: true,
+            });
+        }
+        Ok(results)
+    }
+
+    /// Read the migrations of an `up --single-transaction` run, refusing the ones that cannot share
+    /// a transaction: migrations outside a transaction or in batches commit on their own, per-migration
+    /// hooks run in transactions of their own, and the isolation level is set once per transaction.
+    fn single_transaction_batch(&self, migration_dir: &Path, ids: &[String], timeout: Option<u64>) -> Result<Vec<(String, String, String, util::MigrationMeta)>> {
+        let config_hooks = self.repo.hooks();
+        if !config_hooks.for_stage(HookStage::BeforeEach).is_empty() || !config_hooks.for_stage(HookStage::AfterEach).is_empty() {
+            anyhow::bail!("--single-transaction cannot run the before_each and after_each hooks of the config");
+        }
+        let mut batch: Vec<(String, String, String, util::MigrationMeta)> = Vec::with_capacity(ids.len());
+        for id in ids {
+            let (up_sql, down_sql, meta) = util::read_migration_with_meta(migration_dir, id)?;
+            let exec = meta.exec_options(timeout);
+            if !exec.transaction {
+                anyhow::bail!("migration {} sets transaction = false and cannot run with --single-transaction", id);
+            }
+            if exec.batch.is_some() {
+                anyhow::bail!("migration {} runs in batches and cannot run with --single-transaction", id);
+            }
+            if meta.hooks.as_ref().is_some_and(|hooks| !hooks.for_stage(HookStage::BeforeEach).is_empty() || !hooks.for_stage(HookStage::AfterEach).is_empty()) {
+                anyhow::bail!("migration {} has hooks and cannot run with --single-transaction", id);
+            }
+            if let Some((first, ..)) = batch.first().filter(|(_, _, _, first_meta)| first_meta.isolation != meta.isolation) {
+                anyhow::bail!("migrations {} and {} use different isolation levels and cannot run with --single-transaction", first, id);
+            }
+            batch.push((id.clone(), up_sql, down_sql, meta));
+        }
+        Ok(batch)
+    }
+
+    /// The checkpoint of the last failed `up`, checked against the database and the pending migrations
+    async fn resume_checkpoint(&self, path: &Path, to_apply: &[String]) -> Result<checkpoint::Checkpoint> {
+        let Some(resumed) = checkpoint::load(path, &self.repo.scope())? else {
+            anyhow::bail!("No checkpoint to resume from, the last `up` did not fail");
+        };
+        let last = self.repo.fetch_last_id().await?;
+        if last != resumed.last_applied {
+            anyhow::bail!(
+                "The database changed since the checkpoint: the last applied migration is {}, the checkpoint expects {}; run `up` without --resume",
+                last.as_deref().unwrap_or("none"),
+                resumed.last_applied.as_deref().unwrap_or("none")
+            );
+        }
+        if to_apply.first() != Some(&resumed.failed) {
+            anyhow::bail!("Migration {} of the checkpoint is no longer the next one to apply; run `up` without --resume", resumed.failed);
+        }
+        match resumed.statements_done {
+            | 0 => crate::say!("⏩ Resuming at migration {}, which failed at {}", resumed.failed, resumed.at.format("%Y-%m-%d %H:%M:%S UTC")),
+            | done => crate::say!("⏩ Resuming at migration {} after its {} statement(s) that already ran", resumed.failed, done),
+        }
+        Ok(resumed)
+    }
+
+    fn save_checkpoint(&self, path: &Path, last_applied: Option<&str>, failed: &str, exec: &util::ExecOptions, error: &anyhow::Error) -> Result<()> {
+        // A transaction rolls the whole migration back; otherwise every statement before the failing one stays
+        let statements_done = match exec.transaction {
+            | true => 0,
+            | false => error.chain().find_map(|e| e.downcast_ref::<util::StatementError>()).map(|e| e.number - 1).unwrap_or(exec.skip_statements),
+        };
+        let saved = checkpoint::Checkpoint {
+            last_applied: last_applied.map(String::from),
+            failed: failed.to_string(),
+            statements_done,
+            error: format!("{:#}", error),
+            at: Utc::now(),
+        };
+        checkpoint::save(path, &self.repo.scope(), saved)?;
+        crate::esay!("📍 Checkpoint written at migration {}; fix it and continue with `up --resume`", failed);
+        Ok(())
+    }
+
+    /// Drop the checkpoint once its failed migration got applied
+    fn clear_checkpoint(&self, path: &Path, applied: &[String]) -> Result<()> {
+        let scope = self.repo.scope();
+        match checkpoint::load(path, &scope)? {
+            | Some(saved) if applied.contains(&saved.failed) => checkpoint::clear(path, &scope),
+            | _ => Ok(()),
+        }
+    }
+
+    /// Log a dry run or a failed attempt, whose entry went down with its rolled-back transaction;
+    /// a failure to log only produces a warning, the outcome of the migration matters more
+    async fn log_attempt(&self, id: &str, operation: &str, sql: &str, started: std::time::Instant, dry_run: bool, error: Option<&anyhow::Error>) {
+        if !dry_run && error.is_none() {
+            return;
+        }
+        let outcome = log::LogOutcome { duration_ms: Some(started.elapsed().as_millis() as i64), dry_run, error: error.map(|e| format!("{:#}", e)) };
+        if let Err(e) = self.repo.log_attempt(id, operation, sql, &outcome).await {
+            crate::esay!("⚠️  Failed to log the {} of migration {}: {:#}", operation, id, e);
+        }
+    }
+
+    /// Run a migration step with the retries of its meta.toml. Transactional migrations are also
+    /// retried on transient errors as configured; one run outside a transaction may have left
+    /// statements behind, so only an explicit `retries` repeats it.
+    async fn with_retries<F, Fut>(&self, id: &str, meta: &util::MigrationMeta, exec: &util::ExecOptions, step: F) -> Result<()>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<()>>,
+    {
+        let policy = match exec.transaction {
+            | true => self.repo.retry().clone(),
+            | false => util::RetryConfig { attempts: 0, ..self.repo.retry().clone() },
+        };
+        util::with_retries(id, meta.retries.unwrap_or(0), &policy, |e| self.repo.is_transient(e), step).await
+    }
+
+    /// Pending migrations left out by a skip marker
+    fn note_skipped(skipped: &[String]) {
+        for id in skipped {
+            crate::say!("⏭️  Skipping {} (skip in meta.toml)", id);
+        }
+    }
+
+    fn warn_modified(results: &[util::VerifyResult]) {
+        let modified: Vec<&str> = results.iter().filter(|r| r.is_modified()).map(|r| r.id.as_str()).collect();
+        if !modified.is_empty() {
+            crate::say!("⚠️  {} applied migration(s) were modified after being applied: {}. Run `verify` for details.", modified.len(), modified.join(", "));
+        }
+    }
+
+    /// Record how many migrations are left pending for the metrics exports, if any is enabled
+    pub async fn record_pending(&self, path: &Path) {
+        if !telemetry::enabled() {
+            return;
+        }
+        let Some(migration_dir) = path.parent() else { return };
+        let (Ok(local), Ok(applied)) = (util::get_local_migrations(path), self.repo.fetch_applied_ids().await) else { return };
+        let pending = local.difference(&applied).filter(|id| !util::is_skipped(migration_dir, id, self.repo.profile())).count();
+        telemetry::record_pending(pending);
+    }
+
+    /// Compare the database with the local migrations without changing anything; fails with a
+    /// `CheckFailure` for the exit code when migrations are pending or applied ones drifted
+    pub async fn check(&self, path: &Path, tags: &[String], output: OutputFormat) -> Result<()> {
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        let applied = self.repo.fetch_applied_ids().await?;
+        let mut pending: Vec<String> = util::get_local_migrations(path)?
+            .difference(&applied)
+            .filter(|id| util::has_any_tag(migration_dir, id, tags) && !util::is_skipped(migration_dir, id, self.repo.profile()))
+            .cloned()
+            .collect();
+        util::sort_ids(&mut pending);
+        let verified = self.verify_checksums(path).await?;
+        let modified: Vec<String> = verified.iter().filter(|r| r.is_modified()).map(|r| r.id.clone()).collect();
+        let missing: Vec<String> = verified.iter().filter(|r| !r.local).map(|r| r.id.clone()).collect();
+
+        let failure = match (pending.is_empty(), modified.is_empty() && missing.is_empty()) {
+            | (_, false) => Some(CheckFailure::Drift),
+            | (false, true) => Some(CheckFailure::Pending),
+            | (true, true) => None,
+        };
+        match output {
+            | OutputFormat::Human => {
+                if !pending.is_empty() {
+                    crate::summary!("⏳ {} migration(s) pending: {}", pending.len(), pending.join(", "));
+                }
+                if !modified.is_empty() {
+                    crate::summary!("⚠️  {} applied migration(s) were modified: {}", modified.len(), modified.join(", "));
+                }
+                if !missing.is_empty() {
+                    crate::summary!("⚠️  {} applied migration(s) are missing locally: {}", missing.len(), missing.join(", "));
+                }
+                if failure.is_none() {
+                    crate::summary!("✅ All {} migration(s) are applied.", applied.len());
+                }
+            },
+            | OutputFormat::Json => {
+                let status = match failure {
+                    | Some(CheckFailure::Drift) => "drift",
+                    | Some(CheckFailure::Pending) => "pending",
+                    | None => "ok",
+                };
+                let out = CheckOut { command: "check", job: output::job(), status, pending, modified, missing };
+                println!("{}", serde_json::to_string_pretty(&out)?);
+            },
+        }
+        match failure {
+            | Some(failure) => Err(failure.into()),
+            | None => Ok(()),
+        }
+    }
+
+    pub async fn verify(&self, path: &Path, output: OutputFormat) -> Result<()> {
+        let results = self.verify_checksums(path).await?;
+        let modified = results.iter().filter(|r| r.is_modified()).count();
+
+        match output {
+            OutputFormat::Human => {
+                if results.is_empty() {
+                    println!("No migrations applied.");
+                    return Ok(())
+                }
+                util::render_verify_table(&results);
+                let missing = results.iter().filter(|r| !r.local).count();
+                if missing > 0 {
+                    crate::say!("⚠️  {} applied migration(s) do not exist locally.", missing);
+                }
+                if modified == 0 {
+                    crate::summary!("✅ All local migration files match the applied SQL.");
+                }
+            }
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(&results)?);
+            }
+        }
+
+        if modified > 0 {
+            anyhow::bail!("{} applied migration(s) were modified after being applied", modified);
+        }
+        Ok(())
+    }
+
+    /// Benchmark the pending migrations. The repo has to be a scratch copy of the database, and
+    /// `run` executes the migrations on a fresh copy for every iteration, since migrations that
+    /// run outside a transaction leave their changes behind.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn bench<F>(&self, path: &Path, timeout: Option<u64>, iterations: usize, baseline: Option<&Path>, save: Option<&Path>, threshold: f64, output: OutputFormat, run: F) -> Result<()>
+    where
+        F: AsyncFn(&[(String, String, util::ExecOptions)]) -> Result<Vec<std::time::Duration>>,
+    {
+        if iterations == 0 {
+            anyhow::bail!("bench requires at least one iteration");
+        }
+        let local = util::get_local_migrations(path)?;
+        let applied = self.repo.fetch_applied_ids().await?;
+
+        let mut pending: Vec<String> = local.difference(&applied).cloned().collect();
+        util::sort_ids(&mut pending);
+
+        if pending.is_empty() && matches!(output, OutputFormat::Human) {
+            println!("All migrations are up to date.");
+            return Ok(())
+        }
+
+        let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+        let mut migrations: Vec<(String, String, util::ExecOptions)> = Vec::with_capacity(pending.len());
+        for id in &pending {
+            let (up_sql, _down_sql, meta) = util::read_migration_with_meta(migration_dir, id)?;
+            migrations.push((id.clone(), up_sql, meta.exec_options(timeout)));
+        }
+
+        let mut samples: BTreeMap<String, Vec<std::time::Duration>> = BTreeMap::new();
+        for iteration in 1..=iterations {
+            if matches!(output, OutputFormat::Human) {
+                crate::say!("⏱️  Iteration {}/{} ({} migration(s))", iteration, iterations, migrations.len());
+            }
+            let timings = run(&migrations).await?;
+            for ((id, ..), elapsed) in migrations.iter().zip(timings) {
+                samples.entry(id.clone()).or_default().push(elapsed);
+            }
+        }
+
+        let report = BenchReport::from_samples(iterations, &samples);
+        let baseline = baseline.map(BenchReport::read).transpose()?;
+        let regressions = baseline.as_ref().map(|b| bench::find_regressions(&report, b, threshold)).unwrap_or_default();
+
+        match output {
+            OutputFormat::Human => {
+                bench::render_bench_table(&report, baseline.as_ref(), threshold);
+            }
+            OutputFormat::Json => {
+                #[derive(serde::Serialize)]
+                struct BenchOut<'a> {
+                    report: &'a BenchReport,
+                    regressions: &'a [bench::BenchRegression],
+                }
+                println!("{}", serde_json::to_string_pretty(&BenchOut { report: &report, regressions: &regressions })?);
+            }
+        }
+
+        if let Some(save) = save {
+            report.write(save)?;
+            if matches!(output, OutputFormat::Human) {
+                crate::say!("💾 Saved bench baseline to {}", save.display());
+            }
+        }
+
+        if !regressions.is_empty() {
+            anyhow::bail!("{} migration(s) are more than {}% slower than the baseline", regressions.len(), threshold);
+        }
         Ok(())
     }
 }
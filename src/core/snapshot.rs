@@ -0,0 +1,143 @@
+use {
+    crate::core::{
+        bootstrap,
+        migration_diff::{self, MigrationOperation},
+    },
+    anyhow::{Context, Result},
+    chrono::Utc,
+    comfy_table::{Cell, CellAlignment, ContentArrangement},
+    serde::Serialize,
+    std::{collections::BTreeMap, path::Path},
+};
+
+const HEADER_PREFIX: &str = "-- qop snapshot";
+const LAST_MIGRATION_KEY: &str = "-- last_migration: ";
+
+/// Prefix a schema dump with the snapshot header recording the last applied migration
+pub fn render_snapshot(last_migration: Option<&str>, schema: &str) -> String {
+    let mut out = String::new();
+    out.push_str(&format!("{}\n", HEADER_PREFIX));
+    out.push_str(&format!("-- generated_at: {}\n", Utc::now().to_rfc3339()));
+    out.push_str(&format!("-- qop_version: {}\n", env!("CARGO_PKG_VERSION")));
+    out.push_str(&format!("{}{}\n", LAST_MIGRATION_KEY, last_migration.unwrap_or("none")));
+    out.push('\n');
+    out.push_str(schema.trim_start());
+    if !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out
+}
+
+pub fn write_snapshot(path: &Path, content: &str) -> Result<()> {
+    std::fs::write(path, content).with_context(|| format!("Failed to write snapshot: {}", path.display()))
+}
+
+/// Read a snapshot file as `(last_migration, schema)`; the last migration is `None` if not recorded
+pub fn read_snapshot(path: &Path) -> Result<(Option<String>, String)> {
+    let content = std::fs::read_to_string(path).with_context(|| format!("Failed to read snapshot: {}", path.display()))?;
+    let last_migration = content
+        .lines()
+        .find_map(|line| line.strip_prefix(LAST_MIGRATION_KEY))
+        .map(|id| id.trim().to_string())
+        .filter(|id| id != "none");
+    Ok((last_migration, content))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DriftKind {
+    /// Present in the live schema only
+    Created,
+    /// Present in the snapshot only
+    Dropped,
+    /// Definition differs between snapshot and live schema
+    Altered,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DriftEntry {
+    pub object: String,
+    pub kind: DriftKind,
+}
+
+/// Compare the recorded schema with the live one, object by object
+pub fn schema_drift(recorded: &str, live: &str) -> Vec<DriftEntry> {
+    let recorded: BTreeMap<String, Vec<String>> = schema_objects(recorded).into_iter().collect();
+    let live: BTreeMap<String, Vec<String>> = schema_objects(live).into_iter().collect();
+    let mut entries = Vec::new();
+    for (object, definition) in &live {
+        match recorded.get(object) {
+            | None => entries.push(DriftEntry { object: object.clone(), kind: DriftKind::Created }),
+            | Some(recorded) if recorded != definition => entries.push(DriftEntry { object: object.clone(), kind: DriftKind::Altered }),
+            | Some(_) => {},
+        }
+    }
+    for object in recorded.keys().filter(|object| !live.contains_key(*object)) {
+        entries.push(DriftEntry { object: object.clone(), kind: DriftKind::Dropped });
+    }
+    entries.sort_by(|a, b| a.object.cmp(&b.object));
+    entries
+}
+
+/// Group the statements of a schema dump by the object they define, e.g. `table public.users`,
+/// in the order the objects first appear. ALTER TABLE statements and unnamed indexes are
+/// attributed to their table; statements that name no object are keyed by their own text.
+pub fn schema_objects(schema: &str) -> Vec<(String, Vec<String>)> {
+    let mut objects: Vec<(String, Vec<String>)> = Vec::new();
+    for statement in migration_diff::split_statements(&bootstrap::strip_session_settings(schema)) {
+        let normalized = statement.split_whitespace().collect::<Vec<_>>().join(" ");
+        let key = match migration_diff::parse_statement(&statement) {
+            | MigrationOperation::CreateTable { name, .. } | MigrationOperation::AlterTable { name, .. } => {
+                format!("table {}", migration_diff::normalize_ident(&name))
+            },
+            | MigrationOperation::CreateIndex { name: Some(name), .. } => format!("index {}", migration_diff::normalize_ident(&name)),
+            | MigrationOperation::CreateIndex { table, .. } => format!("table {}", migration_diff::normalize_ident(&table)),
+            | MigrationOperation::CreateView { name, .. } => format!("view {}", migration_diff::normalize_ident(&name)),
+            | _ => created_object(&normalized).unwrap_or_else(|| format!("statement {}", normalized)),
+        };
+        match objects.iter_mut().find(|(existing, _)| *existing == key) {
+            | Some((_, statements)) => statements.push(normalized),
+            | None => objects.push((key, vec![normalized])),
+        }
+    }
+    objects
+}
+
+/// `<kind> <name>` of any other CREATE statement, e.g. `function public.touch`
+fn created_object(statement: &str) -> Option<String> {
+    let mut words = statement.split_whitespace().peekable();
+    if !words.next()?.eq_ignore_ascii_case("CREATE") {
+        return None;
+    }
+    let mut kind = words.next()?.to_lowercase();
+    if kind == "or" {
+        words.next();
+        kind = words.next()?.to_lowercase();
+    }
+    let mut name = words.next()?;
+    if name.eq_ignore_ascii_case("IF") {
+        words.next();
+        words.next();
+        name = words.next()?;
+    }
+    let name = name.split('(').next().unwrap_or_default();
+    Some(format!("{} {}", kind, migration_diff::normalize_ident(name)))
+}
+
+pub fn render_drift_table(entries: &[DriftEntry]) {
+    let mut table = crate::core::output::table();
+    table
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec![Cell::new("Object"), Cell::new("Change")]);
+
+    for entry in entries {
+        let kind = match entry.kind {
+            | DriftKind::Created => "➕ created",
+            | DriftKind::Dropped => "➖ dropped",
+            | DriftKind::Altered => "✏️ altered",
+        };
+        table.add_row(vec![Cell::new(&entry.object), Cell::new(kind).set_alignment(CellAlignment::Center)]);
+    }
+
+    println!("{table}");
+}
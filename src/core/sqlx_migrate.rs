@@ -0,0 +1,203 @@
+use {
+    crate::core::{
+        bootstrap,
+        import::{self, Conversion, ForeignMigration},
+        migration::MigrationMeta,
+        repo::Dialect,
+    },
+    anyhow::{Context, Result},
+    std::{
+        collections::{BTreeMap, HashSet},
+        path::Path,
+    },
+};
+
+/// Name of the tool in the source label of imported migrations
+pub const TOOL: &str = "sqlx";
+
+/// Table `sqlx migrate` and `sqlx::migrate!` track applied migrations in
+pub const HISTORY_TABLE: &str = "_sqlx_migrations";
+
+/// Columns of the tracking table, in the order `convert` expects the rows
+pub const HISTORY_COLUMNS: [&str; 2] = ["version", "success"];
+
+/// First line of a migration sqlx runs outside a transaction
+const NO_TRANSACTION: &str = "-- no-transaction";
+
+/// Files of one migration version
+#[derive(Default)]
+struct Files {
+    description: String,
+    /// `<version>_<name>.sql`, which sqlx cannot revert
+    simple: Option<String>,
+    up: Option<String>,
+    down: Option<String>,
+}
+
+/// Convert the migrations in an sqlx `migrations/` directory: `<version>_<name>.sql`, or
+/// `<version>_<name>.up.sql` and `.down.sql` for reversible ones. Versions with a successful row in
+/// `history`, the rows of `_sqlx_migrations`, are marked as applied.
+pub fn convert(dir: &Path, dialect: Dialect, history: &[Vec<Option<String>>]) -> Result<Conversion> {
+    let applied: HashSet<i64> = history
+        .iter()
+        .filter_map(|row| {
+            let [version, success] = row.as_slice() else { return None };
+            // Booleans read as text are `true` on Postgres and `1` on SQLite
+            match success.as_deref() {
+                | Some("true" | "1" | "t") => version.as_deref()?.trim().parse().ok(),
+                | _ => None,
+            }
+        })
+        .collect();
+
+    let mut conversion = Conversion::default();
+    let mut versions: BTreeMap<i64, Files> = BTreeMap::new();
+    let mut names: Vec<String> = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to read directory: {}", dir.display()))? {
+        let path = entry?.path();
+        let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        if path.is_file() && name.ends_with(".sql") {
+            names.push(name);
+        }
+    }
+    names.sort();
+    for name in names {
+        let stem = name.trim_end_matches(".sql");
+        let (stem, kind) = match stem.strip_suffix(".up").or(stem.strip_suffix(".down")) {
+            | Some(base) => (base, &stem[base.len() + 1..]),
+            | None => (stem, ""),
+        };
+        let Some((version, description)) = stem.split_once('_').and_then(|(version, description)| Some((version.parse::<i64>().ok()?, description))) else {
+            conversion.skipped.push((name.clone(), "its name is not `<version>_<name>.sql`".to_string()));
+            continue;
+        };
+        let sql = std::fs::read_to_string(dir.join(&name)).with_context(|| format!("Failed to read migration file: {}", dir.join(&name).display()))?;
+        let files = versions.entry(version).or_default();
+        let slot = match kind {
+            | "up" => &mut files.up,
+            | "down" => &mut files.down,
+            | _ => &mut files.simple,
+        };
+        // The first file of a version wins, later ones are reported as skipped
+        if slot.is_some() {
+            conversion.skipped.push((name.clone(), format!("version {} is used twice", version)));
+            continue;
+        }
+        *slot = Some(sql);
+        files.description = description.replace('_', " ");
+    }
+
+    for (version, files) in versions {
+        let key = version.to_string();
+        let (up_sql, down_sql) = match (files.simple, files.up, files.down) {
+            | (Some(_), Some(_), _) | (Some(_), _, Some(_)) => {
+                conversion.skipped.push((key, "it has both a .sql and an .up.sql or .down.sql file".to_string()));
+                continue;
+            },
+            | (Some(sql), None, None) => {
+                conversion.notes.push((key.clone(), "sqlx cannot revert it, down.sql was generated from up.sql".to_string()));
+                let down = bootstrap::generate_migration_down_sql(&sql, dialect);
+                (sql, down)
+            },
+            | (None, Some(up), Some(down)) => (up, down),
+            | (None, Some(up), None) => {
+                conversion.notes.push((key.clone(), "it has no .down.sql, down.sql was generated from up.sql".to_string()));
+                let down = bootstrap::generate_migration_down_sql(&up, dialect);
+                (up, down)
+            },
+            | (None, None, _) => {
+                conversion.skipped.push((key, "it only has a .down.sql".to_string()));
+                continue;
+            },
+        };
+
+        let mut meta = MigrationMeta { comment: Some(files.description).filter(|description| !description.is_empty()), ..Default::default() };
+        if up_sql.trim_start().starts_with(NO_TRANSACTION) {
+            meta.transaction = Some(false);
+        }
+        meta.labels.insert(import::SOURCE_LABEL.to_string(), import::source(TOOL, &key));
+        conversion.migrations.push(ForeignMigration { applied: applied.contains(&version), key, up_sql, down_sql, meta });
+    }
+    Ok(conversion)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::core::import::Fixture,
+    };
+
+    fn row(version: &str, success: &str) -> Vec<Option<String>> {
+        vec![Some(version.to_string()), Some(success.to_string())]
+    }
+
+    #[test]
+    fn converts_simple_and_reversible_migrations() {
+        let fixture = Fixture::new(&[
+            ("20240101000000_create_users.sql", "CREATE TABLE users (id INTEGER);\n"),
+            ("20240102000000_add_posts.up.sql", "-- no-transaction\nCREATE TABLE posts (id INTEGER);\n"),
+            ("20240102000000_add_posts.down.sql", "DROP TABLE posts;\n"),
+            ("20240103000000_add_tags.up.sql", "CREATE TABLE tags (id INTEGER);\n"),
+            ("README.md", "not a migration"),
+        ]);
+        let history = [row("20240101000000", "true"), row("20240102000000", "0"), row("20240103000000", "1")];
+        let conversion = convert(&fixture.path(""), Dialect::Sqlite, &history).unwrap();
+
+        let keys: Vec<&str> = conversion.migrations.iter().map(|migration| migration.key.as_str()).collect();
+        assert_eq!(keys, vec!["20240101000000", "20240102000000", "20240103000000"]);
+        assert!(conversion.skipped.is_empty());
+
+        let [users, posts, tags] = conversion.migrations.as_slice() else { panic!("expected three migrations") };
+        assert_eq!(users.up_sql, "CREATE TABLE users (id INTEGER);\n");
+        assert!(users.down_sql.contains("DROP TABLE"), "{}", users.down_sql);
+        assert_eq!(users.meta.comment.as_deref(), Some("create users"));
+        assert_eq!(users.meta.labels.get(import::SOURCE_LABEL).map(String::as_str), Some("sqlx:20240101000000"));
+        assert!(users.applied);
+
+        assert_eq!(posts.down_sql, "DROP TABLE posts;\n");
+        assert_eq!(posts.meta.transaction, Some(false));
+        assert!(!posts.applied);
+
+        assert!(tags.applied);
+        assert_eq!(
+            conversion.notes,
+            vec![
+                ("20240101000000".to_string(), "sqlx cannot revert it, down.sql was generated from up.sql".to_string()),
+                ("20240103000000".to_string(), "it has no .down.sql, down.sql was generated from up.sql".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_what_sqlx_would_reject() {
+        let fixture = Fixture::new(&[
+            ("1_mixed.sql", "SELECT 1;\n"),
+            ("1_mixed.up.sql", "SELECT 2;\n"),
+            ("2_orphan.down.sql", "SELECT 3;\n"),
+            ("latest.sql", "SELECT 4;\n"),
+            ("3_twice.sql", "SELECT 5;\n"),
+            ("3_again.sql", "SELECT 6;\n"),
+        ]);
+        let conversion = convert(&fixture.path(""), Dialect::Postgres, &[]).unwrap();
+        assert_eq!(
+            conversion.skipped,
+            vec![
+                ("3_twice.sql".to_string(), "version 3 is used twice".to_string()),
+                ("latest.sql".to_string(), "its name is not `<version>_<name>.sql`".to_string()),
+                ("1".to_string(), "it has both a .sql and an .up.sql or .down.sql file".to_string()),
+                ("2".to_string(), "it only has a .down.sql".to_string()),
+            ]
+        );
+        let [again] = conversion.migrations.as_slice() else { panic!("expected one migration") };
+        assert_eq!(again.key, "3");
+        assert_eq!(again.up_sql, "SELECT 6;\n");
+        assert_eq!(again.meta.comment.as_deref(), Some("again"));
+    }
+
+    #[test]
+    fn missing_directory() {
+        let fixture = Fixture::new(&[]);
+        assert!(convert(&fixture.path("migrations"), Dialect::Sqlite, &[]).is_err());
+    }
+}
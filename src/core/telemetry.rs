@@ -0,0 +1,108 @@
+use {
+    crate::core::output,
+    std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Mutex,
+        },
+        time::{Duration, Instant, SystemTime},
+    },
+};
+
+/// A migration that ran during this invocation, for the OTLP and Prometheus exports and the report
+#[derive(Debug, Clone)]
+pub struct MigrationRun {
+    pub id: String,
+    pub direction: &'static str,
+    /// Target or tenant the migration ran for, empty outside of runs over several
+    pub job: String,
+    pub start: SystemTime,
+    pub duration: Duration,
+    pub rows: u64,
+    pub sql: String,
+    pub dry_run: bool,
+    pub error: Option<String>,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+static RUNS: Mutex<Vec<MigrationRun>> = Mutex::new(Vec::new());
+
+/// Migrations left pending after the run, by job
+static PENDING: Mutex<Vec<(String, usize)>> = Mutex::new(Vec::new());
+
+/// Rows changed so far by the statements of the running migrations, by job and migration id
+static ROWS: Mutex<Vec<(String, u64)>> = Mutex::new(Vec::new());
+
+/// Record migration runs from now on, for an export at the end of the invocation
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Whether migration runs are recorded, which only an export or the report asks for
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+fn key(migration_id: &str) -> String {
+    format!("{}/{}", output::job(), migration_id)
+}
+
+/// Count the rows a statement of `migration_id` changed
+pub fn add_rows(migration_id: &str, rows: u64) {
+    if !enabled() {
+        return;
+    }
+    let key = key(migration_id);
+    let mut counts = ROWS.lock().unwrap_or_else(|e| e.into_inner());
+    match counts.iter_mut().find(|(running, _)| running == &key) {
+        | Some((_, count)) => *count += rows,
+        | None => counts.push((key, rows)),
+    }
+}
+
+/// Record that migration `id` ran `sql` from `started` until now, failing with `error`
+pub fn record(id: &str, direction: &'static str, sql: &str, started: Instant, dry_run: bool, error: Option<&anyhow::Error>) {
+    if !enabled() {
+        return;
+    }
+    let key = key(id);
+    let rows = {
+        let mut counts = ROWS.lock().unwrap_or_else(|e| e.into_inner());
+        let rows = counts.iter().find(|(running, _)| running == &key).map(|(_, rows)| *rows).unwrap_or(0);
+        counts.retain(|(running, _)| running != &key);
+        rows
+    };
+    let duration = started.elapsed();
+    let run = MigrationRun {
+        id: id.to_string(),
+        direction,
+        job: output::job(),
+        start: SystemTime::now() - duration,
+        duration,
+        rows,
+        sql: sql.to_string(),
+        dry_run,
+        error: error.map(|e| format!("{:#}", e)),
+    };
+    crate::core::events::migration_finished(&run);
+    RUNS.lock().unwrap_or_else(|e| e.into_inner()).push(run);
+}
+
+/// Record how many migrations the current job left pending
+pub fn record_pending(count: usize) {
+    let job = output::job();
+    let mut pending = PENDING.lock().unwrap_or_else(|e| e.into_inner());
+    pending.retain(|(recorded, _)| recorded != &job);
+    pending.push((job, count));
+}
+
+/// The migrations recorded so far, in the order they finished
+pub fn runs() -> Vec<MigrationRun> {
+    RUNS.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// The pending migrations recorded so far, by job
+pub fn pending() -> Vec<(String, usize)> {
+    PENDING.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
@@ -0,0 +1,315 @@
+use {
+    crate::core::{
+        migration as util,
+        repo::MigrationRepository,
+        service::{MigrationService, Selection},
+    },
+    anyhow::Result,
+    chrono::{Local, NaiveDateTime, TimeZone},
+    ratatui::{
+        crossterm::event::{self, Event, KeyCode, KeyEventKind},
+        layout::{Constraint, Layout},
+        style::{Color, Modifier, Style},
+        symbols::border,
+        text::Line,
+        widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
+        DefaultTerminal, Frame,
+    },
+    std::{collections::BTreeMap, path::Path},
+};
+
+const HELP: &str = "↑/↓ move  enter view SQL  a apply  r revert  l lock/unlock  s sync  R reload  q quit";
+
+/// A migration as shown in the TUI, merged from the local directory and the tracking table
+struct MigrationRow {
+    id: String,
+    applied_at: Option<NaiveDateTime>,
+    local: bool,
+    comment: Option<String>,
+    locked: bool,
+}
+
+#[derive(Clone, Copy)]
+enum Action {
+    Apply,
+    Revert,
+    ToggleLock,
+    Sync,
+}
+
+enum Mode {
+    Table,
+    Sql { title: String, text: String, scroll: u16 },
+    Confirm { action: Action, id: String },
+}
+
+struct App<'a, R: MigrationRepository> {
+    svc: &'a MigrationService<R>,
+    path: &'a Path,
+    migration_dir: &'a Path,
+    timeout: Option<u64>,
+    rows: Vec<MigrationRow>,
+    state: TableState,
+    mode: Mode,
+    status: String,
+}
+
+/// Run the interactive migration browser until the user quits. Its actions go through the
+/// service like the matching commands, with their run lock, hooks, retries and attempt log.
+pub async fn run<R: MigrationRepository>(svc: &MigrationService<R>, path: &Path, timeout: Option<u64>) -> Result<()> {
+    let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
+    let mut app = App {
+        svc,
+        path,
+        migration_dir,
+        timeout,
+        rows: Vec::new(),
+        state: TableState::default(),
+        mode: Mode::Table,
+        status: String::new(),
+    };
+    app.reload().await?;
+
+    let mut terminal = ratatui::init();
+    let result = app.event_loop(&mut terminal).await;
+    ratatui::restore();
+    result
+}
+
+impl<R: MigrationRepository> App<'_, R> {
+    async fn reload(&mut self) -> Result<()> {
+        let history = self.svc.repo().fetch_history().await?;
+        let local = util::get_local_migrations(self.path)?;
+
+        let mut all: BTreeMap<String, MigrationRow> = BTreeMap::new();
+        for id in local {
+            let meta = util::read_migration_meta(self.migration_dir, &id).unwrap_or_default();
+            let (locked, comment) = (meta.is_locked(), meta.comment);
+            all.insert(id.clone(), MigrationRow { id, applied_at: None, local: true, comment, locked });
+        }
+        for (id, ts, comment, locked) in history {
+            let row = all.entry(id.clone()).or_insert(MigrationRow { id, applied_at: None, local: false, comment: None, locked: false });
+            // The tracking table wins for applied migrations
+            row.applied_at = Some(ts);
+            row.comment = comment;
+            row.locked = locked;
+        }
+        self.rows = all.into_values().collect();
+        self.rows.sort_by(|a, b| util::compare_ids(&a.id, &b.id));
+
+        let selected = self.state.selected().unwrap_or(0).min(self.rows.len().saturating_sub(1));
+        self.state.select(if self.rows.is_empty() { None } else { Some(selected) });
+        Ok(())
+    }
+
+    fn selected(&self) -> Option<&MigrationRow> {
+        self.state.selected().and_then(|i| self.rows.get(i))
+    }
+
+    async fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else { continue };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            match &mut self.mode {
+                | Mode::Table => match key.code {
+                    | KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                    | KeyCode::Down | KeyCode::Char('j') => self.state.select_next(),
+                    | KeyCode::Up | KeyCode::Char('k') => self.state.select_previous(),
+                    | KeyCode::Home | KeyCode::Char('g') => self.state.select_first(),
+                    | KeyCode::End | KeyCode::Char('G') => self.state.select_last(),
+                    | KeyCode::Enter | KeyCode::Char('v') => {
+                        if let Err(e) = self.view_sql().await {
+                            self.status = format!("❌ {:#}", e);
+                        }
+                    },
+                    | KeyCode::Char('a') => self.confirm(Action::Apply),
+                    | KeyCode::Char('r') => self.confirm(Action::Revert),
+                    | KeyCode::Char('l') => self.confirm(Action::ToggleLock),
+                    | KeyCode::Char('s') => self.confirm(Action::Sync),
+                    | KeyCode::Char('R') => {
+                        self.status = match self.reload().await {
+                            | Ok(()) => "🔄 Reloaded".to_string(),
+                            | Err(e) => format!("❌ {:#}", e),
+                        };
+                    },
+                    | _ => {},
+                },
+                | Mode::Sql { scroll, .. } => match key.code {
+                    | KeyCode::Char('q') | KeyCode::Esc | KeyCode::Enter => self.mode = Mode::Table,
+                    | KeyCode::Down | KeyCode::Char('j') => *scroll = scroll.saturating_add(1),
+                    | KeyCode::Up | KeyCode::Char('k') => *scroll = scroll.saturating_sub(1),
+                    | KeyCode::PageDown => *scroll = scroll.saturating_add(20),
+                    | KeyCode::PageUp => *scroll = scroll.saturating_sub(20),
+                    | KeyCode::Home | KeyCode::Char('g') => *scroll = 0,
+                    | _ => {},
+                },
+                | Mode::Confirm { action, id } => {
+                    let (action, id) = (*action, id.clone());
+                    self.mode = Mode::Table;
+                    if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                        // The service and hooks print to the terminal, so the TUI steps aside while they run
+                        ratatui::restore();
+                        let result = self.execute(action, &id).await;
+                        *terminal = ratatui::init();
+                        self.status = match result {
+                            | Ok(message) => message,
+                            | Err(e) => format!("❌ {:#}", e),
+                        };
+                        if let Err(e) = self.reload().await {
+                            self.status = format!("❌ {:#}", e);
+                        }
+                    } else {
+                        self.status = "Cancelled.".to_string();
+                    }
+                },
+            }
+        }
+    }
+
+    fn confirm(&mut self, action: Action) {
+        let Some(row) = self.selected() else { return };
+        let id = row.id.clone();
+        self.mode = Mode::Confirm { action, id };
+    }
+
+    async fn view_sql(&mut self) -> Result<()> {
+        let Some(row) = self.selected() else { return Ok(()) };
+        let id = row.id.clone();
+        let (up_sql, down_sql, source) = if row.local {
+            let (up_sql, down_sql) = util::read_migration_files(self.migration_dir, &id)?;
+            (up_sql, down_sql, "local")
+        } else {
+            let (_, up_sql, down_sql, _) = self.svc.repo().fetch_all_migrations().await?
+                .into_iter()
+                .find(|(m, ..)| *m == id)
+                .ok_or_else(|| anyhow::anyhow!("migration {} not found", id))?;
+            (up_sql, down_sql, "remote")
+        };
+        let text = format!("-- up.sql\n{}\n\n-- down.sql\n{}", up_sql.trim_end(), down_sql.trim_end());
+        self.mode = Mode::Sql { title: format!(" {} ({}) ", id, source), text, scroll: 0 };
+        Ok(())
+    }
+
+    async fn execute(&self, action: Action, id: &str) -> Result<String> {
+        let result = self.run_action(action, id).await;
+        // Apply and revert take the run lock, other runs may go ahead while the TUI idles
+        self.svc.repo().release_run_lock().await?;
+        result
+    }
+
+    async fn run_action(&self, action: Action, id: &str) -> Result<String> {
+        let row = self.rows.iter().find(|r| r.id == id).ok_or_else(|| anyhow::anyhow!("migration {} not found", id))?;
+        let ids = [id.to_string()];
+        let selection = Selection { ids: &ids, from: None, to: None };
+        match action {
+            | Action::Apply => {
+                let locked = util::read_migration_meta(self.migration_dir, id)?.is_locked();
+                self.svc.apply_up(self.path, selection, self.timeout, true, false, locked).await?;
+                Ok(format!("✅ Applied {}", id))
+            },
+            | Action::Revert => {
+                if row.locked {
+                    anyhow::bail!("migration {} is locked; unlock it first", id);
+                }
+                self.svc.apply_down(self.path, selection, self.timeout, !row.local, true, false, false).await?;
+                Ok(format!("↩️  Reverted {}", id))
+            },
+            | Action::ToggleLock => {
+                let locked = !row.locked;
+                self.svc.set_locked(self.path, id, locked).await?;
+                Ok(format!("{} {}", if locked { "🔒 Locked" } else { "🔓 Unlocked" }, id))
+            },
+            | Action::Sync => {
+                if row.applied_at.is_none() || !row.local {
+                    anyhow::bail!("migration {} must be applied and exist locally to be synced", id);
+                }
+                self.svc.repair(self.path, id, true, true, true).await?;
+                Ok(format!("🔧 Synced stored SQL of {}", id))
+            },
+        }
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status, help] = Layout::vertical([Constraint::Min(0), Constraint::Length(1), Constraint::Length(1)]).areas(frame.area());
+
+        if let Mode::Sql { title, text, scroll } = &self.mode {
+            let sql = Paragraph::new(text.as_str())
+                .block(block(title.as_str()))
+                .scroll((*scroll, 0));
+            frame.render_widget(sql, main);
+        } else {
+            let rows = self.rows.iter().map(|row| {
+                let remote = row.applied_at
+                    .map(|ts| Local.from_utc_datetime(&ts).format("%Y-%m-%d %H:%M:%S %Z").to_string())
+                    .unwrap_or_else(|| "-".to_string());
+                let color = match (row.applied_at.is_some(), row.local) {
+                    | (true, true) => Color::Green,
+                    | (false, _) => Color::Yellow,
+                    | (true, false) => Color::Red,
+                };
+                Row::new(vec![
+                    Cell::from(row.id.clone()),
+                    Cell::from(remote),
+                    Cell::from(if row.local { "yes" } else { "no" }),
+                    Cell::from(row.comment.clone().unwrap_or_else(|| "-".to_string())),
+                    Cell::from(if row.locked { "locked" } else { "" }),
+                ])
+                .style(fg(color))
+            });
+            let widths = [Constraint::Length(20), Constraint::Length(26), Constraint::Length(6), Constraint::Min(10), Constraint::Length(7)];
+            let table = Table::new(rows, widths)
+                .header(Row::new(vec!["Migration ID", "Remote", "Local", "Comment", "Locked"]).style(Style::default().add_modifier(Modifier::BOLD)))
+                .block(block(" qop migrations "))
+                .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            frame.render_stateful_widget(table, main, &mut self.state);
+        }
+
+        let status_line = match &self.mode {
+            | Mode::Confirm { action, id } => {
+                let verb = match action {
+                    | Action::Apply => "Apply",
+                    | Action::Revert => "Revert",
+                    | Action::ToggleLock => "Toggle the lock of",
+                    | Action::Sync => "Overwrite the stored SQL with the local files of",
+                };
+                format!("❓ {} migration '{}'? [y/N]", verb, id)
+            },
+            | _ => self.status.clone(),
+        };
+        frame.render_widget(Paragraph::new(Line::from(crate::core::output::decorate(status_line))), status);
+        let help_text = if matches!(self.mode, Mode::Sql { .. }) { "↑/↓ scroll  esc back" } else { HELP };
+        frame.render_widget(Paragraph::new(Line::from(crate::core::output::ascii(help_text))).style(fg(Color::DarkGray)), help);
+    }
+}
+
+/// Borders of `--plain`
+const ASCII_BORDER: border::Set = border::Set {
+    top_left: "+",
+    top_right: "+",
+    bottom_left: "+",
+    bottom_right: "+",
+    vertical_left: "|",
+    vertical_right: "|",
+    horizontal_top: "-",
+    horizontal_bottom: "-",
+};
+
+fn block(title: &str) -> Block<'_> {
+    let block = Block::default().borders(Borders::ALL).title(title);
+    match crate::core::output::plain() {
+        | true => block.border_set(ASCII_BORDER),
+        | false => block,
+    }
+}
+
+/// Foreground color unless colors are disabled by the user config, `--color` or `NO_COLOR`
+fn fg(color: Color) -> Style {
+    match crate::core::output::preferences().color {
+        | true => Style::default().fg(color),
+        | false => Style::default(),
+    }
+}
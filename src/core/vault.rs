@@ -0,0 +1,109 @@
+use {
+    crate::config::{VaultAuth, VaultSource},
+    anyhow::{Context, Result},
+    serde_json::Value,
+    std::time::Duration,
+};
+
+/// Fetch a secret from Vault and turn it into a connection string, either by reading a single
+/// field or by filling a template with all fields of the secret
+pub fn read_secret(source: &VaultSource) -> Result<String> {
+    let address = match &source.address {
+        | Some(address) => address.clone(),
+        | None => std::env::var("VAULT_ADDR").context("no Vault address configured and VAULT_ADDR is not set")?,
+    };
+    let address = address.trim_end_matches('/');
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(Duration::from_secs(source.timeout.unwrap_or(30))))
+        .http_status_as_error(false)
+        .build()
+        .into();
+
+    let token = match &source.auth {
+        | VaultAuth::Token { token_env } => {
+            let var = token_env.as_deref().unwrap_or("VAULT_TOKEN");
+            std::env::var(var).with_context(|| format!("missing Vault token in environment variable {}", var))?
+        },
+        | VaultAuth::Approle { mount, role_id, secret_id_env } => {
+            let secret_id = std::env::var(secret_id_env).with_context(|| format!("missing AppRole secret id in environment variable {}", secret_id_env))?;
+            let url = format!("{}/v1/auth/{}/login", address, mount.as_deref().unwrap_or("approle"));
+            let mut request = agent.post(&url);
+            if let Some(namespace) = &source.namespace {
+                request = request.header("X-Vault-Namespace", namespace);
+            }
+            let response = request
+                .send_json(serde_json::json!({ "role_id": role_id, "secret_id": secret_id }))
+                .with_context(|| format!("Vault AppRole login at {} failed", url))?;
+            let body = read_response(response, &url)?;
+            body.pointer("/auth/client_token")
+                .and_then(Value::as_str)
+                .map(|token| token.to_string())
+                .ok_or_else(|| anyhow::anyhow!("Vault AppRole login at {} returned no client token", url))?
+        },
+    };
+
+    let url = format!("{}/v1/{}", address, source.path.trim_start_matches('/'));
+    let mut request = agent.get(&url).header("X-Vault-Token", &token);
+    if let Some(namespace) = &source.namespace {
+        request = request.header("X-Vault-Namespace", namespace);
+    }
+    let response = request.call().with_context(|| format!("Vault request to {} failed", url))?;
+    let body = read_response(response, &url)?;
+
+    // KV v2 nests the secret in data.data, KV v1 and dynamic engines return it in data
+    let data = body.pointer("/data/data").filter(|d| d.is_object()).or_else(|| body.get("data")).ok_or_else(|| anyhow::anyhow!("Vault response from {} contains no data", url))?;
+    match (&source.field, &source.template) {
+        | (Some(field), None) => field_value(data, field).ok_or_else(|| anyhow::anyhow!("Vault secret {} has no field {}", source.path, field)),
+        | (None, Some(template)) => render_template(template, data, &source.path),
+        | _ => anyhow::bail!("Vault source {} needs exactly one of `field` or `template`", source.path),
+    }
+}
+
+fn read_response(mut response: ureq::http::Response<ureq::Body>, url: &str) -> Result<Value> {
+    let status = response.status();
+    let body: Value = response.body_mut().read_json().unwrap_or(Value::Null);
+    if !status.is_success() {
+        let errors = body
+            .get("errors")
+            .and_then(Value::as_array)
+            .map(|errors| errors.iter().filter_map(Value::as_str).collect::<Vec<_>>().join("; "))
+            .unwrap_or_default();
+        anyhow::bail!("Vault returned {} for {}: {}", status, url, errors);
+    }
+    Ok(body)
+}
+
+fn field_value(data: &Value, field: &str) -> Option<String> {
+    match data.get(field)? {
+        | Value::String(value) => Some(value.clone()),
+        | Value::Null => None,
+        | value => Some(value.to_string()),
+    }
+}
+
+/// Replace `{field}` placeholders with the percent-encoded field values, e.g. credentials from
+/// the database secrets engine in `postgresql://{username}:{password}@db:5432/app`
+fn render_template(template: &str, data: &Value, path: &str) -> Result<String> {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..].find('}').map(|i| start + i).ok_or_else(|| anyhow::anyhow!("unterminated placeholder in Vault template"))?;
+        let field = &rest[start + 1..end];
+        let value = field_value(data, field).ok_or_else(|| anyhow::anyhow!("Vault secret {} has no field {}", path, field))?;
+        out.push_str(&rest[..start]);
+        out.push_str(&percent_encode(&value));
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            | b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            | b => format!("%{:02X}", b),
+        })
+        .collect()
+}
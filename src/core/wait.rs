@@ -0,0 +1,69 @@
+use {
+    anyhow::Result,
+    std::{
+        future::Future,
+        time::{Duration, Instant},
+    },
+};
+
+/// Upper bound of a single attempt when the interval is shorter; the pool retries refused connections
+/// internally, so without a bound one attempt would swallow the whole wait.
+const MIN_ATTEMPT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Run `probe` every `interval` until it succeeds; fails with the last error once `timeout` has passed
+pub async fn wait_until_ready<F, Fut>(timeout: Duration, interval: Duration, mut probe: F) -> Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let start = Instant::now();
+    let deadline = start + timeout;
+    let mut attempts = 0;
+    loop {
+        attempts += 1;
+        let attempt_timeout = interval.max(MIN_ATTEMPT_TIMEOUT).min(deadline.saturating_duration_since(Instant::now()));
+        let err = match tokio::time::timeout(attempt_timeout, probe()).await {
+            | Ok(Ok(())) => {
+                crate::summary!("✅ Database is ready after {:.1}s ({} attempt(s)).", start.elapsed().as_secs_f64(), attempts);
+                return Ok(())
+            },
+            | Ok(Err(e)) => e,
+            | Err(_) => anyhow::anyhow!("connection attempt timed out after {:.1}s", attempt_timeout.as_secs_f64()),
+        };
+        if Instant::now() + interval >= deadline {
+            anyhow::bail!("database not ready after {:.1}s ({} attempt(s)): {}", start.elapsed().as_secs_f64(), attempts, err);
+        }
+        crate::say!("⏳ Database not ready (attempt {}): {}", attempts, err);
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Default seconds `up` and `down` wait for the run lock of another qop run
+pub const RUN_LOCK_TIMEOUT: u64 = 60;
+
+/// Retry pacing while another qop run holds the run lock
+pub struct RunLockWait {
+    start: Instant,
+    timeout: Duration,
+    announced: bool,
+}
+
+impl RunLockWait {
+    pub fn new(timeout_seconds: Option<u64>) -> Self {
+        Self { start: Instant::now(), timeout: Duration::from_secs(timeout_seconds.unwrap_or(RUN_LOCK_TIMEOUT)), announced: false }
+    }
+
+    /// After a failed attempt: wait before the next one, or fail once the timeout has passed
+    pub async fn retry(&mut self) -> Result<()> {
+        let waited = self.start.elapsed();
+        if waited >= self.timeout {
+            anyhow::bail!("another qop run still holds the run lock after {:.0}s; retry later, or pass --no-lock if that run is gone", waited.as_secs_f64());
+        }
+        if !self.announced {
+            crate::esay!("⏳ Another qop run is applying migrations, waiting up to {}s for it to finish...", self.timeout.as_secs());
+            self.announced = true;
+        }
+        tokio::time::sleep(Duration::from_millis(500).min(self.timeout - waited)).await;
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+use {
+    anyhow::{Context, Result},
+    serde::{Deserialize, Serialize},
+};
+
+const DAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// Times of the week commands may change the database, e.g. for production profiles
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct WindowConfig {
+    /// IANA time zone the windows are given in, e.g. `Europe/Berlin` (default: the system time zone)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub allow: Vec<Window>,
+}
+
+/// From `from` to `to` on each of `days`; a window ending before it starts runs past midnight
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Window {
+    /// `mon` to `sun`, the day the window starts on; every day when empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<String>,
+    /// `HH:MM`
+    pub from: String,
+    /// `HH:MM`, `24:00` for the end of the day
+    pub to: String,
+}
+
+impl Window {
+    fn describe(&self) -> String {
+        let days = if self.days.is_empty() { "daily".to_string() } else { self.days.join(",") };
+        format!("{} {}-{}", days, self.from, self.to)
+    }
+
+    /// Whether the minute of the week falls into the window
+    fn contains(&self, weekday: usize, minute: u32) -> Result<bool> {
+        let from = parse_time(&self.from)?;
+        let to = parse_time(&self.to)?;
+        let days = self
+            .days
+            .iter()
+            .map(|day| {
+                DAYS.iter()
+                    .position(|known| known.eq_ignore_ascii_case(day))
+                    .ok_or_else(|| anyhow::anyhow!("unknown day `{}` in maintenance window, expected one of {}", day, DAYS.join(", ")))
+            })
+            .collect::<Result<Vec<usize>>>()?;
+        let on = |day: usize| days.is_empty() || days.contains(&day);
+        Ok(match from <= to {
+            | true => on(weekday) && from <= minute && minute < to,
+            | false => (on(weekday) && minute >= from) || (on((weekday + 6) % 7) && minute < to),
+        })
+    }
+}
+
+fn parse_time(time: &str) -> Result<u32> {
+    let parsed = time.split_once(':').and_then(|(hours, minutes)| Some((hours.parse::<u32>().ok()?, minutes.parse::<u32>().ok()?)));
+    match parsed {
+        | Some((hours, minutes)) if minutes < 60 && (hours < 24 || (hours, minutes) == (24, 0)) => Ok(hours * 60 + minutes),
+        | _ => anyhow::bail!("invalid time `{}` in maintenance window, expected HH:MM", time),
+    }
+}
+
+impl WindowConfig {
+    /// Fail unless the current time is inside one of the allowed windows
+    pub fn check(&self) -> Result<()> {
+        let zone = match &self.timezone {
+            | Some(name) => jiff::tz::TimeZone::get(name).with_context(|| format!("unknown time zone `{}` in maintenance window", name))?,
+            | None => jiff::tz::TimeZone::system(),
+        };
+        let now = jiff::Timestamp::now().to_zoned(zone);
+        let weekday = now.weekday().to_monday_zero_offset() as usize;
+        let minute = now.hour() as u32 * 60 + now.minute() as u32;
+        for window in &self.allow {
+            if window.contains(weekday, minute)? {
+                return Ok(());
+            }
+        }
+        let allowed: Vec<String> = self.allow.iter().map(Window::describe).collect();
+        anyhow::bail!(
+            "outside the maintenance window (now {} {}, allowed: {}); pass --override-window to run anyway",
+            now.strftime("%a %H:%M"),
+            now.time_zone().iana_name().unwrap_or("local time"),
+            if allowed.is_empty() { "never".to_string() } else { allowed.join("; ") }
+        )
+    }
+}
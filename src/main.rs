@@ -12,6 +12,7 @@ use {
 #[tokio::main]
 async fn main() -> Result<()> {
     let cmd = crate::args::ClapArgumentLoader::load()?;
+    crate::core::output::init(cmd.user.ui);
 
     match cmd.command {
         | crate::args::Command::Manual { path, format } => {
@@ -33,9 +34,101 @@ async fn main() -> Result<()> {
             reference::build_shell_completion(&path, &shell)?;
             Ok(())
         },
-        | crate::args::Command::Subsystem(subsystem) => {
-            crate::subsystem::driver::dispatch(subsystem).await
+        | crate::args::Command::Subsystem { targets, parallel, metrics, report, events } => {
+            #[cfg(feature = "otel")]
+            if crate::core::otel::configured() {
+                crate::core::telemetry::enable();
+            }
+            if metrics.is_enabled() || report.is_some() {
+                crate::core::telemetry::enable();
+            }
+            if let Some(events) = &events {
+                crate::core::events::open(events)?;
+                crate::core::events::run_started(&targets.iter().filter_map(|(name, _)| name.clone()).collect::<Vec<_>>());
+            }
+            let started = std::time::Instant::now();
+            let result = run_targets(targets, parallel).await;
+            crate::core::events::run_finished(started.elapsed(), result.as_ref().err());
+            if let Some(notify) = &cmd.user.notify {
+                notify_finished(notify, result.is_ok(), started.elapsed());
+            }
+            #[cfg(feature = "otel")]
+            crate::core::otel::export(started.elapsed(), result.is_ok());
+            if metrics.is_enabled() {
+                crate::core::prometheus::export(&metrics, started.elapsed(), result.is_ok());
+            }
+            if let Some(report) = &report {
+                crate::core::report::write(report, started.elapsed(), result.is_ok());
+            }
+            // `--check` and prompts without a terminal report through the exit code, returning the
+            // error would always exit with 1
+            if let Some(error) = result.as_ref().err() {
+                let code = match crate::core::check::failure(error) {
+                    | Some(failure) => Some(failure.exit_code()),
+                    | None => crate::core::migration::prompt_required(error).then_some(crate::core::migration::PromptRequired::EXIT_CODE),
+                };
+                if let Some(code) = code {
+                    crate::esay!("❌ {:#}", error);
+                    std::process::exit(code.into());
+                }
+            }
+            result
         },
         // If command parsing evolves to allow no subcommand, we could default to interactive here
     }
 }
+
+async fn run_targets(targets: Vec<(Option<String>, crate::args::Subsystem)>, parallel: usize) -> Result<()> {
+    // Running in parallel, a failing target does not stop the others
+    if parallel > 1 && targets.len() > 1 {
+        let jobs: Vec<crate::core::parallel::Job> = targets
+            .into_iter()
+            .map(|(name, subsystem)| {
+                let name = name.unwrap_or_default();
+                let job = {
+                    let name = name.clone();
+                    async move {
+                        crate::esay!("🎯 Target {}", name);
+                        let result = crate::subsystem::driver::dispatch(subsystem).await;
+                        if let Err(e) = &result {
+                            crate::esay!("❌ Target {} failed: {:#}", name, e);
+                        }
+                        result
+                    }
+                };
+                (name, Box::pin(job) as _)
+            })
+            .collect();
+        return crate::core::parallel::report("target", &crate::core::parallel::run(jobs, parallel).await);
+    }
+    for (name, subsystem) in targets {
+        match name {
+            | Some(name) => {
+                // stderr keeps JSON output of the command itself parseable
+                crate::esay!("🎯 Target {}", name);
+                crate::subsystem::driver::dispatch(subsystem).await.with_context(|| format!("target {} failed", name))?;
+            },
+            | None => crate::subsystem::driver::dispatch(subsystem).await?,
+        }
+    }
+    Ok(())
+}
+
+/// Run the notification command of the user config; failures only produce a warning
+fn notify_finished(notify: &crate::config::NotifyConfig, success: bool, elapsed: std::time::Duration) {
+    if elapsed.as_secs() < notify.min_duration.unwrap_or(10) {
+        return;
+    }
+    let status = std::process::Command::new(&notify.program)
+        .args(&notify.args)
+        .env("QOP_STATUS", if success { "success" } else { "failure" })
+        .env("QOP_ELAPSED", elapsed.as_secs().to_string())
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::null())
+        .status();
+    match status {
+        | Ok(status) if status.success() => {},
+        | Ok(status) => crate::esay!("⚠️  Notification command {} exited with {}", notify.program, status),
+        | Err(e) => crate::esay!("⚠️  Failed to run notification command {}: {}", notify.program, e),
+    }
+}
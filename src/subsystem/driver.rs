@@ -1,11 +1,15 @@
 use anyhow::Context;
 #[cfg(any(feature = "sub+postgres", feature = "sub+sqlite"))]
-use crate::core::service::MigrationService;
+use crate::core::{repo::MigrationRepository, service::{MigrationService, NewSources, Selection}};
 
 /// Note: The old `MigrationDriver` trait and driver structs have been removed.
 
 pub(crate) async fn dispatch(subsystem: crate::args::Subsystem) -> anyhow::Result<()> {
     match subsystem {
+        #[cfg(feature = "sub+postgres")]
+        crate::args::Subsystem::Postgres { path, config, command } if config.tenants.is_some() && command.per_tenant() => {
+            super::postgres::tenants::fan_out(path, config, command).await
+        }
         #[cfg(feature = "sub+postgres")]
         crate::args::Subsystem::Postgres { path, config, command } => {
             // driver removed; construct repos directly per command
@@ -15,41 +19,75 @@ pub(crate) async fn dispatch(subsystem: crate::args::Subsystem) -> anyhow::Resul
                     let svc = MigrationService::new(repo);
                     svc.init().await
                 }
-                crate::subsystem::postgres::commands::Command::New { comment, locked } => {
+                crate::subsystem::postgres::commands::Command::New { comment, locked, up, down, stdin, auto_down, template } => {
+                    let template = crate::core::migration::load_template(&path, config.templates.as_deref(), template.as_deref())?;
                     let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
                     let svc = MigrationService::new(repo);
-                    svc.new_migration(&path, comment.as_deref(), locked).await
+                    svc.new_migration(&path, comment.as_deref(), locked, NewSources { up: up.as_deref(), down: down.as_deref(), stdin, template: &template, auto_down }).await
                 }
-                crate::subsystem::postgres::commands::Command::Up { timeout, count, diff: _, dry, yes } => {
+                crate::subsystem::postgres::commands::Command::Up { tags, check: true, .. } => {
+                    let out = match crate::core::output::json() {
+                        | true => crate::core::service::OutputFormat::Json,
+                        | false => crate::core::service::OutputFormat::Human,
+                    };
                     let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
                     let svc = MigrationService::new(repo);
-                    svc.up(&path, timeout, count, yes, dry).await
+                    svc.check(&path, &tags, out).await
                 }
-                crate::subsystem::postgres::commands::Command::Down { timeout, count, remote, diff: _, dry, yes, unlock } => {
+                crate::subsystem::postgres::commands::Command::Up { timeout, count, tags, assert_idempotent: true, .. } => {
                     let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
                     let svc = MigrationService::new(repo);
-                    svc.down(&path, timeout, count, remote, yes, dry, unlock).await
+                    svc.assert_idempotent(&path, timeout, count, &tags).await
+                }
+                crate::subsystem::postgres::commands::Command::Up { timeout, count, tags, single_transaction, keep_going, shadow: true, .. } => {
+                    let (repo, shadow) = super::postgres::repo::PostgresRepo::shadow(&path, config.clone()).await?;
+                    let svc = MigrationService::new(repo);
+                    let result = svc.shadow_up(&path, &shadow.name, timeout, count, &tags, single_transaction, keep_going).await;
+                    drop(svc);
+                    shadow.remove().await?;
+                    result
+                }
+                crate::subsystem::postgres::commands::Command::Up { timeout, count, tags, diff: _, dry, yes, single_transaction, keep_going, shadow: false, resume, .. } => {
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    let result = svc.up(&path, timeout, count, &tags, yes, dry, single_transaction, keep_going, resume).await;
+                    svc.record_pending(&path).await;
+                    result
+                }
+                crate::subsystem::postgres::commands::Command::Down { timeout, count, to, tags, remote, diff: _, dry, yes, unlock } => {
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    let result = svc.down(&path, timeout, count, to.as_deref(), &tags, remote, yes, dry, unlock).await;
+                    svc.record_pending(&path).await;
+                    result
                 }
                 crate::subsystem::postgres::commands::Command::Apply(apply_cmd) => match apply_cmd {
-                    crate::subsystem::postgres::commands::MigrationApply::Up { id, timeout, dry, yes } => {
+                    crate::subsystem::postgres::commands::MigrationApply::Up { ids, from, to, timeout, dry, yes } => {
                         let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
                         let svc = MigrationService::new(repo);
-                        svc.apply_up(&path, &id, timeout, yes, dry, false).await
+                        let result = svc.apply_up(&path, Selection { ids: &ids, from: from.as_deref(), to: to.as_deref() }, timeout, yes, dry, false).await;
+                        svc.record_pending(&path).await;
+                        result
                     }
-                    crate::subsystem::postgres::commands::MigrationApply::Down { id, timeout, remote, dry, yes, unlock } => {
+                    crate::subsystem::postgres::commands::MigrationApply::Down { ids, from, to, timeout, remote, dry, yes, unlock } => {
                         let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
                         let svc = MigrationService::new(repo);
-                        svc.apply_down(&path, &id, timeout, remote, yes, dry, unlock).await
+                        let result = svc.apply_down(&path, Selection { ids: &ids, from: from.as_deref(), to: to.as_deref() }, timeout, remote, yes, dry, unlock).await;
+                        svc.record_pending(&path).await;
+                        result
                     }
                 },
-                crate::subsystem::postgres::commands::Command::List { output } => {
+                crate::subsystem::postgres::commands::Command::List { tags, output, check, wide } => {
                     let out = match output {
                         super::postgres::commands::Output::Human => crate::core::service::OutputFormat::Human,
                         super::postgres::commands::Output::Json => crate::core::service::OutputFormat::Json,
                     };
                     let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
                     let svc = MigrationService::new(repo);
-                    svc.list(out).await
+                    match check {
+                        | true => svc.check(&path, &tags, out).await,
+                        | false => svc.list(&tags, wide, out).await,
+                    }
                 }
                 crate::subsystem::postgres::commands::Command::Config(cfg) => match cfg {
                     super::postgres::commands::ConfigCommand::Init { connection } => {
@@ -68,20 +106,323 @@ pub(crate) async fn dispatch(subsystem: crate::args::Subsystem) -> anyhow::Resul
                         println!("Bootstrapped postgres config to {}", path.display());
                         Ok(())
                     }
+                    super::postgres::commands::ConfigCommand::Upgrade { dry_run } => {
+                        let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read config file: {}", path.display()))?;
+                        let (upgraded, changes) = crate::core::config_upgrade::upgrade_config(&content, env!("CARGO_PKG_VERSION"))?;
+                        if changes.is_empty() {
+                            crate::summary!("✅ Config is up to date.");
+                            return Ok(());
+                        }
+                        crate::say!("📋 Changes to {}:", path.display());
+                        for change in &changes {
+                            crate::say!("  - {}", change);
+                        }
+                        if dry_run {
+                            println!("\n{}", upgraded);
+                            return Ok(());
+                        }
+                        // Only write configs this version can actually load
+                        toml::from_str::<crate::config::Config>(&upgraded).context("The upgraded config is still invalid; nothing was written")?;
+                        std::fs::write(&path, &upgraded).with_context(|| format!("Failed to write config file to: {}", path.display()))?;
+                        crate::summary!("✅ Upgraded config written to {}", path.display());
+                        Ok(())
+                    }
                 },
                 crate::subsystem::postgres::commands::Command::History(history_cmd) => match history_cmd {
                     crate::subsystem::postgres::commands::HistoryCommand::Fix => {
                         let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
                         super::postgres::migration::history_fix(&path, &repo.config.schema, &repo.config.tables.migrations, &repo.pool).await
                     }
+                    crate::subsystem::postgres::commands::HistoryCommand::Verify { fix, yes, output } => {
+                        let out = match output {
+                            super::postgres::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                            super::postgres::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                        };
+                        let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                        let svc = MigrationService::new(repo);
+                        svc.history_verify(fix, yes, out).await
+                    }
                     crate::subsystem::postgres::commands::HistoryCommand::Sync => {
                         let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
                         super::postgres::migration::history_sync(&path, &repo.config.schema, &repo.config.tables.migrations, &repo.pool).await
                     }
                 },
-                crate::subsystem::postgres::commands::Command::Diff => {
+                crate::subsystem::postgres::commands::Command::Verify { output } => {
+                    let out = match output {
+                        super::postgres::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                        super::postgres::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                    };
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.verify(&path, out).await
+                }
+                crate::subsystem::postgres::commands::Command::Squash { from, to, comment, yes } => {
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.squash(&path, &from, &to, comment.as_deref(), yes).await
+                }
+                crate::subsystem::postgres::commands::Command::Baseline { id, yes } => {
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.baseline(&path, &id, yes).await
+                }
+                crate::subsystem::postgres::commands::Command::Log(log_cmd) => {
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    match log_cmd {
+                        crate::subsystem::postgres::commands::LogCommand::List { migration_id, operation, since, until, limit, output } => {
+                            let out = match output {
+                                super::postgres::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                                super::postgres::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                            };
+                            svc.log_list(migration_id.as_deref(), operation.as_deref(), since.as_deref(), until.as_deref(), limit, out).await
+                        }
+                        crate::subsystem::postgres::commands::LogCommand::Show { id, output } => {
+                            let out = match output {
+                                super::postgres::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                                super::postgres::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                            };
+                            svc.log_show(&id, out).await
+                        }
+                        crate::subsystem::postgres::commands::LogCommand::Export { format, since, until, out } => {
+                            let format = match format {
+                                super::postgres::commands::LogExportFormat::Csv => crate::core::log::ExportFormat::Csv,
+                                super::postgres::commands::LogExportFormat::Json => crate::core::log::ExportFormat::Json,
+                            };
+                            svc.log_export(format, since.as_deref(), until.as_deref(), out.as_deref()).await
+                        }
+                    }
+                }
+                crate::subsystem::postgres::commands::Command::Rename { id, new_id, comment, yes } => {
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.rename(&path, &id, &new_id, comment.as_deref(), yes).await
+                }
+                crate::subsystem::postgres::commands::Command::Lock { id } => {
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.set_locked(&path, &id, true).await
+                }
+                crate::subsystem::postgres::commands::Command::Unlock { id } => {
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.set_locked(&path, &id, false).await
+                }
+                crate::subsystem::postgres::commands::Command::Remove { id, force, also_remote, yes } => {
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.remove(&path, &id, force, also_remote, yes).await
+                }
+                crate::subsystem::postgres::commands::Command::Prune { yes } => {
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.prune(&path, yes).await
+                }
+                crate::subsystem::postgres::commands::Command::Repair { id, up, down, yes } => {
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.repair(&path, &id, up, down, yes).await
+                }
+                crate::subsystem::postgres::commands::Command::Snapshot { out } => {
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.snapshot(out.as_deref()).await
+                }
+                crate::subsystem::postgres::commands::Command::Test { timeout } => {
+                    let (repo, db) = super::postgres::repo::PostgresRepo::ephemeral(&path, config.clone()).await?;
+                    let svc = MigrationService::new(repo);
+                    let result = svc.test(&path, timeout).await;
+                    drop(svc);
+                    db.remove().await?;
+                    result
+                }
+                crate::subsystem::postgres::commands::Command::Generate { from_db: true, comment, yes, .. } => {
+                    let live = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let (repo, db) = super::postgres::repo::PostgresRepo::ephemeral(&path, config.clone()).await?;
+                    let svc = MigrationService::new(repo);
+                    let result = svc.generate_from_db(&path, &live, comment.as_deref(), yes).await;
+                    drop(svc);
+                    db.remove().await?;
+                    result
+                }
+                crate::subsystem::postgres::commands::Command::Generate { schema, comment, yes, .. } => {
+                    let file = crate::core::declarative::schema_file(&path, config.schema_file.as_deref(), schema.as_deref());
+                    let (repo, db) = super::postgres::repo::PostgresRepo::ephemeral(&path, config.clone()).await?;
+                    let (desired, desired_db) = match super::postgres::repo::PostgresRepo::ephemeral(&path, config.clone()).await {
+                        | Ok(desired) => desired,
+                        | Err(e) => {
+                            drop(repo);
+                            db.remove().await?;
+                            return Err(e);
+                        },
+                    };
+                    let svc = MigrationService::new(repo);
+                    let result = svc.generate(&path, &desired, &file, comment.as_deref(), yes).await;
+                    drop((svc, desired));
+                    db.remove().await?;
+                    desired_db.remove().await?;
+                    result
+                }
+                crate::subsystem::postgres::commands::Command::Lint { all, strict, large_table_rows, output } => {
+                    let out = match output {
+                        super::postgres::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                        super::postgres::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                    };
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.lint(&path, all, strict, large_table_rows, out).await
+                }
+                crate::subsystem::postgres::commands::Command::Plan { count, tags, output } => {
+                    let out = match output {
+                        super::postgres::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                        super::postgres::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                    };
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.plan(&path, count, &tags, out).await
+                }
+                crate::subsystem::postgres::commands::Command::Fmt { check, all } => {
+                    let applied = match all {
+                        | true => Default::default(),
+                        | false => super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?.fetch_applied_ids().await?,
+                    };
+                    crate::core::fmt::format_migrations(&path, &config.fmt.clone().unwrap_or_default(), check, &applied)
+                }
+                crate::subsystem::postgres::commands::Command::Tui { timeout } => {
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.tui(&path, timeout).await
+                }
+                crate::subsystem::postgres::commands::Command::Graph { format } => {
+                    let format = match format {
+                        super::postgres::commands::GraphFormat::Ascii => crate::core::graph::GraphFormat::Ascii,
+                        super::postgres::commands::GraphFormat::Dot => crate::core::graph::GraphFormat::Dot,
+                    };
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.graph(format).await
+                }
+                crate::subsystem::postgres::commands::Command::Export { out } => {
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.export(&path, &out).await
+                }
+                crate::subsystem::postgres::commands::Command::Import { bundle, from, history_table, history, force, yes } => {
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    match from {
+                        super::postgres::commands::ImportFormat::Bundle => svc.import(&path, &bundle, force, yes).await,
+                        super::postgres::commands::ImportFormat::Liquibase => svc.import_liquibase(&path, &bundle, history_table.as_deref(), history, yes).await,
+                        super::postgres::commands::ImportFormat::Sqlx => svc.import_sqlx(&path, &bundle, history_table.as_deref(), history, yes).await,
+                        super::postgres::commands::ImportFormat::Alembic => svc.import_alembic(&path, &bundle, history_table.as_deref(), history, yes).await,
+                        super::postgres::commands::ImportFormat::Dbmate => svc.import_dbmate(&path, &bundle, history_table.as_deref(), history, yes).await,
+                    }
+                }
+                crate::subsystem::postgres::commands::Command::Seed(seed_cmd) => {
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    match seed_cmd {
+                        crate::subsystem::postgres::commands::SeedCommand::Run { profile, allowed, timeout, force, dry, yes } => {
+                            svc.seed_run(profile.as_deref(), allowed.as_deref(), timeout, force, yes, dry).await
+                        }
+                        crate::subsystem::postgres::commands::SeedCommand::List { profile, output } => {
+                            let out = match output {
+                                super::postgres::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                                super::postgres::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                            };
+                            svc.seed_list(profile.as_deref(), out).await
+                        }
+                        crate::subsystem::postgres::commands::SeedCommand::Reset { profile, yes } => {
+                            svc.seed_reset(profile.as_deref(), yes).await
+                        }
+                    }
+                }
+                crate::subsystem::postgres::commands::Command::Meta(meta_cmd) => match meta_cmd {
+                    crate::subsystem::postgres::commands::MetaCommand::Check { output } => {
+                        let out = match output {
+                            super::postgres::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                            super::postgres::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                        };
+                        crate::core::meta::check_metas(&path, out)
+                    }
+                    crate::subsystem::postgres::commands::MetaCommand::Fix { yes } => crate::core::meta::fix_metas(&path, yes),
+                },
+                crate::subsystem::postgres::commands::Command::Bootstrap { comment, baseline, yes } => {
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.bootstrap(&path, comment.as_deref(), baseline, yes).await
+                }
+                crate::subsystem::postgres::commands::Command::Wait { timeout, interval } => {
+                    crate::core::wait::wait_until_ready(timeout, interval, || async {
+                        let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), false).await?;
+                        repo.ping().await
+                    })
+                    .await
+                }
+                crate::subsystem::postgres::commands::Command::Ping { output } => {
+                    let out = match output {
+                        super::postgres::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                        super::postgres::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                    };
+                    let source = config.connection.describe();
+                    let target = super::postgres::migration::resolve_connection(&path, &config)?;
+                    let start = std::time::Instant::now();
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), false)
+                        .await
+                        .with_context(|| format!("Failed to connect to {} (from {})", crate::core::ping::redact_connection(&target), source))?;
+                    let connect = start.elapsed();
+                    let svc = MigrationService::new(repo);
+                    svc.ping(&source, &target, connect, out).await
+                }
+                crate::subsystem::postgres::commands::Command::Console { client, args } => {
+                    let uri = super::postgres::migration::resolve_connection(&path, &config)?;
+                    // The password goes through the environment so it does not show up in the process list
+                    let (uri, password) = crate::core::console::split_password(&uri);
+                    let mut envs = vec![("PGOPTIONS", format!("-c search_path={}", config.schema))];
+                    envs.extend(super::postgres::migration::ssl_env(&path, &config));
+                    if let Some(password) = password {
+                        envs.push(("PGPASSWORD", password));
+                    }
+                    let client_args: Vec<String> = std::iter::once(uri).chain(args).collect();
+                    crate::core::console::exec_client(client.as_deref().unwrap_or("psql"), &client_args, &envs)
+                }
+                crate::subsystem::postgres::commands::Command::Drift { snapshot, output } => {
+                    let out = match output {
+                        super::postgres::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                        super::postgres::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                    };
+                    let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.drift(snapshot.as_deref(), out).await
+                }
+                crate::subsystem::postgres::commands::Command::Bench { timeout, iterations, baseline, save, threshold, output } => {
+                    let out = match output {
+                        super::postgres::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                        super::postgres::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                    };
+                    // Migrations run for real, on copies of the database that are dropped afterwards
+                    let (repo, shadow) = super::postgres::repo::PostgresRepo::shadow(&path, config.clone()).await?;
+                    let svc = MigrationService::new(repo);
+                    let run = async |migrations: &[(String, String, crate::core::migration::ExecOptions)]| {
+                        let (repo, shadow) = super::postgres::repo::PostgresRepo::shadow(&path, config.clone()).await?;
+                        let timings = repo.bench_migrations(migrations).await;
+                        drop(repo);
+                        shadow.remove().await?;
+                        timings
+                    };
+                    let result = svc.bench(&path, timeout, iterations, baseline.as_deref(), save.as_deref(), threshold, out, run).await;
+                    drop(svc);
+                    shadow.remove().await?;
+                    result
+                }
+                crate::subsystem::postgres::commands::Command::Diff { down, count, id, output } => {
+                    let out = match output {
+                        super::postgres::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                        super::postgres::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                    };
                     let repo = super::postgres::repo::PostgresRepo::from_config(&path, config.clone(), true).await?;
-                    super::postgres::migration::diff(&path, &repo.config.schema, &repo.config.tables.migrations, &repo.pool).await
+                    let svc = MigrationService::new(repo);
+                    svc.diff(&path, down, count, id.as_deref(), out).await
                 },
             }
         }
@@ -94,41 +435,72 @@ pub(crate) async fn dispatch(subsystem: crate::args::Subsystem) -> anyhow::Resul
                     let svc = MigrationService::new(repo);
                     svc.init().await
                 }
-                crate::subsystem::sqlite::commands::Command::New { comment, locked } => {
+                crate::subsystem::sqlite::commands::Command::New { comment, locked, up, down, stdin, auto_down, template } => {
+                    let template = crate::core::migration::load_template(&path, config.templates.as_deref(), template.as_deref())?;
                     let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
                     let svc = MigrationService::new(repo);
-                    svc.new_migration(&path, comment.as_deref(), locked).await
+                    svc.new_migration(&path, comment.as_deref(), locked, NewSources { up: up.as_deref(), down: down.as_deref(), stdin, template: &template, auto_down }).await
                 }
-                crate::subsystem::sqlite::commands::Command::Up { timeout, count, diff: _, dry, yes } => {
+                crate::subsystem::sqlite::commands::Command::Up { tags, check: true, .. } => {
+                    let out = match crate::core::output::json() {
+                        | true => crate::core::service::OutputFormat::Json,
+                        | false => crate::core::service::OutputFormat::Human,
+                    };
                     let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
                     let svc = MigrationService::new(repo);
-                    svc.up(&path, timeout, count, yes, dry).await
+                    svc.check(&path, &tags, out).await
                 }
-                crate::subsystem::sqlite::commands::Command::Down { timeout, count, remote, diff: _, dry, yes, unlock } => {
+                crate::subsystem::sqlite::commands::Command::Up { timeout, count, tags, assert_idempotent: true, .. } => {
                     let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
                     let svc = MigrationService::new(repo);
-                    svc.down(&path, timeout, count, remote, yes, dry, unlock).await
+                    svc.assert_idempotent(&path, timeout, count, &tags).await
+                }
+                crate::subsystem::sqlite::commands::Command::Up { timeout, count, tags, single_transaction, keep_going, shadow: true, .. } => {
+                    let (repo, shadow) = super::sqlite::repo::SqliteRepo::shadow(&path, config.clone()).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.shadow_up(&path, &shadow.path.display().to_string(), timeout, count, &tags, single_transaction, keep_going).await
+                }
+                crate::subsystem::sqlite::commands::Command::Up { timeout, count, tags, diff: _, dry, yes, single_transaction, keep_going, shadow: false, resume, .. } => {
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    let result = svc.up(&path, timeout, count, &tags, yes, dry, single_transaction, keep_going, resume).await;
+                    svc.record_pending(&path).await;
+                    result
+                }
+                crate::subsystem::sqlite::commands::Command::Down { timeout, count, to, tags, remote, diff: _, dry, yes, unlock } => {
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    let result = svc.down(&path, timeout, count, to.as_deref(), &tags, remote, yes, dry, unlock).await;
+                    svc.record_pending(&path).await;
+                    result
                 }
                 crate::subsystem::sqlite::commands::Command::Apply(apply_cmd) => match apply_cmd {
-                    crate::subsystem::sqlite::commands::MigrationApply::Up { id, timeout, dry, yes } => {
+                    crate::subsystem::sqlite::commands::MigrationApply::Up { ids, from, to, timeout, dry, yes } => {
                         let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
                         let svc = MigrationService::new(repo);
-                        svc.apply_up(&path, &id, timeout, yes, dry, false).await
+                        let result = svc.apply_up(&path, Selection { ids: &ids, from: from.as_deref(), to: to.as_deref() }, timeout, yes, dry, false).await;
+                        svc.record_pending(&path).await;
+                        result
                     }
-                    crate::subsystem::sqlite::commands::MigrationApply::Down { id, timeout, remote, dry, yes, unlock } => {
+                    crate::subsystem::sqlite::commands::MigrationApply::Down { ids, from, to, timeout, remote, dry, yes, unlock } => {
                         let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
                         let svc = MigrationService::new(repo);
-                        svc.apply_down(&path, &id, timeout, remote, yes, dry, unlock).await
+                        let result = svc.apply_down(&path, Selection { ids: &ids, from: from.as_deref(), to: to.as_deref() }, timeout, remote, yes, dry, unlock).await;
+                        svc.record_pending(&path).await;
+                        result
                     }
                 },
-                crate::subsystem::sqlite::commands::Command::List { output } => {
+                crate::subsystem::sqlite::commands::Command::List { tags, output, check, wide } => {
                     let out = match output {
                         super::sqlite::commands::Output::Human => crate::core::service::OutputFormat::Human,
                         super::sqlite::commands::Output::Json => crate::core::service::OutputFormat::Json,
                     };
                     let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
                     let svc = MigrationService::new(repo);
-                    svc.list(out).await
+                    match check {
+                        | true => svc.check(&path, &tags, out).await,
+                        | false => svc.list(&tags, wide, out).await,
+                    }
                 }
                 crate::subsystem::sqlite::commands::Command::Config(cfg) => match cfg {
                     super::sqlite::commands::ConfigCommand::Init { path: db_path } => {
@@ -147,20 +519,293 @@ pub(crate) async fn dispatch(subsystem: crate::args::Subsystem) -> anyhow::Resul
                         println!("Bootstrapped sqlite config to {}", path.display());
                         Ok(())
                     }
+                    super::sqlite::commands::ConfigCommand::Upgrade { dry_run } => {
+                        let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read config file: {}", path.display()))?;
+                        let (upgraded, changes) = crate::core::config_upgrade::upgrade_config(&content, env!("CARGO_PKG_VERSION"))?;
+                        if changes.is_empty() {
+                            crate::summary!("✅ Config is up to date.");
+                            return Ok(());
+                        }
+                        crate::say!("📋 Changes to {}:", path.display());
+                        for change in &changes {
+                            crate::say!("  - {}", change);
+                        }
+                        if dry_run {
+                            println!("\n{}", upgraded);
+                            return Ok(());
+                        }
+                        // Only write configs this version can actually load
+                        toml::from_str::<crate::config::Config>(&upgraded).context("The upgraded config is still invalid; nothing was written")?;
+                        std::fs::write(&path, &upgraded).with_context(|| format!("Failed to write config file to: {}", path.display()))?;
+                        crate::summary!("✅ Upgraded config written to {}", path.display());
+                        Ok(())
+                    }
                 },
                 crate::subsystem::sqlite::commands::Command::History(history_cmd) => match history_cmd {
                     crate::subsystem::sqlite::commands::HistoryCommand::Fix => {
                         let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
                         super::sqlite::migration::history_fix(&path, &repo.config.tables.migrations, &repo.pool).await
                     }
+                    crate::subsystem::sqlite::commands::HistoryCommand::Verify { fix, yes, output } => {
+                        let out = match output {
+                            super::sqlite::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                            super::sqlite::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                        };
+                        let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                        let svc = MigrationService::new(repo);
+                        svc.history_verify(fix, yes, out).await
+                    }
                     crate::subsystem::sqlite::commands::HistoryCommand::Sync => {
                         let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
                         super::sqlite::migration::history_sync(&path, &repo.config.tables.migrations, &repo.pool).await
                     }
                 },
-                crate::subsystem::sqlite::commands::Command::Diff => {
+                crate::subsystem::sqlite::commands::Command::Verify { output } => {
+                    let out = match output {
+                        super::sqlite::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                        super::sqlite::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                    };
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.verify(&path, out).await
+                }
+                crate::subsystem::sqlite::commands::Command::Squash { from, to, comment, yes } => {
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.squash(&path, &from, &to, comment.as_deref(), yes).await
+                }
+                crate::subsystem::sqlite::commands::Command::Baseline { id, yes } => {
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.baseline(&path, &id, yes).await
+                }
+                crate::subsystem::sqlite::commands::Command::Log(log_cmd) => {
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    match log_cmd {
+                        crate::subsystem::sqlite::commands::LogCommand::List { migration_id, operation, since, until, limit, output } => {
+                            let out = match output {
+                                super::sqlite::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                                super::sqlite::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                            };
+                            svc.log_list(migration_id.as_deref(), operation.as_deref(), since.as_deref(), until.as_deref(), limit, out).await
+                        }
+                        crate::subsystem::sqlite::commands::LogCommand::Show { id, output } => {
+                            let out = match output {
+                                super::sqlite::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                                super::sqlite::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                            };
+                            svc.log_show(&id, out).await
+                        }
+                        crate::subsystem::sqlite::commands::LogCommand::Export { format, since, until, out } => {
+                            let format = match format {
+                                super::sqlite::commands::LogExportFormat::Csv => crate::core::log::ExportFormat::Csv,
+                                super::sqlite::commands::LogExportFormat::Json => crate::core::log::ExportFormat::Json,
+                            };
+                            svc.log_export(format, since.as_deref(), until.as_deref(), out.as_deref()).await
+                        }
+                    }
+                }
+                crate::subsystem::sqlite::commands::Command::Rename { id, new_id, comment, yes } => {
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.rename(&path, &id, &new_id, comment.as_deref(), yes).await
+                }
+                crate::subsystem::sqlite::commands::Command::Lock { id } => {
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.set_locked(&path, &id, true).await
+                }
+                crate::subsystem::sqlite::commands::Command::Unlock { id } => {
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.set_locked(&path, &id, false).await
+                }
+                crate::subsystem::sqlite::commands::Command::Remove { id, force, also_remote, yes } => {
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.remove(&path, &id, force, also_remote, yes).await
+                }
+                crate::subsystem::sqlite::commands::Command::Prune { yes } => {
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.prune(&path, yes).await
+                }
+                crate::subsystem::sqlite::commands::Command::Repair { id, up, down, yes } => {
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.repair(&path, &id, up, down, yes).await
+                }
+                crate::subsystem::sqlite::commands::Command::Snapshot { out } => {
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.snapshot(out.as_deref()).await
+                }
+                crate::subsystem::sqlite::commands::Command::Test { timeout } => {
+                    let (repo, _db) = super::sqlite::repo::SqliteRepo::ephemeral(&path, config.clone()).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.test(&path, timeout).await
+                }
+                crate::subsystem::sqlite::commands::Command::Generate { from_db: true, comment, yes, .. } => {
+                    let live = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let (repo, _db) = super::sqlite::repo::SqliteRepo::ephemeral(&path, config.clone()).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.generate_from_db(&path, &live, comment.as_deref(), yes).await
+                }
+                crate::subsystem::sqlite::commands::Command::Generate { schema, comment, yes, .. } => {
+                    let file = crate::core::declarative::schema_file(&path, config.schema_file.as_deref(), schema.as_deref());
+                    let (repo, _db) = super::sqlite::repo::SqliteRepo::ephemeral(&path, config.clone()).await?;
+                    let (desired, _desired_db) = super::sqlite::repo::SqliteRepo::ephemeral(&path, config.clone()).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.generate(&path, &desired, &file, comment.as_deref(), yes).await
+                }
+                crate::subsystem::sqlite::commands::Command::Lint { all, strict, large_table_rows, output } => {
+                    let out = match output {
+                        super::sqlite::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                        super::sqlite::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                    };
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.lint(&path, all, strict, large_table_rows, out).await
+                }
+                crate::subsystem::sqlite::commands::Command::Plan { count, tags, output } => {
+                    let out = match output {
+                        super::sqlite::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                        super::sqlite::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                    };
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.plan(&path, count, &tags, out).await
+                }
+                crate::subsystem::sqlite::commands::Command::Fmt { check, all } => {
+                    let applied = match all {
+                        | true => Default::default(),
+                        | false => super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?.fetch_applied_ids().await?,
+                    };
+                    crate::core::fmt::format_migrations(&path, &config.fmt.clone().unwrap_or_default(), check, &applied)
+                }
+                crate::subsystem::sqlite::commands::Command::Tui { timeout } => {
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.tui(&path, timeout).await
+                }
+                crate::subsystem::sqlite::commands::Command::Graph { format } => {
+                    let format = match format {
+                        super::sqlite::commands::GraphFormat::Ascii => crate::core::graph::GraphFormat::Ascii,
+                        super::sqlite::commands::GraphFormat::Dot => crate::core::graph::GraphFormat::Dot,
+                    };
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.graph(format).await
+                }
+                crate::subsystem::sqlite::commands::Command::Export { out } => {
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.export(&path, &out).await
+                }
+                crate::subsystem::sqlite::commands::Command::Import { bundle, from, history_table, history, force, yes } => {
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    match from {
+                        super::sqlite::commands::ImportFormat::Bundle => svc.import(&path, &bundle, force, yes).await,
+                        super::sqlite::commands::ImportFormat::Liquibase => svc.import_liquibase(&path, &bundle, history_table.as_deref(), history, yes).await,
+                        super::sqlite::commands::ImportFormat::Sqlx => svc.import_sqlx(&path, &bundle, history_table.as_deref(), history, yes).await,
+                        super::sqlite::commands::ImportFormat::Alembic => svc.import_alembic(&path, &bundle, history_table.as_deref(), history, yes).await,
+                        super::sqlite::commands::ImportFormat::Dbmate => svc.import_dbmate(&path, &bundle, history_table.as_deref(), history, yes).await,
+                    }
+                }
+                crate::subsystem::sqlite::commands::Command::Seed(seed_cmd) => {
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    match seed_cmd {
+                        crate::subsystem::sqlite::commands::SeedCommand::Run { profile, allowed, timeout, force, dry, yes } => {
+                            svc.seed_run(profile.as_deref(), allowed.as_deref(), timeout, force, yes, dry).await
+                        }
+                        crate::subsystem::sqlite::commands::SeedCommand::List { profile, output } => {
+                            let out = match output {
+                                super::sqlite::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                                super::sqlite::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                            };
+                            svc.seed_list(profile.as_deref(), out).await
+                        }
+                        crate::subsystem::sqlite::commands::SeedCommand::Reset { profile, yes } => {
+                            svc.seed_reset(profile.as_deref(), yes).await
+                        }
+                    }
+                }
+                crate::subsystem::sqlite::commands::Command::Meta(meta_cmd) => match meta_cmd {
+                    crate::subsystem::sqlite::commands::MetaCommand::Check { output } => {
+                        let out = match output {
+                            super::sqlite::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                            super::sqlite::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                        };
+                        crate::core::meta::check_metas(&path, out)
+                    }
+                    crate::subsystem::sqlite::commands::MetaCommand::Fix { yes } => crate::core::meta::fix_metas(&path, yes),
+                },
+                crate::subsystem::sqlite::commands::Command::Bootstrap { comment, baseline, yes } => {
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.bootstrap(&path, comment.as_deref(), baseline, yes).await
+                }
+                crate::subsystem::sqlite::commands::Command::Wait { timeout, interval } => {
+                    crate::core::wait::wait_until_ready(timeout, interval, || async {
+                        let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), false).await?;
+                        repo.ping().await
+                    })
+                    .await
+                }
+                crate::subsystem::sqlite::commands::Command::Ping { output } => {
+                    let out = match output {
+                        super::sqlite::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                        super::sqlite::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                    };
+                    let source = config.connection.describe();
+                    let target = super::sqlite::migration::resolve_connection(&path, &config)?;
+                    let start = std::time::Instant::now();
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), false)
+                        .await
+                        .with_context(|| format!("Failed to connect to {} (from {})", crate::core::ping::redact_connection(&target), source))?;
+                    let connect = start.elapsed();
+                    let svc = MigrationService::new(repo);
+                    svc.ping(&source, &target, connect, out).await
+                }
+                crate::subsystem::sqlite::commands::Command::Console { client, args } => {
+                    let uri = super::sqlite::migration::resolve_connection(&path, &config)?;
+                    let client_args: Vec<String> = std::iter::once(crate::core::console::sqlite_file(&uri)).chain(args).collect();
+                    crate::core::console::exec_client(client.as_deref().unwrap_or("sqlite3"), &client_args, &[])
+                }
+                crate::subsystem::sqlite::commands::Command::Drift { snapshot, output } => {
+                    let out = match output {
+                        super::sqlite::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                        super::sqlite::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                    };
                     let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
-                    super::sqlite::migration::diff(&path, &repo.config.tables.migrations, &repo.pool).await
+                    let svc = MigrationService::new(repo);
+                    svc.drift(snapshot.as_deref(), out).await
+                }
+                crate::subsystem::sqlite::commands::Command::Bench { timeout, iterations, baseline, save, threshold, output } => {
+                    let out = match output {
+                        super::sqlite::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                        super::sqlite::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                    };
+                    // Migrations run for real, on copies of the database that are removed afterwards
+                    let (repo, _shadow) = super::sqlite::repo::SqliteRepo::shadow(&path, config.clone()).await?;
+                    let svc = MigrationService::new(repo);
+                    let run = async |migrations: &[(String, String, crate::core::migration::ExecOptions)]| {
+                        let (repo, _shadow) = super::sqlite::repo::SqliteRepo::shadow(&path, config.clone()).await?;
+                        repo.bench_migrations(migrations).await
+                    };
+                    svc.bench(&path, timeout, iterations, baseline.as_deref(), save.as_deref(), threshold, out, run).await
+                }
+                crate::subsystem::sqlite::commands::Command::Diff { down, count, id, output } => {
+                    let out = match output {
+                        super::sqlite::commands::Output::Human => crate::core::service::OutputFormat::Human,
+                        super::sqlite::commands::Output::Json => crate::core::service::OutputFormat::Json,
+                    };
+                    let repo = super::sqlite::repo::SqliteRepo::from_config(&path, config.clone(), true).await?;
+                    let svc = MigrationService::new(repo);
+                    svc.diff(&path, down, count, id.as_deref(), out).await
                 },
             }
         }
@@ -1,32 +1,183 @@
 use serde::{Deserialize, Serialize};
-use crate::config::DataSource;
+use crate::config::{DataSource, Tables};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct SubsystemPostgres {
     pub connection: DataSource<String>,
+    /// Server `test` and `generate` create their throwaway database on, instead of starting a
+    /// container; the user needs the CREATEDB privilege
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scratch: Option<DataSource<String>>,
+    #[serde(default, deserialize_with = "crate::core::duration::deserialize_seconds")]
     pub timeout: Option<u64>,
+    /// Seconds a statement may wait for a lock before failing, applied next to `timeout`
+    #[serde(default, deserialize_with = "crate::core::duration::deserialize_seconds", skip_serializing_if = "Option::is_none")]
+    pub lock_timeout: Option<u64>,
+    /// Look for long-running transactions holding locks on the tables a migration alters before running it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lock_check: Option<LockCheckConfig>,
+    /// Schema of the tracking tables
     pub schema: String,
+    /// Schemas searched for unqualified names in migrations, in order; the server default when empty
+    #[serde(default, alias = "schemas", skip_serializing_if = "Vec::is_empty")]
+    pub search_path: Vec<String>,
+    #[serde(default)]
     pub tables: Tables,
+    /// Refuse destructive commands and always confirm `up`, e.g. for production profiles
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub protected: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fmt: Option<crate::core::fmt::FormatConfig>,
+    /// Directory of the templates for `new`, relative to the config file (default `templates`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub templates: Option<String>,
+    /// Directory of the seeds, relative to the config file (default `seeds`), e.g. `fixtures`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seeds: Option<String>,
+    /// Seeds a protected config still runs, by name as shown by `seed list`; all others are skipped
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_seeds: Vec<String>,
+    /// SQL snippets and commands run around `up`, `down` and `apply`
+    #[serde(default, skip_serializing_if = "crate::core::hooks::Hooks::is_empty")]
+    pub hooks: crate::core::hooks::Hooks,
+    /// Schema snapshot rewritten after every up/down, relative to the config file; compared by `drift`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<String>,
+    /// Declarative schema diffed by `generate`, relative to the config file (default `schema.sql`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_file: Option<String>,
+    /// Seconds `up` and `down` wait for the run lock of another qop run (default 60)
+    #[serde(default, deserialize_with = "crate::core::duration::deserialize_seconds", skip_serializing_if = "Option::is_none")]
+    pub run_lock_timeout: Option<u64>,
+    /// Skip the run lock, set by `--no-lock`
+    #[serde(skip)]
+    pub no_run_lock: bool,
+    /// Naming scheme of new migrations
+    #[serde(default, skip_serializing_if = "crate::core::migration::IdConfig::is_default")]
+    pub ids: crate::core::migration::IdConfig,
+    /// Retries of migrations that fail on a transient error
+    #[serde(default, skip_serializing_if = "crate::core::migration::RetryConfig::is_default")]
+    pub retry: crate::core::migration::RetryConfig,
+    /// Times commands that change the database may run, e.g. `up`, `down`, `init`, `squash` or `tui`;
+    /// refused outside of them unless `--override-window` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<crate::core::window::WindowConfig>,
+    /// Schemas that each get all migrations and tracking tables of their own, for schema-per-tenant databases
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenants: Option<TenantsConfig>,
+    /// Profile the config was loaded with; set from the command line, never read from the file
+    #[serde(skip)]
+    pub profile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ssl_mode: Option<SslMode>,
+    /// CA certificate used to verify the server, relative to the config file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub root_cert: Option<String>,
+    /// Client certificate for mutual TLS, relative to the config file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_cert: Option<String>,
+    /// Private key of the client certificate, relative to the config file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_key: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The `lock_check` table: before DDL runs, sessions whose transaction has been open for a while
+/// and holds a lock on an affected table are reported, or waited for
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct LockCheckConfig {
+    #[serde(default)]
+    pub mode: LockCheckMode,
+    /// Seconds a transaction has to be open to count as long-running (default 10)
+    #[serde(default, deserialize_with = "crate::core::duration::deserialize_seconds", skip_serializing_if = "Option::is_none")]
+    pub min_duration: Option<u64>,
+    /// Seconds `wait` waits for such transactions to finish before failing (default 300)
+    #[serde(default, deserialize_with = "crate::core::duration::deserialize_seconds", skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockCheckMode {
+    /// Report the sessions and run the migration anyway
+    #[default]
+    Warn,
+    /// Wait until the sessions are gone, up to `timeout`
+    Wait,
+}
+
+/// The `tenants` table: commands that work on the database run once per tenant schema
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
-pub struct Tables {
-    pub migrations: String,
-    pub log: String,
+pub struct TenantsConfig {
+    /// Tenant schemas, e.g. `["tenant_a", "tenant_b"]`
+    #[serde(default)]
+    pub schemas: Vec<String>,
+    /// Query whose first column lists further tenant schemas, run on every invocation, e.g.
+    /// `SELECT schema_name FROM tenants WHERE active`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    /// Tenants migrated at a time (default 1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parallel: Option<usize>,
+}
+
+/// libpq `sslmode`; overrides the mode given in the connection string
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SslMode {
+    Disable,
+    Allow,
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+impl SslMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            | SslMode::Disable => "disable",
+            | SslMode::Allow => "allow",
+            | SslMode::Prefer => "prefer",
+            | SslMode::Require => "require",
+            | SslMode::VerifyCa => "verify-ca",
+            | SslMode::VerifyFull => "verify-full",
+        }
+    }
 }
 
 impl Default for SubsystemPostgres {
     fn default() -> Self {
         Self {
             connection: DataSource::Static(String::new()),
+            scratch: None,
             timeout: None,
+            lock_timeout: None,
+            lock_check: None,
             schema: "public".to_string(),
-            tables: Tables {
-                migrations: "__qop_migrations".to_string(),
-                log: "__qop_log".to_string(),
-            },
+            search_path: Vec::new(),
+            tables: Tables::default(),
+            protected: false,
+            fmt: None,
+            seeds: None,
+            allowed_seeds: Vec::new(),
+            hooks: Default::default(),
+            snapshot: None,
+            schema_file: None,
+            run_lock_timeout: None,
+            no_run_lock: false,
+            ids: Default::default(),
+            retry: Default::default(),
+            window: None,
+            tenants: None,
+            profile: None,
+            templates: None,
+            ssl_mode: None,
+            root_cert: None,
+            client_cert: None,
+            client_key: None,
         }
     }
 }
@@ -4,10 +4,11 @@ use {
     anyhow::{Context, Result},
     chrono::{NaiveDateTime, Utc},
     sqlx::{postgres::PgRow, Pool, Postgres, QueryBuilder, Row},
-    sqlx::postgres::PgPoolOptions,
+    sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode},
     std::{
         collections::{HashMap, HashSet},
-        path::Path,
+        path::{Path, PathBuf},
+        str::FromStr,
     },
 };
 use std::io::{self, Write};
@@ -36,39 +37,101 @@ pub(crate) fn build_table_query<'a>(base_sql: &'a str, schema: &str, table: &str
     query
 }
 
-pub(crate) async fn set_timeout_if_needed<'e, E>(executor: E, timeout_seconds: Option<u64>) -> Result<()>
-where
-    E: sqlx::Executor<'e, Database = Postgres>,
-{
+pub(crate) async fn set_timeout_if_needed(conn: &mut sqlx::PgConnection, timeout_seconds: Option<u64>, lock_timeout_seconds: Option<u64>) -> Result<()> {
+    set_timeouts(conn, timeout_seconds, lock_timeout_seconds, true).await
+}
+
+/// Session-level variant for statements that run outside a transaction; use a detached connection
+pub(crate) async fn set_session_timeouts(conn: &mut sqlx::PgConnection, timeout_seconds: Option<u64>, lock_timeout_seconds: Option<u64>) -> Result<()> {
+    set_timeouts(conn, timeout_seconds, lock_timeout_seconds, false).await
+}
+
+// SET does not accept bind parameters, set_config is the equivalent that does
+async fn set_timeouts(conn: &mut sqlx::PgConnection, timeout_seconds: Option<u64>, lock_timeout_seconds: Option<u64>, local: bool) -> Result<()> {
     if let Some(seconds) = timeout_seconds {
         let ms: i64 = (seconds as i64) * 1000;
-        sqlx::query("SET LOCAL statement_timeout = $1")
-            .bind(ms)
-            .execute(executor)
+        sqlx::query("SELECT set_config('statement_timeout', $1, $2)")
+            .bind(ms.to_string())
+            .bind(local)
+            .execute(&mut *conn)
+            .await?;
+    }
+    // Lets DDL give up quickly when it has to wait for a lock instead of queueing application traffic behind it
+    if let Some(seconds) = lock_timeout_seconds {
+        let ms: i64 = (seconds as i64) * 1000;
+        sqlx::query("SELECT set_config('lock_timeout', $1, $2)")
+            .bind(ms.to_string())
+            .bind(local)
+            .execute(&mut *conn)
             .await?;
     }
     Ok(())
 }
 
-use crate::core::migration::prompt_for_confirmation_with_diff;
+/// Resolve unqualified names in migration SQL against the configured schemas; the tracking
+/// tables are always qualified with `schema` and unaffected
+pub(crate) async fn set_search_path(conn: &mut sqlx::PgConnection, schemas: &[String], local: bool) -> Result<()> {
+    if schemas.is_empty() {
+        return Ok(());
+    }
+    let search_path = schemas.iter().map(|schema| quote_ident(schema)).collect::<Vec<_>>().join(", ");
+    sqlx::query("SELECT set_config('search_path', $1, $2)")
+        .bind(search_path)
+        .bind(local)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// A session whose transaction has been open for a while and holds a lock on a table
+pub(crate) struct BlockingSession {
+    pub pid: i32,
+    pub state: String,
+    pub application: String,
+    pub seconds: i64,
+    pub table: String,
+}
+
+/// Sessions other than this one whose transaction is older than `min_duration` seconds and holds a
+/// lock on one of the tables; tables are resolved against the search path and missing ones ignored
+pub(crate) async fn find_blocking_sessions(conn: &mut sqlx::PgConnection, tables: &[String], min_duration: u64) -> Result<Vec<BlockingSession>> {
+    let rows = sqlx::query(
+        "SELECT DISTINCT a.pid, COALESCE(a.state, '') AS state, COALESCE(a.application_name, '') AS application, \
+         EXTRACT(EPOCH FROM now() - a.xact_start)::bigint AS seconds, l.relation::regclass::text AS relation \
+         FROM pg_locks l JOIN pg_stat_activity a ON a.pid = l.pid \
+         WHERE l.relation IN (SELECT to_regclass(t) FROM unnest($1::text[]) t) \
+         AND a.pid <> pg_backend_pid() AND a.xact_start < now() - make_interval(secs => $2) \
+         ORDER BY seconds DESC",
+    )
+    .bind(tables)
+    .bind(min_duration as f64)
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| BlockingSession { pid: row.get("pid"), state: row.get("state"), application: row.get("application"), seconds: row.get("seconds"), table: row.get("relation") })
+        .collect())
+}
 
-fn display_migration_diff_from_sql(_migration_id: &str, sql: &str, _direction: &str) -> Result<()> {
-    crate::core::migration::display_sql_migration(_migration_id, sql, _direction)
+use crate::core::migration::{prompt_for_confirmation_with_diff, DiffView};
+
+fn display_migration_diff_from_sql(_migration_id: &str, sql: &str, _direction: &str, view: DiffView) -> Result<()> {
+    crate::core::migration::display_sql_migration(_migration_id, sql, _direction, view)
 }
 
 fn create_bulk_migrations_diff_fn<'a>(
     migrations: &'a [String],
     migration_dir: &'a Path,
     direction: &'a str
-) -> impl Fn() -> Result<()> + 'a {
-    move || -> Result<()> {
+) -> impl Fn(DiffView) -> Result<()> + 'a {
+    move |view: DiffView| -> Result<()> {
         for migration_id in migrations {
             let (up_sql, down_sql) = crate::core::migration::read_migration_files(
                 migration_dir, migration_id
             )?;
             let sql = if direction == "UP" { up_sql } else { down_sql };
             
-            display_migration_diff_from_sql(migration_id, &sql, direction)?;
+            display_migration_diff_from_sql(migration_id, &sql, direction, view)?;
         }
         Ok(())
     }
@@ -78,8 +141,8 @@ fn create_bulk_reverts_diff_fn<'a>(
     migrations: &'a [sqlx::postgres::PgRow],
     migration_dir: &'a Path,
     remote: bool
-) -> impl Fn() -> Result<()> + 'a {
-    move || -> Result<()> {
+) -> impl Fn(DiffView) -> Result<()> + 'a {
+    move |view: DiffView| -> Result<()> {
         for row in migrations {
             let id: String = row.get("id");
             let down_sql: String = if remote {
@@ -91,7 +154,7 @@ fn create_bulk_reverts_diff_fn<'a>(
                 down_sql
             };
             
-            display_migration_diff_from_sql(&id, &down_sql, "DOWN")?;
+            display_migration_diff_from_sql(&id, &down_sql, "DOWN", view)?;
         }
         Ok(())
     }
@@ -101,9 +164,9 @@ fn create_single_migration_diff_fn<'a>(
     migration_id: &'a str,
     sql: &'a str,
     direction: &'a str
-) -> impl Fn() -> Result<()> + 'a {
-    move || -> Result<()> {
-        display_migration_diff_from_sql(migration_id, sql, direction)
+) -> impl Fn(DiffView) -> Result<()> + 'a {
+    move |view: DiffView| -> Result<()> {
+        display_migration_diff_from_sql(migration_id, sql, direction, view)
     }
 }
 
@@ -127,12 +190,14 @@ pub(crate) async fn get_last_migration_id(
     schema: &str,
     table: &str,
 ) -> Result<Option<String>> {
-    let mut query = build_table_query("SELECT id FROM ", schema, table);
-    query.push(" ORDER BY id DESC LIMIT 1");
-    Ok(query.build()
-        .fetch_optional(&mut **tx)
+    // Ids order by value, which ORDER BY cannot express for numeric ids of mixed width
+    let ids: Vec<String> = build_table_query("SELECT id FROM ", schema, table).build()
+        .fetch_all(&mut **tx)
         .await?
-        .map(|row| row.get("id")))
+        .into_iter()
+        .map(|row| row.get("id"))
+        .collect();
+    Ok(ids.into_iter().max_by(|a, b| crate::core::migration::compare_ids(a, b)))
 }
 
 pub(crate) async fn insert_migration_record<'e, E>(
@@ -142,23 +207,33 @@ pub(crate) async fn insert_migration_record<'e, E>(
     id: &str,
     up_sql: &str,
     down_sql: &str,
-    comment: Option<&str>,
+    meta: &crate::core::migration::MigrationMeta,
     pre_migration_id: Option<&str>,
     locked: bool,
+    duration_ms: Option<i64>,
 ) -> Result<()>
 where
     E: sqlx::Executor<'e, Database = Postgres>,
 {
+    let (applied_by, hostname) = crate::core::migration::applied_by();
     let mut query = build_table_query("INSERT INTO ", schema, table);
-    query.push(" (id, version, up, down, comment, pre, locked) VALUES ($1, $2, $3, $4, $5, $6, $7)");
+    query.push(" (id, version, up, down, comment, pre, locked, up_sha256, down_sha256, author, ticket, labels, duration_ms, applied_by, hostname) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)");
     query.build()
         .bind(id)
         .bind(env!("CARGO_PKG_VERSION"))
         .bind(up_sql)
         .bind(down_sql)
-        .bind(comment)
+        .bind(meta.comment.as_deref())
         .bind(pre_migration_id)
         .bind(locked)
+        .bind(crate::core::migration::sql_checksum(up_sql))
+        .bind(crate::core::migration::sql_checksum(down_sql))
+        .bind(meta.author.as_deref())
+        .bind(meta.ticket.as_deref())
+        .bind(meta.provenance().labels_json())
+        .bind(duration_ms)
+        .bind(applied_by)
+        .bind(hostname)
         .execute(executor)
         .await?;
     Ok(())
@@ -218,9 +293,9 @@ pub(crate) async fn get_all_migration_data(
     schema: &str,
     table: &str,
 ) -> Result<Vec<PgRow>> {
-    let mut query = build_table_query("SELECT id, up, down FROM ", schema, table);
-    query.push(" ORDER BY id ASC");
-    Ok(query.build().fetch_all(&mut **tx).await?)
+    let mut rows = build_table_query("SELECT id, up, down, comment FROM ", schema, table).build().fetch_all(&mut **tx).await?;
+    rows.sort_by(|a, b| crate::core::migration::compare_ids(a.get("id"), b.get("id")));
+    Ok(rows)
 }
 
 pub(crate) use crate::core::migration::normalize_migration_id;
@@ -230,9 +305,9 @@ pub(crate) async fn get_recent_migrations_for_revert(
     schema: &str,
     table: &str,
 ) -> Result<Vec<PgRow>> {
-    let mut query = build_table_query("SELECT id, down FROM ", schema, table);
-    query.push(" ORDER BY id DESC");
-    Ok(query.build().fetch_all(&mut **tx).await?)
+    let mut rows = build_table_query("SELECT id, down FROM ", schema, table).build().fetch_all(&mut **tx).await?;
+    rows.sort_by(|a, b| crate::core::migration::compare_ids(b.get("id"), a.get("id")));
+    Ok(rows)
 }
 
 pub(crate) async fn get_migration_down_sql(
@@ -247,41 +322,133 @@ pub(crate) async fn get_migration_down_sql(
     Ok(row.get("down"))
 }
 
+/// Columns added to the migrations table after its first release with their types, created by
+/// `ensure_tracking_columns`
+const LATE_COLUMNS: [(&str, &str); 8] = [("up_sha256", "VARCHAR"), ("down_sha256", "VARCHAR"), ("author", "VARCHAR"), ("ticket", "VARCHAR"), ("labels", "VARCHAR"), ("duration_ms", "BIGINT"), ("applied_by", "VARCHAR"), ("hostname", "VARCHAR")];
+
+/// Columns added to the log table after its first release
+const LATE_LOG_COLUMNS: [(&str, &str); 8] = [
+    ("duration_ms", "BIGINT"),
+    ("applied_by", "VARCHAR"),
+    ("hostname", "VARCHAR"),
+    ("version", "VARCHAR"),
+    ("invocation", "TEXT"),
+    ("dry_run", "BOOLEAN"),
+    ("status", "VARCHAR"),
+    ("error", "TEXT"),
+];
+
+/// Add the given columns to an existing table that lacks them; the names of the added columns
+async fn add_missing_columns(tx: &mut sqlx::Transaction<'_, Postgres>, schema: &str, table: &str, columns: &[(&'static str, &str)]) -> Result<Vec<&'static str>> {
+    let existing: Vec<String> = sqlx::query_scalar("SELECT column_name::text FROM information_schema.columns WHERE table_schema = $1 AND table_name = $2")
+        .bind(schema)
+        .bind(table)
+        .fetch_all(&mut **tx)
+        .await?;
+    let missing: Vec<(&'static str, &str)> = columns.iter().copied().filter(|(column, _)| !existing.iter().any(|e| e == column)).collect();
+    // No columns at all means the table does not exist yet
+    if existing.is_empty() || missing.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut alter = build_table_query("ALTER TABLE ", schema, table);
+    alter.push(missing.iter().map(|(column, kind)| format!(" ADD COLUMN IF NOT EXISTS {} {}", column, kind)).collect::<Vec<_>>().join(","));
+    alter.build().execute(&mut **tx).await?;
+    Ok(missing.into_iter().map(|(column, _)| column).collect())
+}
+
+/// Add the columns of `LATE_COLUMNS` and `LATE_LOG_COLUMNS` to tracking tables created before they
+/// existed; the checksums are backfilled from the stored SQL
+pub(crate) async fn ensure_tracking_columns(tx: &mut sqlx::Transaction<'_, Postgres>, schema: &str, table: &str, log_table: &str) -> Result<()> {
+    add_missing_columns(tx, schema, log_table, &LATE_LOG_COLUMNS).await?;
+    let missing = add_missing_columns(tx, schema, table, &LATE_COLUMNS).await?;
+    if !missing.contains(&"up_sha256") {
+        return Ok(());
+    }
+
+    let mut select = build_table_query("SELECT id, up, down FROM ", schema, table);
+    for row in select.build().fetch_all(&mut **tx).await? {
+        let (id, up, down): (String, String, String) = (row.get("id"), row.get("up"), row.get("down"));
+        let mut update = build_table_query("UPDATE ", schema, table);
+        update.push(" SET up_sha256 = ").push_bind(crate::core::migration::sql_checksum(&up));
+        update.push(", down_sha256 = ").push_bind(crate::core::migration::sql_checksum(&down));
+        update.push(" WHERE id = ").push_bind(id);
+        update.build().execute(&mut **tx).await?;
+    }
+    Ok(())
+}
+
 pub(crate) async fn get_table_version(
     tx: &mut sqlx::Transaction<'_, Postgres>,
+    schema: &str,
     table: &str,
 ) -> Result<Option<String>> {
-    let mut query = QueryBuilder::new("SELECT version FROM ");
-    query.push(table);
-    query.push(" ORDER BY id DESC LIMIT 1");
-    Ok(query.build()
-        .fetch_optional(&mut **tx)
-        .await?
+    let rows = build_table_query("SELECT id, version FROM ", schema, table).build().fetch_all(&mut **tx).await?;
+    Ok(rows
+        .into_iter()
+        .max_by(|a, b| crate::core::migration::compare_ids(a.get("id"), b.get("id")))
         .map(|row| row.get("version")))
 }
 
+/// Run the statements of a migration one by one, so a failure names the statement and its line
 pub(crate) async fn execute_sql_statements(
     tx: &mut sqlx::Transaction<'_, Postgres>,
     sql: &str,
     migration_id: &str,
 ) -> Result<()> {
-    match sqlx::raw_sql(sql).execute(&mut **tx).await {
-        Ok(_) => {
-            // Statement executed successfully
-        }
-        Err(e) => {
-            return Err(anyhow::anyhow!(
-                "Failed to execute statements in migration {}: {}",
-                migration_id,
-                e,
-            ));
-        }
+    let statements = crate::core::migration_diff::split_statements_with_lines(sql);
+    let total = statements.len();
+    for (index, (line, statement)) in statements.iter().enumerate() {
+        crate::core::migration::report_statement_progress(migration_id, index, total);
+        crate::verbose!("▶️  {} statement {}/{}:\n{}", migration_id, index + 1, total, statement);
+        let started = std::time::Instant::now();
+        let rows = match sqlx::raw_sql(statement).execute(&mut **tx).await {
+            | Ok(done) => done.rows_affected(),
+            | Err(e) => {
+                crate::core::migration::report_statement_progress(migration_id, total, total);
+                // 25001 active_sql_transaction, e.g. CREATE INDEX CONCURRENTLY inside a transaction block
+                let hint = match e.as_database_error().and_then(|e| e.code()).is_some_and(|code| code == "25001") {
+                    | true => "\nSet `transaction = false` in its meta.toml to run it outside a transaction.",
+                    | false => "",
+                };
+                return Err(crate::core::migration::statement_error(migration_id, index + 1, total, *line, statement, e, hint));
+            },
+        };
+        crate::core::telemetry::add_rows(migration_id, rows);
+        crate::core::events::statement_executed(migration_id, index + 1, total, *line, rows, started.elapsed());
+        crate::verbose!("⏱️  {:.1} ms", started.elapsed().as_secs_f64() * 1000.0);
     }
+    crate::core::migration::report_statement_progress(migration_id, total, total);
     Ok(())
 }
 
-pub(crate) async fn build_pool_from_config(path: &Path, subsystem_config: &SubsystemPostgres, check_cli_version: bool) -> Result<Pool<Postgres>> {
-    let uri = match &subsystem_config.connection {
+/// Run statements one at a time in autocommit mode. A multi-statement query would run in an
+/// implicit transaction, which e.g. CREATE INDEX CONCURRENTLY refuses. The first `skip` statements
+/// are left out, they ran before a resumed failure.
+pub(crate) async fn execute_statements_individually(conn: &mut sqlx::PgConnection, sql: &str, migration_id: &str, skip: usize) -> Result<()> {
+    let statements = crate::core::migration_diff::split_statements_with_lines(sql);
+    let total = statements.len();
+    for (index, (line, statement)) in statements.iter().enumerate().skip(skip) {
+        crate::core::migration::report_statement_progress(migration_id, index, total);
+        crate::verbose!("▶️  {} statement {}/{}:\n{}", migration_id, index + 1, total, statement);
+        let started = std::time::Instant::now();
+        let rows = match sqlx::raw_sql(statement).execute(&mut *conn).await {
+            | Ok(done) => done.rows_affected(),
+            | Err(e) => {
+                crate::core::migration::report_statement_progress(migration_id, total, total);
+                return Err(crate::core::migration::statement_error(migration_id, index + 1, total, *line, statement, e, ""));
+            },
+        };
+        crate::core::telemetry::add_rows(migration_id, rows);
+        crate::core::events::statement_executed(migration_id, index + 1, total, *line, rows, started.elapsed());
+        crate::verbose!("⏱️  {:.1} ms", started.elapsed().as_secs_f64() * 1000.0);
+    }
+    crate::core::migration::report_statement_progress(migration_id, total, total);
+    Ok(())
+}
+
+/// Resolve the connection string from the configured data source
+pub(crate) fn resolve_connection(path: &Path, subsystem_config: &SubsystemPostgres) -> Result<String> {
+    Ok(match &subsystem_config.connection {
         | DataSource::Static(connection) => connection.to_owned(),
         | DataSource::FromEnv(var) => {
             std::env::var(var).with_context(|| {
@@ -292,12 +459,103 @@ pub(crate) async fn build_pool_from_config(path: &Path, subsystem_config: &Subsy
                 )
             })?
         },
-    };
+        | DataSource::File(file) => {
+            crate::config::read_from_file(path, file).with_context(|| {
+                format!(
+                    "Failed to read connection file '{}' referenced by [subsystem.postgres].connection in {}",
+                    file,
+                    path.display()
+                )
+            })?
+        },
+        | DataSource::Command(command) => {
+            crate::config::read_from_command(path, command).with_context(|| {
+                format!(
+                    "Failed to run connection command '{}' referenced by [subsystem.postgres].connection in {}",
+                    command.program,
+                    path.display()
+                )
+            })?
+        },
+        #[cfg(feature = "keyring")]
+        | DataSource::Keyring(keyring) => {
+            crate::config::read_from_keyring(keyring).with_context(|| {
+                format!(
+                    "Failed to read keyring entry '{}/{}' referenced by [subsystem.postgres].connection in {}",
+                    keyring.service,
+                    keyring.user,
+                    path.display()
+                )
+            })?
+        },
+        #[cfg(feature = "vault")]
+        | DataSource::Vault(vault) => {
+            crate::core::vault::read_secret(vault).with_context(|| {
+                format!(
+                    "Failed to read Vault secret '{}' referenced by [subsystem.postgres].connection in {}",
+                    vault.path,
+                    path.display()
+                )
+            })?
+        },
+    })
+}
+
+fn config_relative(path: &Path, file: &str) -> PathBuf {
+    path.parent().map(|dir| dir.join(file)).unwrap_or_else(|| file.into())
+}
+
+fn ssl_files(path: &Path, subsystem_config: &SubsystemPostgres) -> Vec<(PathBuf, &'static str)> {
+    [(&subsystem_config.root_cert, "PGSSLROOTCERT"), (&subsystem_config.client_cert, "PGSSLCERT"), (&subsystem_config.client_key, "PGSSLKEY")]
+        .into_iter()
+        .filter_map(|(file, var)| file.as_ref().map(|file| (config_relative(path, file), var)))
+        .collect()
+}
+
+/// The TLS settings as libpq environment variables, for psql and pg_dump
+pub(crate) fn ssl_env(path: &Path, subsystem_config: &SubsystemPostgres) -> Vec<(&'static str, String)> {
+    let mut envs: Vec<(&'static str, String)> = subsystem_config.ssl_mode.iter().map(|mode| ("PGSSLMODE", mode.as_str().to_string())).collect();
+    envs.extend(ssl_files(path, subsystem_config).into_iter().map(|(file, var)| (var, file.display().to_string())));
+    envs
+}
+
+pub(crate) async fn build_pool_from_config(path: &Path, subsystem_config: &SubsystemPostgres, check_cli_version: bool) -> Result<Pool<Postgres>> {
+    build_pool_with_options(connect_options(path, subsystem_config)?, subsystem_config, check_cli_version).await
+}
+
+/// The connect options for the configured data source, including the TLS settings
+pub(crate) fn connect_options(path: &Path, subsystem_config: &SubsystemPostgres) -> Result<PgConnectOptions> {
+    let uri = resolve_connection(path, subsystem_config)?;
+    let mut options = PgConnectOptions::from_str(&uri)?;
+    if let Some(mode) = subsystem_config.ssl_mode {
+        options = options.ssl_mode(PgSslMode::from_str(mode.as_str())?);
+    }
+    if let Some(root_cert) = &subsystem_config.root_cert {
+        options = options.ssl_root_cert(config_relative(path, root_cert));
+    }
+    if let Some(client_cert) = &subsystem_config.client_cert {
+        options = options.ssl_client_cert(config_relative(path, client_cert));
+    }
+    if let Some(client_key) = &subsystem_config.client_key {
+        options = options.ssl_client_key(config_relative(path, client_key));
+    }
+    Ok(options)
+}
 
-    let pool = PgPoolOptions::new().max_connections(10).connect(&uri).await?;
+pub(crate) async fn build_pool_with_options(options: PgConnectOptions, subsystem_config: &SubsystemPostgres, check_cli_version: bool) -> Result<Pool<Postgres>> {
+    crate::verbose!(
+        "🔌 Connecting to {}:{}/{} as {} (sslmode {:?}, schema {})",
+        options.get_host(),
+        options.get_port(),
+        options.get_database().unwrap_or(options.get_username()),
+        options.get_username(),
+        options.get_ssl_mode(),
+        subsystem_config.schema
+    );
+    let pool = PgPoolOptions::new().max_connections(10).connect_with(options).await?;
     if check_cli_version {
         let mut tx = pool.begin().await?;
-        let last_migration_version = get_table_version(&mut tx, &subsystem_config.tables.migrations).await?;
+        let last_migration_version = get_table_version(&mut tx, &subsystem_config.schema, &subsystem_config.tables.migrations).await?;
         if let Some(version) = last_migration_version {
             let cli_version = semver::Version::parse(env!("CARGO_PKG_VERSION"))?;
             if !(cli_version.major == 0 && cli_version.minor == 0 && cli_version.patch == 0) {
@@ -307,6 +565,7 @@ pub(crate) async fn build_pool_from_config(path: &Path, subsystem_config: &Subsy
                 }
             }
         }
+        ensure_tracking_columns(&mut tx, &subsystem_config.schema, &subsystem_config.tables.migrations, &subsystem_config.tables.log).await?;
         tx.commit().await?;
     }
     Ok(pool)
@@ -323,18 +582,60 @@ pub(crate) async fn insert_log_entry<'c, E>(
     operation: &str,
     sql_command: &str,
 ) -> Result<()>
+where
+    E: sqlx::Executor<'c, Database = Postgres>,
+{
+    insert_timed_log_entry(executor, schema, log_table, migration_id, operation, sql_command, None).await
+}
+
+/// `insert_log_entry` for operations that executed SQL, with the time it took
+pub(crate) async fn insert_timed_log_entry<'c, E>(
+    executor: E,
+    schema: &str,
+    log_table: &str,
+    migration_id: &str,
+    operation: &str,
+    sql_command: &str,
+    duration_ms: Option<i64>,
+) -> Result<()>
+where
+    E: sqlx::Executor<'c, Database = Postgres>,
+{
+    let outcome = crate::core::log::LogOutcome { duration_ms, ..Default::default() };
+    insert_log_record(executor, schema, log_table, migration_id, operation, sql_command, &outcome).await
+}
+
+/// Write a log entry with the qop version, the invocation and who ran it, and how the operation went
+pub(crate) async fn insert_log_record<'c, E>(
+    executor: E,
+    schema: &str,
+    log_table: &str,
+    migration_id: &str,
+    operation: &str,
+    sql_command: &str,
+    outcome: &crate::core::log::LogOutcome,
+) -> Result<()>
 where
     E: sqlx::Executor<'c, Database = Postgres>,
 {
     let log_id = uuid::Uuid::now_v7().to_string();
+    let (applied_by, hostname) = crate::core::migration::applied_by();
     let mut query = build_table_query("INSERT INTO ", schema, log_table);
-    query.push(" (id, migration_id, operation, sql_command) VALUES ($1, $2, $3, $4)");
+    query.push(" (id, migration_id, operation, sql_command, duration_ms, applied_by, hostname, version, invocation, dry_run, status, error) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)");
     query
         .build()
         .bind(log_id)
         .bind(migration_id)
         .bind(operation)
         .bind(sql_command)
+        .bind(outcome.duration_ms)
+        .bind(applied_by)
+        .bind(hostname)
+        .bind(env!("CARGO_PKG_VERSION"))
+        .bind(crate::core::log::invocation())
+        .bind(outcome.dry_run)
+        .bind(outcome.status())
+        .bind(outcome.error.as_deref())
         .execute(executor)
         .await?;
     Ok(())
@@ -346,12 +647,12 @@ pub async fn init_with_pool(schema: &str, migrations_table: &str, log_table: &st
     {
         // Create migrations table
         let mut query = build_table_query("CREATE TABLE IF NOT EXISTS ", schema, migrations_table);
-        query.push(" (id VARCHAR PRIMARY KEY, version VARCHAR NOT NULL, up VARCHAR NOT NULL, down VARCHAR NOT NULL, created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP, pre VARCHAR, comment VARCHAR, locked BOOLEAN NOT NULL DEFAULT FALSE)");
+        query.push(" (id VARCHAR PRIMARY KEY, version VARCHAR NOT NULL, up VARCHAR NOT NULL, down VARCHAR NOT NULL, created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP, pre VARCHAR, comment VARCHAR, locked BOOLEAN NOT NULL DEFAULT FALSE, up_sha256 VARCHAR, down_sha256 VARCHAR, author VARCHAR, ticket VARCHAR, labels VARCHAR, duration_ms BIGINT, applied_by VARCHAR, hostname VARCHAR)");
         query.build().execute(&mut *tx).await?;
         
         // Create log table
         let mut log_query = build_table_query("CREATE TABLE IF NOT EXISTS ", schema, log_table);
-        log_query.push(" (id VARCHAR PRIMARY KEY, migration_id VARCHAR NOT NULL, operation VARCHAR NOT NULL, sql_command TEXT NOT NULL, executed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP)");
+        log_query.push(" (id VARCHAR PRIMARY KEY, migration_id VARCHAR NOT NULL, operation VARCHAR NOT NULL, sql_command TEXT NOT NULL, executed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP, duration_ms BIGINT, applied_by VARCHAR, hostname VARCHAR, version VARCHAR, invocation TEXT, dry_run BOOLEAN, status VARCHAR, error TEXT)");
         log_query.build().execute(&mut *tx).await?;
     };
     tx.commit().await?;
@@ -374,7 +675,7 @@ pub async fn up(path: &Path, timeout: Option<u64>, count: Option<usize>, diff: b
 
     let mut tx = pool.begin().await?;
 
-    set_timeout_if_needed(&mut *tx, effective_timeout).await?;
+    set_timeout_if_needed(&mut tx, effective_timeout, config.lock_timeout).await?;
 
     let applied_migrations = get_applied_migrations(&mut tx, &schema, &migrations_table).await?;
     let mut last_migration_id = get_last_migration_id(&mut tx, &schema, &migrations_table).await?;
@@ -385,7 +686,7 @@ pub async fn up(path: &Path, timeout: Option<u64>, count: Option<usize>, diff: b
     let mut migrations_to_apply: Vec<String> =
         local_migrations.difference(&applied_migrations).cloned().collect();
 
-    migrations_to_apply.sort();
+    crate::core::migration::sort_ids(&mut migrations_to_apply);
 
     let migrations_to_apply = if let Some(count) = count {
         migrations_to_apply.into_iter().take(count).collect()
@@ -395,18 +696,18 @@ pub async fn up(path: &Path, timeout: Option<u64>, count: Option<usize>, diff: b
 
     // Linear history enforcement: Check for out-of-order migrations
     if !applied_migrations.is_empty() && !migrations_to_apply.is_empty() {
-        let max_applied_migration = applied_migrations.iter().max().cloned().unwrap_or_default();
+        let max_applied_migration = applied_migrations.iter().max_by(|a, b| crate::core::migration::compare_ids(a, b)).cloned().unwrap_or_default();
         
         let out_of_order_migrations: Vec<&String> = migrations_to_apply
             .iter()
-            .filter(|id| id.as_str() < max_applied_migration.as_str())
+            .filter(|id| crate::core::migration::compare_ids(id, &max_applied_migration).is_lt())
             .collect();
 
         if !out_of_order_migrations.is_empty() {
-            println!("⚠️  Non-linear history detected!");
+            crate::say!("⚠️  Non-linear history detected!");
             println!("The following migrations would create a non-linear history:");
             for migration in &out_of_order_migrations {
-                println!("  - {}", migration);
+                crate::say!("  - {}", migration);
             }
             println!("Latest applied migration: {}", max_applied_migration);
             println!();
@@ -448,40 +749,40 @@ pub async fn up(path: &Path, timeout: Option<u64>, count: Option<usize>, diff: b
             let input = input.trim().to_lowercase();
             
             if input != "y" && input != "yes" {
-                println!("❌ Migration cancelled.");
+                crate::say!("❌ Migration cancelled.");
                 return Ok(());
             }
             
             if dry {
-                println!("\n🧪 Running migrations in dry-run mode...");
+                crate::say!("\n🧪 Running migrations in dry-run mode...");
             } else {
-                println!("\n🚀 Applying migrations...");
+                crate::say!("\n🚀 Applying migrations...");
             }
         } else if dry {
-            println!("\n🧪 Running migrations in dry-run mode...");
+            crate::say!("\n🧪 Running migrations in dry-run mode...");
         } else {
             // Prompt for confirmation when not using diff and not in silent mode
-            println!("\n📋 About to apply {} migration(s):", migrations_to_apply.len());
+            crate::say!("\n📋 About to apply {} migration(s):", migrations_to_apply.len());
             for migration_id in &migrations_to_apply {
-                println!("  - {}", migration_id);
+                crate::say!("  - {}", migration_id);
             }
             
             let diff_fn = create_bulk_migrations_diff_fn(&migrations_to_apply, migration_dir, "UP");
             
             if !prompt_for_confirmation_with_diff("❓ Do you want to proceed with applying these migrations?", yes, diff_fn)? {
-                println!("❌ Migration cancelled.");
+                crate::say!("❌ Migration cancelled.");
                 return Ok(());
             }
             
-            println!("\n🚀 Applying migrations...");
+            crate::say!("\n🚀 Applying migrations...");
         }
         
         // Apply each migration in its own transaction
         for migration_id in &migrations_to_apply {
             if dry {
-                println!("⏳ Testing migration: {}", migration_id);
+                crate::say!("⏳ Testing migration: {}", migration_id);
             } else {
-                println!("⏳ Applying migration: {}", migration_id);
+                crate::say!("⏳ Applying migration: {}", migration_id);
             }
             let id = migration_id.as_str();
 
@@ -493,7 +794,7 @@ pub async fn up(path: &Path, timeout: Option<u64>, count: Option<usize>, diff: b
             let mut migration_tx = pool.begin().await?;
 
             // Set timeout for this transaction if specified
-            set_timeout_if_needed(&mut *migration_tx, effective_timeout).await?;
+            set_timeout_if_needed(&mut migration_tx, effective_timeout, config.lock_timeout).await?;
 
             // Execute the migration SQL
             execute_sql_statements(&mut migration_tx, &up_sql, id).await?;
@@ -506,18 +807,19 @@ pub async fn up(path: &Path, timeout: Option<u64>, count: Option<usize>, diff: b
                 id,
                 &up_sql,
                 &down_sql,
-                None, // comment not available in this legacy function
+                &Default::default(), // meta not available in this legacy function
                 last_migration_id.as_deref(),
                 false, // locked not available in this legacy function
+                None,
             ).await?;
 
             // Commit or rollback based on dry-run mode
             if dry {
                 migration_tx.rollback().await?;
-                println!("🔄 Migration {} executed and rolled back (dry-run mode).", migration_id);
+                crate::say!("🔄 Migration {} executed and rolled back (dry-run mode).", migration_id);
             } else {
                 migration_tx.commit().await?;
-                println!("✅ Migration {} applied successfully.", migration_id);
+                crate::say!("✅ Migration {} applied successfully.", migration_id);
             }
             if !dry {
                 last_migration_id = Some(id.to_string());
@@ -525,9 +827,9 @@ pub async fn up(path: &Path, timeout: Option<u64>, count: Option<usize>, diff: b
         }
 
         if dry {
-            println!("\n🎉 Successfully executed {} migration(s) in dry-run mode! (No changes were committed)", migrations_to_apply.len());
+            crate::summary!("\n🎉 Successfully executed {} migration(s) in dry-run mode! (No changes were committed)", migrations_to_apply.len());
         } else {
-            println!("\n🎉 Successfully applied {} migration(s)!", migrations_to_apply.len());
+            crate::summary!("\n🎉 Successfully applied {} migration(s)!", migrations_to_apply.len());
         }
     }
 
@@ -548,7 +850,7 @@ pub async fn down(path: &Path, timeout: Option<u64>, count: Option<usize>, remot
     
     let mut tx = pool.begin().await?;
 
-    set_timeout_if_needed(&mut *tx, effective_timeout).await?;
+    set_timeout_if_needed(&mut tx, effective_timeout, config.lock_timeout).await?;
 
     let last_migrations = get_recent_migrations_for_revert(&mut tx, &schema, &migrations_table).await?;
 
@@ -592,27 +894,27 @@ pub async fn down(path: &Path, timeout: Option<u64>, count: Option<usize>, remot
             let input = input.trim().to_lowercase();
             
             if input != "y" && input != "yes" {
-                println!("❌ Revert cancelled.");
+                crate::say!("❌ Revert cancelled.");
                 return Ok(());
             }
             
-            println!("\n🔄 Reverting migrations...");
+            crate::say!("\n🔄 Reverting migrations...");
         } else {
             // Prompt for confirmation when not using diff and not in silent mode
-            println!("\n📋 About to revert {} migration(s):", migrations_to_revert.len());
+            crate::say!("\n📋 About to revert {} migration(s):", migrations_to_revert.len());
             for row in &migrations_to_revert {
                 let id: String = row.get("id");
-                println!("  - {}", id);
+                crate::say!("  - {}", id);
             }
             
             let diff_fn = create_bulk_reverts_diff_fn(&migrations_to_revert, migration_dir, remote);
             
             if !prompt_for_confirmation_with_diff("❓ Do you want to proceed with reverting these migrations?", yes, diff_fn)? {
-                println!("❌ Revert cancelled.");
+                crate::say!("❌ Revert cancelled.");
                 return Ok(());
             }
             
-            println!("\n🔄 Reverting migrations...");
+            crate::say!("\n🔄 Reverting migrations...");
         }
         
         // Revert each migration in its own transaction
@@ -632,7 +934,7 @@ pub async fn down(path: &Path, timeout: Option<u64>, count: Option<usize>, remot
             let mut revert_tx = pool.begin().await?;
 
             // Set timeout for this transaction if specified
-            set_timeout_if_needed(&mut *revert_tx, effective_timeout).await?;
+            set_timeout_if_needed(&mut revert_tx, effective_timeout, config.lock_timeout).await?;
 
             // Execute the down migration SQL
             execute_sql_statements(&mut revert_tx, &down_sql, &id).await?;
@@ -643,10 +945,10 @@ pub async fn down(path: &Path, timeout: Option<u64>, count: Option<usize>, remot
             // Commit or rollback based on dry-run mode
             if dry {
                 revert_tx.rollback().await?;
-                println!("🔄 Migration {} reverted and rolled back (dry-run mode).", id);
+                crate::say!("🔄 Migration {} reverted and rolled back (dry-run mode).", id);
             } else {
                 revert_tx.commit().await?;
-                println!("✅ Migration {} reverted.", id);
+                crate::say!("✅ Migration {} reverted.", id);
             }
         }
     }
@@ -657,7 +959,8 @@ pub async fn down(path: &Path, timeout: Option<u64>, count: Option<usize>, remot
 // Note: This function is deprecated - use the core migration creation service instead
 // which goes through util::create_migration_directory()
 pub async fn new_migration(path: &Path) -> Result<()> {
-    crate::core::migration::create_migration_directory(path, None, false)?;
+    let id = crate::core::migration::IdConfig::default().next_id(&crate::core::migration::get_local_migrations(path)?)?;
+    crate::core::migration::create_migration_directory(path, &id, None, false, &Default::default())?;
     Ok(())
 }
 
@@ -704,10 +1007,10 @@ pub async fn apply_up(path: &Path, id: &str, timeout: Option<u64>, dry: bool, ye
     let mut needs_confirmation = false;
     if !applied_migrations.is_empty() {
         let max_applied_migration =
-            applied_migrations.iter().max().cloned().unwrap_or_default();
+            applied_migrations.iter().max_by(|a, b| crate::core::migration::compare_ids(a, b)).cloned().unwrap_or_default();
 
-        if target_migration_id.as_str() < max_applied_migration.as_str() {
-            println!("⚠️  Non-linear history detected!");
+        if crate::core::migration::compare_ids(&target_migration_id, &max_applied_migration).is_lt() {
+            crate::say!("⚠️  Non-linear history detected!");
             println!(
                 "Applying migration {} would create a non-linear history.",
                 target_migration_id
@@ -744,7 +1047,7 @@ pub async fn apply_up(path: &Path, id: &str, timeout: Option<u64>, dry: bool, ye
     let diff_fn = create_single_migration_diff_fn(&target_migration_id, &up_sql, "UP");
     
     if !prompt_for_confirmation_with_diff(&format!("❓ Do you want to apply migration '{}'?", target_migration_id), yes, diff_fn)? {
-        println!("❌ Operation cancelled.");
+        crate::say!("❌ Operation cancelled.");
         return Ok(());
     }
 
@@ -758,7 +1061,7 @@ pub async fn apply_up(path: &Path, id: &str, timeout: Option<u64>, dry: bool, ye
     // Execute the migration
     let mut migration_tx = pool.begin().await?;
 
-    set_timeout_if_needed(&mut *migration_tx, effective_timeout).await?;
+    set_timeout_if_needed(&mut migration_tx, effective_timeout, config.lock_timeout).await?;
 
     if dry {
         println!("Testing migration: {}", target_migration_id);
@@ -775,17 +1078,18 @@ pub async fn apply_up(path: &Path, id: &str, timeout: Option<u64>, dry: bool, ye
         &target_migration_id,
         &up_sql,
         &down_sql,
-        None, // comment not available in this legacy function
+        &Default::default(), // meta not available in this legacy function
         last_migration_id.as_deref(),
         false, // locked not available in this legacy function
+        None,
     ).await?;
 
     if dry {
         migration_tx.rollback().await?;
-        println!("🔄 Migration {} executed and rolled back (dry-run mode).", target_migration_id);
+        crate::say!("🔄 Migration {} executed and rolled back (dry-run mode).", target_migration_id);
     } else {
         migration_tx.commit().await?;
-        println!("✅ Migration {} applied successfully.", target_migration_id);
+        crate::say!("✅ Migration {} applied successfully.", target_migration_id);
     }
 
     Ok(())
@@ -827,10 +1131,10 @@ pub async fn apply_down(path: &Path, id: &str, timeout: Option<u64>, remote: boo
     let mut needs_confirmation = false;
     if !applied_migrations.is_empty() {
         let max_applied_migration =
-            applied_migrations.iter().max().cloned().unwrap_or_default();
+            applied_migrations.iter().max_by(|a, b| crate::core::migration::compare_ids(a, b)).cloned().unwrap_or_default();
 
         if target_migration_id != max_applied_migration {
-            println!("⚠️  Non-linear history detected!");
+            crate::say!("⚠️  Non-linear history detected!");
             println!(
                 "Reverting migration {} would create a non-linear history.",
                 target_migration_id
@@ -879,14 +1183,14 @@ pub async fn apply_down(path: &Path, id: &str, timeout: Option<u64>, remote: boo
     let diff_fn = create_single_migration_diff_fn(&target_migration_id, &down_sql, "DOWN");
     
     if !prompt_for_confirmation_with_diff(&format!("❓ Do you want to revert migration '{}'?", target_migration_id), yes, diff_fn)? {
-        println!("❌ Operation cancelled.");
+        crate::say!("❌ Operation cancelled.");
         return Ok(());
     }
 
     // Execute the down migration
     let mut revert_tx = pool.begin().await?;
 
-    set_timeout_if_needed(&mut *revert_tx, effective_timeout).await?;
+    set_timeout_if_needed(&mut revert_tx, effective_timeout, config.lock_timeout).await?;
 
     if dry {
         println!("Testing revert migration: {}", target_migration_id);
@@ -900,10 +1204,10 @@ pub async fn apply_down(path: &Path, id: &str, timeout: Option<u64>, remote: boo
 
     if dry {
         revert_tx.rollback().await?;
-        println!("🔄 Migration {} reverted and rolled back (dry-run mode).", target_migration_id);
+        crate::say!("🔄 Migration {} reverted and rolled back (dry-run mode).", target_migration_id);
     } else {
         revert_tx.commit().await?;
-        println!("✅ Migration {} reverted successfully.", target_migration_id);
+        crate::say!("✅ Migration {} reverted successfully.", target_migration_id);
     }
 
     Ok(())
@@ -920,7 +1224,7 @@ pub async fn list(path: &Path, schema: &str, migrations_table: &str, pool: &Pool
     remote.sort_by(|a, b| a.0.cmp(&b.0));
 
     let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
-    crate::core::migration::render_migration_table(&local_migrations, &remote, migration_dir)?;
+    crate::core::migration::render_migration_table(&local_migrations, &remote, &Default::default(), &Default::default(), None, migration_dir, None)?;
 
     tx.commit().await?;
 
@@ -936,7 +1240,7 @@ pub async fn history_fix(path: &Path, schema: &str, migrations_table: &str, pool
 
     let applied_migrations = get_applied_migrations(&mut tx, &schema, &migrations_table).await?;
 
-    let max_applied_migration = applied_migrations.iter().max().cloned().unwrap_or_default();
+    let max_applied_migration = applied_migrations.iter().max_by(|a, b| crate::core::migration::compare_ids(a, b)).cloned().unwrap_or_default();
 
     let max_applied_ts = applied_migrations
         .iter()
@@ -946,14 +1250,17 @@ pub async fn history_fix(path: &Path, schema: &str, migrations_table: &str, pool
 
     let mut next_ts = std::cmp::max(max_applied_ts, Utc::now().timestamp_millis());
 
-    let out_of_order_migrations: Vec<String> = local_migrations
+    let mut out_of_order_migrations: Vec<String> = local_migrations
         .difference(&applied_migrations)
-        .filter(|id| id.as_str() < max_applied_migration.as_str())
+        .filter(|id| crate::core::migration::compare_ids(id, &max_applied_migration).is_lt())
         .cloned()
         .collect();
+    // Renamed oldest first, so they keep their order
+    crate::core::migration::sort_ids(&mut out_of_order_migrations);
 
+    let mut renamed = Vec::new();
     if out_of_order_migrations.is_empty() {
-        println!("No out-of-order migrations to fix.");
+        crate::summary!("No out-of-order migrations to fix.");
     } else {
         for old_id in out_of_order_migrations {
             next_ts += 1;
@@ -969,11 +1276,13 @@ pub async fn history_fix(path: &Path, schema: &str, migrations_table: &str, pool
                 )
             })?;
 
-            println!("Shuffled migration {} to {}", old_id, new_id);
+            crate::say!("Shuffled migration {} to {}", old_id, new_id);
+            renamed.push(crate::core::migration::RenamedOut { from: old_id, to: next_ts.to_string() });
         }
     }
 
     tx.commit().await?;
+    crate::core::output::result(&crate::core::migration::HistoryFixOut { command: "history fix", renamed })?;
 
     Ok(())
 }
@@ -986,8 +1295,9 @@ pub async fn history_sync(path: &Path, schema: &str, migrations_table: &str, poo
 
     let all_migrations = get_all_migration_data(&mut tx, &schema, &migrations_table).await?;
 
+    let mut synced = Vec::new();
     if all_migrations.is_empty() {
-        println!("No migrations to sync.");
+        crate::summary!("No migrations to sync.");
     } else {
         for row in all_migrations {
             let id: String = row.get("id");
@@ -1015,42 +1325,13 @@ pub async fn history_sync(path: &Path, schema: &str, migrations_table: &str, poo
                 format!("Failed to write down migration: {}", down_path.display())
             })?;
 
-            println!("Synced migration: {}", id);
+            crate::say!("Synced migration: {}", id);
+            synced.push(id);
         }
     }
 
     tx.commit().await?;
-
-    Ok(())
-}
-
-pub async fn diff(path: &Path, schema: &str, migrations_table: &str, pool: &Pool<Postgres>) -> Result<()> {
-    let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
-    let local_migrations = get_local_migrations(path)?;
-    let schema = schema;
-
-    let mut tx = pool.begin().await?;
-
-    let applied_migrations = get_applied_migrations(&mut tx, &schema, &migrations_table).await?;
-
-    tx.commit().await?;
-
-    let mut migrations_to_apply: Vec<String> =
-        local_migrations.difference(&applied_migrations).cloned().collect();
-
-    migrations_to_apply.sort();
-
-    if migrations_to_apply.is_empty() {
-        println!("All migrations are up to date.");
-    } else {
-        for migration_id in &migrations_to_apply {
-            let (up_sql, _down_sql) = crate::core::migration::read_migration_files(
-                migration_dir, migration_id
-            )?;
-            // Render with same formatting as interactive 'd'
-            crate::core::migration::display_sql_migration(migration_id, &up_sql, "UP")?;
-        }
-    }
+    crate::core::output::result(&crate::core::migration::HistorySyncOut { command: "history sync", synced })?;
 
     Ok(())
 }
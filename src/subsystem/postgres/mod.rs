@@ -2,26 +2,45 @@ pub mod commands;
 pub mod migration;
 pub mod repo;
 pub mod config;
+pub mod tenants;
 
 #[cfg(feature = "sub+postgres")]
-use crate::config::{Config, Subsystem, DataSource};
+use crate::config::{Config, Subsystem, DataSource, Tables};
 #[cfg(feature = "sub+postgres")]
 use crate::subsystem::postgres::config::SubsystemPostgres;
 
 #[cfg(feature = "sub+postgres")]
 pub fn build_sample(connection: &str) -> crate::config::Config {
-    use crate::subsystem::postgres::config::Tables;
-
     Config {
         version: env!("CARGO_PKG_VERSION").to_string(),
         subsystem: Subsystem::Postgres(SubsystemPostgres {
             connection: DataSource::Static(connection.to_string()),
+            scratch: None,
             timeout: Some(60),
-            tables: Tables {
-                migrations: "__qop_migrations".to_string(),
-                log: "__qop_log".to_string(),
-            },
+            lock_timeout: None,
+            lock_check: None,
+            tables: Tables::default(),
+            protected: false,
+            fmt: None,
+            seeds: None,
+            allowed_seeds: Vec::new(),
+            hooks: Default::default(),
+            snapshot: None,
+            schema_file: None,
+            run_lock_timeout: None,
+            no_run_lock: false,
+            ids: Default::default(),
+            retry: Default::default(),
+            window: None,
+            tenants: None,
+            profile: None,
+            templates: None,
+            ssl_mode: None,
+            root_cert: None,
+            client_cert: None,
+            client_key: None,
             schema: "public".to_string(),
+            search_path: Vec::new(),
         }),
     }
 }
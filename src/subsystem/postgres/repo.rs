@@ -1,9 +1,9 @@
 use {
-    crate::core::repo::MigrationRepository,
+    crate::core::{migration::{ExecOptions, MigrationMeta}, repo::MigrationRepository},
     crate::subsystem::postgres::migration as pg,
-    anyhow::Result,
+    anyhow::{Context, Result},
     chrono::NaiveDateTime,
-    sqlx::{Pool, Postgres, Row},
+    sqlx::{Connection, Pool, Postgres, Row},
     std::collections::HashSet,
 };
 
@@ -11,32 +11,293 @@ pub struct PostgresRepo {
     pub config: crate::subsystem::postgres::config::SubsystemPostgres,
     pub pool: Pool<Postgres>,
     pub path: std::path::PathBuf,
+    /// Connection holding the advisory run lock
+    run_lock: tokio::sync::Mutex<Option<sqlx::PgConnection>>,
 }
 
 impl PostgresRepo {
     pub async fn from_config(path: &std::path::Path, config: crate::subsystem::postgres::config::SubsystemPostgres, check_cli_version: bool) -> Result<Self> {
         let pool = pg::build_pool_from_config(path, &config, check_cli_version).await?;
-        Ok(Self { config, pool, path: path.to_path_buf() })
+        Ok(Self { config, pool, path: path.to_path_buf(), run_lock: Default::default() })
+    }
+
+    /// Connect to a fresh throwaway database, created on the `scratch` server when one is
+    /// configured and in a container otherwise
+    pub async fn ephemeral(path: &std::path::Path, mut config: crate::subsystem::postgres::config::SubsystemPostgres) -> Result<(Self, EphemeralDatabase)> {
+        let (pool, db) = match config.scratch.clone() {
+            | Some(scratch) => {
+                config.connection = scratch;
+                let admin = pg::connect_options(path, &config)?;
+                let name = format!("qop_scratch_{}", uuid::Uuid::now_v7().simple());
+                let mut conn = sqlx::PgConnection::connect_with(&admin).await.context("Failed to connect to the scratch server")?;
+                sqlx::raw_sql(&format!("CREATE DATABASE {}", pg::quote_ident(&name)))
+                    .execute(&mut conn)
+                    .await
+                    .with_context(|| format!("Failed to create scratch database '{}' (the user needs the CREATEDB privilege)", name))?;
+                conn.close().await?;
+                // pg_dump connects through the connection string as well, so it has to name the new database
+                let uri = pg::resolve_connection(path, &config)?;
+                let separator = if uri.contains('?') { '&' } else { '?' };
+                config.connection = crate::config::DataSource::Static(format!("{}{}dbname={}", uri, separator, name));
+                let options = pg::connect_options(path, &config)?;
+                let pool = match pg::build_pool_with_options(options, &config, false).await {
+                    | Ok(pool) => pool,
+                    | Err(e) => {
+                        ShadowDatabase { name, admin, pool: None }.remove().await?;
+                        return Err(e);
+                    },
+                };
+                (pool.clone(), EphemeralDatabase::Scratch(ShadowDatabase { name, admin, pool: Some(pool) }))
+            },
+            | None => Self::container(path, &mut config).await?,
+        };
+        let repo = Self { config, pool, path: path.to_path_buf(), run_lock: Default::default() };
+        let mut query = sqlx::QueryBuilder::new("CREATE SCHEMA IF NOT EXISTS ");
+        query.push(pg::quote_ident(&repo.config.schema));
+        if let Err(e) = query.build().execute(&repo.pool).await {
+            db.remove().await?;
+            return Err(e.into());
+        }
+        Ok((repo, db))
+    }
+
+    #[cfg(feature = "containers")]
+    async fn container(path: &std::path::Path, config: &mut crate::subsystem::postgres::config::SubsystemPostgres) -> Result<(Pool<Postgres>, EphemeralDatabase)> {
+        use testcontainers_modules::testcontainers::runners::AsyncRunner;
+        let container = testcontainers_modules::postgres::Postgres::default()
+            .start()
+            .await
+            .context("Failed to start an ephemeral Postgres container (is Docker running?)")?;
+        let host = container.get_host().await?;
+        let port = container.get_host_port_ipv4(5432).await?;
+        config.connection = crate::config::DataSource::Static(format!("postgres://postgres:postgres@{}:{}/postgres", host, port));
+        let pool = pg::build_pool_with_options(pg::connect_options(path, config)?, config, false).await?;
+        Ok((pool, EphemeralDatabase::Container(container)))
+    }
+
+    #[cfg(not(feature = "containers"))]
+    async fn container(_path: &std::path::Path, _config: &mut crate::subsystem::postgres::config::SubsystemPostgres) -> Result<(Pool<Postgres>, EphemeralDatabase)> {
+        anyhow::bail!("No throwaway database available: set `scratch` in the config to a server qop may create databases on, or build qop with the `containers` feature to start one in Docker")
+    }
+
+    /// Connect to a copy of the configured database, created with it as the template. Postgres
+    /// only copies a database nobody is connected to, so this fails while other sessions are open.
+    pub async fn shadow(path: &std::path::Path, mut config: crate::subsystem::postgres::config::SubsystemPostgres) -> Result<(Self, ShadowDatabase)> {
+        let options = pg::connect_options(path, &config)?;
+        let source = options.get_database().unwrap_or(options.get_username()).to_string();
+        let name = format!("{}_shadow_{}", source.chars().take(48).collect::<String>(), &uuid::Uuid::now_v7().simple().to_string()[20..]);
+        let maintenance = if source == "postgres" { "template1" } else { "postgres" };
+        let admin = options.clone().database(maintenance);
+        let mut conn = sqlx::PgConnection::connect_with(&admin)
+            .await
+            .with_context(|| format!("Failed to connect to the '{}' database to create the shadow database", maintenance))?;
+        sqlx::raw_sql(&format!("CREATE DATABASE {} TEMPLATE {}", pg::quote_ident(&name), pg::quote_ident(&source)))
+            .execute(&mut conn)
+            .await
+            .with_context(|| format!("Failed to copy database '{}' into shadow database '{}' (no other sessions may be connected to it)", source, name))?;
+        conn.close().await?;
+
+        // Nobody else knows the copy, there is no run to wait for
+        config.no_run_lock = true;
+        let pool = match pg::build_pool_with_options(options.database(&name), &config, true).await {
+            | Ok(pool) => pool,
+            | Err(e) => {
+                ShadowDatabase { name, admin, pool: None }.remove().await?;
+                return Err(e);
+            },
+        };
+        let db = ShadowDatabase { name, admin, pool: Some(pool.clone()) };
+        Ok((Self { config, pool, path: path.to_path_buf(), run_lock: Default::default() }, db))
+    }
+
+    /// Create a `(name, checksum, applied_at)` tracking table as used by seeds and repeatable
+    /// migrations; both may be used on databases initialized before their table existed
+    async fn ensure_checksum_table(&self, tx: &mut sqlx::Transaction<'_, Postgres>, table: &str) -> Result<()> {
+        let mut query = pg::build_table_query("CREATE TABLE IF NOT EXISTS ", &self.config.schema, table);
+        query.push(" (name VARCHAR PRIMARY KEY, checksum VARCHAR NOT NULL, applied_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP)");
+        query.build().execute(&mut **tx).await?;
+        Ok(())
+    }
+
+    async fn fetch_checksums(&self, table: &str) -> Result<Vec<(String, String, NaiveDateTime)>> {
+        let mut tx = self.pool.begin().await?;
+        self.ensure_checksum_table(&mut tx, table).await?;
+        let mut q = pg::build_table_query("SELECT name, checksum, applied_at FROM ", &self.config.schema, table);
+        q.push(" ORDER BY name");
+        let rows = q.build().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(rows.into_iter().map(|row| (row.get("name"), row.get("checksum"), row.get("applied_at"))).collect())
+    }
+
+    /// Execute the SQL and record its checksum under `name` in `table` in one transaction
+    #[allow(clippy::too_many_arguments)]
+    async fn run_checksummed(&self, table: &str, operation: &str, name: &str, sql: &str, checksum: &str, timeout: Option<u64>, dry_run: bool) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        self.ensure_checksum_table(&mut tx, table).await?;
+        pg::set_timeout_if_needed(&mut tx, timeout, self.config.lock_timeout).await?;
+        pg::set_search_path(&mut tx, &self.config.search_path, true).await?;
+        pg::execute_sql_statements(&mut tx, sql, name).await?;
+        let mut q = pg::build_table_query("INSERT INTO ", &self.config.schema, table);
+        q.push(" (name, checksum) VALUES (").push_bind(name).push(", ").push_bind(checksum).push(")");
+        q.push(" ON CONFLICT (name) DO UPDATE SET checksum = excluded.checksum, applied_at = CURRENT_TIMESTAMP");
+        q.build().execute(&mut *tx).await?;
+        pg::insert_log_entry(&mut *tx, &self.config.schema, &self.config.tables.log, &format!("{}:{}", operation, name), operation, sql).await?;
+        if dry_run { tx.rollback().await?; } else { tx.commit().await?; }
+        Ok(())
+    }
+
+    /// Report or wait for long-running transactions holding locks on the tables the SQL alters, as
+    /// configured by `lock_check`, instead of queueing the DDL and all traffic after it behind them
+    async fn check_lock_contention(&self, id: &str, sql: &str) -> Result<()> {
+        use crate::subsystem::postgres::config::LockCheckMode;
+        let Some(check) = &self.config.lock_check else { return Ok(()) };
+        let tables = crate::core::migration_diff::locked_tables(&crate::core::migration_diff::parse_migration_operations(sql));
+        if tables.is_empty() {
+            return Ok(());
+        }
+        let timeout = std::time::Duration::from_secs(check.timeout.unwrap_or(300));
+        let start = std::time::Instant::now();
+        let mut announced = false;
+        loop {
+            let mut tx = self.pool.begin().await?;
+            pg::set_search_path(&mut tx, &self.config.search_path, true).await?;
+            let sessions = pg::find_blocking_sessions(&mut tx, &tables, check.min_duration.unwrap_or(10)).await?;
+            tx.rollback().await?;
+            if sessions.is_empty() {
+                return Ok(());
+            }
+            let described = sessions
+                .iter()
+                .map(|s| match s.application.is_empty() {
+                    | true => format!("pid {} on {} ({}, open for {}s)", s.pid, s.table, s.state, s.seconds),
+                    | false => format!("pid {} on {} ({}, open for {}s, {})", s.pid, s.table, s.state, s.seconds, s.application),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            match check.mode {
+                | LockCheckMode::Warn => {
+                    crate::esay!("⚠️  Migration {} needs locks that long-running transactions hold: {}", id, described);
+                    return Ok(());
+                },
+                | LockCheckMode::Wait if start.elapsed() >= timeout => {
+                    anyhow::bail!("Migration {} still waits for long-running transactions after {}s: {}", id, timeout.as_secs(), described);
+                },
+                | LockCheckMode::Wait => {
+                    if !announced {
+                        crate::esay!("⏳ Migration {} waits for long-running transactions holding its locks: {}", id, described);
+                        announced = true;
+                    }
+                    tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+                },
+            }
+        }
+    }
+
+    /// SET TRANSACTION has to run before any other statement of the transaction
+    async fn set_isolation(&self, tx: &mut sqlx::Transaction<'_, Postgres>, exec: &ExecOptions) -> Result<()> {
+        if let Some(isolation) = exec.isolation {
+            sqlx::raw_sql(&format!("SET TRANSACTION ISOLATION LEVEL {}", isolation.as_sql())).execute(&mut **tx).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Throwaway database of `test` and `generate`, dropped by `remove`
+pub enum EphemeralDatabase {
+    /// Database created on the `scratch` server
+    Scratch(ShadowDatabase),
+    /// Postgres container, stopped and removed when dropped
+    #[cfg(feature = "containers")]
+    Container(testcontainers_modules::testcontainers::ContainerAsync<testcontainers_modules::postgres::Postgres>),
+}
+
+impl EphemeralDatabase {
+    pub async fn remove(self) -> Result<()> {
+        match self {
+            | EphemeralDatabase::Scratch(db) => db.remove().await,
+            #[cfg(feature = "containers")]
+            | EphemeralDatabase::Container(container) => {
+                drop(container);
+                Ok(())
+            },
+        }
+    }
+}
+
+/// Scratch copy of the configured database, dropped by `remove`
+pub struct ShadowDatabase {
+    pub name: String,
+    admin: sqlx::postgres::PgConnectOptions,
+    pool: Option<Pool<Postgres>>,
+}
+
+impl ShadowDatabase {
+    /// Close the connections to the copy and drop it
+    pub async fn remove(self) -> Result<()> {
+        if let Some(pool) = self.pool {
+            pool.close().await;
+        }
+        let mut conn = sqlx::PgConnection::connect_with(&self.admin).await?;
+        sqlx::raw_sql(&format!("DROP DATABASE IF EXISTS {}", pg::quote_ident(&self.name)))
+            .execute(&mut conn)
+            .await
+            .with_context(|| format!("Failed to drop database '{}'", self.name))?;
+        conn.close().await?;
+        Ok(())
     }
 }
 
 #[async_trait::async_trait(?Send)]
 impl MigrationRepository for PostgresRepo {
+    async fn acquire_run_lock(&self) -> Result<()> {
+        let mut held = self.run_lock.lock().await;
+        if self.config.no_run_lock || held.is_some() {
+            return Ok(());
+        }
+        // A session-level lock on a connection of its own, released when qop exits or is killed
+        let mut conn = self.pool.acquire().await?.detach();
+        let key = format!("qop:{}.{}", self.config.schema, self.config.tables.migrations);
+        let mut wait = crate::core::wait::RunLockWait::new(self.config.run_lock_timeout);
+        while !sqlx::query_scalar::<_, bool>("SELECT pg_try_advisory_lock(hashtextextended($1, 0))").bind(&key).fetch_one(&mut conn).await? {
+            wait.retry().await?;
+        }
+        *held = Some(conn);
+        Ok(())
+    }
+
+    async fn release_run_lock(&self) -> Result<()> {
+        // Ending the session releases its advisory lock
+        if let Some(conn) = self.run_lock.lock().await.take() {
+            sqlx::Connection::close(conn).await?;
+        }
+        Ok(())
+    }
+
     async fn init_store(&self) -> Result<()> {
         let mut tx = self.pool.begin().await?;
         {
+            // Tenant schemas are created on init, like the tables in them
+            let mut schema_query = sqlx::QueryBuilder::new("CREATE SCHEMA IF NOT EXISTS ");
+            schema_query.push(pg::quote_ident(&self.config.schema));
+            schema_query.build().execute(&mut *tx).await?;
+
             // Create migrations table
             let mut query = pg::build_table_query("CREATE TABLE IF NOT EXISTS ", &self.config.schema, &self.config.tables.migrations);
-            query.push(" (id VARCHAR PRIMARY KEY, version VARCHAR NOT NULL, up VARCHAR NOT NULL, down VARCHAR NOT NULL, created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP, pre VARCHAR, comment VARCHAR, locked BOOLEAN NOT NULL DEFAULT FALSE)");
+            query.push(" (id VARCHAR PRIMARY KEY, version VARCHAR NOT NULL, up VARCHAR NOT NULL, down VARCHAR NOT NULL, created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP, pre VARCHAR, comment VARCHAR, locked BOOLEAN NOT NULL DEFAULT FALSE, up_sha256 VARCHAR, down_sha256 VARCHAR, author VARCHAR, ticket VARCHAR, labels VARCHAR, duration_ms BIGINT, applied_by VARCHAR, hostname VARCHAR)");
             query.build().execute(&mut *tx).await?;
+            pg::ensure_tracking_columns(&mut tx, &self.config.schema, &self.config.tables.migrations, &self.config.tables.log).await?;
             
             // Create log table
             let mut log_query = pg::build_table_query("CREATE TABLE IF NOT EXISTS ", &self.config.schema, &self.config.tables.log);
-            log_query.push(" (id VARCHAR PRIMARY KEY, migration_id VARCHAR NOT NULL, operation VARCHAR NOT NULL, sql_command TEXT NOT NULL, executed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP)");
+            log_query.push(" (id VARCHAR PRIMARY KEY, migration_id VARCHAR NOT NULL, operation VARCHAR NOT NULL, sql_command TEXT NOT NULL, executed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP, duration_ms BIGINT, applied_by VARCHAR, hostname VARCHAR, version VARCHAR, invocation TEXT, dry_run BOOLEAN, status VARCHAR, error TEXT)");
             log_query.build().execute(&mut *tx).await?;
+
+            // Create seed and repeatable migration tables
+            self.ensure_checksum_table(&mut tx, &self.config.tables.seeds).await?;
+            self.ensure_checksum_table(&mut tx, &self.config.tables.repeatables).await?;
         }
         tx.commit().await?;
-        println!("Initialized migration tables.");
+        crate::summary!("Initialized migration tables.");
         Ok(())
     }
 
@@ -54,24 +315,172 @@ impl MigrationRepository for PostgresRepo {
         Ok(id)
     }
 
-    async fn apply_migration(&self, id: &str, up_sql: &str, down_sql: &str, comment: Option<&str>, pre: Option<&str>, timeout: Option<u64>, dry_run: bool, locked: bool) -> Result<()> {
+    async fn apply_migration(&self, id: &str, up_sql: &str, down_sql: &str, meta: &MigrationMeta, pre: Option<&str>, exec: &ExecOptions, dry_run: bool, locked: bool) -> Result<()> {
+        self.check_lock_contention(id, up_sql).await?;
+        let started = std::time::Instant::now();
+        if let Some(batch) = exec.batch {
+            if dry_run {
+                anyhow::bail!("Migration {} runs in batches and cannot be dry-run", id);
+            }
+            let batch_sql = batch.render(id, up_sql)?;
+            let (mut number, mut total) = (0, 0);
+            loop {
+                number += 1;
+                let mut tx = self.pool.begin().await?;
+                self.set_isolation(&mut tx, exec).await?;
+                pg::set_timeout_if_needed(&mut tx, exec.timeout, self.config.lock_timeout).await?;
+                pg::set_search_path(&mut tx, &self.config.search_path, true).await?;
+                let rows = sqlx::raw_sql(&batch_sql)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Failed to execute batch {} of migration {}: {}", number, id, e))?
+                    .rows_affected();
+                tx.commit().await?;
+                crate::core::telemetry::add_rows(id, rows);
+                total += rows;
+                if batch.finish_batch(id, number, rows, total) {
+                    break;
+                }
+                batch.pause().await;
+            }
+
+            let duration_ms = Some(started.elapsed().as_millis() as i64);
+            let mut tx = self.pool.begin().await?;
+            pg::insert_migration_record(&mut *tx, &self.config.schema, &self.config.tables.migrations, id, up_sql, down_sql, meta, pre, locked, duration_ms).await?;
+            pg::insert_timed_log_entry(&mut *tx, &self.config.schema, &self.config.tables.log, id, "up", up_sql, duration_ms).await?;
+            tx.commit().await?;
+            return Ok(());
+        }
+        if !exec.transaction {
+            if dry_run {
+                anyhow::bail!("Migration {} runs without a transaction and cannot be dry-run", id);
+            }
+            // A detached connection keeps the session-level timeouts out of the pool
+            let mut conn = self.pool.acquire().await?.detach();
+            pg::set_session_timeouts(&mut conn, exec.timeout, self.config.lock_timeout).await?;
+            pg::set_search_path(&mut conn, &self.config.search_path, false).await?;
+            pg::execute_statements_individually(&mut conn, up_sql, id, exec.skip_statements).await?;
+            sqlx::Connection::close(conn).await?;
+
+            let duration_ms = Some(started.elapsed().as_millis() as i64);
+            let mut tx = self.pool.begin().await?;
+            pg::insert_migration_record(&mut *tx, &self.config.schema, &self.config.tables.migrations, id, up_sql, down_sql, meta, pre, locked, duration_ms).await?;
+            pg::insert_timed_log_entry(&mut *tx, &self.config.schema, &self.config.tables.log, id, "up", up_sql, duration_ms).await?;
+            tx.commit().await?;
+            return Ok(());
+        }
+
         let mut tx = self.pool.begin().await?;
-        pg::set_timeout_if_needed(&mut *tx, timeout).await?;
+        self.set_isolation(&mut tx, exec).await?;
+        pg::set_timeout_if_needed(&mut tx, exec.timeout, self.config.lock_timeout).await?;
+        pg::set_search_path(&mut tx, &self.config.search_path, true).await?;
 
         // Execute migration
         pg::execute_sql_statements(&mut tx, up_sql, id).await?;
-        pg::insert_migration_record(&mut *tx, &self.config.schema, &self.config.tables.migrations, id, up_sql, down_sql, comment, pre, locked).await?;
+        let duration_ms = Some(started.elapsed().as_millis() as i64);
+        pg::insert_migration_record(&mut *tx, &self.config.schema, &self.config.tables.migrations, id, up_sql, down_sql, meta, pre, locked, duration_ms).await?;
 
         // Log successful migration
-        pg::insert_log_entry(&mut *tx, &self.config.schema, &self.config.tables.log, id, "up", up_sql).await?;
+        pg::insert_timed_log_entry(&mut *tx, &self.config.schema, &self.config.tables.log, id, "up", up_sql, duration_ms).await?;
 
         if dry_run { tx.rollback().await?; } else { tx.commit().await?; }
         Ok(())
     }
 
-    async fn revert_migration(&self, id: &str, down_sql: &str, timeout: Option<u64>, dry_run: bool, unlock: bool) -> Result<()> {
+    async fn apply_migrations(&self, migrations: &[(String, String, String, MigrationMeta)], pre: Option<&str>, timeout: Option<u64>, keep_going: bool, dry_run: bool) -> Result<Vec<(String, anyhow::Error)>> {
+        for (id, up_sql, ..) in migrations {
+            self.check_lock_contention(id, up_sql).await?;
+        }
+        let mut tx = self.pool.begin().await?;
+        // The caller made sure all migrations share one isolation level
+        if let Some((_, _, _, meta)) = migrations.first() {
+            self.set_isolation(&mut tx, &meta.exec_options(timeout)).await?;
+        }
+        pg::set_search_path(&mut tx, &self.config.search_path, true).await?;
+        let mut pre = pre;
+        let mut failed = Vec::new();
+        for (id, up_sql, down_sql, meta) in migrations {
+            sqlx::raw_sql("SAVEPOINT qop_migration").execute(&mut *tx).await?;
+            let started = std::time::Instant::now();
+            let applied = async {
+                // SET LOCAL holds until the next one, so every migration gets its own timeout
+                pg::set_timeout_if_needed(&mut tx, meta.exec_options(timeout).timeout, self.config.lock_timeout).await?;
+                pg::execute_sql_statements(&mut tx, up_sql, id).await?;
+                let duration_ms = Some(started.elapsed().as_millis() as i64);
+                pg::insert_migration_record(&mut *tx, &self.config.schema, &self.config.tables.migrations, id, up_sql, down_sql, meta, pre, meta.is_locked(), duration_ms).await?;
+                pg::insert_timed_log_entry(&mut *tx, &self.config.schema, &self.config.tables.log, id, "up", up_sql, duration_ms).await
+            }
+            .await;
+            match applied {
+                | Ok(()) => {
+                    sqlx::raw_sql("RELEASE SAVEPOINT qop_migration").execute(&mut *tx).await?;
+                    pre = Some(id);
+                },
+                | Err(e) if keep_going => {
+                    sqlx::raw_sql("ROLLBACK TO SAVEPOINT qop_migration").execute(&mut *tx).await?;
+                    failed.push((id.clone(), e));
+                },
+                | Err(e) => return Err(e.context(format!("Migration {} failed, none of the {} migrations were applied", id, migrations.len()))),
+            }
+        }
+        if dry_run { tx.rollback().await?; } else { tx.commit().await?; }
+        Ok(failed)
+    }
+
+    async fn check_idempotency(&self, migrations: &[(String, String, MigrationMeta)], timeout: Option<u64>) -> Result<Vec<(String, anyhow::Error)>> {
         let mut tx = self.pool.begin().await?;
-        pg::set_timeout_if_needed(&mut *tx, timeout).await?;
+        pg::set_search_path(&mut tx, &self.config.search_path, true).await?;
+        let mut failed = Vec::new();
+        for (id, up_sql, meta) in migrations {
+            pg::set_timeout_if_needed(&mut tx, meta.exec_options(timeout).timeout, self.config.lock_timeout).await?;
+            pg::execute_sql_statements(&mut tx, up_sql, id).await.with_context(|| format!("Migration {} failed on its first run", id))?;
+            // The second run may fail, the savepoint keeps the transaction usable for the next migration
+            sqlx::raw_sql("SAVEPOINT qop_migration").execute(&mut *tx).await?;
+            match pg::execute_sql_statements(&mut tx, up_sql, id).await {
+                | Ok(()) => {
+                    sqlx::raw_sql("RELEASE SAVEPOINT qop_migration").execute(&mut *tx).await?;
+                },
+                | Err(e) => {
+                    sqlx::raw_sql("ROLLBACK TO SAVEPOINT qop_migration").execute(&mut *tx).await?;
+                    failed.push((id.clone(), e));
+                },
+            }
+        }
+        tx.rollback().await?;
+        Ok(failed)
+    }
+
+    async fn revert_migration(&self, id: &str, down_sql: &str, exec: &ExecOptions, dry_run: bool, unlock: bool) -> Result<()> {
+        self.check_lock_contention(id, down_sql).await?;
+        let started = std::time::Instant::now();
+        if !exec.transaction {
+            if dry_run {
+                anyhow::bail!("Migration {} runs without a transaction and cannot be dry-run", id);
+            }
+            let mut tx = self.pool.begin().await?;
+            let is_locked = pg::is_migration_locked(&mut *tx, &self.config.schema, &self.config.tables.migrations, id).await?;
+            tx.commit().await?;
+            if is_locked && !unlock {
+                anyhow::bail!("Migration {} is locked and cannot be reverted without --unlock flag", id);
+            }
+
+            let mut conn = self.pool.acquire().await?.detach();
+            pg::set_session_timeouts(&mut conn, exec.timeout, self.config.lock_timeout).await?;
+            pg::set_search_path(&mut conn, &self.config.search_path, false).await?;
+            pg::execute_statements_individually(&mut conn, down_sql, id, 0).await?;
+            sqlx::Connection::close(conn).await?;
+
+            let mut tx = self.pool.begin().await?;
+            pg::delete_migration_record(&mut *tx, &self.config.schema, &self.config.tables.migrations, id).await?;
+            pg::insert_timed_log_entry(&mut *tx, &self.config.schema, &self.config.tables.log, id, "down", down_sql, Some(started.elapsed().as_millis() as i64)).await?;
+            tx.commit().await?;
+            return Ok(());
+        }
+
+        let mut tx = self.pool.begin().await?;
+        self.set_isolation(&mut tx, exec).await?;
+        pg::set_timeout_if_needed(&mut tx, exec.timeout, self.config.lock_timeout).await?;
+        pg::set_search_path(&mut tx, &self.config.search_path, true).await?;
         
         // Check if migration is locked
         let is_locked = pg::is_migration_locked(&mut *tx, &self.config.schema, &self.config.tables.migrations, id).await?;
@@ -81,10 +490,11 @@ impl MigrationRepository for PostgresRepo {
         
         // Execute revert migration
         pg::execute_sql_statements(&mut tx, down_sql, id).await?;
+        let duration_ms = Some(started.elapsed().as_millis() as i64);
         pg::delete_migration_record(&mut *tx, &self.config.schema, &self.config.tables.migrations, id).await?;
 
         // Log successful revert
-        pg::insert_log_entry(&mut *tx, &self.config.schema, &self.config.tables.log, id, "down", down_sql).await?;
+        pg::insert_timed_log_entry(&mut *tx, &self.config.schema, &self.config.tables.log, id, "down", down_sql, duration_ms).await?;
 
         if dry_run { tx.rollback().await?; } else { tx.commit().await?; }
         Ok(())
@@ -120,5 +530,387 @@ impl MigrationRepository for PostgresRepo {
         Ok(rows.into_iter().map(|row| (row.get("id"), row.get("up"), row.get("down"), row.get("comment"))).collect())
     }
 
+    async fn fetch_provenance(&self) -> Result<std::collections::HashMap<String, crate::core::migration::Provenance>> {
+        let mut tx = self.pool.begin().await?;
+        let rows = pg::build_table_query("SELECT id, author, ticket, labels FROM ", &self.config.schema, &self.config.tables.migrations).build().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.get("id"), crate::core::migration::Provenance::from_columns(row.get("author"), row.get("ticket"), row.get("labels"))))
+            .collect())
+    }
+
+    async fn fetch_applied_by(&self) -> Result<crate::core::migration::AppliedBy> {
+        let mut tx = self.pool.begin().await?;
+        let rows = pg::build_table_query("SELECT id, applied_by, hostname FROM ", &self.config.schema, &self.config.tables.migrations).build().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(rows.into_iter().map(|row| (row.get("id"), (row.get("applied_by"), row.get("hostname")))).collect())
+    }
+
+    async fn fetch_versions(&self) -> Result<std::collections::HashMap<String, (String, Option<String>)>> {
+        let mut tx = self.pool.begin().await?;
+        let rows = pg::build_table_query("SELECT id, version, comment FROM ", &self.config.schema, &self.config.tables.migrations).build().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(rows.into_iter().map(|row| (row.get("id"), (row.get("version"), row.get("comment")))).collect())
+    }
+
+    async fn fetch_durations(&self) -> Result<std::collections::HashMap<String, i64>> {
+        let mut tx = self.pool.begin().await?;
+        let mut q = pg::build_table_query("SELECT id, duration_ms FROM ", &self.config.schema, &self.config.tables.migrations);
+        q.push(" WHERE duration_ms IS NOT NULL");
+        let rows = q.build().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(rows.into_iter().map(|row| (row.get("id"), row.get("duration_ms"))).collect())
+    }
+
+    async fn fetch_migration_checksums(&self) -> Result<Vec<(String, Option<String>, Option<String>)>> {
+        let mut tx = self.pool.begin().await?;
+        let mut q = pg::build_table_query("SELECT id, up_sha256, down_sha256 FROM ", &self.config.schema, &self.config.tables.migrations);
+        let rows = q.build().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+        let mut checksums: Vec<(String, Option<String>, Option<String>)> = rows.into_iter().map(|row| (row.get("id"), row.get("up_sha256"), row.get("down_sha256"))).collect();
+        checksums.sort_by(|a, b| crate::core::migration::compare_ids(&a.0, &b.0));
+        Ok(checksums)
+    }
+
+    async fn bench_migrations(&self, migrations: &[(String, String, ExecOptions)]) -> Result<Vec<std::time::Duration>> {
+        let mut timings = Vec::with_capacity(migrations.len());
+        for (id, up_sql, exec) in migrations {
+            let started = std::time::Instant::now();
+            if exec.transaction {
+                let mut tx = self.pool.begin().await?;
+                self.set_isolation(&mut tx, exec).await?;
+                pg::set_timeout_if_needed(&mut tx, exec.timeout, self.config.lock_timeout).await?;
+                pg::set_search_path(&mut tx, &self.config.search_path, true).await?;
+                pg::execute_sql_statements(&mut tx, up_sql, id).await?;
+                tx.commit().await?;
+            } else {
+                let mut conn = self.pool.acquire().await?.detach();
+                pg::set_session_timeouts(&mut conn, exec.timeout, self.config.lock_timeout).await?;
+                pg::set_search_path(&mut conn, &self.config.search_path, false).await?;
+                pg::execute_statements_individually(&mut conn, up_sql, id, 0).await?;
+                sqlx::Connection::close(conn).await?;
+            }
+            timings.push(started.elapsed());
+        }
+        Ok(timings)
+    }
+
+    async fn squash_migrations(&self, ids: &[String], id: &str, up_sql: &str, down_sql: &str, meta: &MigrationMeta, pre: Option<&str>, locked: bool) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for old_id in ids {
+            pg::delete_migration_record(&mut *tx, &self.config.schema, &self.config.tables.migrations, old_id).await?;
+        }
+        pg::insert_migration_record(&mut *tx, &self.config.schema, &self.config.tables.migrations, id, up_sql, down_sql, meta, pre, locked, None).await?;
+        pg::insert_log_entry(&mut *tx, &self.config.schema, &self.config.tables.log, id, "squash", &ids.join(",")).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn baseline_migrations(&self, migrations: &[(String, String, String, MigrationMeta, bool)]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for (id, up_sql, down_sql, meta, locked) in migrations {
+            let pre = pg::get_last_migration_id(&mut tx, &self.config.schema, &self.config.tables.migrations).await?;
+            pg::insert_migration_record(&mut *tx, &self.config.schema, &self.config.tables.migrations, id, up_sql, down_sql, meta, pre.as_deref(), *locked, None).await?;
+            pg::insert_log_entry(&mut *tx, &self.config.schema, &self.config.tables.log, id, "baseline", up_sql).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn rename_migration(&self, id: &str, new_id: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let mut q = pg::build_table_query("UPDATE ", &self.config.schema, &self.config.tables.migrations);
+        q.push(" SET id = ").push_bind(new_id).push(" WHERE id = ").push_bind(id);
+        q.build().execute(&mut *tx).await?;
+        let mut q = pg::build_table_query("UPDATE ", &self.config.schema, &self.config.tables.migrations);
+        q.push(" SET pre = ").push_bind(new_id).push(" WHERE pre = ").push_bind(id);
+        q.build().execute(&mut *tx).await?;
+        let mut q = pg::build_table_query("UPDATE ", &self.config.schema, &self.config.tables.log);
+        q.push(" SET migration_id = ").push_bind(new_id).push(" WHERE migration_id = ").push_bind(id);
+        q.build().execute(&mut *tx).await?;
+        pg::insert_log_entry(&mut *tx, &self.config.schema, &self.config.tables.log, new_id, "rename", id).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn set_migration_locked(&self, id: &str, locked: bool) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let mut q = pg::build_table_query("UPDATE ", &self.config.schema, &self.config.tables.migrations);
+        q.push(" SET locked = ").push_bind(locked).push(" WHERE id = ").push_bind(id);
+        let updated = q.build().execute(&mut *tx).await?.rows_affected() > 0;
+        if updated {
+            pg::insert_log_entry(&mut *tx, &self.config.schema, &self.config.tables.log, id, if locked { "lock" } else { "unlock" }, "").await?;
+        }
+        tx.commit().await?;
+        Ok(updated)
+    }
+
+    async fn forget_migration(&self, id: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        pg::delete_migration_record(&mut *tx, &self.config.schema, &self.config.tables.migrations, id).await?;
+        let mut q = pg::build_table_query("DELETE FROM ", &self.config.schema, &self.config.tables.log);
+        q.push(" WHERE migration_id = ").push_bind(id);
+        q.build().execute(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn prune_migrations(&self, ids: &[String]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for id in ids {
+            pg::delete_migration_record(&mut *tx, &self.config.schema, &self.config.tables.migrations, id).await?;
+            pg::insert_log_entry(&mut *tx, &self.config.schema, &self.config.tables.log, id, "prune", "").await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn repair_migration(&self, id: &str, up_sql: Option<&str>, down_sql: Option<&str>) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for (column, sql) in [("up", up_sql), ("down", down_sql)] {
+            let Some(sql) = sql else { continue };
+            let mut q = pg::build_table_query("UPDATE ", &self.config.schema, &self.config.tables.migrations);
+            q.push(format!(" SET {} = ", column)).push_bind(sql);
+            q.push(format!(", {}_sha256 = ", column)).push_bind(crate::core::migration::sql_checksum(sql));
+            q.push(" WHERE id = ").push_bind(id);
+            q.build().execute(&mut *tx).await?;
+            pg::insert_log_entry(&mut *tx, &self.config.schema, &self.config.tables.log, id, &format!("repair {}", column), sql).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn dump_schema(&self) -> Result<String> {
+        let uri = pg::resolve_connection(&self.path, &self.config)?;
+        let output = std::process::Command::new("pg_dump")
+            .arg("--schema-only")
+            .arg("--no-owner")
+            .arg("--no-privileges")
+            .arg(format!("--exclude-table={}.{}", pg::quote_ident(&self.config.schema), pg::quote_ident(&self.config.tables.migrations)))
+            .arg(format!("--exclude-table={}.{}", pg::quote_ident(&self.config.schema), pg::quote_ident(&self.config.tables.log)))
+            .arg(format!("--exclude-table={}.{}", pg::quote_ident(&self.config.schema), pg::quote_ident(&self.config.tables.seeds)))
+            .arg(format!("--exclude-table={}.{}", pg::quote_ident(&self.config.schema), pg::quote_ident(&self.config.tables.repeatables)))
+            .arg(&uri)
+            .envs(pg::ssl_env(&self.path, &self.config))
+            .output()
+            .map_err(|e| anyhow::anyhow!("Failed to run pg_dump (is it installed and on PATH?): {}", e))?;
+        if !output.status.success() {
+            anyhow::bail!("pg_dump failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        // Newer pg_dump versions emit psql meta-commands (\restrict, \unrestrict) that are not SQL
+        let dump = String::from_utf8(output.stdout)?;
+        Ok(dump.lines().filter(|line| !line.starts_with('\\')).collect::<Vec<_>>().join("\n") + "\n")
+    }
+
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn fetch_table_status(&self) -> Result<Vec<(String, bool)>> {
+        let tables = [&self.config.tables.migrations, &self.config.tables.log, &self.config.tables.seeds, &self.config.tables.repeatables];
+        let mut status = Vec::new();
+        for table in tables {
+            let name = format!("{}.{}", self.config.schema, table);
+            let exists: bool = sqlx::query_scalar("SELECT to_regclass(quote_ident($1) || '.' || quote_ident($2)) IS NOT NULL")
+                .bind(&self.config.schema)
+                .bind(table)
+                .fetch_one(&self.pool)
+                .await?;
+            status.push((name, exists));
+        }
+        Ok(status)
+    }
+
+    async fn estimate_rows(&self, table: &str) -> Result<Option<i64>> {
+        let row = sqlx::query("SELECT reltuples::bigint AS estimate FROM pg_class WHERE oid = to_regclass($1)")
+            .bind(table)
+            .fetch_optional(&self.pool)
+            .await?;
+        // reltuples is -1 for tables that were never analyzed
+        Ok(row.map(|r| r.get::<i64, _>("estimate")).filter(|n| *n >= 0))
+    }
+
+    async fn fetch_foreign_rows(&self, table: &str, columns: &[&str]) -> Result<Option<Vec<Vec<Option<String>>>>> {
+        // Unquoted, so the name folds to lower case and follows the search_path like the other tool's queries
+        let exists: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL").bind(table).fetch_one(&self.pool).await?;
+        if !exists {
+            return Ok(None);
+        }
+        let select: Vec<String> = columns.iter().map(|column| format!("CAST({} AS TEXT)", column)).collect();
+        let rows = sqlx::query(&format!("SELECT {} FROM {}", select.join(", "), table)).fetch_all(&self.pool).await?;
+        Ok(Some(rows.into_iter().map(|row| (0..columns.len()).map(|i| row.get::<Option<String>, _>(i)).collect()).collect()))
+    }
+
+    async fn fetch_chain(&self) -> Result<Vec<(String, Option<String>, NaiveDateTime)>> {
+        let mut tx = self.pool.begin().await?;
+        let mut q = pg::build_table_query("SELECT id, pre, created_at FROM ", &self.config.schema, &self.config.tables.migrations);
+        let rows = q.build().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+        let mut chain: Vec<(String, Option<String>, NaiveDateTime)> = rows.into_iter().map(|row| (row.get("id"), row.get("pre"), row.get("created_at"))).collect();
+        chain.sort_by(|a, b| crate::core::migration::compare_ids(&a.0, &b.0));
+        Ok(chain)
+    }
+
+    async fn relink_migrations(&self, links: &[(String, Option<String>)]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for (id, pre) in links {
+            let mut q = pg::build_table_query("UPDATE ", &self.config.schema, &self.config.tables.migrations);
+            q.push(" SET pre = ").push_bind(pre.as_deref()).push(" WHERE id = ").push_bind(id);
+            q.build().execute(&mut *tx).await?;
+            pg::insert_log_entry(&mut *tx, &self.config.schema, &self.config.tables.log, id, "relink", pre.as_deref().unwrap_or("")).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn fetch_seeds(&self) -> Result<Vec<(String, String, NaiveDateTime)>> {
+        self.fetch_checksums(&self.config.tables.seeds).await
+    }
+
+    async fn run_seed(&self, name: &str, sql: &str, checksum: &str, timeout: Option<u64>, dry_run: bool) -> Result<()> {
+        self.run_checksummed(&self.config.tables.seeds, "seed", name, sql, checksum, timeout, dry_run).await
+    }
+
+    async fn reset_seeds(&self, names: &[String]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        self.ensure_checksum_table(&mut tx, &self.config.tables.seeds).await?;
+        for name in names {
+            let mut q = pg::build_table_query("DELETE FROM ", &self.config.schema, &self.config.tables.seeds);
+            q.push(" WHERE name = ").push_bind(name);
+            q.build().execute(&mut *tx).await?;
+            pg::insert_log_entry(&mut *tx, &self.config.schema, &self.config.tables.log, &format!("seed:{}", name), "seed reset", "").await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn fetch_repeatables(&self) -> Result<Vec<(String, String, NaiveDateTime)>> {
+        self.fetch_checksums(&self.config.tables.repeatables).await
+    }
+
+    async fn run_repeatable(&self, name: &str, sql: &str, checksum: &str, timeout: Option<u64>, dry_run: bool) -> Result<()> {
+        self.run_checksummed(&self.config.tables.repeatables, "repeat", name, sql, checksum, timeout, dry_run).await
+    }
+
+    async fn execute_sql(&self, sql: &str, timeout: Option<u64>, dry_run: bool) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        pg::set_timeout_if_needed(&mut tx, timeout, self.config.lock_timeout).await?;
+        pg::set_search_path(&mut tx, &self.config.search_path, true).await?;
+        pg::execute_sql_statements(&mut tx, sql, "hook").await?;
+        if dry_run { tx.rollback().await?; } else { tx.commit().await?; }
+        Ok(())
+    }
+
+    async fn log_attempt(&self, migration_id: &str, operation: &str, sql: &str, outcome: &crate::core::log::LogOutcome) -> Result<()> {
+        pg::insert_log_record(&self.pool, &self.config.schema, &self.config.tables.log, migration_id, operation, sql, outcome).await
+    }
+
+    async fn fetch_log(&self, filter: &crate::core::log::LogFilter) -> Result<Vec<crate::core::log::LogEntry>> {
+        let mut tx = self.pool.begin().await?;
+        let mut q = pg::build_table_query("SELECT id, migration_id, operation, sql_command, executed_at, duration_ms, applied_by, hostname, version, invocation, dry_run, status, error FROM ", &self.config.schema, &self.config.tables.log);
+        q.push(" WHERE 1=1");
+        if let Some(migration_id) = &filter.migration_id {
+            q.push(" AND migration_id = ").push_bind(migration_id.clone());
+        }
+        if let Some(operation) = &filter.operation {
+            q.push(" AND operation = ").push_bind(operation.clone());
+        }
+        if let Some(since) = filter.since {
+            q.push(" AND executed_at >= ").push_bind(since);
+        }
+        if let Some(until) = filter.until {
+            q.push(" AND executed_at <= ").push_bind(until);
+        }
+        q.push(" ORDER BY executed_at DESC, id DESC");
+        if let Some(limit) = filter.limit {
+            q.push(" LIMIT ").push_bind(limit as i64);
+        }
+        let rows = q.build().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(rows.into_iter().map(|row| crate::core::log::LogEntry {
+            id: row.get("id"),
+            migration_id: row.get("migration_id"),
+            operation: row.get("operation"),
+            sql_command: row.get("sql_command"),
+            executed_at: row.get("executed_at"),
+            duration_ms: row.get("duration_ms"),
+            applied_by: row.get("applied_by"),
+            hostname: row.get("hostname"),
+            version: row.get("version"),
+            invocation: row.get("invocation"),
+            dry_run: row.get("dry_run"),
+            status: row.get("status"),
+            error: row.get("error"),
+        }).collect())
+    }
+
+    async fn fetch_log_entry(&self, id: &str) -> Result<Option<crate::core::log::LogEntry>> {
+        let mut tx = self.pool.begin().await?;
+        let mut q = pg::build_table_query("SELECT id, migration_id, operation, sql_command, executed_at, duration_ms, applied_by, hostname, version, invocation, dry_run, status, error FROM ", &self.config.schema, &self.config.tables.log);
+        q.push(" WHERE id = ").push_bind(id);
+        let row = q.build().fetch_optional(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(row.map(|row| crate::core::log::LogEntry {
+            id: row.get("id"),
+            migration_id: row.get("migration_id"),
+            operation: row.get("operation"),
+            sql_command: row.get("sql_command"),
+            executed_at: row.get("executed_at"),
+            duration_ms: row.get("duration_ms"),
+            applied_by: row.get("applied_by"),
+            hostname: row.get("hostname"),
+            version: row.get("version"),
+            invocation: row.get("invocation"),
+            dry_run: row.get("dry_run"),
+            status: row.get("status"),
+            error: row.get("error"),
+        }))
+    }
+
     fn get_path(&self) -> &std::path::Path { &self.path }
+
+    fn profile(&self) -> Option<&str> {
+        self.config.profile.as_deref()
+    }
+
+    fn scope(&self) -> String {
+        let options = self.pool.connect_options();
+        format!(
+            "postgres://{}:{}/{}/{}.{}",
+            options.get_host(),
+            options.get_port(),
+            options.get_database().unwrap_or(options.get_username()),
+            self.config.schema,
+            self.config.tables.migrations
+        )
+    }
+
+    fn snapshot_file(&self) -> Option<std::path::PathBuf> {
+        let file = self.config.snapshot.as_ref()?;
+        Some(self.path.parent().map(|dir| dir.join(file)).unwrap_or_else(|| file.into()))
+    }
+
+    fn seeds_dir(&self) -> std::path::PathBuf {
+        let dir = self.config.seeds.as_deref().unwrap_or(crate::core::seed::SEEDS_DIR);
+        self.path.parent().map(|parent| parent.join(dir)).unwrap_or_else(|| dir.into())
+    }
+
+    fn hooks(&self) -> &crate::core::hooks::Hooks { &self.config.hooks }
+
+    fn ids(&self) -> &crate::core::migration::IdConfig { &self.config.ids }
+
+    fn retry(&self) -> &crate::core::migration::RetryConfig { &self.config.retry }
+
+    fn is_transient(&self, error: &anyhow::Error) -> bool {
+        match crate::core::migration::find_cause::<sqlx::Error>(error) {
+            // 40001 serialization_failure, 40P01 deadlock_detected, 55P03 lock_not_available (lock_timeout)
+            | Some(sqlx::Error::Database(e)) => e.code().is_some_and(|code| matches!(code.as_ref(), "40001" | "40P01" | "55P03")),
+            | Some(sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut) => true,
+            | _ => false,
+        }
+    }
+
+    fn dialect(&self) -> crate::core::repo::Dialect { crate::core::repo::Dialect::Postgres }
 }
@@ -0,0 +1,66 @@
+use {
+    crate::{
+        core::parallel::{self, Job},
+        subsystem::postgres::{commands::Command, config::SubsystemPostgres},
+    },
+    anyhow::{Context, Result},
+    std::path::{Path, PathBuf},
+};
+
+/// The config of a single tenant: the tracking tables live in the tenant schema, which also comes
+/// first on the search path so unqualified names in migrations resolve to it
+pub fn tenant_config(config: &SubsystemPostgres, schema: &str) -> SubsystemPostgres {
+    let mut config = config.clone();
+    config.tenants = None;
+    config.schema = schema.to_string();
+    config.search_path.retain(|s| s != schema);
+    config.search_path.insert(0, schema.to_string());
+    config
+}
+
+/// The configured tenant schemas followed by the ones the tenant query returns, without duplicates
+pub async fn resolve_schemas(path: &Path, config: &SubsystemPostgres) -> Result<Vec<String>> {
+    let Some(tenants) = &config.tenants else {
+        return Ok(Vec::new());
+    };
+    let mut schemas = tenants.schemas.clone();
+    if let Some(query) = &tenants.query {
+        let pool = crate::subsystem::postgres::migration::build_pool_from_config(path, config, false).await?;
+        let discovered: Vec<String> = sqlx::query_scalar(query).fetch_all(&pool).await.context("Failed to run the tenant query")?;
+        pool.close().await;
+        for schema in discovered {
+            if !schemas.contains(&schema) {
+                schemas.push(schema);
+            }
+        }
+    }
+    Ok(schemas)
+}
+
+/// Run the command once per tenant schema, `tenants.parallel` of them at a time, each with a pool
+/// of its own. A failing tenant does not stop the others; the run fails at the end if any tenant did.
+pub async fn fan_out(path: PathBuf, config: SubsystemPostgres, command: Command) -> Result<()> {
+    let schemas = resolve_schemas(&path, &config).await?;
+    if schemas.is_empty() {
+        anyhow::bail!("no tenant schemas configured");
+    }
+    let parallel = config.tenants.as_ref().and_then(|tenants| tenants.parallel).unwrap_or(1);
+
+    let jobs: Vec<Job> = schemas
+        .iter()
+        .map(|schema| {
+            let subsystem = crate::args::Subsystem::Postgres { path: path.clone(), config: tenant_config(&config, schema), command: command.clone() };
+            let job = async move {
+                // stderr keeps JSON output of the command itself parseable
+                crate::esay!("🏢 Tenant {}", schema);
+                let result = Box::pin(crate::subsystem::driver::dispatch(subsystem)).await;
+                if let Err(e) = &result {
+                    crate::esay!("❌ Tenant {} failed: {:#}", schema, e);
+                }
+                result
+            };
+            (schema.clone(), Box::pin(job) as _)
+        })
+        .collect();
+    parallel::report("tenant", &parallel::run(jobs, parallel).await)
+}
@@ -1,13 +1,17 @@
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum MigrationApply {
     Up {
-        id: String,
+        ids: Vec<String>,
+        from: Option<String>,
+        to: Option<String>,
         timeout: Option<u64>,
         dry: bool,
         yes: bool,
     },
     Down {
-        id: String,
+        ids: Vec<String>,
+        from: Option<String>,
+        to: Option<String>,
         timeout: Option<u64>,
         remote: bool,
         dry: bool,
@@ -16,15 +20,67 @@ pub enum MigrationApply {
     },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum HistoryCommand {
     Sync,
     Fix,
+    Verify {
+        fix: bool,
+        yes: bool,
+        output: Output,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub enum LogCommand {
+    List {
+        migration_id: Option<String>,
+        operation: Option<String>,
+        since: Option<String>,
+        until: Option<String>,
+        limit: Option<usize>,
+        output: Output,
+    },
+    Show { id: String, output: Output },
+    Export {
+        format: LogExportFormat,
+        since: Option<String>,
+        until: Option<String>,
+        out: Option<std::path::PathBuf>,
+    },
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
+pub enum LogExportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Debug, Clone)]
 pub enum ConfigCommand {
     Init { path: String },
+    Upgrade { dry_run: bool },
+}
+
+#[derive(Debug, Clone)]
+pub enum MetaCommand {
+    Check { output: Output },
+    Fix { yes: bool },
+}
+
+#[derive(Debug, Clone)]
+pub enum SeedCommand {
+    Run {
+        profile: Option<String>,
+        /// Seeds a protected config may run; None runs all of them
+        allowed: Option<Vec<String>>,
+        timeout: Option<u64>,
+        force: bool,
+        dry: bool,
+        yes: bool,
+    },
+    List { profile: Option<String>, output: Output },
+    Reset { profile: Option<String>, yes: bool },
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -33,20 +89,52 @@ pub enum Output {
     Json,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
+pub enum GraphFormat {
+    Ascii,
+    Dot,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ImportFormat {
+    Bundle,
+    Liquibase,
+    Sqlx,
+    Alembic,
+    Dbmate,
+}
+
+#[derive(Debug, Clone)]
 pub enum Command {
     Init,
-    New { comment: Option<String>, locked: bool },
+    New {
+        comment: Option<String>,
+        locked: bool,
+        up: Option<std::path::PathBuf>,
+        down: Option<std::path::PathBuf>,
+        stdin: bool,
+        auto_down: bool,
+        template: Option<String>,
+    },
     Up {
         timeout: Option<u64>,
         count: Option<usize>,
+        tags: Vec<String>,
         diff: bool,
         dry: bool,
         yes: bool,
+        single_transaction: bool,
+        keep_going: bool,
+        shadow: bool,
+        assert_idempotent: bool,
+        resume: bool,
+        check: bool,
     },
     Down {
         timeout: Option<u64>,
-        count: usize,
+        count: Option<usize>,
+        to: Option<String>,
+        tags: Vec<String>,
         remote: bool,
         diff: bool,
         dry: bool,
@@ -54,8 +142,151 @@ pub enum Command {
         unlock: bool,
     },
     Apply(MigrationApply),
-    List { output: Output },
+    List { tags: Vec<String>, output: Output, check: bool, wide: bool },
     History(HistoryCommand),
-    Diff,
+    Diff {
+        down: bool,
+        count: Option<usize>,
+        id: Option<String>,
+        output: Output,
+    },
+    Verify { output: Output },
     Config(ConfigCommand),
+    Squash {
+        from: String,
+        to: String,
+        comment: Option<String>,
+        yes: bool,
+    },
+    Baseline { id: String, yes: bool },
+    Log(LogCommand),
+    Rename {
+        id: String,
+        new_id: String,
+        comment: Option<String>,
+        yes: bool,
+    },
+    Lock { id: String },
+    Unlock { id: String },
+    Remove {
+        id: String,
+        force: bool,
+        also_remote: bool,
+        yes: bool,
+    },
+    Prune { yes: bool },
+    Repair {
+        id: String,
+        up: bool,
+        down: bool,
+        yes: bool,
+    },
+    Snapshot { out: Option<std::path::PathBuf> },
+    Test { timeout: Option<u64> },
+    Generate {
+        schema: Option<std::path::PathBuf>,
+        from_db: bool,
+        comment: Option<String>,
+        yes: bool,
+    },
+    Lint {
+        all: bool,
+        strict: bool,
+        large_table_rows: i64,
+        output: Output,
+    },
+    Plan {
+        count: Option<usize>,
+        tags: Vec<String>,
+        output: Output,
+    },
+    Fmt { check: bool, all: bool },
+    Tui { timeout: Option<u64> },
+    Graph { format: GraphFormat },
+    Export { out: std::path::PathBuf },
+    Import { bundle: std::path::PathBuf, from: ImportFormat, history_table: Option<String>, history: bool, force: bool, yes: bool },
+    Seed(SeedCommand),
+    Meta(MetaCommand),
+    Bootstrap { comment: Option<String>, baseline: bool, yes: bool },
+    Wait { timeout: std::time::Duration, interval: std::time::Duration },
+    Ping { output: Output },
+    Console { client: Option<String>, args: Vec<String> },
+    Drift { snapshot: Option<std::path::PathBuf>, output: Output },
+    Bench {
+        timeout: Option<u64>,
+        iterations: usize,
+        baseline: Option<std::path::PathBuf>,
+        save: Option<std::path::PathBuf>,
+        threshold: f64,
+        output: Output,
+    },
+}
+
+impl Command {
+    /// Commands that protected configs refuse without `--force-protected`; `bench` runs on copies of
+    /// the database and is allowed
+    pub fn protected_name(&self) -> Option<&'static str> {
+        match self {
+            | Command::Down { .. } => Some("down"),
+            | Command::Apply(MigrationApply::Down { .. }) => Some("apply down"),
+            | Command::History(HistoryCommand::Fix) => Some("history fix"),
+            | Command::History(HistoryCommand::Verify { fix: true, .. }) => Some("history verify --fix"),
+            | Command::Remove { .. } => Some("rm"),
+            | Command::Prune { .. } => Some("prune"),
+            | Command::Squash { .. } => Some("squash"),
+            | Command::Repair { .. } => Some("repair"),
+            | Command::Seed(SeedCommand::Reset { .. }) => Some("seed reset"),
+            // Reverts and rewrites the stored SQL interactively
+            | Command::Tui { .. } => Some("tui"),
+            | _ => None,
+        }
+    }
+
+    /// Commands that change the database and are refused outside the maintenance window; `up --shadow`
+    /// and `bench` only change a copy of it
+    pub fn window_name(&self) -> Option<&'static str> {
+        match self {
+            | Command::Init => Some("init"),
+            | Command::Up { dry: false, shadow: false, assert_idempotent: false, check: false, .. } => Some("up"),
+            | Command::Down { dry: false, .. } => Some("down"),
+            | Command::Apply(MigrationApply::Up { dry: false, .. }) => Some("apply up"),
+            | Command::Apply(MigrationApply::Down { dry: false, .. }) => Some("apply down"),
+            | Command::History(HistoryCommand::Fix) => Some("history fix"),
+            | Command::History(HistoryCommand::Verify { fix: true, .. }) => Some("history verify --fix"),
+            | Command::Squash { .. } => Some("squash"),
+            | Command::Baseline { .. } => Some("baseline"),
+            | Command::Rename { .. } => Some("rename"),
+            | Command::Lock { .. } => Some("lock"),
+            | Command::Unlock { .. } => Some("unlock"),
+            | Command::Remove { also_remote: true, .. } => Some("rm --also-remote"),
+            | Command::Prune { .. } => Some("prune"),
+            | Command::Repair { .. } => Some("repair"),
+            | Command::Seed(SeedCommand::Run { dry: false, .. }) => Some("seed run"),
+            | Command::Seed(SeedCommand::Reset { .. }) => Some("seed reset"),
+            | Command::Bootstrap { baseline: true, .. } => Some("bootstrap --baseline"),
+            // Imports from other tools record the migrations they applied
+            | Command::Import { from: ImportFormat::Liquibase | ImportFormat::Sqlx | ImportFormat::Alembic | ImportFormat::Dbmate, history: true, .. } => Some("import"),
+            | Command::Tui { .. } => Some("tui"),
+            | _ => None,
+        }
+    }
+
+    /// Ignore `--yes` on commands that apply migrations, so protected configs always prompt;
+    /// whether the command prompts now
+    pub fn require_confirmation(&mut self) -> bool {
+        match self {
+            | Command::Up { yes, check: false, .. } | Command::Apply(MigrationApply::Up { yes, .. }) => {
+                *yes = false;
+                true
+            },
+            | _ => false,
+        }
+    }
+
+    /// Limit `seed run` to the given seeds, so protected configs only run whitelisted ones
+    pub fn restrict_seeds(&mut self, allowed: &[String]) {
+        if let Command::Seed(SeedCommand::Run { allowed: restricted, .. }) = self {
+            *restricted = Some(allowed.to_vec());
+        }
+    }
 }
@@ -1,19 +1,56 @@
 use serde::{Deserialize, Serialize};
-use crate::config::DataSource;
+use crate::config::{DataSource, Tables};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct SubsystemSqlite {
     pub connection: DataSource<String>,
+    #[serde(default, deserialize_with = "crate::core::duration::deserialize_seconds")]
     pub timeout: Option<u64>,
+    #[serde(default)]
     pub tables: Tables,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub struct Tables {
-    pub migrations: String,
-    pub log: String,
+    /// Refuse destructive commands and always confirm `up`, e.g. for production profiles
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub protected: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fmt: Option<crate::core::fmt::FormatConfig>,
+    /// Directory of the templates for `new`, relative to the config file (default `templates`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub templates: Option<String>,
+    /// Directory of the seeds, relative to the config file (default `seeds`), e.g. `fixtures`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seeds: Option<String>,
+    /// Seeds a protected config still runs, by name as shown by `seed list`; all others are skipped
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_seeds: Vec<String>,
+    /// SQL snippets and commands run around `up`, `down` and `apply`
+    #[serde(default, skip_serializing_if = "crate::core::hooks::Hooks::is_empty")]
+    pub hooks: crate::core::hooks::Hooks,
+    /// Schema snapshot rewritten after every up/down, relative to the config file; compared by `drift`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<String>,
+    /// Declarative schema diffed by `generate`, relative to the config file (default `schema.sql`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_file: Option<String>,
+    /// Seconds `up` and `down` wait for the run lock of another qop run (default 60)
+    #[serde(default, deserialize_with = "crate::core::duration::deserialize_seconds", skip_serializing_if = "Option::is_none")]
+    pub run_lock_timeout: Option<u64>,
+    /// Skip the run lock, set by `--no-lock`
+    #[serde(skip)]
+    pub no_run_lock: bool,
+    /// Naming scheme of new migrations
+    #[serde(default, skip_serializing_if = "crate::core::migration::IdConfig::is_default")]
+    pub ids: crate::core::migration::IdConfig,
+    /// Retries of migrations that fail on a transient error
+    #[serde(default, skip_serializing_if = "crate::core::migration::RetryConfig::is_default")]
+    pub retry: crate::core::migration::RetryConfig,
+    /// Times commands that change the database may run, e.g. `up`, `down`, `init`, `squash` or `tui`;
+    /// refused outside of them unless `--override-window` is given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub window: Option<crate::core::window::WindowConfig>,
+    /// Profile the config was loaded with; set from the command line, never read from the file
+    #[serde(skip)]
+    pub profile: Option<String>,
 }
 
 impl Default for SubsystemSqlite {
@@ -21,10 +58,21 @@ impl Default for SubsystemSqlite {
         Self {
             connection: DataSource::Static(String::new()),
             timeout: None,
-            tables: Tables {
-                migrations: "__qop_migrations".to_string(),
-                log: "__qop_log".to_string(),
-            },
+            tables: Tables::default(),
+            protected: false,
+            fmt: None,
+            seeds: None,
+            allowed_seeds: Vec::new(),
+            hooks: Default::default(),
+            snapshot: None,
+            schema_file: None,
+            run_lock_timeout: None,
+            no_run_lock: false,
+            ids: Default::default(),
+            retry: Default::default(),
+            window: None,
+            profile: None,
+            templates: None,
         }
     }
 }
@@ -50,23 +50,23 @@ where
     Ok(())
 }
 
-use crate::core::migration::prompt_for_confirmation_with_diff;
+use crate::core::migration::{prompt_for_confirmation_with_diff, DiffView};
 
-fn display_sql_migration(migration_id: &str, sql: &str, direction: &str) {
-    let _ = crate::core::migration::display_sql_migration(migration_id, sql, direction);
+fn display_sql_migration(migration_id: &str, sql: &str, direction: &str, view: DiffView) {
+    let _ = crate::core::migration::display_sql_migration(migration_id, sql, direction, view);
 }
 
 fn create_bulk_migrations_diff_fn<'a>(
     migrations: &'a [String],
     migration_dir: &'a Path
-) -> impl Fn() -> Result<()> + 'a {
-    move || -> Result<()> {
+) -> impl Fn(DiffView) -> Result<()> + 'a {
+    move |view: DiffView| -> Result<()> {
         for migration_id in migrations {
             let (up_sql, _down_sql) = crate::core::migration::read_migration_files(
                 migration_dir, migration_id
             )?;
             
-            display_sql_migration(migration_id, &up_sql, "UP");
+            display_sql_migration(migration_id, &up_sql, "UP", view);
         }
         Ok(())
     }
@@ -76,8 +76,8 @@ fn create_bulk_reverts_diff_fn<'a>(
     migrations: &'a [SqliteRow],
     migration_dir: &'a Path,
     remote: bool
-) -> impl Fn() -> Result<()> + 'a {
-    move || -> Result<()> {
+) -> impl Fn(DiffView) -> Result<()> + 'a {
+    move |view: DiffView| -> Result<()> {
         for row in migrations {
             let id: String = row.get("id");
             let down_sql: String = if remote {
@@ -89,7 +89,7 @@ fn create_bulk_reverts_diff_fn<'a>(
                 down_sql
             };
             
-            display_sql_migration(&id, &down_sql, "DOWN");
+            display_sql_migration(&id, &down_sql, "DOWN", view);
         }
         Ok(())
     }
@@ -99,9 +99,9 @@ fn create_single_migration_diff_fn<'a>(
     migration_id: &'a str,
     sql: &'a str,
     direction: &'a str
-) -> impl Fn() -> Result<()> + 'a {
-    move || -> Result<()> {
-        display_sql_migration(migration_id, sql, direction);
+) -> impl Fn(DiffView) -> Result<()> + 'a {
+    move |view: DiffView| -> Result<()> {
+        display_sql_migration(migration_id, sql, direction, view);
         Ok(())
     }
 }
@@ -124,12 +124,71 @@ pub(crate) async fn get_last_migration_id(
     tx: &mut sqlx::Transaction<'_, Sqlite>,
     table: &str,
 ) -> Result<Option<String>> {
-    let mut query = build_table_query("SELECT id FROM ", table);
-    query.push(" ORDER BY id DESC LIMIT 1");
-    Ok(query.build()
-        .fetch_optional(&mut **tx)
+    // Ids order by value, which ORDER BY cannot express for numeric ids of mixed width
+    let ids: Vec<String> = build_table_query("SELECT id FROM ", table).build()
+        .fetch_all(&mut **tx)
         .await?
-        .map(|row| row.get("id")))
+        .into_iter()
+        .map(|row| row.get("id"))
+        .collect();
+    Ok(ids.into_iter().max_by(|a, b| crate::core::migration::compare_ids(a, b)))
+}
+
+/// Columns added to the migrations table after its first release with their types, created by
+/// `ensure_tracking_columns`
+const LATE_COLUMNS: [(&str, &str); 8] = [("up_sha256", "TEXT"), ("down_sha256", "TEXT"), ("author", "TEXT"), ("ticket", "TEXT"), ("labels", "TEXT"), ("duration_ms", "INTEGER"), ("applied_by", "TEXT"), ("hostname", "TEXT")];
+
+/// Columns added to the log table after its first release
+const LATE_LOG_COLUMNS: [(&str, &str); 8] = [
+    ("duration_ms", "INTEGER"),
+    ("applied_by", "TEXT"),
+    ("hostname", "TEXT"),
+    ("version", "TEXT"),
+    ("invocation", "TEXT"),
+    ("dry_run", "BOOLEAN"),
+    ("status", "TEXT"),
+    ("error", "TEXT"),
+];
+
+/// Add the given columns to an existing table that lacks them; the names of the added columns
+async fn add_missing_columns(tx: &mut sqlx::Transaction<'_, Sqlite>, table: &str, columns: &[(&'static str, &str)]) -> Result<Vec<&'static str>> {
+    let existing: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?)")
+        .bind(table)
+        .fetch_all(&mut **tx)
+        .await?;
+    // No columns at all means the table does not exist yet
+    if existing.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut missing = Vec::new();
+    for (column, kind) in columns.iter().filter(|(column, _)| !existing.iter().any(|e| e == column)) {
+        let mut alter = build_table_query("ALTER TABLE ", table);
+        alter.push(format!(" ADD COLUMN {} {}", column, kind));
+        alter.build().execute(&mut **tx).await?;
+        missing.push(*column);
+    }
+    Ok(missing)
+}
+
+/// Add the columns of `LATE_COLUMNS` and `LATE_LOG_COLUMNS` to tracking tables created before they
+/// existed; the checksums are backfilled from the stored SQL
+pub(crate) async fn ensure_tracking_columns(tx: &mut sqlx::Transaction<'_, Sqlite>, table: &str, log_table: &str) -> Result<()> {
+    add_missing_columns(tx, log_table, &LATE_LOG_COLUMNS).await?;
+    let missing = add_missing_columns(tx, table, &LATE_COLUMNS).await?;
+    if !missing.contains(&"up_sha256") {
+        return Ok(());
+    }
+
+    let mut select = build_table_query("SELECT id, up, down FROM ", table);
+    for row in select.build().fetch_all(&mut **tx).await? {
+        let (id, up, down): (String, String, String) = (row.get("id"), row.get("up"), row.get("down"));
+        let mut update = build_table_query("UPDATE ", table);
+        update.push(" SET up_sha256 = ").push_bind(crate::core::migration::sql_checksum(&up));
+        update.push(", down_sha256 = ").push_bind(crate::core::migration::sql_checksum(&down));
+        update.push(" WHERE id = ").push_bind(id);
+        update.build().execute(&mut **tx).await?;
+    }
+    Ok(())
 }
 
 pub(crate) async fn insert_migration_record<'e, E>(
@@ -138,23 +197,33 @@ pub(crate) async fn insert_migration_record<'e, E>(
     id: &str,
     up_sql: &str,
     down_sql: &str,
-    comment: Option<&str>,
+    meta: &crate::core::migration::MigrationMeta,
     pre_migration_id: Option<&str>,
     locked: bool,
+    duration_ms: Option<i64>,
 ) -> Result<()>
 where
     E: sqlx::Executor<'e, Database = Sqlite>,
 {
+    let (applied_by, hostname) = crate::core::migration::applied_by();
     let mut query = build_table_query("INSERT INTO ", table);
-    query.push(" (id, version, up, down, comment, pre, locked) VALUES (?, ?, ?, ?, ?, ?, ?)");
+    query.push(" (id, version, up, down, comment, pre, locked, up_sha256, down_sha256, author, ticket, labels, duration_ms, applied_by, hostname) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)");
     query.build()
         .bind(id)
         .bind(env!("CARGO_PKG_VERSION"))
         .bind(up_sql)
         .bind(down_sql)
-        .bind(comment)
+        .bind(meta.comment.as_deref())
         .bind(pre_migration_id)
         .bind(locked)
+        .bind(crate::core::migration::sql_checksum(up_sql))
+        .bind(crate::core::migration::sql_checksum(down_sql))
+        .bind(meta.author.as_deref())
+        .bind(meta.ticket.as_deref())
+        .bind(meta.provenance().labels_json())
+        .bind(duration_ms)
+        .bind(applied_by)
+        .bind(hostname)
         .execute(executor)
         .await?;
     Ok(())
@@ -211,18 +280,18 @@ pub(crate) async fn get_recent_migrations_for_revert(
     tx: &mut sqlx::Transaction<'_, Sqlite>,
     table: &str,
 ) -> Result<Vec<SqliteRow>> {
-    let mut query = build_table_query("SELECT id, down FROM ", table);
-    query.push(" ORDER BY id DESC");
-    Ok(query.build().fetch_all(&mut **tx).await?)
+    let mut rows = build_table_query("SELECT id, down FROM ", table).build().fetch_all(&mut **tx).await?;
+    rows.sort_by(|a, b| crate::core::migration::compare_ids(b.get("id"), a.get("id")));
+    Ok(rows)
 }
 
 pub(crate) async fn get_all_migration_data(
     tx: &mut sqlx::Transaction<'_, Sqlite>,
     table: &str,
 ) -> Result<Vec<SqliteRow>> {
-    let mut query = build_table_query("SELECT id, up, down FROM ", table);
-    query.push(" ORDER BY id ASC");
-    Ok(query.build().fetch_all(&mut **tx).await?)
+    let mut rows = build_table_query("SELECT id, up, down FROM ", table).build().fetch_all(&mut **tx).await?;
+    rows.sort_by(|a, b| crate::core::migration::compare_ids(a.get("id"), b.get("id")));
+    Ok(rows)
 }
 
 pub(crate) async fn get_migration_down_sql(
@@ -241,38 +310,79 @@ pub(crate) async fn get_table_version(
     tx: &mut sqlx::Transaction<'_, Sqlite>,
     table: &str,
 ) -> Result<Option<String>> {
-    let mut query = QueryBuilder::new("SELECT version FROM ");
+    let mut query = QueryBuilder::new("SELECT id, version FROM ");
     query.push(table);
-    query.push(" ORDER BY id DESC LIMIT 1");
-    Ok(query.build()
-        .fetch_optional(&mut **tx)
-        .await?
+    let rows = query.build().fetch_all(&mut **tx).await?;
+    Ok(rows
+        .into_iter()
+        .max_by(|a, b| crate::core::migration::compare_ids(a.get("id"), b.get("id")))
         .map(|row| row.get("version")))
 }
 
 
+/// Whether the row count SQLite reports for `statement` is its own: other statements report the count
+/// of the last INSERT, UPDATE or DELETE
+fn changes_rows(statement: &str) -> bool {
+    let keyword = statement.split_whitespace().next().unwrap_or_default().to_uppercase();
+    matches!(keyword.as_str(), "INSERT" | "UPDATE" | "DELETE" | "REPLACE" | "WITH")
+}
+
+/// Run the statements of a migration one by one, so a failure names the statement and its line
 pub(crate) async fn execute_sql_statements(
     tx: &mut sqlx::Transaction<'_, Sqlite>,
     sql: &str,
     migration_id: &str,
 ) -> Result<()> {
-    match sqlx::raw_sql(sql).execute(&mut **tx).await {
-        Ok(_) => {
-            // Statement executed successfully
-        }
-        Err(e) => {
-            return Err(anyhow::anyhow!(
-                "Failed to execute statements in migration {}: {}",
-                migration_id,
-                e,
-            ));
-        }
+    let statements = crate::core::migration_diff::split_statements_with_lines(sql);
+    let total = statements.len();
+    for (index, (line, statement)) in statements.iter().enumerate() {
+        crate::core::migration::report_statement_progress(migration_id, index, total);
+        crate::verbose!("▶️  {} statement {}/{}:\n{}", migration_id, index + 1, total, statement);
+        let started = std::time::Instant::now();
+        let rows = match sqlx::raw_sql(statement).execute(&mut **tx).await {
+            | Ok(done) if changes_rows(statement) => done.rows_affected(),
+            | Ok(_) => 0,
+            | Err(e) => {
+                crate::core::migration::report_statement_progress(migration_id, total, total);
+                return Err(crate::core::migration::statement_error(migration_id, index + 1, total, *line, statement, e, ""));
+            },
+        };
+        crate::core::telemetry::add_rows(migration_id, rows);
+        crate::core::events::statement_executed(migration_id, index + 1, total, *line, rows, started.elapsed());
+        crate::verbose!("⏱️  {:.1} ms", started.elapsed().as_secs_f64() * 1000.0);
     }
+    crate::core::migration::report_statement_progress(migration_id, total, total);
     Ok(())
 }
 
-pub(crate) async fn build_pool_from_config(path: &Path, sqlite_config: &SubsystemSqlite, check_cli_version: bool) -> Result<Pool<Sqlite>> {
-    let uri = match &sqlite_config.connection {
+/// Run statements one at a time, each committed on its own. The first `skip` statements are left
+/// out, they ran before a resumed failure.
+pub(crate) async fn execute_statements_individually(conn: &mut sqlx::SqliteConnection, sql: &str, migration_id: &str, skip: usize) -> Result<()> {
+    let statements = crate::core::migration_diff::split_statements_with_lines(sql);
+    let total = statements.len();
+    for (index, (line, statement)) in statements.iter().enumerate().skip(skip) {
+        crate::core::migration::report_statement_progress(migration_id, index, total);
+        crate::verbose!("▶️  {} statement {}/{}:\n{}", migration_id, index + 1, total, statement);
+        let started = std::time::Instant::now();
+        let rows = match sqlx::raw_sql(statement).execute(&mut *conn).await {
+            | Ok(done) if changes_rows(statement) => done.rows_affected(),
+            | Ok(_) => 0,
+            | Err(e) => {
+                crate::core::migration::report_statement_progress(migration_id, total, total);
+                return Err(crate::core::migration::statement_error(migration_id, index + 1, total, *line, statement, e, ""));
+            },
+        };
+        crate::core::telemetry::add_rows(migration_id, rows);
+        crate::core::events::statement_executed(migration_id, index + 1, total, *line, rows, started.elapsed());
+        crate::verbose!("⏱️  {:.1} ms", started.elapsed().as_secs_f64() * 1000.0);
+    }
+    crate::core::migration::report_statement_progress(migration_id, total, total);
+    Ok(())
+}
+
+/// Resolve the connection string from the configured data source
+pub(crate) fn resolve_connection(path: &Path, sqlite_config: &SubsystemSqlite) -> Result<String> {
+    Ok(match &sqlite_config.connection {
         | DataSource::Static(connection) => connection.to_owned(),
         | DataSource::FromEnv(var) => {
             std::env::var(var).with_context(|| {
@@ -283,9 +393,53 @@ pub(crate) async fn build_pool_from_config(path: &Path, sqlite_config: &Subsyste
                 )
             })?
         },
-    };
+        | DataSource::File(file) => {
+            crate::config::read_from_file(path, file).with_context(|| {
+                format!(
+                    "Failed to read connection file '{}' referenced by [subsystem.sqlite].connection in {}",
+                    file,
+                    path.display()
+                )
+            })?
+        },
+        | DataSource::Command(command) => {
+            crate::config::read_from_command(path, command).with_context(|| {
+                format!(
+                    "Failed to run connection command '{}' referenced by [subsystem.sqlite].connection in {}",
+                    command.program,
+                    path.display()
+                )
+            })?
+        },
+        #[cfg(feature = "keyring")]
+        | DataSource::Keyring(keyring) => {
+            crate::config::read_from_keyring(keyring).with_context(|| {
+                format!(
+                    "Failed to read keyring entry '{}/{}' referenced by [subsystem.sqlite].connection in {}",
+                    keyring.service,
+                    keyring.user,
+                    path.display()
+                )
+            })?
+        },
+        #[cfg(feature = "vault")]
+        | DataSource::Vault(vault) => {
+            crate::core::vault::read_secret(vault).with_context(|| {
+                format!(
+                    "Failed to read Vault secret '{}' referenced by [subsystem.sqlite].connection in {}",
+                    vault.path,
+                    path.display()
+                )
+            })?
+        },
+    })
+}
+
+pub(crate) async fn build_pool_from_config(path: &Path, sqlite_config: &SubsystemSqlite, check_cli_version: bool) -> Result<Pool<Sqlite>> {
+    let uri = resolve_connection(path, sqlite_config)?;
 
     let pool = SqlitePoolOptions::new().max_connections(1).connect(&uri).await?;
+    crate::verbose!("🔌 Opened {}", pool.connect_options().get_filename().display());
     if check_cli_version {
         let mut tx = pool.begin().await?;
         let table_exists = sqlx::query("SELECT name FROM sqlite_master WHERE type='table' AND name=?")
@@ -303,6 +457,7 @@ pub(crate) async fn build_pool_from_config(path: &Path, sqlite_config: &Subsyste
                     }
                 }
             }
+            ensure_tracking_columns(&mut tx, &sqlite_config.tables.migrations, &sqlite_config.tables.log).await?;
         }
         tx.commit().await?;
     }
@@ -321,18 +476,58 @@ pub(crate) async fn insert_log_entry<'c, E>(
     operation: &str,
     sql_command: &str,
 ) -> Result<()>
+where
+    E: sqlx::Executor<'c, Database = Sqlite>,
+{
+    insert_timed_log_entry(executor, log_table, migration_id, operation, sql_command, None).await
+}
+
+/// `insert_log_entry` for operations that executed SQL, with the time it took
+pub(crate) async fn insert_timed_log_entry<'c, E>(
+    executor: E,
+    log_table: &str,
+    migration_id: &str,
+    operation: &str,
+    sql_command: &str,
+    duration_ms: Option<i64>,
+) -> Result<()>
+where
+    E: sqlx::Executor<'c, Database = Sqlite>,
+{
+    let outcome = crate::core::log::LogOutcome { duration_ms, ..Default::default() };
+    insert_log_record(executor, log_table, migration_id, operation, sql_command, &outcome).await
+}
+
+/// Write a log entry with the qop version, the invocation and who ran it, and how the operation went
+pub(crate) async fn insert_log_record<'c, E>(
+    executor: E,
+    log_table: &str,
+    migration_id: &str,
+    operation: &str,
+    sql_command: &str,
+    outcome: &crate::core::log::LogOutcome,
+) -> Result<()>
 where
     E: sqlx::Executor<'c, Database = Sqlite>,
 {
     let log_id = uuid::Uuid::now_v7().to_string();
+    let (applied_by, hostname) = crate::core::migration::applied_by();
     let mut query = build_table_query("INSERT INTO ", log_table);
-    query.push(" (id, migration_id, operation, sql_command) VALUES (?, ?, ?, ?)");
+    query.push(" (id, migration_id, operation, sql_command, duration_ms, applied_by, hostname, version, invocation, dry_run, status, error) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)");
     query
         .build()
         .bind(log_id)
         .bind(migration_id)
         .bind(operation)
         .bind(sql_command)
+        .bind(outcome.duration_ms)
+        .bind(applied_by)
+        .bind(hostname)
+        .bind(env!("CARGO_PKG_VERSION"))
+        .bind(crate::core::log::invocation())
+        .bind(outcome.dry_run)
+        .bind(outcome.status())
+        .bind(outcome.error.as_deref())
         .execute(executor)
         .await?;
     Ok(())
@@ -344,12 +539,12 @@ pub async fn init_with_pool(migrations_table: &str, log_table: &str, pool: &Pool
     {
         // Create migrations table
         let mut query = build_table_query("CREATE TABLE IF NOT EXISTS ", migrations_table);
-        query.push(" (id TEXT PRIMARY KEY, version TEXT NOT NULL, up TEXT NOT NULL, down TEXT NOT NULL, created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP, pre TEXT, comment TEXT, locked BOOLEAN NOT NULL DEFAULT 0)");
+        query.push(" (id TEXT PRIMARY KEY, version TEXT NOT NULL, up TEXT NOT NULL, down TEXT NOT NULL, created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP, pre TEXT, comment TEXT, locked BOOLEAN NOT NULL DEFAULT 0, up_sha256 TEXT, down_sha256 TEXT, author TEXT, ticket TEXT, labels TEXT, duration_ms INTEGER, applied_by TEXT, hostname TEXT)");
         query.build().execute(&mut *tx).await?;
         
         // Create log table
         let mut log_query = build_table_query("CREATE TABLE IF NOT EXISTS ", log_table);
-        log_query.push(" (id TEXT PRIMARY KEY, migration_id TEXT NOT NULL, operation TEXT NOT NULL, sql_command TEXT NOT NULL, executed_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP)");
+        log_query.push(" (id TEXT PRIMARY KEY, migration_id TEXT NOT NULL, operation TEXT NOT NULL, sql_command TEXT NOT NULL, executed_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP, duration_ms INTEGER, applied_by TEXT, hostname TEXT, version TEXT, invocation TEXT, dry_run BOOLEAN, status TEXT, error TEXT)");
         log_query.build().execute(&mut *tx).await?;
     };
     tx.commit().await?;
@@ -358,7 +553,8 @@ pub async fn init_with_pool(migrations_table: &str, log_table: &str, pool: &Pool
 }
 
 pub async fn new_migration(path: &Path) -> Result<()> {
-    let migration_id_path = create_migration_directory(path, None, false)?;
+    let id = crate::core::migration::IdConfig::default().next_id(&crate::core::migration::get_local_migrations(path)?)?;
+    let migration_id_path = create_migration_directory(path, &id, None, false, &Default::default())?;
     println!("Created new migration: {}", migration_id_path.display());
     Ok(())
 }
@@ -388,7 +584,7 @@ pub async fn up(path: &Path, timeout: Option<u64>, count: Option<usize>, _diff:
     let mut migrations_to_apply: Vec<String> =
         local_migrations.difference(&applied_migrations).cloned().collect();
 
-    migrations_to_apply.sort();
+    crate::core::migration::sort_ids(&mut migrations_to_apply);
 
     let migrations_to_apply = if let Some(count) = count {
         migrations_to_apply.into_iter().take(count).collect()
@@ -402,7 +598,7 @@ pub async fn up(path: &Path, timeout: Option<u64>, count: Option<usize>, _diff:
         &migrations_to_apply
     );
     if !out_of_order_migrations.is_empty() {
-        let max_applied = applied_migrations.iter().max().cloned().unwrap_or_default();
+        let max_applied = applied_migrations.iter().max_by(|a, b| crate::core::migration::compare_ids(a, b)).cloned().unwrap_or_default();
         if !crate::core::migration::handle_non_linear_warning(&out_of_order_migrations, &max_applied)? {
             println!("Operation cancelled.");
             return Ok(());
@@ -413,21 +609,21 @@ pub async fn up(path: &Path, timeout: Option<u64>, count: Option<usize>, _diff:
         println!("All migrations are up to date.");
     } else {
         // Prompt for confirmation when not in silent mode
-        println!("\n📋 About to apply {} migration(s):", migrations_to_apply.len());
+        crate::say!("\n📋 About to apply {} migration(s):", migrations_to_apply.len());
         for migration_id in &migrations_to_apply {
-            println!("  - {}", migration_id);
+            crate::say!("  - {}", migration_id);
         }
         
         let diff_fn = create_bulk_migrations_diff_fn(&migrations_to_apply, migration_dir);
         
         if !prompt_for_confirmation_with_diff("❓ Do you want to proceed with applying these migrations?", yes, diff_fn)? {
-            println!("❌ Migration cancelled.");
+            crate::say!("❌ Migration cancelled.");
             return Ok(());
         }
         
         // Apply each migration in its own transaction
         for migration_id in &migrations_to_apply {
-            println!("⏳ Applying migration: {}", migration_id);
+            crate::say!("⏳ Applying migration: {}", migration_id);
             let id = migration_id.as_str();
 
             let (up_sql, down_sql) = crate::core::migration::read_migration_files(
@@ -450,18 +646,19 @@ pub async fn up(path: &Path, timeout: Option<u64>, count: Option<usize>, _diff:
                 id,
                 &up_sql,
                 &down_sql,
-                None, // comment not available in this legacy function
+                &Default::default(), // meta not available in this legacy function
                 last_migration_id.as_deref(),
                 false, // locked not available in this legacy function
+                None,
             ).await?;
 
             // Commit or rollback based on dry-run mode
             if dry {
                 migration_tx.rollback().await?;
-                println!("🔄 Migration {} executed and rolled back (dry-run mode).", migration_id);
+                crate::say!("🔄 Migration {} executed and rolled back (dry-run mode).", migration_id);
             } else {
                 migration_tx.commit().await?;
-                println!("✅ Migration {} applied successfully.", migration_id);
+                crate::say!("✅ Migration {} applied successfully.", migration_id);
                 last_migration_id = Some(id.to_string());
             }
         }
@@ -506,16 +703,16 @@ pub async fn down(path: &Path, timeout: Option<u64>, count: Option<usize>, remot
         println!("No migrations to revert.");
     } else {
         // Prompt for confirmation when not in silent mode
-        println!("\n📋 About to revert {} migration(s):", migrations_to_revert.len());
+        crate::say!("\n📋 About to revert {} migration(s):", migrations_to_revert.len());
         for row in &migrations_to_revert {
             let id: String = row.get("id");
-            println!("  - {}", id);
+            crate::say!("  - {}", id);
         }
         
         let diff_fn = create_bulk_reverts_diff_fn(&migrations_to_revert, migration_dir, remote);
         
         if !prompt_for_confirmation_with_diff("❓ Do you want to proceed with reverting these migrations?", yes, diff_fn)? {
-            println!("❌ Revert cancelled.");
+            crate::say!("❌ Revert cancelled.");
             return Ok(());
         }
         
@@ -547,10 +744,10 @@ pub async fn down(path: &Path, timeout: Option<u64>, count: Option<usize>, remot
             // Commit or rollback based on dry-run mode
             if dry {
                 revert_tx.rollback().await?;
-                println!("🔄 Migration {} reverted and rolled back (dry-run mode).", id);
+                crate::say!("🔄 Migration {} reverted and rolled back (dry-run mode).", id);
             } else {
                 revert_tx.commit().await?;
-                println!("✅ Migration {} reverted.", id);
+                crate::say!("✅ Migration {} reverted.", id);
             }
         }
     }
@@ -580,7 +777,7 @@ pub async fn list(path: &Path, migrations_table: &str, pool: &Pool<Sqlite>) -> R
     remote.sort_by(|a, b| a.0.cmp(&b.0));
 
     let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
-    crate::core::migration::render_migration_table(&local_migrations, &remote, migration_dir)?;
+    crate::core::migration::render_migration_table(&local_migrations, &remote, &Default::default(), &Default::default(), None, migration_dir, None)?;
 
     tx.commit().await?;
 
@@ -630,10 +827,10 @@ pub async fn apply_up(path: &Path, id: &str, timeout: Option<u64>, dry: bool, ye
     let mut needs_confirmation = false;
     if !applied_migrations.is_empty() {
         let max_applied_migration =
-            applied_migrations.iter().max().cloned().unwrap_or_default();
+            applied_migrations.iter().max_by(|a, b| crate::core::migration::compare_ids(a, b)).cloned().unwrap_or_default();
 
-        if target_migration_id.as_str() < max_applied_migration.as_str() {
-            println!("⚠️  Non-linear history detected!");
+        if crate::core::migration::compare_ids(&target_migration_id, &max_applied_migration).is_lt() {
+            crate::say!("⚠️  Non-linear history detected!");
             println!(
                 "Applying migration {} would create a non-linear history.",
                 target_migration_id
@@ -670,7 +867,7 @@ pub async fn apply_up(path: &Path, id: &str, timeout: Option<u64>, dry: bool, ye
     let diff_fn = create_single_migration_diff_fn(&target_migration_id, &up_sql, "UP");
     
     if !prompt_for_confirmation_with_diff(&format!("❓ Do you want to apply migration '{}'?", target_migration_id), yes, diff_fn)? {
-        println!("❌ Operation cancelled.");
+        crate::say!("❌ Operation cancelled.");
         return Ok(());
     }
 
@@ -700,17 +897,18 @@ pub async fn apply_up(path: &Path, id: &str, timeout: Option<u64>, dry: bool, ye
         &target_migration_id,
         &up_sql,
         &down_sql,
-        None, // comment not available in this legacy function
+        &Default::default(), // meta not available in this legacy function
         last_migration_id.as_deref(),
         false, // locked not available in this legacy function
+        None,
     ).await?;
 
     if dry {
         migration_tx.rollback().await?;
-        println!("🔄 Migration {} executed and rolled back (dry-run mode).", target_migration_id);
+        crate::say!("🔄 Migration {} executed and rolled back (dry-run mode).", target_migration_id);
     } else {
         migration_tx.commit().await?;
-        println!("✅ Migration {} applied successfully.", target_migration_id);
+        crate::say!("✅ Migration {} applied successfully.", target_migration_id);
     }
 
     Ok(())
@@ -751,10 +949,10 @@ pub async fn apply_down(path: &Path, id: &str, timeout: Option<u64>, remote: boo
     let mut needs_confirmation = false;
     if !applied_migrations.is_empty() {
         let max_applied_migration =
-            applied_migrations.iter().max().cloned().unwrap_or_default();
+            applied_migrations.iter().max_by(|a, b| crate::core::migration::compare_ids(a, b)).cloned().unwrap_or_default();
 
         if target_migration_id != max_applied_migration {
-            println!("⚠️  Non-linear history detected!");
+            crate::say!("⚠️  Non-linear history detected!");
             println!(
                 "Reverting migration {} would create a non-linear history.",
                 target_migration_id
@@ -802,7 +1000,7 @@ pub async fn apply_down(path: &Path, id: &str, timeout: Option<u64>, remote: boo
     let diff_fn = create_single_migration_diff_fn(&target_migration_id, &down_sql, "DOWN");
     
     if !prompt_for_confirmation_with_diff(&format!("❓ Do you want to revert migration '{}'?", target_migration_id), yes, diff_fn)? {
-        println!("❌ Operation cancelled.");
+        crate::say!("❌ Operation cancelled.");
         return Ok(());
     }
 
@@ -823,10 +1021,10 @@ pub async fn apply_down(path: &Path, id: &str, timeout: Option<u64>, remote: boo
 
     if dry {
         revert_tx.rollback().await?;
-        println!("🔄 Migration {} reverted and rolled back (dry-run mode).", target_migration_id);
+        crate::say!("🔄 Migration {} reverted and rolled back (dry-run mode).", target_migration_id);
     } else {
         revert_tx.commit().await?;
-        println!("✅ Migration {} reverted successfully.", target_migration_id);
+        crate::say!("✅ Migration {} reverted successfully.", target_migration_id);
     }
 
     Ok(())
@@ -840,7 +1038,7 @@ pub async fn history_fix(path: &Path, migrations_table: &str, pool: &Pool<Sqlite
 
     let applied_migrations = get_applied_migrations(&mut tx, migrations_table).await?;
 
-    let max_applied_migration = applied_migrations.iter().max().cloned().unwrap_or_default();
+    let max_applied_migration = applied_migrations.iter().max_by(|a, b| crate::core::migration::compare_ids(a, b)).cloned().unwrap_or_default();
 
     let max_applied_ts = applied_migrations
         .iter()
@@ -850,14 +1048,17 @@ pub async fn history_fix(path: &Path, migrations_table: &str, pool: &Pool<Sqlite
 
     let mut next_ts = std::cmp::max(max_applied_ts, Utc::now().timestamp_millis());
 
-    let out_of_order_migrations: Vec<String> = local_migrations
+    let mut out_of_order_migrations: Vec<String> = local_migrations
         .difference(&applied_migrations)
-        .filter(|id| id.as_str() < max_applied_migration.as_str())
+        .filter(|id| crate::core::migration::compare_ids(id, &max_applied_migration).is_lt())
         .cloned()
         .collect();
+    // Renamed oldest first, so they keep their order
+    crate::core::migration::sort_ids(&mut out_of_order_migrations);
 
+    let mut renamed = Vec::new();
     if out_of_order_migrations.is_empty() {
-        println!("No out-of-order migrations to fix.");
+        crate::summary!("No out-of-order migrations to fix.");
     } else {
         for old_id in out_of_order_migrations {
             next_ts += 1;
@@ -873,11 +1074,13 @@ pub async fn history_fix(path: &Path, migrations_table: &str, pool: &Pool<Sqlite
                 )
             })?;
 
-            println!("Shuffled migration {} to {}", old_id, new_id);
+            crate::say!("Shuffled migration {} to {}", old_id, new_id);
+            renamed.push(crate::core::migration::RenamedOut { from: old_id, to: next_ts.to_string() });
         }
     }
 
     tx.commit().await?;
+    crate::core::output::result(&crate::core::migration::HistoryFixOut { command: "history fix", renamed })?;
 
     Ok(())
 }
@@ -890,8 +1093,9 @@ pub async fn history_sync(path: &Path, migrations_table: &str, pool: &Pool<Sqlit
     // Get all migrations from the database
     let all_migrations = get_all_migration_data(&mut tx, migrations_table).await?;
 
+    let mut synced = Vec::new();
     if all_migrations.is_empty() {
-        println!("No migrations to sync.");
+        crate::summary!("No migrations to sync.");
     } else {
         for row in all_migrations {
             let id: String = row.get("id");
@@ -919,41 +1123,13 @@ pub async fn history_sync(path: &Path, migrations_table: &str, pool: &Pool<Sqlit
                 format!("Failed to write down migration: {}", down_path.display())
             })?;
 
-            println!("Synced migration: {}", id);
+            crate::say!("Synced migration: {}", id);
+            synced.push(id);
         }
     }
 
     tx.commit().await?;
-
-    Ok(())
-}
-
-pub async fn diff(path: &Path, migrations_table: &str, pool: &Pool<Sqlite>) -> Result<()> {
-    let migration_dir = path.parent().ok_or_else(|| anyhow::anyhow!("invalid migration path: {}", path.display()))?;
-    let local_migrations = get_local_migrations(path)?;
-
-    let mut tx = pool.begin().await?;
-
-    let applied_migrations = get_applied_migrations(&mut tx, migrations_table).await?;
-
-    tx.commit().await?;
-
-    let mut pending_migrations: Vec<String> =
-        local_migrations.difference(&applied_migrations).cloned().collect();
-
-    pending_migrations.sort();
-
-    if pending_migrations.is_empty() {
-        println!("All migrations are up to date.");
-    } else {
-        for migration_id in &pending_migrations {
-            let (up_sql, _down_sql) = crate::core::migration::read_migration_files(
-                migration_dir, migration_id
-            )?;
-            // Render with same formatting as interactive 'd'
-            crate::core::migration::display_sql_migration(migration_id, &up_sql, "UP")?;
-        }
-    }
+    crate::core::output::result(&crate::core::migration::HistorySyncOut { command: "history sync", synced })?;
 
     Ok(())
 }
@@ -5,23 +5,32 @@ pub mod repo;
 pub mod config;
 
 #[cfg(feature = "sub+sqlite")]
-use crate::config::{Config, Subsystem, DataSource};
+use crate::config::{Config, Subsystem, DataSource, Tables};
 #[cfg(feature = "sub+sqlite")]
 use crate::subsystem::sqlite::config::SubsystemSqlite;
 
 #[cfg(feature = "sub+sqlite")]
 pub fn build_sample_with_db_path(db_path: &std::path::Path) -> crate::config::Config {
-    use crate::subsystem::sqlite::config::Tables;
-
     Config {
         version: env!("CARGO_PKG_VERSION").to_string(),
         subsystem: Subsystem::Sqlite(SubsystemSqlite {
             connection: DataSource::Static(db_path.to_string_lossy().to_string()),
             timeout: Some(60),
-            tables: Tables {
-                migrations: "__qop_migrations".to_string(),
-                log: "__qop_log".to_string(),
-            },
+            tables: Tables::default(),
+            protected: false,
+            fmt: None,
+            seeds: None,
+            allowed_seeds: Vec::new(),
+            hooks: Default::default(),
+            snapshot: None,
+            schema_file: None,
+            run_lock_timeout: None,
+            no_run_lock: false,
+            ids: Default::default(),
+            retry: Default::default(),
+            window: None,
+            profile: None,
+            templates: None,
         }),
     }
 }
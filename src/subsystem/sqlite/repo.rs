@@ -1,8 +1,8 @@
 use {
-    crate::core::repo::MigrationRepository,
+    crate::core::{migration::{ExecOptions, Isolation, MigrationMeta}, repo::MigrationRepository},
     crate::subsystem::sqlite::migration as sq,
     crate::subsystem::sqlite::migration,
-    anyhow::Result,
+    anyhow::{Context, Result},
     chrono::NaiveDateTime,
     sqlx::{Pool, Sqlite},
     sqlx::sqlite::SqliteRow,
@@ -14,32 +14,156 @@ pub struct SqliteRepo {
     pub config: crate::subsystem::sqlite::config::SubsystemSqlite,
     pub pool: Pool<Sqlite>,
     pub path: std::path::PathBuf,
+    /// Lock file next to the database, locked while held
+    run_lock: tokio::sync::Mutex<Option<std::fs::File>>,
 }
 
 impl SqliteRepo {
     pub async fn from_config(path: &std::path::Path, config: crate::subsystem::sqlite::config::SubsystemSqlite, check_cli_version: bool) -> Result<Self> {
         let pool = sq::build_pool_from_config(path, &config, check_cli_version).await?;
-        Ok(Self { config, pool, path: path.to_path_buf() })
+        Ok(Self { config, pool, path: path.to_path_buf(), run_lock: Default::default() })
+    }
+
+    /// Connect to a fresh database in a temporary file instead of the configured one
+    pub async fn ephemeral(path: &std::path::Path, mut config: crate::subsystem::sqlite::config::SubsystemSqlite) -> Result<(Self, EphemeralDatabase)> {
+        let db_path = std::env::temp_dir().join(format!("qop-test-{}.db", uuid::Uuid::now_v7()));
+        std::fs::File::create(&db_path).with_context(|| format!("Failed to create temporary database: {}", db_path.display()))?;
+        let db = EphemeralDatabase { path: db_path };
+        config.connection = crate::config::DataSource::Static(format!("sqlite://{}", db.path.display()));
+        let repo = Self::from_config(path, config, false).await?;
+        Ok((repo, db))
+    }
+
+    /// Connect to a copy of the configured database in a temporary file. `VACUUM INTO` copies a
+    /// consistent snapshot, including changes still in the write-ahead log.
+    pub async fn shadow(path: &std::path::Path, mut config: crate::subsystem::sqlite::config::SubsystemSqlite) -> Result<(Self, EphemeralDatabase)> {
+        let db = EphemeralDatabase { path: std::env::temp_dir().join(format!("qop-shadow-{}.db", uuid::Uuid::now_v7())) };
+        let source = sq::build_pool_from_config(path, &config, false).await?;
+        sqlx::query("VACUUM INTO ?")
+            .bind(db.path.display().to_string())
+            .execute(&source)
+            .await
+            .with_context(|| format!("Failed to copy the database to {}", db.path.display()))?;
+        source.close().await;
+        // Nobody else knows the copy, there is no run to wait for
+        config.no_run_lock = true;
+        config.connection = crate::config::DataSource::Static(format!("sqlite://{}", db.path.display()));
+        let repo = Self::from_config(path, config, true).await?;
+        Ok((repo, db))
+    }
+
+    /// Create a `(name, checksum, applied_at)` tracking table as used by seeds and repeatable
+    /// migrations; both may be used on databases initialized before their table existed
+    async fn ensure_checksum_table(&self, tx: &mut sqlx::Transaction<'_, Sqlite>, table: &str) -> Result<()> {
+        let mut query = sq::build_table_query("CREATE TABLE IF NOT EXISTS ", table);
+        query.push(" (name TEXT PRIMARY KEY, checksum TEXT NOT NULL, applied_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP)");
+        query.build().execute(&mut **tx).await?;
+        Ok(())
+    }
+
+    async fn fetch_checksums(&self, table: &str) -> Result<Vec<(String, String, NaiveDateTime)>> {
+        let mut tx = self.pool.begin().await?;
+        self.ensure_checksum_table(&mut tx, table).await?;
+        let mut q = sq::build_table_query("SELECT name, checksum, applied_at FROM ", table);
+        q.push(" ORDER BY name");
+        let rows = q.build().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(rows.into_iter().map(|row| (row.get("name"), row.get("checksum"), row.get("applied_at"))).collect())
+    }
+
+    /// Execute the SQL and record its checksum under `name` in `table` in one transaction
+    #[allow(clippy::too_many_arguments)]
+    async fn run_checksummed(&self, table: &str, operation: &str, name: &str, sql: &str, checksum: &str, timeout: Option<u64>, dry_run: bool) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        self.ensure_checksum_table(&mut tx, table).await?;
+        sq::set_timeout_if_needed(&mut *tx, timeout).await?;
+        sq::execute_sql_statements(&mut tx, sql, name).await?;
+        let mut q = sq::build_table_query("INSERT INTO ", table);
+        q.push(" (name, checksum) VALUES (").push_bind(name).push(", ").push_bind(checksum).push(")");
+        q.push(" ON CONFLICT (name) DO UPDATE SET checksum = excluded.checksum, applied_at = CURRENT_TIMESTAMP");
+        q.build().execute(&mut *tx).await?;
+        sq::insert_log_entry(&mut *tx, &self.config.tables.log, &format!("{}:{}", operation, name), operation, sql).await?;
+        if dry_run { tx.rollback().await?; } else { tx.commit().await?; }
+        Ok(())
+    }
+}
+
+/// Temporary database file, deleted when dropped
+pub struct EphemeralDatabase {
+    pub path: std::path::PathBuf,
+}
+
+impl Drop for EphemeralDatabase {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// SQLite transactions are always serializable
+fn check_isolation(exec: &ExecOptions) -> Result<()> {
+    match exec.isolation {
+        | None | Some(Isolation::Serializable) => Ok(()),
+        | Some(isolation) => anyhow::bail!("SQLite only supports serializable transactions, not {}", isolation.as_sql().to_lowercase()),
     }
 }
 
 #[async_trait::async_trait(?Send)]
 impl MigrationRepository for SqliteRepo {
+    async fn acquire_run_lock(&self) -> Result<()> {
+        let mut held = self.run_lock.lock().await;
+        if self.config.no_run_lock || held.is_some() {
+            return Ok(());
+        }
+        // In-memory databases have no file and are private to this run anyway
+        let db_file: Option<String> = sqlx::query_scalar("SELECT file FROM pragma_database_list WHERE name = 'main'").fetch_optional(&self.pool).await?;
+        let Some(db_file) = db_file.filter(|file| !file.is_empty()) else {
+            return Ok(());
+        };
+        let lock_path = format!("{}.qop-lock", db_file);
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("Failed to open run lock file: {}", lock_path))?;
+        let mut wait = crate::core::wait::RunLockWait::new(self.config.run_lock_timeout);
+        loop {
+            match file.try_lock() {
+                | Ok(()) => break,
+                | Err(std::fs::TryLockError::WouldBlock) => wait.retry().await?,
+                | Err(std::fs::TryLockError::Error(e)) => return Err(e).with_context(|| format!("Failed to lock run lock file: {}", lock_path)),
+            }
+        }
+        *held = Some(file);
+        Ok(())
+    }
+
+    async fn release_run_lock(&self) -> Result<()> {
+        // Closing the file releases its lock
+        self.run_lock.lock().await.take();
+        Ok(())
+    }
+
     async fn init_store(&self) -> Result<()> {
         let mut tx = self.pool.begin().await?;
         {
             // Create migrations table
             let mut query = sq::build_table_query("CREATE TABLE IF NOT EXISTS ", &self.config.tables.migrations);
-            query.push(" (id TEXT PRIMARY KEY, version TEXT NOT NULL, up TEXT NOT NULL, down TEXT NOT NULL, created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP, pre TEXT, comment TEXT, locked BOOLEAN NOT NULL DEFAULT 0)");
+            query.push(" (id TEXT PRIMARY KEY, version TEXT NOT NULL, up TEXT NOT NULL, down TEXT NOT NULL, created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP, pre TEXT, comment TEXT, locked BOOLEAN NOT NULL DEFAULT 0, up_sha256 TEXT, down_sha256 TEXT, author TEXT, ticket TEXT, labels TEXT, duration_ms INTEGER, applied_by TEXT, hostname TEXT)");
             query.build().execute(&mut *tx).await?;
+            sq::ensure_tracking_columns(&mut tx, &self.config.tables.migrations, &self.config.tables.log).await?;
             
             // Create log table
             let mut log_query = sq::build_table_query("CREATE TABLE IF NOT EXISTS ", &self.config.tables.log);
-            log_query.push(" (id TEXT PRIMARY KEY, migration_id TEXT NOT NULL, operation TEXT NOT NULL, sql_command TEXT NOT NULL, executed_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP)");
+            log_query.push(" (id TEXT PRIMARY KEY, migration_id TEXT NOT NULL, operation TEXT NOT NULL, sql_command TEXT NOT NULL, executed_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP, duration_ms INTEGER, applied_by TEXT, hostname TEXT, version TEXT, invocation TEXT, dry_run BOOLEAN, status TEXT, error TEXT)");
             log_query.build().execute(&mut *tx).await?;
+
+            // Create seed and repeatable migration tables
+            self.ensure_checksum_table(&mut tx, &self.config.tables.seeds).await?;
+            self.ensure_checksum_table(&mut tx, &self.config.tables.repeatables).await?;
         }
         tx.commit().await?;
-        println!("Initialized migration tables.");
+        crate::summary!("Initialized migration tables.");
         Ok(())
     }
 
@@ -57,24 +181,151 @@ impl MigrationRepository for SqliteRepo {
         Ok(id)
     }
 
-    async fn apply_migration(&self, id: &str, up_sql: &str, down_sql: &str, comment: Option<&str>, pre: Option<&str>, timeout: Option<u64>, dry_run: bool, locked: bool) -> Result<()> {
+    async fn apply_migration(&self, id: &str, up_sql: &str, down_sql: &str, meta: &MigrationMeta, pre: Option<&str>, exec: &ExecOptions, dry_run: bool, locked: bool) -> Result<()> {
+        check_isolation(exec)?;
+        let started = std::time::Instant::now();
+        if let Some(batch) = exec.batch {
+            if dry_run {
+                anyhow::bail!("Migration {} runs in batches and cannot be dry-run", id);
+            }
+            let batch_sql = batch.render(id, up_sql)?;
+            let (mut number, mut total) = (0, 0);
+            loop {
+                number += 1;
+                let mut tx = self.pool.begin().await?;
+                sq::set_timeout_if_needed(&mut *tx, exec.timeout).await?;
+                let rows = sqlx::raw_sql(&batch_sql)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Failed to execute batch {} of migration {}: {}", number, id, e))?
+                    .rows_affected();
+                tx.commit().await?;
+                crate::core::telemetry::add_rows(id, rows);
+                total += rows;
+                if batch.finish_batch(id, number, rows, total) {
+                    break;
+                }
+                batch.pause().await;
+            }
+
+            let mut tx = self.pool.begin().await?;
+            let duration_ms = Some(started.elapsed().as_millis() as i64);
+            sq::insert_migration_record(&mut *tx, &self.config.tables.migrations, id, up_sql, down_sql, meta, pre, locked, duration_ms).await?;
+            sq::insert_timed_log_entry(&mut *tx, &self.config.tables.log, id, "up", up_sql, duration_ms).await?;
+            tx.commit().await?;
+            return Ok(());
+        }
+        if !exec.transaction {
+            if dry_run {
+                anyhow::bail!("Migration {} runs without a transaction and cannot be dry-run", id);
+            }
+            // Outside a transaction SQLite commits every statement on its own
+            let mut conn = self.pool.acquire().await?;
+            sq::set_timeout_if_needed(&mut *conn, exec.timeout).await?;
+            sq::execute_statements_individually(&mut conn, up_sql, id, exec.skip_statements).await?;
+            drop(conn);
+
+            let mut tx = self.pool.begin().await?;
+            let duration_ms = Some(started.elapsed().as_millis() as i64);
+            sq::insert_migration_record(&mut *tx, &self.config.tables.migrations, id, up_sql, down_sql, meta, pre, locked, duration_ms).await?;
+            sq::insert_timed_log_entry(&mut *tx, &self.config.tables.log, id, "up", up_sql, duration_ms).await?;
+            tx.commit().await?;
+            return Ok(());
+        }
+
         let mut tx = self.pool.begin().await?;
-        sq::set_timeout_if_needed(&mut *tx, timeout).await?;
+        sq::set_timeout_if_needed(&mut *tx, exec.timeout).await?;
         
         // Execute migration
         sq::execute_sql_statements(&mut tx, up_sql, id).await?;
-        sq::insert_migration_record(&mut *tx, &self.config.tables.migrations, id, up_sql, down_sql, comment, pre, locked).await?;
+        let duration_ms = Some(started.elapsed().as_millis() as i64);
+        sq::insert_migration_record(&mut *tx, &self.config.tables.migrations, id, up_sql, down_sql, meta, pre, locked, duration_ms).await?;
         
         // Log successful migration
-        sq::insert_log_entry(&mut *tx, &self.config.tables.log, id, "up", up_sql).await?;
+        sq::insert_timed_log_entry(&mut *tx, &self.config.tables.log, id, "up", up_sql, duration_ms).await?;
         
         if dry_run { tx.rollback().await?; } else { tx.commit().await?; }
         Ok(())
     }
 
-    async fn revert_migration(&self, id: &str, down_sql: &str, timeout: Option<u64>, dry_run: bool, unlock: bool) -> Result<()> {
+    async fn apply_migrations(&self, migrations: &[(String, String, String, MigrationMeta)], pre: Option<&str>, timeout: Option<u64>, keep_going: bool, dry_run: bool) -> Result<Vec<(String, anyhow::Error)>> {
         let mut tx = self.pool.begin().await?;
-        sq::set_timeout_if_needed(&mut *tx, timeout).await?;
+        let mut pre = pre;
+        let mut failed = Vec::new();
+        for (id, up_sql, down_sql, meta) in migrations {
+            let exec = meta.exec_options(timeout);
+            check_isolation(&exec)?;
+            sqlx::raw_sql("SAVEPOINT qop_migration").execute(&mut *tx).await?;
+            let started = std::time::Instant::now();
+            let applied = async {
+                sq::set_timeout_if_needed(&mut *tx, exec.timeout).await?;
+                sq::execute_sql_statements(&mut tx, up_sql, id).await?;
+                let duration_ms = Some(started.elapsed().as_millis() as i64);
+                sq::insert_migration_record(&mut *tx, &self.config.tables.migrations, id, up_sql, down_sql, meta, pre, meta.is_locked(), duration_ms).await?;
+                sq::insert_timed_log_entry(&mut *tx, &self.config.tables.log, id, "up", up_sql, duration_ms).await
+            }
+            .await;
+            match applied {
+                | Ok(()) => {
+                    sqlx::raw_sql("RELEASE SAVEPOINT qop_migration").execute(&mut *tx).await?;
+                    pre = Some(id);
+                },
+                | Err(e) if keep_going => {
+                    sqlx::raw_sql("ROLLBACK TO SAVEPOINT qop_migration").execute(&mut *tx).await?;
+                    failed.push((id.clone(), e));
+                },
+                | Err(e) => return Err(e.context(format!("Migration {} failed, none of the {} migrations were applied", id, migrations.len()))),
+            }
+        }
+        if dry_run { tx.rollback().await?; } else { tx.commit().await?; }
+        Ok(failed)
+    }
+
+    async fn check_idempotency(&self, migrations: &[(String, String, MigrationMeta)], timeout: Option<u64>) -> Result<Vec<(String, anyhow::Error)>> {
+        let mut tx = self.pool.begin().await?;
+        let mut failed = Vec::new();
+        for (id, up_sql, meta) in migrations {
+            sq::set_timeout_if_needed(&mut *tx, meta.exec_options(timeout).timeout).await?;
+            sq::execute_sql_statements(&mut tx, up_sql, id).await.with_context(|| format!("Migration {} failed on its first run", id))?;
+            sqlx::raw_sql("SAVEPOINT qop_migration").execute(&mut *tx).await?;
+            match sq::execute_sql_statements(&mut tx, up_sql, id).await {
+                | Ok(()) => {
+                    sqlx::raw_sql("RELEASE SAVEPOINT qop_migration").execute(&mut *tx).await?;
+                },
+                | Err(e) => {
+                    sqlx::raw_sql("ROLLBACK TO SAVEPOINT qop_migration").execute(&mut *tx).await?;
+                    failed.push((id.clone(), e));
+                },
+            }
+        }
+        tx.rollback().await?;
+        Ok(failed)
+    }
+
+    async fn revert_migration(&self, id: &str, down_sql: &str, exec: &ExecOptions, dry_run: bool, unlock: bool) -> Result<()> {
+        check_isolation(exec)?;
+        let started = std::time::Instant::now();
+        if !exec.transaction {
+            if dry_run {
+                anyhow::bail!("Migration {} runs without a transaction and cannot be dry-run", id);
+            }
+            let mut conn = self.pool.acquire().await?;
+            if sq::is_migration_locked(&mut *conn, &self.config.tables.migrations, id).await? && !unlock {
+                anyhow::bail!("Migration {} is locked and cannot be reverted without --unlock flag", id);
+            }
+            sq::set_timeout_if_needed(&mut *conn, exec.timeout).await?;
+            sqlx::raw_sql(down_sql).execute(&mut *conn).await.map_err(|e| anyhow::anyhow!("Failed to execute statements in migration {}: {}", id, e))?;
+            drop(conn);
+
+            let mut tx = self.pool.begin().await?;
+            sq::delete_migration_record(&mut *tx, &self.config.tables.migrations, id).await?;
+            sq::insert_timed_log_entry(&mut *tx, &self.config.tables.log, id, "down", down_sql, Some(started.elapsed().as_millis() as i64)).await?;
+            tx.commit().await?;
+            return Ok(());
+        }
+
+        let mut tx = self.pool.begin().await?;
+        sq::set_timeout_if_needed(&mut *tx, exec.timeout).await?;
         
         // Check if migration is locked
         let is_locked = sq::is_migration_locked(&mut *tx, &self.config.tables.migrations, id).await?;
@@ -84,10 +335,11 @@ impl MigrationRepository for SqliteRepo {
         
         // Execute revert migration
         sq::execute_sql_statements(&mut tx, down_sql, id).await?;
+        let duration_ms = Some(started.elapsed().as_millis() as i64);
         sq::delete_migration_record(&mut *tx, &self.config.tables.migrations, id).await?;
         
         // Log successful revert
-        sq::insert_log_entry(&mut *tx, &self.config.tables.log, id, "down", down_sql).await?;
+        sq::insert_timed_log_entry(&mut *tx, &self.config.tables.log, id, "down", down_sql, duration_ms).await?;
         
         if dry_run { tx.rollback().await?; } else { tx.commit().await?; }
         Ok(())
@@ -124,11 +376,377 @@ impl MigrationRepository for SqliteRepo {
         let mut tx = self.pool.begin().await?;
         let mut q = sqlx::QueryBuilder::new("SELECT id, up, down, comment FROM ");
         q.push(migration::quote_ident(&self.config.tables.migrations));
-        q.push(" ORDER BY id ASC");
         let rows = q.build().fetch_all(&mut *tx).await?;
         tx.commit().await?;
-        Ok(rows.into_iter().map(|row| (row.get("id"), row.get("up"), row.get("down"), row.get("comment"))).collect())
+        let mut migrations: Vec<(String, String, String, Option<String>)> = rows.into_iter().map(|row| (row.get("id"), row.get("up"), row.get("down"), row.get("comment"))).collect();
+        migrations.sort_by(|a, b| crate::core::migration::compare_ids(&a.0, &b.0));
+        Ok(migrations)
+    }
+
+    async fn fetch_provenance(&self) -> Result<std::collections::HashMap<String, crate::core::migration::Provenance>> {
+        let mut tx = self.pool.begin().await?;
+        let rows = sq::build_table_query("SELECT id, author, ticket, labels FROM ", &self.config.tables.migrations).build().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.get("id"), crate::core::migration::Provenance::from_columns(row.get("author"), row.get("ticket"), row.get("labels"))))
+            .collect())
+    }
+
+    async fn fetch_applied_by(&self) -> Result<crate::core::migration::AppliedBy> {
+        let mut tx = self.pool.begin().await?;
+        let rows = sq::build_table_query("SELECT id, applied_by, hostname FROM ", &self.config.tables.migrations).build().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(rows.into_iter().map(|row| (row.get("id"), (row.get("applied_by"), row.get("hostname")))).collect())
+    }
+
+    async fn fetch_versions(&self) -> Result<std::collections::HashMap<String, (String, Option<String>)>> {
+        let mut tx = self.pool.begin().await?;
+        let rows = sq::build_table_query("SELECT id, version, comment FROM ", &self.config.tables.migrations).build().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(rows.into_iter().map(|row| (row.get("id"), (row.get("version"), row.get("comment")))).collect())
+    }
+
+    async fn fetch_durations(&self) -> Result<std::collections::HashMap<String, i64>> {
+        let mut tx = self.pool.begin().await?;
+        let mut q = sq::build_table_query("SELECT id, duration_ms FROM ", &self.config.tables.migrations);
+        q.push(" WHERE duration_ms IS NOT NULL");
+        let rows = q.build().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(rows.into_iter().map(|row| (row.get("id"), row.get("duration_ms"))).collect())
+    }
+
+    async fn fetch_migration_checksums(&self) -> Result<Vec<(String, Option<String>, Option<String>)>> {
+        let mut tx = self.pool.begin().await?;
+        let mut q = sq::build_table_query("SELECT id, up_sha256, down_sha256 FROM ", &self.config.tables.migrations);
+        let rows = q.build().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+        let mut checksums: Vec<(String, Option<String>, Option<String>)> = rows.into_iter().map(|row| (row.get("id"), row.get("up_sha256"), row.get("down_sha256"))).collect();
+        checksums.sort_by(|a, b| crate::core::migration::compare_ids(&a.0, &b.0));
+        Ok(checksums)
+    }
+
+    async fn bench_migrations(&self, migrations: &[(String, String, ExecOptions)]) -> Result<Vec<std::time::Duration>> {
+        let mut timings = Vec::with_capacity(migrations.len());
+        for (id, up_sql, exec) in migrations {
+            let started = std::time::Instant::now();
+            if exec.transaction {
+                let mut tx = self.pool.begin().await?;
+                sq::set_timeout_if_needed(&mut *tx, exec.timeout).await?;
+                sq::execute_sql_statements(&mut tx, up_sql, id).await?;
+                tx.commit().await?;
+            } else {
+                let mut conn = self.pool.acquire().await?;
+                sq::set_timeout_if_needed(&mut *conn, exec.timeout).await?;
+                sq::execute_statements_individually(&mut conn, up_sql, id, 0).await?;
+            }
+            timings.push(started.elapsed());
+        }
+        Ok(timings)
+    }
+
+    async fn squash_migrations(&self, ids: &[String], id: &str, up_sql: &str, down_sql: &str, meta: &MigrationMeta, pre: Option<&str>, locked: bool) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for old_id in ids {
+            sq::delete_migration_record(&mut *tx, &self.config.tables.migrations, old_id).await?;
+        }
+        sq::insert_migration_record(&mut *tx, &self.config.tables.migrations, id, up_sql, down_sql, meta, pre, locked, None).await?;
+        sq::insert_log_entry(&mut *tx, &self.config.tables.log, id, "squash", &ids.join(",")).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn baseline_migrations(&self, migrations: &[(String, String, String, MigrationMeta, bool)]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for (id, up_sql, down_sql, meta, locked) in migrations {
+            let pre = sq::get_last_migration_id(&mut tx, &self.config.tables.migrations).await?;
+            sq::insert_migration_record(&mut *tx, &self.config.tables.migrations, id, up_sql, down_sql, meta, pre.as_deref(), *locked, None).await?;
+            sq::insert_log_entry(&mut *tx, &self.config.tables.log, id, "baseline", up_sql).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn rename_migration(&self, id: &str, new_id: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let mut q = sq::build_table_query("UPDATE ", &self.config.tables.migrations);
+        q.push(" SET id = ").push_bind(new_id).push(" WHERE id = ").push_bind(id);
+        q.build().execute(&mut *tx).await?;
+        let mut q = sq::build_table_query("UPDATE ", &self.config.tables.migrations);
+        q.push(" SET pre = ").push_bind(new_id).push(" WHERE pre = ").push_bind(id);
+        q.build().execute(&mut *tx).await?;
+        let mut q = sq::build_table_query("UPDATE ", &self.config.tables.log);
+        q.push(" SET migration_id = ").push_bind(new_id).push(" WHERE migration_id = ").push_bind(id);
+        q.build().execute(&mut *tx).await?;
+        sq::insert_log_entry(&mut *tx, &self.config.tables.log, new_id, "rename", id).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn set_migration_locked(&self, id: &str, locked: bool) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let mut q = sq::build_table_query("UPDATE ", &self.config.tables.migrations);
+        q.push(" SET locked = ").push_bind(locked).push(" WHERE id = ").push_bind(id);
+        let updated = q.build().execute(&mut *tx).await?.rows_affected() > 0;
+        if updated {
+            sq::insert_log_entry(&mut *tx, &self.config.tables.log, id, if locked { "lock" } else { "unlock" }, "").await?;
+        }
+        tx.commit().await?;
+        Ok(updated)
+    }
+
+    async fn forget_migration(&self, id: &str) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sq::delete_migration_record(&mut *tx, &self.config.tables.migrations, id).await?;
+        let mut q = sq::build_table_query("DELETE FROM ", &self.config.tables.log);
+        q.push(" WHERE migration_id = ").push_bind(id);
+        q.build().execute(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn prune_migrations(&self, ids: &[String]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for id in ids {
+            sq::delete_migration_record(&mut *tx, &self.config.tables.migrations, id).await?;
+            sq::insert_log_entry(&mut *tx, &self.config.tables.log, id, "prune", "").await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn repair_migration(&self, id: &str, up_sql: Option<&str>, down_sql: Option<&str>) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for (column, sql) in [("up", up_sql), ("down", down_sql)] {
+            let Some(sql) = sql else { continue };
+            let mut q = sq::build_table_query("UPDATE ", &self.config.tables.migrations);
+            q.push(format!(" SET {} = ", column)).push_bind(sql);
+            q.push(format!(", {}_sha256 = ", column)).push_bind(crate::core::migration::sql_checksum(sql));
+            q.push(" WHERE id = ").push_bind(id);
+            q.build().execute(&mut *tx).await?;
+            sq::insert_log_entry(&mut *tx, &self.config.tables.log, id, &format!("repair {}", column), sql).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn dump_schema(&self) -> Result<String> {
+        let mut tx = self.pool.begin().await?;
+        let rows = sqlx::query(
+            "SELECT sql FROM sqlite_master WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite_%' AND name NOT IN (?, ?, ?, ?) AND tbl_name NOT IN (?, ?, ?, ?) \
+             ORDER BY CASE type WHEN 'table' THEN 0 WHEN 'view' THEN 1 WHEN 'index' THEN 2 ELSE 3 END, name",
+        )
+            .bind(&self.config.tables.migrations)
+            .bind(&self.config.tables.log)
+            .bind(&self.config.tables.seeds)
+            .bind(&self.config.tables.repeatables)
+            .bind(&self.config.tables.migrations)
+            .bind(&self.config.tables.log)
+            .bind(&self.config.tables.seeds)
+            .bind(&self.config.tables.repeatables)
+            .fetch_all(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(rows.into_iter().map(|row| format!("{};\n", row.get::<String, _>("sql"))).collect::<Vec<_>>().join("\n"))
+    }
+
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn fetch_table_status(&self) -> Result<Vec<(String, bool)>> {
+        let tables = [&self.config.tables.migrations, &self.config.tables.log, &self.config.tables.seeds, &self.config.tables.repeatables];
+        let mut status = Vec::new();
+        for table in tables {
+            let exists = sqlx::query("SELECT name FROM sqlite_master WHERE type='table' AND name=?")
+                .bind(table)
+                .fetch_optional(&self.pool)
+                .await?
+                .is_some();
+            status.push((table.clone(), exists));
+        }
+        Ok(status)
+    }
+
+    async fn estimate_rows(&self, _table: &str) -> Result<Option<i64>> {
+        // SQLite cannot build indexes concurrently, so table sizes are irrelevant for linting
+        Ok(None)
+    }
+
+    async fn fetch_foreign_rows(&self, table: &str, columns: &[&str]) -> Result<Option<Vec<Vec<Option<String>>>>> {
+        let exists = sqlx::query("SELECT name FROM sqlite_master WHERE type='table' AND name=? COLLATE NOCASE")
+            .bind(table)
+            .fetch_optional(&self.pool)
+            .await?
+            .is_some();
+        if !exists {
+            return Ok(None);
+        }
+        let select: Vec<String> = columns.iter().map(|column| format!("CAST({} AS TEXT)", column)).collect();
+        let rows = sqlx::query(&format!("SELECT {} FROM {}", select.join(", "), table)).fetch_all(&self.pool).await?;
+        Ok(Some(rows.into_iter().map(|row| (0..columns.len()).map(|i| row.get::<Option<String>, _>(i)).collect()).collect()))
+    }
+
+    async fn fetch_chain(&self) -> Result<Vec<(String, Option<String>, NaiveDateTime)>> {
+        let mut tx = self.pool.begin().await?;
+        let mut q = sq::build_table_query("SELECT id, pre, created_at FROM ", &self.config.tables.migrations);
+        let rows = q.build().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+        let mut chain: Vec<(String, Option<String>, NaiveDateTime)> = rows.into_iter().map(|row| (row.get("id"), row.get("pre"), row.get("created_at"))).collect();
+        chain.sort_by(|a, b| crate::core::migration::compare_ids(&a.0, &b.0));
+        Ok(chain)
+    }
+
+    async fn relink_migrations(&self, links: &[(String, Option<String>)]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for (id, pre) in links {
+            let mut q = sq::build_table_query("UPDATE ", &self.config.tables.migrations);
+            q.push(" SET pre = ").push_bind(pre.as_deref()).push(" WHERE id = ").push_bind(id);
+            q.build().execute(&mut *tx).await?;
+            sq::insert_log_entry(&mut *tx, &self.config.tables.log, id, "relink", pre.as_deref().unwrap_or("")).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn fetch_seeds(&self) -> Result<Vec<(String, String, NaiveDateTime)>> {
+        self.fetch_checksums(&self.config.tables.seeds).await
+    }
+
+    async fn run_seed(&self, name: &str, sql: &str, checksum: &str, timeout: Option<u64>, dry_run: bool) -> Result<()> {
+        self.run_checksummed(&self.config.tables.seeds, "seed", name, sql, checksum, timeout, dry_run).await
+    }
+
+    async fn reset_seeds(&self, names: &[String]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        self.ensure_checksum_table(&mut tx, &self.config.tables.seeds).await?;
+        for name in names {
+            let mut q = sq::build_table_query("DELETE FROM ", &self.config.tables.seeds);
+            q.push(" WHERE name = ").push_bind(name);
+            q.build().execute(&mut *tx).await?;
+            sq::insert_log_entry(&mut *tx, &self.config.tables.log, &format!("seed:{}", name), "seed reset", "").await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn fetch_repeatables(&self) -> Result<Vec<(String, String, NaiveDateTime)>> {
+        self.fetch_checksums(&self.config.tables.repeatables).await
+    }
+
+    async fn run_repeatable(&self, name: &str, sql: &str, checksum: &str, timeout: Option<u64>, dry_run: bool) -> Result<()> {
+        self.run_checksummed(&self.config.tables.repeatables, "repeat", name, sql, checksum, timeout, dry_run).await
+    }
+
+    async fn execute_sql(&self, sql: &str, timeout: Option<u64>, dry_run: bool) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sq::set_timeout_if_needed(&mut *tx, timeout).await?;
+        sq::execute_sql_statements(&mut tx, sql, "hook").await?;
+        if dry_run { tx.rollback().await?; } else { tx.commit().await?; }
+        Ok(())
+    }
+
+    async fn log_attempt(&self, migration_id: &str, operation: &str, sql: &str, outcome: &crate::core::log::LogOutcome) -> Result<()> {
+        sq::insert_log_record(&self.pool, &self.config.tables.log, migration_id, operation, sql, outcome).await
+    }
+
+    async fn fetch_log(&self, filter: &crate::core::log::LogFilter) -> Result<Vec<crate::core::log::LogEntry>> {
+        let mut tx = self.pool.begin().await?;
+        let mut q = sq::build_table_query("SELECT id, migration_id, operation, sql_command, executed_at, duration_ms, applied_by, hostname, version, invocation, dry_run, status, error FROM ", &self.config.tables.log);
+        q.push(" WHERE 1=1");
+        if let Some(migration_id) = &filter.migration_id {
+            q.push(" AND migration_id = ").push_bind(migration_id.clone());
+        }
+        if let Some(operation) = &filter.operation {
+            q.push(" AND operation = ").push_bind(operation.clone());
+        }
+        if let Some(since) = filter.since {
+            q.push(" AND executed_at >= ").push_bind(since);
+        }
+        if let Some(until) = filter.until {
+            q.push(" AND executed_at <= ").push_bind(until);
+        }
+        q.push(" ORDER BY executed_at DESC, id DESC");
+        if let Some(limit) = filter.limit {
+            q.push(" LIMIT ").push_bind(limit as i64);
+        }
+        let rows = q.build().fetch_all(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(rows.into_iter().map(|row| crate::core::log::LogEntry {
+            id: row.get("id"),
+            migration_id: row.get("migration_id"),
+            operation: row.get("operation"),
+            sql_command: row.get("sql_command"),
+            executed_at: row.get("executed_at"),
+            duration_ms: row.get("duration_ms"),
+            applied_by: row.get("applied_by"),
+            hostname: row.get("hostname"),
+            version: row.get("version"),
+            invocation: row.get("invocation"),
+            dry_run: row.get("dry_run"),
+            status: row.get("status"),
+            error: row.get("error"),
+        }).collect())
+    }
+
+    async fn fetch_log_entry(&self, id: &str) -> Result<Option<crate::core::log::LogEntry>> {
+        let mut tx = self.pool.begin().await?;
+        let mut q = sq::build_table_query("SELECT id, migration_id, operation, sql_command, executed_at, duration_ms, applied_by, hostname, version, invocation, dry_run, status, error FROM ", &self.config.tables.log);
+        q.push(" WHERE id = ").push_bind(id);
+        let row = q.build().fetch_optional(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(row.map(|row| crate::core::log::LogEntry {
+            id: row.get("id"),
+            migration_id: row.get("migration_id"),
+            operation: row.get("operation"),
+            sql_command: row.get("sql_command"),
+            executed_at: row.get("executed_at"),
+            duration_ms: row.get("duration_ms"),
+            applied_by: row.get("applied_by"),
+            hostname: row.get("hostname"),
+            version: row.get("version"),
+            invocation: row.get("invocation"),
+            dry_run: row.get("dry_run"),
+            status: row.get("status"),
+            error: row.get("error"),
+        }))
     }
 
     fn get_path(&self) -> &std::path::Path { &self.path }
+
+    fn profile(&self) -> Option<&str> {
+        self.config.profile.as_deref()
+    }
+
+    fn scope(&self) -> String {
+        format!("sqlite://{}/{}", self.pool.connect_options().get_filename().display(), self.config.tables.migrations)
+    }
+
+    fn snapshot_file(&self) -> Option<std::path::PathBuf> {
+        let file = self.config.snapshot.as_ref()?;
+        Some(self.path.parent().map(|dir| dir.join(file)).unwrap_or_else(|| file.into()))
+    }
+
+    fn seeds_dir(&self) -> std::path::PathBuf {
+        let dir = self.config.seeds.as_deref().unwrap_or(crate::core::seed::SEEDS_DIR);
+        self.path.parent().map(|parent| parent.join(dir)).unwrap_or_else(|| dir.into())
+    }
+
+    fn hooks(&self) -> &crate::core::hooks::Hooks { &self.config.hooks }
+
+    fn ids(&self) -> &crate::core::migration::IdConfig { &self.config.ids }
+
+    fn retry(&self) -> &crate::core::migration::RetryConfig { &self.config.retry }
+
+    fn is_transient(&self, error: &anyhow::Error) -> bool {
+        match crate::core::migration::find_cause::<sqlx::Error>(error) {
+            // SQLITE_BUSY ("database is locked") and SQLITE_LOCKED, including their extended codes
+            | Some(sqlx::Error::Database(e)) => e.code().and_then(|code| code.parse::<u32>().ok()).is_some_and(|code| matches!(code & 0xff, 5 | 6)),
+            | Some(sqlx::Error::PoolTimedOut) => true,
+            | _ => false,
+        }
+    }
+
+    fn dialect(&self) -> crate::core::repo::Dialect { crate::core::repo::Dialect::Sqlite }
 }